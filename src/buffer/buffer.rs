@@ -66,6 +66,13 @@ impl Buffer {
         Ok(())
     }
 
+    /// Detaches the buffer from its block without writing it, the block's file is gone.
+    pub fn discard(&mut self) {
+        self.block_id = None;
+        self.tx_id = -1;
+        self.lsn = -1;
+    }
+
     pub fn flush(&mut self) -> io::Result<()> {
         if self.tx_id >= 0 {
             self.log_mgr.flush(self.lsn)?;
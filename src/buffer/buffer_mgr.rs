@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Condvar, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::time::{Duration, Instant};
 
@@ -11,6 +11,7 @@ use crate::storage::StorageMgr;
 /// Manages the buffer pool, which consists of a collection of Buffer objects.
 /// It employs interrior mutability and also is thread-safe
 pub struct BufferMgr {
+    storage_mgr: Arc<dyn StorageMgr>,
     inner: Mutex<BufferMgrInner>,
     buffers: Box<[RwLock<Buffer>]>,
     condvar: Condvar,
@@ -23,6 +24,8 @@ struct BufferMgrInner {
     pins: Box<[usize]>,
     available_cnt: usize,
     block_to_buffer_idx: HashMap<BlockId, usize>,
+    // files of the modified pages written since the last sync_written_files
+    unsynced_files: HashSet<String>,
 }
 
 pub struct PinnedBufferGuard<'a> {
@@ -42,10 +45,12 @@ impl BufferMgr {
         }
 
         BufferMgr {
+            storage_mgr,
             inner: Mutex::new(BufferMgrInner {
                 pins: vec![0; buffer_cnt].into_boxed_slice(),
                 available_cnt: buffer_cnt,
                 block_to_buffer_idx: HashMap::new(),
+                unsynced_files: HashSet::new(),
             }),
             buffers: buffers.into_boxed_slice(),
             condvar: Condvar::new(),
@@ -58,17 +63,68 @@ impl BufferMgr {
     }
 
     pub fn flush_all(&self, txnum: i32) -> DbResult<()> {
-        let _guard = self.inner.lock().unwrap();
+        let mut inner = self.inner.lock().unwrap();
 
         for buffer in self.buffers.iter() {
             let mut buffer = buffer.write().unwrap();
             if buffer.modifying_tx() == txnum {
-                buffer.flush()?;
+                Self::flush_buffer(&mut inner, &mut buffer)?;
             }
         }
         Ok(())
     }
 
+    /// Writes every modified buffer to disk, regardless of the transaction which modified it.
+    pub fn flush_all_modified(&self) -> DbResult<()> {
+        let mut inner = self.inner.lock().unwrap();
+
+        for buffer in self.buffers.iter() {
+            Self::flush_buffer(&mut inner, &mut buffer.write().unwrap())?;
+        }
+        Ok(())
+    }
+
+    /// Syncs every file which got modified pages written since the last call, e.g. before
+    /// a checkpoint lets the log records of those pages go.
+    pub fn sync_written_files(&self) -> DbResult<()> {
+        // the lock keeps the files from being deleted (see discard_file) while they are synced
+        let mut inner = self.inner.lock().unwrap();
+
+        for file_name in std::mem::take(&mut inner.unsynced_files) {
+            self.storage_mgr.sync(&file_name)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffer if it was modified, remembering its file to be synced.
+    /// Temp files don't survive a restart, so they are never synced.
+    fn flush_buffer(inner: &mut BufferMgrInner, buffer: &mut Buffer) -> DbResult<()> {
+        if buffer.is_modified_by_tx()
+            && let Some(blk) = buffer.block().filter(|blk| !blk.is_temp())
+        {
+            inner.unsynced_files.insert(blk.file_name().to_string());
+        }
+        Ok(buffer.flush()?)
+    }
+
+    /// Detaches the unpinned buffers holding blocks of the file without writing them.
+    /// Called when the file is deleted, so that its pages are never written back.
+    pub fn discard_file(&self, file_name: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.unsynced_files.remove(file_name);
+
+        for (idx, buffer) in self.buffers.iter().enumerate() {
+            if inner.pins[idx] > 0 {
+                continue;
+            }
+            let mut buffer = buffer.write().unwrap();
+            if let Some(blk) = buffer.block().filter(|blk| blk.file_name() == file_name) {
+                inner.block_to_buffer_idx.remove(blk);
+                buffer.discard();
+            }
+        }
+    }
+
     /// Pins the block to a buffer.
     /// If the block is already in a buffer, that buffer is used.
     /// Otherwise, an unpinned buffer is chosen.
@@ -112,6 +168,7 @@ impl BufferMgr {
             if let Some(block) = buffer.block() {
                 inner.block_to_buffer_idx.remove(&block);
             }
+            Self::flush_buffer(inner, &mut buffer)?;
             buffer.assign_to_block(blk.clone())?;
 
             return Ok(Some(idx));
@@ -455,4 +512,34 @@ mod tests {
         assert_eq!(buffer_mgr.available(), 3);
         Ok(())
     }
+
+    #[test]
+    fn test_discard_file() -> DbResult<()> {
+        let env = TestEnvironment::new(3)?;
+        let buffer_mgr = &env.buffer_mgr;
+        env.storage_mgr.append("dropped")?;
+        env.storage_mgr.append("kept")?;
+
+        let dropped = BlockId::new("dropped".to_string(), 0);
+        let kept = BlockId::new("kept".to_string(), 0);
+        for blk in [&dropped, &kept] {
+            let guard = buffer_mgr.pin(blk)?;
+            let mut buffer = guard.borrow_mut();
+            buffer.contents_mut().set_int(0, 42);
+            buffer.set_modified(1, -1);
+        }
+
+        buffer_mgr.discard_file("dropped");
+        env.storage_mgr.delete_file("dropped")?;
+
+        // the discarded page is not written back, which would recreate the file
+        buffer_mgr.flush_all(1)?;
+        assert_eq!(env.storage_mgr.block_cnt("dropped")?, 0);
+        let mut page = crate::storage::Page::new(400);
+        env.storage_mgr.read(&kept, &mut page)?;
+        assert_eq!(page.get_int(0), 42);
+
+        assert!(!buffer_mgr.inner.lock().unwrap().block_to_buffer_idx.contains_key(&dropped));
+        Ok(())
+    }
 }
@@ -24,8 +24,14 @@ pub enum DbError {
     #[error("Lock abort")]
     LockAbort,
 
+    #[error("Constraint violation: {0}")]
+    ConstraintViolation(String),
+
     #[error("Not found")]
     NotFound,
+
+    #[error("Unsupported database format version {0}, version {1} is required")]
+    UnsupportedFormat(i32, i32),
 }
 
 impl From<bincode::Error> for DbError {
@@ -0,0 +1,128 @@
+use std::ops::Bound;
+
+use crate::{
+    DbResult,
+    index::{BTreePage, btree_page::PageType},
+    query::Constant,
+    record::{Layout, RID},
+    storage::BlockId,
+    tx::Transaction,
+};
+
+/// A cursor over the leaf entries whose keys lie between a lower and an upper bound.
+/// It starts at the leaf which would hold the lower bound and follows the leaf chain to the right.
+/// The overflow chain of a leaf is read after the leaf itself, so duplicates of the first key
+/// of a leaf may come after the larger keys stored in that leaf.
+pub struct BTreeRangeCursor<'tx> {
+    tx: Transaction<'tx>,
+    layout: Layout,
+    lower: Bound<Constant>,
+    upper: Bound<Constant>,
+    contents: BTreePage<'tx>,
+    current_slot: Option<usize>,
+    next_leaf: Option<usize>,
+    file_name: String,
+    done: bool,
+}
+
+impl<'tx> BTreeRangeCursor<'tx> {
+    pub fn new(
+        tx: Transaction<'tx>,
+        block_id: BlockId,
+        layout: Layout,
+        lower: Bound<Constant>,
+        upper: Bound<Constant>,
+        file_name: String,
+    ) -> DbResult<Self> {
+        let contents = BTreePage::new(tx.clone(), block_id, layout.clone())?;
+        let next_leaf = contents.get_sibling()?;
+        Ok(Self {
+            tx,
+            layout,
+            lower,
+            upper,
+            contents,
+            current_slot: None,
+            next_leaf,
+            file_name,
+            done: false,
+        })
+    }
+
+    /// Advances to the next entry within the bounds
+    /// Returns false once the entries of the leaves to the right are all above the upper bound
+    pub fn advance(&mut self) -> DbResult<bool> {
+        while !self.done {
+            let slot = self.current_slot.map_or(0, |slot| slot + 1);
+            self.current_slot = Some(slot);
+            if slot >= self.contents.get_number_of_recs()? {
+                self.move_to_next_page()?;
+                continue;
+            }
+
+            let key = self.contents.get_data_value(slot)?;
+            if self.is_above_upper(&key) {
+                //  the rest of the page is larger still, only the overflow chain may match
+                self.move_to_next_page()?;
+            } else if !self.is_below_lower(&key) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn get_data_rid(&self) -> DbResult<RID> {
+        self.contents.get_rid(
+            self.current_slot
+                .expect("Current slot not set in BTreeRangeCursor::get_data_rid"),
+        )
+    }
+
+    /// Moves to the overflow page of the current page if there is one, otherwise to the next leaf
+    /// The scan is done when there is no next leaf or all of its keys are above the upper bound
+    fn move_to_next_page(&mut self) -> DbResult<()> {
+        self.current_slot = None;
+        if let PageType::Leaf(Some(overflow_block_num)) = self.contents.get_flag()? {
+            self.contents = self.page(overflow_block_num)?;
+            return Ok(());
+        }
+
+        let Some(next_leaf) = self.next_leaf else {
+            self.done = true;
+            return Ok(());
+        };
+        self.contents = self.page(next_leaf)?;
+        self.next_leaf = self.contents.get_sibling()?;
+        if matches!(self.contents.get_flag()?, PageType::Leaf(None))
+            && self.contents.get_number_of_recs()? > 0
+            && self.is_above_upper(&self.contents.get_data_value(0)?)
+        {
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    fn page(&self, block_num: usize) -> DbResult<BTreePage<'tx>> {
+        BTreePage::new(
+            self.tx.clone(),
+            BlockId::new(self.file_name.clone(), block_num as i32),
+            self.layout.clone(),
+        )
+    }
+
+    fn is_below_lower(&self, key: &Constant) -> bool {
+        match &self.lower {
+            Bound::Included(lower) => key < lower,
+            Bound::Excluded(lower) => key <= lower,
+            Bound::Unbounded => false,
+        }
+    }
+
+    fn is_above_upper(&self, key: &Constant) -> bool {
+        match &self.upper {
+            Bound::Included(upper) => key > upper,
+            Bound::Excluded(upper) => key >= upper,
+            Bound::Unbounded => false,
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use crate::{
     DbResult,
     index::{
-        BTreePage, Index, btree_internal::BTreeInternal, btree_leaf::BTreeLeaf,
-        btree_page::PageType,
+        BTreePage, BTreeRangeCursor, Index,
+        btree_internal::BTreeInternal,
+        btree_leaf::BTreeLeaf,
+        btree_page::{InternalNodeEntry, PageType},
     },
     metadata::IndexInfo,
     query::Constant,
@@ -10,7 +12,7 @@ use crate::{
     storage::BlockId,
     tx::Transaction,
 };
-use std::fmt;
+use std::{fmt, ops::Bound};
 
 // Original implementation - https://github.com/redixhumayun/simpledb/blob/master/src/btree.rs
 
@@ -22,6 +24,7 @@ pub struct BTreeIndex<'tx> {
     leaf_layout: Layout,
     leaf_table_name: String,
     leaf: Option<BTreeLeaf<'tx>>,
+    range: Option<BTreeRangeCursor<'tx>>,
     root_block: BlockId,
 }
 
@@ -51,6 +54,14 @@ impl<'tx> BTreeIndex<'tx> {
             let min_val = match field_type {
                 FieldType::Integer => Constant::Int(i32::MIN),
                 FieldType::Varchar => Constant::String("".to_string()),
+                FieldType::BigInt => Constant::BigInt(i64::MIN),
+                FieldType::Boolean => Constant::Bool(false),
+                FieldType::Double => Constant::Double(f64::NEG_INFINITY),
+                FieldType::Date => Constant::Date(i32::MIN),
+                FieldType::Timestamp => Constant::Timestamp(i64::MIN),
+                FieldType::Text | FieldType::Blob => {
+                    unreachable!("TEXT and BLOB fields are not indexed")
+                }
             };
             internal_page.insert_internal(0, min_val, 0)?;
         }
@@ -61,11 +72,160 @@ impl<'tx> BTreeIndex<'tx> {
             leaf_layout,
             leaf_table_name,
             leaf: None,
+            range: None,
             root_block: BlockId::new(internal_table_name, 0),
         })
     }
 }
 
+impl<'tx> BTreeIndex<'tx> {
+    /// Loads the entries into the index.
+    /// An empty index is built bottom-up: the sorted entries are written into consecutive leaves
+    /// and each internal level is built from the first keys of the level below it, instead of
+    /// descending the tree once per entry. Into an index which already has entries they are
+    /// inserted one at a time.
+    pub fn bulk_load(&mut self, mut entries: Vec<(Constant, RID)>) -> DbResult<()> {
+        self.close();
+        entries.retain(|(data_val, _)| !data_val.is_null());
+        if !self.is_empty()? {
+            for (data_val, data_rid) in &entries {
+                self.insert(data_val, data_rid)?;
+            }
+            return Ok(());
+        }
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let root = BTreePage::new(
+            self.tx.clone(),
+            self.root_block.clone(),
+            self.internal_layout.clone(),
+        )?;
+        let min_val = root.get_data_value(0)?;
+        let mut level = self.load_leaves(&entries, min_val)?;
+
+        let capacity = root.capacity();
+        let mut height = 0;
+        while level.len() > capacity {
+            level = self.load_internal_level(&level, capacity, height)?;
+            height += 1;
+        }
+        root.format(Self::internal_page_type(height))?;
+        for (slot, entry) in level.into_iter().enumerate() {
+            root.insert_internal(slot, entry.dataval, entry.block_num)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if nothing was ever inserted into the index
+    fn is_empty(&self) -> DbResult<bool> {
+        if self.tx.size(&self.leaf_table_name)? != 1
+            || self.tx.size(self.root_block.file_name())? != 1
+        {
+            return Ok(false);
+        }
+        let first_leaf = BTreePage::new(
+            self.tx.clone(),
+            BlockId::new(self.leaf_table_name.clone(), 0),
+            self.leaf_layout.clone(),
+        )?;
+        Ok(first_leaf.get_number_of_recs()? == 0)
+    }
+
+    /// Writes the sorted entries into the leaf chain starting at block 0.
+    /// Identical keys are kept in one leaf, and those that don't fit go to its overflow pages.
+    /// Returns the directory entries pointing to the leaves, the first one keyed by `min_val`.
+    fn load_leaves(
+        &self,
+        entries: &[(Constant, RID)],
+        min_val: Constant,
+    ) -> DbResult<Vec<InternalNodeEntry>> {
+        let mut page = BTreePage::new(
+            self.tx.clone(),
+            BlockId::new(self.leaf_table_name.clone(), 0),
+            self.leaf_layout.clone(),
+        )?;
+        let capacity = page.capacity();
+        let mut level = vec![InternalNodeEntry {
+            dataval: min_val,
+            block_num: 0,
+        }];
+        let mut slot = 0;
+
+        for group in entries.chunk_by(|a, b| a.0 == b.0) {
+            if slot > 0 && slot + group.len() > capacity {
+                let block_id = self.tx.append(&self.leaf_table_name)?;
+                let next_page =
+                    BTreePage::new(self.tx.clone(), block_id.clone(), self.leaf_layout.clone())?;
+                next_page.format(PageType::Leaf(None))?;
+                page.set_sibling(Some(block_id.number() as usize))?;
+                page = next_page;
+                slot = 0;
+                level.push(InternalNodeEntry {
+                    dataval: group[0].0.clone(),
+                    block_num: block_id.number() as usize,
+                });
+            }
+
+            let (fitting, overflowing) = group.split_at(group.len().min(capacity - slot));
+            for (data_val, data_rid) in fitting {
+                page.insert_leaf(slot, data_val.clone(), *data_rid)?;
+                slot += 1;
+            }
+            for chunk in overflowing.chunks(capacity) {
+                //  the new overflow page continues the chain the leaf pointed to so far
+                let block_id = self.tx.append(&self.leaf_table_name)?;
+                let overflow_page =
+                    BTreePage::new(self.tx.clone(), block_id.clone(), self.leaf_layout.clone())?;
+                overflow_page.format(page.get_flag()?)?;
+                for (overflow_slot, (data_val, data_rid)) in chunk.iter().enumerate() {
+                    overflow_page.insert_leaf(overflow_slot, data_val.clone(), *data_rid)?;
+                }
+                page.set_flag(PageType::Leaf(Some(block_id.number() as usize)))?;
+            }
+        }
+        Ok(level)
+    }
+
+    /// Writes the directory entries of one level into new internal pages of the given height.
+    /// Returns the directory entries pointing to the new pages.
+    fn load_internal_level(
+        &self,
+        level: &[InternalNodeEntry],
+        capacity: usize,
+        height: usize,
+    ) -> DbResult<Vec<InternalNodeEntry>> {
+        let mut next_level = Vec::new();
+        for chunk in level.chunks(capacity) {
+            let block_id = self.tx.append(self.root_block.file_name())?;
+            let page = BTreePage::new(
+                self.tx.clone(),
+                block_id.clone(),
+                self.internal_layout.clone(),
+            )?;
+            page.format(Self::internal_page_type(height))?;
+            for (slot, entry) in chunk.iter().enumerate() {
+                page.insert_internal(slot, entry.dataval.clone(), entry.block_num)?;
+            }
+            next_level.push(InternalNodeEntry {
+                dataval: chunk[0].dataval.clone(),
+                block_num: block_id.number() as usize,
+            });
+        }
+        Ok(next_level)
+    }
+
+    /// Internal pages directly above the leaves have no level, the ones above them count up from 1
+    fn internal_page_type(height: usize) -> PageType {
+        match height {
+            0 => PageType::Internal(None),
+            height => PageType::Internal(Some(height)),
+        }
+    }
+}
+
 impl<'tx> fmt::Display for BTreeIndex<'tx> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "\n=== BTreeIndex: {} ===", self.index_name)?;
@@ -128,7 +288,36 @@ impl<'tx> Index for BTreeIndex<'tx> {
         Ok(())
     }
 
+    fn seek(&mut self, lower: Bound<&Constant>, upper: Bound<&Constant>) -> DbResult<()> {
+        self.close();
+        let leaf_block_num = match lower {
+            Bound::Included(key) | Bound::Excluded(key) => {
+                let mut root = BTreeInternal::new(
+                    self.tx.clone(),
+                    self.root_block.clone(),
+                    self.internal_layout.clone(),
+                    self.root_block.file_name().to_string(),
+                )?;
+                root.search(key)?
+            }
+            //  splits always move entries to the right, so the leftmost leaf stays at block 0
+            Bound::Unbounded => 0,
+        };
+        self.range = Some(BTreeRangeCursor::new(
+            self.tx.clone(),
+            BlockId::new(self.leaf_table_name.clone(), leaf_block_num as i32),
+            self.leaf_layout.clone(),
+            lower.cloned(),
+            upper.cloned(),
+            self.leaf_table_name.clone(),
+        )?);
+        Ok(())
+    }
+
     fn next(&mut self) -> DbResult<bool> {
+        if let Some(range) = self.range.as_mut() {
+            return range.advance();
+        }
         match self
             .leaf
             .as_mut()
@@ -141,10 +330,17 @@ impl<'tx> Index for BTreeIndex<'tx> {
     }
 
     fn get_data_rid(&self) -> DbResult<RID> {
+        if let Some(range) = self.range.as_ref() {
+            return range.get_data_rid();
+        }
         self.leaf.as_ref().unwrap().get_data_rid()
     }
 
+    /// Null keys are not indexed, since no search key ever equals them.
     fn insert(&mut self, data_val: &Constant, data_rid: &RID) -> DbResult<()> {
+        if data_val.is_null() {
+            return Ok(());
+        }
         self.before_first(data_val)?;
         let int_node_id = self.leaf.as_mut().unwrap().insert(*data_rid).unwrap();
         if int_node_id.is_none() {
@@ -167,6 +363,9 @@ impl<'tx> Index for BTreeIndex<'tx> {
     }
 
     fn delete(&mut self, data_val: &Constant, data_rid: &RID) -> DbResult<()> {
+        if data_val.is_null() {
+            return Ok(());
+        }
         self.before_first(data_val)?;
         self.leaf.as_mut().unwrap().delete(*data_rid)?;
         //  TODO: Should the leaf be set to None here?
@@ -178,6 +377,7 @@ impl<'tx> Index for BTreeIndex<'tx> {
         if self.leaf.is_some() {
             self.leaf = None;
         }
+        self.range = None;
     }
 }
 
@@ -270,6 +470,242 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_duplicate_keys_in_overflow_pages() -> DbResult<()> {
+        let db = temp_db()?;
+        let mut index = setup_index(&db)?;
+
+        // far more duplicates than fit in a single leaf, interleaved with other keys
+        for i in 0..300 {
+            index.insert(&Constant::Int(i % 3), &RID::new(i, 1))?;
+        }
+
+        for key in 0..3 {
+            index.before_first(&Constant::Int(key))?;
+            let mut found_blocks = Vec::new();
+            while index.next()? {
+                found_blocks.push(index.get_data_rid()?.block_number());
+            }
+            found_blocks.sort();
+            let expected: Vec<_> = (0..300).filter(|i| i % 3 == key).collect();
+            assert_eq!(found_blocks, expected);
+        }
+
+        index.delete(&Constant::Int(1), &RID::new(298, 1))?;
+        index.before_first(&Constant::Int(1))?;
+        let mut cnt = 0;
+        while index.next()? {
+            cnt += 1;
+        }
+        assert_eq!(cnt, 99);
+        Ok(())
+    }
+
+    fn collect_key(index: &mut BTreeIndex, key: i32) -> DbResult<Vec<i32>> {
+        index.before_first(&Constant::Int(key))?;
+        let mut blocks = Vec::new();
+        while index.next()? {
+            blocks.push(index.get_data_rid()?.block_number());
+        }
+        blocks.sort();
+        Ok(blocks)
+    }
+
+    #[test]
+    fn test_delete_duplicates_in_overflow_pages() -> DbResult<()> {
+        let db = temp_db_with_cfg(|cfg| cfg.block_size(400))?;
+        let mut index = setup_index(&db)?;
+
+        // several leaves worth of one key, next to smaller and larger keys
+        for i in 0..100 {
+            index.insert(&Constant::Int(5), &RID::new(i, 1))?;
+            if i % 5 == 0 {
+                index.insert(&Constant::Int(3), &RID::new(1000 + i, 1))?;
+                index.insert(&Constant::Int(8), &RID::new(2000 + i, 1))?;
+            }
+        }
+        assert_eq!(collect_key(&mut index, 5)?, (0..100).collect::<Vec<_>>());
+
+        // deleting the first entries found empties the primary page of its copies of the key
+        index.before_first(&Constant::Int(5))?;
+        let mut deleted = Vec::new();
+        while deleted.len() < 40 && index.next()? {
+            deleted.push(index.get_data_rid()?);
+        }
+        for rid in &deleted {
+            index.delete(&Constant::Int(5), rid)?;
+        }
+        let mut expected: Vec<_> = (0..100)
+            .filter(|i| !deleted.contains(&RID::new(*i, 1)))
+            .collect();
+        assert_eq!(collect_key(&mut index, 5)?, expected);
+
+        // the overflow chain stays reachable for new entries of the key and smaller keys
+        for i in 100..120 {
+            index.insert(&Constant::Int(5), &RID::new(i, 1))?;
+            index.insert(&Constant::Int(4), &RID::new(3000 + i, 1))?;
+            expected.push(i);
+        }
+        assert_eq!(collect_key(&mut index, 5)?, expected);
+        assert_eq!(
+            collect_key(&mut index, 4)?,
+            (3100..3120).collect::<Vec<_>>()
+        );
+
+        for block in collect_key(&mut index, 5)? {
+            index.delete(&Constant::Int(5), &RID::new(block, 1))?;
+        }
+        assert!(collect_key(&mut index, 5)?.is_empty());
+        assert!(index.delete(&Constant::Int(5), &RID::new(0, 1)).is_err());
+        assert_eq!(collect_key(&mut index, 3)?.len(), 20);
+        assert_eq!(collect_key(&mut index, 8)?.len(), 20);
+        let all = collect_range(&mut index, Bound::Unbounded, Bound::Unbounded)?;
+        assert_eq!(all.len(), 60);
+        Ok(())
+    }
+
+    fn collect_range(
+        index: &mut BTreeIndex,
+        lower: Bound<&Constant>,
+        upper: Bound<&Constant>,
+    ) -> DbResult<Vec<i32>> {
+        index.seek(lower, upper)?;
+        let mut blocks = Vec::new();
+        while index.next()? {
+            blocks.push(index.get_data_rid()?.block_number());
+        }
+        blocks.sort();
+        Ok(blocks)
+    }
+
+    #[test]
+    fn test_range_scan() -> DbResult<()> {
+        let db = temp_db_with_cfg(|cfg| cfg.block_size(400))?;
+        let mut index = setup_index(&db)?;
+
+        // keys are inserted shuffled so that leaves split all over the tree,
+        // the block number of each RID is the key itself
+        let mut keys: Vec<i32> = (0..1000).collect();
+        keys.shuffle(&mut rand::rng());
+        for &key in &keys {
+            index.insert(&Constant::Int(key), &RID::new(key, 0))?;
+        }
+
+        let (lo, hi) = (Constant::Int(100), Constant::Int(200));
+        assert_eq!(
+            collect_range(&mut index, Bound::Included(&lo), Bound::Included(&hi))?,
+            (100..=200).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            collect_range(&mut index, Bound::Excluded(&lo), Bound::Excluded(&hi))?,
+            (101..200).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            collect_range(&mut index, Bound::Excluded(&hi), Bound::Unbounded)?,
+            (201..1000).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            collect_range(&mut index, Bound::Unbounded, Bound::Excluded(&lo))?,
+            (0..100).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            collect_range(&mut index, Bound::Unbounded, Bound::Unbounded)?.len(),
+            1000
+        );
+        assert!(collect_range(&mut index, Bound::Included(&hi), Bound::Excluded(&lo))?.is_empty());
+
+        // an equality lookup still works after a range scan
+        index.before_first(&lo)?;
+        assert!(index.next()?);
+        assert_eq!(index.get_data_rid()?, RID::new(100, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_scan_with_overflow_pages() -> DbResult<()> {
+        let db = temp_db()?;
+        let mut index = setup_index(&db)?;
+
+        for i in 0..300 {
+            index.insert(&Constant::Int(i % 3), &RID::new(i, 1))?;
+        }
+        for i in 300..310 {
+            index.insert(&Constant::Int(i), &RID::new(i, 1))?;
+        }
+
+        let (lo, hi) = (Constant::Int(1), Constant::Int(302));
+        let mut expected: Vec<_> = (0..300).filter(|i| i % 3 != 0).collect();
+        expected.extend(300..=302);
+        assert_eq!(
+            collect_range(&mut index, Bound::Included(&lo), Bound::Included(&hi))?,
+            expected
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bulk_load() -> DbResult<()> {
+        let db = temp_db_with_cfg(|cfg| cfg.block_size(400))?;
+        let mut index = setup_index(&db)?;
+
+        // 5 entries for most keys and far more than fit in a leaf for key 500
+        let mut entries = Vec::new();
+        for i in 0..10000 {
+            entries.push((Constant::Int(i % 2000), RID::new(i, 0)));
+        }
+        for i in 10000..10300 {
+            entries.push((Constant::Int(500), RID::new(i, 0)));
+        }
+        entries.shuffle(&mut rand::rng());
+        index.bulk_load(entries)?;
+
+        for key in [0, 1, 499, 500, 501, 1999] {
+            index.before_first(&Constant::Int(key))?;
+            let mut found = Vec::new();
+            while index.next()? {
+                found.push(index.get_data_rid()?.block_number());
+            }
+            found.sort();
+            let mut expected: Vec<_> = (0..10000).filter(|i| i % 2000 == key).collect();
+            if key == 500 {
+                expected.extend(10000..10300);
+            }
+            assert_eq!(found, expected, "key {}", key);
+        }
+        index.before_first(&Constant::Int(2000))?;
+        assert!(!index.next()?);
+
+        let (lo, hi) = (Constant::Int(490), Constant::Int(510));
+        assert_eq!(
+            collect_range(&mut index, Bound::Included(&lo), Bound::Included(&hi))?.len(),
+            21 * 5 + 300
+        );
+        assert_eq!(
+            collect_range(&mut index, Bound::Unbounded, Bound::Unbounded)?.len(),
+            10300
+        );
+
+        // the loaded tree keeps working with regular inserts and deletes
+        for i in 0..500 {
+            index.insert(&Constant::Int(-i), &RID::new(20000 + i, 0))?;
+        }
+        index.delete(&Constant::Int(500), &RID::new(10299, 0))?;
+        index.before_first(&Constant::Int(-499))?;
+        assert!(index.next()?);
+        assert_eq!(index.get_data_rid()?, RID::new(20499, 0));
+        assert_eq!(
+            collect_range(&mut index, Bound::Unbounded, Bound::Unbounded)?.len(),
+            10799
+        );
+
+        // loading into an index with entries falls back to inserts
+        index.bulk_load(vec![(Constant::Int(5000), RID::new(30000, 0))])?;
+        index.before_first(&Constant::Int(5000))?;
+        assert!(index.next()?);
+        assert_eq!(index.get_data_rid()?, RID::new(30000, 0));
+        Ok(())
+    }
+
     #[test]
     fn test_delete() -> DbResult<()> {
         let db = temp_db()?;
@@ -18,6 +18,8 @@ pub struct BTreeLeaf<'tx> {
     search_key: Constant,
     contents: BTreePage<'tx>,
     current_slot: Option<usize>,
+    // the page whose overflow page is the current page, None while on the primary page
+    previous: Option<BTreePage<'tx>>,
     file_name: String,
 }
 
@@ -48,40 +50,74 @@ impl<'tx> BTreeLeaf<'tx> {
             search_key,
             contents,
             current_slot,
+            previous: None,
             file_name,
         })
     }
 
     /// Advances to the next record that matches the search key
-    /// If we've reached the end of the current page, attempts to follow the overflow chain
+    /// If we've reached the end of the current page, follows the overflow chain to its end
     /// Returns Some(()) if a matching record is found, None otherwise
     pub fn next(&mut self) -> DbResult<Option<()>> {
-        self.current_slot = {
-            match self.current_slot {
-                Some(slot) => Some(slot + 1),
-                None => Some(0),
+        loop {
+            let slot = self.current_slot.map_or(0, |slot| slot + 1);
+            self.current_slot = Some(slot);
+            if slot < self.contents.get_number_of_recs()?
+                && self.contents.get_data_value(slot)? == self.search_key
+            {
+                return Ok(Some(()));
+            }
+            if !self.try_overflow()? {
+                return Ok(None);
             }
-        };
-        if self.current_slot.unwrap() >= self.contents.get_number_of_recs()? {
-            return self.try_overflow();
-        } else if self.contents.get_data_value(self.current_slot.unwrap())? == self.search_key {
-            return Ok(Some(()));
-        } else {
-            return self.try_overflow();
         }
     }
 
     /// Deletes the record with the specified RID from this leaf page or its overflow chain
     /// Returns Ok(()) if the record was found and deleted, error otherwise
-    /// Requires that current_slot is initialized
+    /// An overflow page emptied by the delete is unlinked from the chain, and a primary page
+    /// which loses its last entry with the key of its chain gets one back from the chain
     pub fn delete(&mut self, rid: RID) -> DbResult<()> {
-        while let Some(_) = self.next()? {
-            if self.contents.get_rid(self.current_slot.unwrap())? == rid {
-                self.contents.delete(self.current_slot.unwrap())?;
-                return Ok(());
+        while self.next()?.is_some() {
+            let slot = self.current_slot.unwrap();
+            if self.contents.get_rid(slot)? != rid {
+                continue;
+            }
+            let first_key = self.contents.get_data_value(0)?;
+            self.contents.delete(slot)?;
+            match &self.previous {
+                Some(previous) => {
+                    if self.contents.get_number_of_recs()? == 0 {
+                        previous.set_flag(self.contents.get_flag()?)?;
+                    }
+                }
+                None => self.refill_first_key(first_key)?,
             }
+            return Ok(());
+        }
+        Err(crate::DbError::NotFound)
+    }
+
+    /// Moves an entry of the overflow chain to the first slot of the primary page if the page
+    /// no longer starts with the key of the chain, which is how the chain is found.
+    fn refill_first_key(&self, first_key: Constant) -> DbResult<()> {
+        let PageType::Leaf(Some(overflow_block_num)) = self.contents.get_flag()? else {
+            return Ok(());
+        };
+        if self.contents.get_number_of_recs()? > 0 && self.contents.get_data_value(0)? == first_key
+        {
+            return Ok(());
+        }
+
+        let overflow = self.page(overflow_block_num)?;
+        let last = overflow.get_number_of_recs()? - 1;
+        self.contents
+            .insert_leaf(0, first_key, overflow.get_rid(last)?)?;
+        overflow.delete(last)?;
+        if last == 0 {
+            self.contents.set_flag(overflow.get_flag()?)?;
         }
-        return Err(crate::DbError::NotFound);
+        Ok(())
     }
 
     /// This method will attempt to insert an entry into a [BTreeLeaf] page
@@ -95,6 +131,7 @@ impl<'tx> BTreeLeaf<'tx> {
         {
             let first_entry = self.contents.get_data_value(0)?;
             let new_block_id = self.contents.split(0, self.contents.get_flag()?)?;
+            self.link_sibling(&new_block_id)?;
             self.current_slot = Some(0);
             self.contents.set_flag(PageType::Leaf(None))?;
             self.contents.insert_leaf(0, self.search_key.clone(), rid)?;
@@ -153,6 +190,7 @@ impl<'tx> BTreeLeaf<'tx> {
             }
         }
         let new_block_id = self.contents.split(split_point, PageType::Leaf(None))?;
+        self.link_sibling(&new_block_id)?;
 
         Ok(Some(InternalNodeEntry {
             dataval: split_record,
@@ -160,29 +198,42 @@ impl<'tx> BTreeLeaf<'tx> {
         }))
     }
 
-    /// This method will check to see if an overflow page is present for this block
-    /// An overflow page for a specific page will contain entries that are the same as the first key of the current page
-    /// If no overflow page can be found, just return. Otherwise swap out the current contents for the overflow contents
-    fn try_overflow(&mut self) -> DbResult<Option<()>> {
-        let first_key = self.contents.get_data_value(0)?;
+    /// Links a page split off to the right of this leaf into the leaf chain
+    /// Overflow pages are reached through their primary page and are never linked
+    fn link_sibling(&self, new_block_id: &BlockId) -> DbResult<()> {
+        let new_page = BTreePage::new(self.tx.clone(), new_block_id.clone(), self.layout.clone())?;
+        new_page.set_sibling(self.contents.get_sibling()?)?;
+        self.contents
+            .set_sibling(Some(new_block_id.number() as usize))
+    }
 
-        if first_key != self.search_key
-            || !matches!(self.contents.get_flag()?, PageType::Leaf(Some(_)))
+    /// This method will check to see if an overflow page is present for this block
+    /// The overflow chain of a primary page holds entries with the same key as the first key of the primary page
+    /// If the chain doesn't hold the search key or ends here, return false. Otherwise swap out the current contents for the overflow contents
+    fn try_overflow(&mut self) -> DbResult<bool> {
+        let PageType::Leaf(Some(overflow_block_num)) = self.contents.get_flag()? else {
+            return Ok(false);
+        };
+        if self.previous.is_none()
+            && (self.contents.get_number_of_recs()? == 0
+                || self.contents.get_data_value(0)? != self.search_key)
         {
-            return Ok(None);
+            return Ok(false);
         }
 
-        let PageType::Leaf(Some(overflow_block_num)) = self.contents.get_flag()? else {
-            return Ok(None);
-        };
+        let overflow_contents = self.page(overflow_block_num)?;
+        self.previous = Some(std::mem::replace(&mut self.contents, overflow_contents));
+        // the overflow page is read from its first entry on
+        self.current_slot = None;
+        Ok(true)
+    }
 
-        let overflow_contents = BTreePage::new(
+    fn page(&self, block_num: usize) -> DbResult<BTreePage<'tx>> {
+        BTreePage::new(
             self.tx.clone(),
-            BlockId::new(self.file_name.clone(), overflow_block_num as i32),
+            BlockId::new(self.file_name.clone(), block_num as i32),
             self.layout.clone(),
-        )?;
-        self.contents = overflow_contents;
-        Ok(Some(()))
+        )
     }
 
     pub fn get_data_rid(&self) -> DbResult<RID> {
@@ -1,5 +1,5 @@
 use crate::{
-    DbResult,
+    DbError, DbResult,
     metadata::IndexInfo,
     query::Constant,
     record::{Layout, RID, schema::FieldType},
@@ -60,6 +60,9 @@ pub struct BTreePage<'tx> {
 
 impl<'tx> BTreePage<'tx> {
     const INT_BYTES: usize = 4;
+    /// The header holds the page flag, the record count and the right sibling of a leaf
+    const HEADER_SIZE: usize = 3 * Self::INT_BYTES;
+    const NO_SIBLING: i32 = -1;
 
     // Column name constants
     // const DATA_VAL_COLUMN: &'static str = "dataval";
@@ -106,6 +109,11 @@ impl<'tx> BTreePage<'tx> {
         Ok(self.slot_pos(current_records + 1) > self.tx.block_size())
     }
 
+    /// Returns the number of records the page can hold without being full
+    pub fn capacity(&self) -> usize {
+        (self.tx.block_size() - Self::HEADER_SIZE) / self.layout.slot_size() - 1
+    }
+
     /// This method splits the existing [BTreePage] and moves the records from [slot..]
     /// into a new page and then returns the [BlockId] of the new page
     /// The current page continues to be the same, but with fewer records
@@ -132,24 +140,33 @@ impl<'tx> BTreePage<'tx> {
         Ok(block_id)
     }
 
-    /// Formats a new page by initializing its flag and record count
+    /// Formats a new page by initializing its flag, record count and sibling
     /// Sets all record slots to their zero values based on field types
     pub fn format(&self, page_type: PageType) -> DbResult<()> {
         self.tx.set_int(&self.block_id, 0, page_type.into(), true)?;
         self.tx.set_int(&self.block_id, Self::INT_BYTES, 0, true)?;
-        let record_size = self.layout.slot_size();
-        for i in ((2 * Self::INT_BYTES)..self.tx.block_size()).step_by(record_size) {
+        self.set_sibling(None)?;
+        let mut slot = 0;
+        while self.slot_pos(slot + 1) <= self.tx.block_size() {
             for field in self.layout.schema().fields() {
+                let pos = self.field_position(slot, field);
                 let field_type = self.layout.schema().field_type(field).unwrap();
                 match field_type {
-                    FieldType::Integer => {
-                        self.tx.set_int(&self.block_id, i, 0, false)?;
+                    FieldType::Integer | FieldType::Boolean | FieldType::Date => {
+                        self.tx.set_int(&self.block_id, pos, 0, false)?;
+                    }
+                    FieldType::BigInt | FieldType::Double | FieldType::Timestamp => {
+                        self.tx.set_long(&self.block_id, pos, 0, false)?;
                     }
                     FieldType::Varchar => {
-                        self.tx.set_string(&self.block_id, i, "", false)?;
+                        self.tx.set_string(&self.block_id, pos, "", false)?;
+                    }
+                    FieldType::Text | FieldType::Blob => {
+                        unreachable!("TEXT and BLOB fields are not indexed")
                     }
                 }
             }
+            slot += 1;
         }
         Ok(())
     }
@@ -164,6 +181,20 @@ impl<'tx> BTreePage<'tx> {
         self.tx.set_int(&self.block_id, 0, value.into(), true)
     }
 
+    /// Retrieves the block number of the leaf to the right of this one
+    /// Overflow pages and internal pages have no sibling
+    pub fn get_sibling(&self) -> DbResult<Option<usize>> {
+        let sibling = self.tx.get_int(&self.block_id, 2 * Self::INT_BYTES)?;
+        Ok((sibling != Self::NO_SIBLING).then_some(sibling as usize))
+    }
+
+    /// Updates the block number of the leaf to the right of this one
+    pub fn set_sibling(&self, sibling: Option<usize>) -> DbResult<()> {
+        let value = sibling.map_or(Self::NO_SIBLING, |block_num| block_num as i32);
+        self.tx
+            .set_int(&self.block_id, 2 * Self::INT_BYTES, value, true)
+    }
+
     /// Gets the data value at the specified slot
     pub fn get_data_value(&self, slot: usize) -> DbResult<Constant> {
         let value = self.get_value(slot, IndexInfo::DATA_FIELD)?;
@@ -285,18 +316,23 @@ impl<'tx> BTreePage<'tx> {
             .field_type(field_name)
             .ok_or_else(|| format!("Field {} not found in schema", field_name))
             .unwrap();
-        match field_type {
-            FieldType::Integer => {
-                let value = self.get_int(slot, field_name)?;
-                Ok(Constant::Int(value))
-            }
-            FieldType::Varchar => {
-                let value = self.get_string(slot, field_name)?;
-                Ok(Constant::String(value))
+        let pos = self.field_position(slot, field_name);
+        Ok(match field_type {
+            FieldType::Integer => Constant::Int(self.get_int(slot, field_name)?),
+            FieldType::Varchar => Constant::String(self.get_string(slot, field_name)?),
+            FieldType::BigInt => Constant::BigInt(self.tx.get_long(&self.block_id, pos)?),
+            FieldType::Boolean => Constant::Bool(self.tx.get_bool(&self.block_id, pos)?),
+            FieldType::Double => Constant::Double(self.tx.get_double(&self.block_id, pos)?),
+            FieldType::Date => Constant::Date(self.get_int(slot, field_name)?),
+            FieldType::Timestamp => Constant::Timestamp(self.tx.get_long(&self.block_id, pos)?),
+            FieldType::Text | FieldType::Blob => {
+                unreachable!("TEXT and BLOB fields are not indexed")
             }
-        }
+        })
     }
 
+    /// Stores the value converted to the type of the field, so that an integer key
+    /// can go into an index on a wider numeric field.
     fn set_value(&self, slot: usize, field_name: &str, value: Constant) -> DbResult<()> {
         let expected_type = self
             .layout
@@ -305,13 +341,22 @@ impl<'tx> BTreePage<'tx> {
             .ok_or_else(|| format!("Field {} not found in schema", field_name))
             .unwrap();
 
-        // Check if value type matches schema
-        match (expected_type, &value) {
-            (FieldType::Integer, Constant::Int(v)) => self.set_int(slot, field_name, *v),
-            (FieldType::Varchar, Constant::String(v)) => {
-                self.set_string(slot, field_name, v.clone())
+        let pos = self.field_position(slot, field_name);
+        match value.coerce_to(expected_type) {
+            Some(Constant::Int(v)) | Some(Constant::Date(v)) => self.set_int(slot, field_name, v),
+            Some(Constant::String(v)) => self.set_string(slot, field_name, v),
+            Some(Constant::BigInt(v)) | Some(Constant::Timestamp(v)) => {
+                self.tx.set_long(&self.block_id, pos, v, true)
             }
-            _ => Err(crate::DbError::LockAbort), // TODO
+            Some(Constant::Bool(v)) => self.tx.set_bool(&self.block_id, pos, v, true),
+            Some(Constant::Double(v)) => self.tx.set_double(&self.block_id, pos, v, true),
+            Some(Constant::Bytes(_)) => unreachable!("TEXT and BLOB fields are not indexed"),
+            Some(Constant::Null) | None => Err(DbError::Schema(format!(
+                "Key {} does not fit field {} of type {}",
+                value.to_string(),
+                field_name,
+                expected_type
+            ))),
         }
     }
 
@@ -322,7 +367,7 @@ impl<'tx> BTreePage<'tx> {
 
     /// Calculates the starting byte position of a record slot
     fn slot_pos(&self, slot: usize) -> usize {
-        Self::INT_BYTES + Self::INT_BYTES + slot * self.layout.slot_size()
+        Self::HEADER_SIZE + slot * self.layout.slot_size()
     }
 
     pub fn block_id(&self) -> &BlockId {
@@ -412,6 +457,10 @@ mod tests {
 
         assert_eq!(page.get_flag()?, PageType::Leaf(None));
         assert_eq!(page.get_number_of_recs()?, 0);
+        assert_eq!(page.get_sibling()?, None);
+
+        page.set_sibling(Some(3))?;
+        assert_eq!(page.get_sibling()?, Some(3));
         Ok(())
     }
 
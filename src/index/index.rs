@@ -1,3 +1,5 @@
+use std::ops::Bound;
+
 use crate::{DbResult, query::Constant, record::RID};
 
 pub trait Index {
@@ -8,6 +10,10 @@ pub trait Index {
     /// Returns false if there are no more index records with that search key
     fn next(&mut self) -> DbResult<bool>;
 
+    /// Position the index before the first record whose search key lies between the bounds.
+    /// Subsequent calls to next move through all such records
+    fn seek(&mut self, lower: Bound<&Constant>, upper: Bound<&Constant>) -> DbResult<()>;
+
     /// Get the RID stored in the current index record
     fn get_data_rid(&self) -> DbResult<RID>;
 
@@ -1,9 +1,11 @@
+pub mod btree_cursor;
 pub mod btree_index;
 pub mod btree_internal;
 pub mod btree_leaf;
 pub mod btree_page;
 pub mod index;
 
+pub use btree_cursor::BTreeRangeCursor;
 pub use btree_index::BTreeIndex;
 pub use btree_page::BTreePage;
 pub use index::Index;
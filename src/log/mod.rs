@@ -1,16 +1,26 @@
 use std::io;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::storage::BlockId;
 use crate::storage::StorageMgr;
 use crate::storage::page::Page;
 
 /// Manages the database log, which is used for recovery.
+/// The log is split into numbered segment files `<log_file>.<n>`, so that segments
+/// no longer needed for recovery can be deleted. The file `<log_file>` itself is a
+/// control block holding the numbers of the first and the current segment.
 /// It employs interrior mutability and also is thread-safe
 pub struct LogMgr {
     storage_mgr: Arc<dyn StorageMgr>,
     log_file: String,
+    segment_blocks: i32,
+    durability: Durability,
     inner: Mutex<LogMgrInner>,
+    /// Signalled by the group commit leader once its sync is done.
+    synced: Condvar,
+    /// Signalled when a flush joins the batch of the group commit leader.
+    joined: Condvar,
 }
 
 struct LogMgrInner {
@@ -18,45 +28,134 @@ struct LogMgrInner {
     current_blk: BlockId,
     latest_lsn: i32,
     last_saved_lsn: i32,
+    last_synced_lsn: i32,
+    /// The highest LSN a grouped flush is waiting for.
+    requested_lsn: i32,
+    sync_in_progress: bool,
+    first_segment: i32,
+    current_segment: i32,
+    /// False after the control block was written or a segment created, until both are synced.
+    control_synced: bool,
+}
+
+/// How hard `LogMgr::flush` tries to make the log survive a power loss.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Durability {
+    /// Log pages are handed to the OS but never synced.
+    None,
+    /// Every flush syncs the log file before returning.
+    PerCommit,
+    /// Concurrent flushes are batched into a single sync. The first flusher waits up to
+    /// `max_delay` for the flushes of the records appended by others, then syncs on behalf
+    /// of all of them. It doesn't wait when every appended record is already asked for.
+    Grouped { max_delay: Duration },
 }
 
 impl LogMgr {
+    /// Number of blocks in a log segment unless configured otherwise.
+    pub const DEFAULT_SEGMENT_BLOCKS: usize = 256;
+
+    /// Offset of the boundary, i.e. the position of the most recently written record in a block.
+    const BOUNDARY_OFFSET: usize = 0;
+    /// Offset of the LSN of the latest record appended up to and including a block.
+    /// It lets LSNs survive restarts and lets the iterator number the records it returns.
+    const LAST_LSN_OFFSET: usize = 4;
+    const HEADER_SIZE: usize = 8;
+
+    /// Offsets in the control block.
+    const FIRST_SEGMENT_OFFSET: usize = 0;
+    const CURRENT_SEGMENT_OFFSET: usize = 4;
+
     pub fn new(storage_mgr: Arc<dyn StorageMgr>, log_file: &str) -> io::Result<Self> {
-        let block_size = storage_mgr.block_size();
-        let mut log_page = Page::new(block_size);
-        log_page.set_int(0, storage_mgr.block_size() as i32);
+        Self::with_segment_blocks(storage_mgr, log_file, Self::DEFAULT_SEGMENT_BLOCKS)
+    }
 
-        let block_cnt = storage_mgr.block_cnt(log_file)?;
-        let current_blk = if block_cnt == 0 {
-            Self::append_new_block(&*storage_mgr, log_file)?
+    pub fn with_segment_blocks(
+        storage_mgr: Arc<dyn StorageMgr>,
+        log_file: &str,
+        segment_blocks: usize,
+    ) -> io::Result<Self> {
+        assert!(segment_blocks > 0, "Log segment can't be empty");
+        let block_size = storage_mgr.block_size();
+        let mut log_page = Self::new_log_page(block_size, 0);
+
+        let is_new_log = storage_mgr.block_cnt(log_file)? == 0;
+        let (first_segment, current_segment, current_blk) = if is_new_log {
+            let blk = Self::append_new_block(&*storage_mgr, &segment_file(log_file, 0), 0)?;
+            storage_mgr.append(log_file)?;
+            Self::write_control(&*storage_mgr, log_file, 0, 0)?;
+            (0, 0, blk)
         } else {
-            let blk = BlockId::new(log_file.to_string(), block_cnt - 1);
+            let mut control = Page::new(block_size);
+            storage_mgr.read(&Self::control_blk(log_file), &mut control)?;
+            let first_segment = control.get_int(Self::FIRST_SEGMENT_OFFSET);
+            let current_segment = control.get_int(Self::CURRENT_SEGMENT_OFFSET);
+
+            let file = segment_file(log_file, current_segment);
+            let blk = BlockId::new(file.clone(), storage_mgr.block_cnt(&file)? - 1);
             storage_mgr.read(&blk, &mut log_page)?;
-            blk
+            (first_segment, current_segment, blk)
         };
+        let latest_lsn = log_page.get_int(Self::LAST_LSN_OFFSET);
 
         Ok(LogMgr {
             storage_mgr,
             log_file: log_file.to_string(),
+            segment_blocks: segment_blocks as i32,
+            durability: Durability::PerCommit,
             inner: Mutex::new(LogMgrInner {
                 log_page,
                 current_blk,
-                latest_lsn: 0,
-                last_saved_lsn: 0,
+                latest_lsn,
+                last_saved_lsn: latest_lsn,
+                last_synced_lsn: latest_lsn,
+                requested_lsn: latest_lsn,
+                sync_in_progress: false,
+                first_segment,
+                current_segment,
+                control_synced: !is_new_log,
             }),
+            synced: Condvar::new(),
+            joined: Condvar::new(),
         })
     }
 
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    fn new_log_page(block_size: usize, last_lsn: i32) -> Page {
+        let mut log_page = Page::new(block_size);
+        log_page.set_int(Self::BOUNDARY_OFFSET, block_size as i32);
+        log_page.set_int(Self::LAST_LSN_OFFSET, last_lsn);
+        log_page
+    }
+
     // Helper method to append a new block to the log file
-    fn append_new_block(fm: &dyn StorageMgr, log_file: &str) -> io::Result<BlockId> {
+    fn append_new_block(fm: &dyn StorageMgr, log_file: &str, last_lsn: i32) -> io::Result<BlockId> {
         let blk = fm.append(log_file)?;
-        let blocksize = fm.block_size();
-        let mut logpage = Page::new(blocksize);
-        logpage.set_int(0, blocksize as i32);
+        let logpage = Self::new_log_page(fm.block_size(), last_lsn);
         fm.write(&blk, &logpage)?;
         Ok(blk)
     }
 
+    fn control_blk(log_file: &str) -> BlockId {
+        BlockId::new(log_file.to_string(), 0)
+    }
+
+    fn write_control(
+        fm: &dyn StorageMgr,
+        log_file: &str,
+        first_segment: i32,
+        current_segment: i32,
+    ) -> io::Result<()> {
+        let mut control = Page::new(fm.block_size());
+        control.set_int(Self::FIRST_SEGMENT_OFFSET, first_segment);
+        control.set_int(Self::CURRENT_SEGMENT_OFFSET, current_segment);
+        fm.write(&Self::control_blk(log_file), &control)
+    }
+
     /// Writes the current log page to disk.
     fn flush_internal(&self, inner: &mut LogMgrInner) -> io::Result<()> {
         self.storage_mgr
@@ -65,80 +164,266 @@ impl LogMgr {
         Ok(())
     }
 
+    /// Writes the current log page to disk and syncs the current segment file.
+    fn sync_internal(&self, inner: &mut LogMgrInner) -> io::Result<()> {
+        self.flush_internal(inner)?;
+        self.storage_mgr.sync(inner.current_blk.file_name())?;
+        self.sync_control(inner)?;
+        inner.last_synced_lsn = inner.latest_lsn;
+        Ok(())
+    }
+
+    /// Syncs the control block and the directory listing the segments, unless
+    /// they haven't changed since the last time.
+    fn sync_control(&self, inner: &mut LogMgrInner) -> io::Result<()> {
+        if !inner.control_synced {
+            self.storage_mgr.sync(&self.log_file)?;
+            self.storage_mgr.sync_dir()?;
+            inner.control_synced = true;
+        }
+        Ok(())
+    }
+
+    /// Makes sure that the record with the given LSN (and all before it) is written
+    /// to disk, as durably as the configured `Durability` requires.
     pub fn flush(&self, lsn: i32) -> io::Result<()> {
         let mut inner: std::sync::MutexGuard<'_, LogMgrInner> = self.inner.lock().unwrap();
-        if lsn >= inner.last_saved_lsn {
-            return self.flush_internal(&mut inner);
+        match self.durability {
+            Durability::None => {
+                if lsn >= inner.last_saved_lsn {
+                    return self.flush_internal(&mut inner);
+                }
+                Ok(())
+            }
+            Durability::PerCommit => {
+                if lsn > inner.last_synced_lsn {
+                    return self.sync_internal(&mut inner);
+                }
+                Ok(())
+            }
+            Durability::Grouped { max_delay } => {
+                if lsn > inner.requested_lsn {
+                    inner.requested_lsn = lsn;
+                    self.joined.notify_all();
+                }
+                while lsn > inner.last_synced_lsn {
+                    if inner.sync_in_progress {
+                        inner = self.synced.wait(inner).unwrap();
+                        continue;
+                    }
+
+                    // become the leader: let the committers which appended records join
+                    // the batch, then sync for all
+                    inner.sync_in_progress = true;
+                    let deadline = Instant::now() + max_delay;
+                    while inner.requested_lsn < inner.latest_lsn {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            break;
+                        }
+                        inner = self.joined.wait_timeout(inner, deadline - now).unwrap().0;
+                    }
+
+                    // the records appended during the sync are left for the next batch
+                    let synced_lsn = inner.latest_lsn;
+                    let file_name = inner.current_blk.file_name().to_string();
+                    let mut result = self.flush_internal(&mut inner);
+                    if result.is_ok() {
+                        drop(inner);
+                        result = self.storage_mgr.sync(&file_name);
+                        inner = self.inner.lock().unwrap();
+                    }
+                    let result = result.and_then(|_| self.sync_control(&mut inner));
+                    if result.is_ok() {
+                        inner.last_synced_lsn = inner.last_synced_lsn.max(synced_lsn);
+                    }
+                    inner.sync_in_progress = false;
+                    self.synced.notify_all();
+                    result?;
+                }
+                Ok(())
+            }
         }
-        Ok(())
+    }
+
+    /// Returns the size of the largest record which fits into a log block.
+    pub fn max_record_size(&self) -> usize {
+        self.storage_mgr.block_size() - Self::HEADER_SIZE - std::mem::size_of::<i32>()
     }
 
     /// Appends a log record to the log.
     /// Returns the LSN (Log Sequence Number) of the appended record.
+    /// LSNs keep increasing across restarts.
     /// This method is thread-safe.
     pub fn append(&self, record: &[u8]) -> io::Result<i32> {
         let mut inner = self.inner.lock().unwrap();
 
-        let boundary = inner.log_page.get_int(0);
+        let boundary = inner.log_page.get_int(Self::BOUNDARY_OFFSET);
 
         let rec_size: usize = record.len();
         let bytes_needed = rec_size + std::mem::size_of::<i32>();
 
         // Check if there's enough space in the current block
-        if (boundary - bytes_needed as i32) < std::mem::size_of::<i32>() as i32 {
+        if (boundary - bytes_needed as i32) < Self::HEADER_SIZE as i32 {
             self.flush_internal(&mut inner)?;
+            self.move_to_new_block(&mut inner)?;
+        }
+
+        // Calculate position for the new record
+        let boundary = inner.log_page.get_int(Self::BOUNDARY_OFFSET);
+        let recpos = boundary - bytes_needed as i32;
 
-            inner.current_blk = Self::append_new_block(&*self.storage_mgr, &self.log_file)?;
-            inner.log_page = Page::new(self.storage_mgr.block_size());
-            inner
-                .log_page
-                .set_int(0, self.storage_mgr.block_size() as i32);
-            let boundary = inner.log_page.get_int(0);
-            let recpos = boundary - bytes_needed as i32;
-
-            // Write the record and update the boundary
-            inner.log_page.set_bytes(recpos as usize, record);
-            inner.log_page.set_int(0, recpos);
+        // Write the record and update the boundary
+        inner.log_page.set_bytes(recpos as usize, record);
+        inner.log_page.set_int(Self::BOUNDARY_OFFSET, recpos);
+
+        inner.latest_lsn += 1;
+        let latest_lsn = inner.latest_lsn;
+        inner.log_page.set_int(Self::LAST_LSN_OFFSET, latest_lsn);
+
+        Ok(latest_lsn)
+    }
+
+    /// Appends a new block to the current segment, or starts the next segment once
+    /// the current one is full.
+    fn move_to_new_block(&self, inner: &mut LogMgrInner) -> io::Result<()> {
+        let latest_lsn = inner.latest_lsn;
+
+        if inner.current_blk.number() + 1 < self.segment_blocks {
+            inner.current_blk = Self::append_new_block(
+                &*self.storage_mgr,
+                inner.current_blk.file_name(),
+                latest_lsn,
+            )?;
         } else {
-            // Calculate position for the new record
-            let recpos = boundary - bytes_needed as i32;
+            // records synced later go to the next file, so the finished segment is synced now
+            if self.durability != Durability::None {
+                self.storage_mgr.sync(inner.current_blk.file_name())?;
+            }
 
-            // Write the record and update the boundary
-            inner.log_page.set_bytes(recpos as usize, record);
-            inner.log_page.set_int(0, recpos);
+            // the segment must exist before the control block points to it
+            let segment = inner.current_segment + 1;
+            inner.current_blk = Self::append_new_block(
+                &*self.storage_mgr,
+                &segment_file(&self.log_file, segment),
+                latest_lsn,
+            )?;
+            Self::write_control(
+                &*self.storage_mgr,
+                &self.log_file,
+                inner.first_segment,
+                segment,
+            )?;
+            inner.current_segment = segment;
+            inner.control_synced = false;
         }
 
-        inner.latest_lsn += 1;
+        inner.log_page = Self::new_log_page(self.storage_mgr.block_size(), latest_lsn);
+        Ok(())
+    }
 
-        Ok(inner.latest_lsn)
+    /// Returns the LSN of the most recently appended record.
+    pub fn latest_lsn(&self) -> i32 {
+        self.inner.lock().unwrap().latest_lsn
+    }
+
+    /// Deletes the segments which only contain records with LSNs lower than the given one.
+    /// The current segment is never deleted.
+    pub fn truncate(&self, lsn: i32) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let mut first_segment = inner.first_segment;
+        let mut page = Page::new(self.storage_mgr.block_size());
+        while first_segment < inner.current_segment {
+            let file = segment_file(&self.log_file, first_segment);
+            let last_blk = BlockId::new(file.clone(), self.storage_mgr.block_cnt(&file)? - 1);
+            self.storage_mgr.read(&last_blk, &mut page)?;
+            if page.get_int(Self::LAST_LSN_OFFSET) >= lsn {
+                break;
+            }
+            first_segment += 1;
+        }
+
+        if first_segment == inner.first_segment {
+            return Ok(());
+        }
+
+        // the control block must stop pointing to the segments before they are deleted
+        Self::write_control(
+            &*self.storage_mgr,
+            &self.log_file,
+            first_segment,
+            inner.current_segment,
+        )?;
+        inner.control_synced = false;
+        if self.durability != Durability::None {
+            self.sync_control(&mut inner)?;
+        }
+        for segment in inner.first_segment..first_segment {
+            self.storage_mgr
+                .delete_file(&segment_file(&self.log_file, segment))?;
+        }
+        inner.first_segment = first_segment;
+        if self.durability != Durability::None {
+            self.storage_mgr.sync_dir()?;
+        }
+        Ok(())
     }
 
     /// Returns an iterator over all log records, starting with the most recent.
-    pub fn iterator(&self) -> io::Result<LogIterator> {
+    pub fn iterator(&self) -> io::Result<LogIterator<'_>> {
         let mut inner = self.inner.lock().unwrap();
         self.flush_internal(&mut inner)?;
-        LogIterator::new(&self.storage_mgr, inner.current_blk.clone())
+        LogIterator::new(
+            &self.storage_mgr,
+            &self.log_file,
+            inner.first_segment,
+            inner.current_segment,
+            inner.current_blk.clone(),
+        )
     }
 }
 
+/// Returns the name of the file holding the given log segment.
+fn segment_file(log_file: &str, segment: i32) -> String {
+    format!("{log_file}.{segment}")
+}
+
 /// An iterator over log records, starting from the most recent and moving backwards.
+/// It moves on to the previous segment once the first block of a segment is read.
 pub struct LogIterator<'a> {
     storage_mgr: &'a Arc<dyn StorageMgr>,
+    log_file: String,
+    first_segment: i32,
+    segment: i32,
     blk: BlockId,
     page: Page,
     current_pos: usize,
     boundary: usize,
+    next_lsn: i32,
+    current_lsn: i32,
 }
 
 impl<'a> LogIterator<'a> {
-    fn new(storage_mgr: &'a Arc<dyn StorageMgr>, blk: BlockId) -> io::Result<Self> {
+    fn new(
+        storage_mgr: &'a Arc<dyn StorageMgr>,
+        log_file: &str,
+        first_segment: i32,
+        segment: i32,
+        blk: BlockId,
+    ) -> io::Result<Self> {
         let page = Page::new(storage_mgr.block_size());
         let mut iter = LogIterator {
             storage_mgr,
+            log_file: log_file.to_string(),
+            first_segment,
+            segment,
             blk: blk.clone(),
             page,
             current_pos: 0,
             boundary: 0,
+            next_lsn: 0,
+            current_lsn: 0,
         };
         iter.move_to_block(&blk)?;
         Ok(iter)
@@ -146,27 +431,44 @@ impl<'a> LogIterator<'a> {
 
     fn move_to_block(&mut self, blk: &BlockId) -> io::Result<()> {
         self.storage_mgr.read(blk, &mut self.page)?;
-        self.boundary = self.page.get_int(0) as usize;
+        self.boundary = self.page.get_int(LogMgr::BOUNDARY_OFFSET) as usize;
         self.current_pos = self.boundary;
+        self.next_lsn = self.page.get_int(LogMgr::LAST_LSN_OFFSET);
         Ok(())
     }
 
     pub fn has_next(&self) -> bool {
-        self.current_pos < self.storage_mgr.block_size() || self.blk.number() > 0
+        self.current_pos < self.storage_mgr.block_size()
+            || self.blk.number() > 0
+            || self.segment > self.first_segment
     }
 
     pub fn next(&mut self) -> io::Result<Vec<u8>> {
-        if self.current_pos == self.storage_mgr.block_size() {
-            let new_blk = BlockId::new(self.blk.file_name().to_string(), self.blk.number() - 1);
+        while self.current_pos == self.storage_mgr.block_size() {
+            let new_blk = if self.blk.number() > 0 {
+                BlockId::new(self.blk.file_name().to_string(), self.blk.number() - 1)
+            } else {
+                self.segment -= 1;
+                let file = segment_file(&self.log_file, self.segment);
+                let block_cnt = self.storage_mgr.block_cnt(&file)?;
+                BlockId::new(file, block_cnt - 1)
+            };
             self.blk = new_blk.clone();
             self.move_to_block(&new_blk)?;
         }
 
         let record_bytes = self.page.get_bytes(self.current_pos);
         self.current_pos += std::mem::size_of::<i32>() + record_bytes.len();
+        self.current_lsn = self.next_lsn;
+        self.next_lsn -= 1;
 
         Ok(record_bytes)
     }
+
+    /// Returns the LSN of the record most recently returned by `next`.
+    pub fn current_lsn(&self) -> i32 {
+        self.current_lsn
+    }
 }
 
 impl Drop for LogMgr {
@@ -176,18 +478,106 @@ impl Drop for LogMgr {
             if inner.latest_lsn > inner.last_saved_lsn {
                 let _ = self.flush_internal(&mut inner);
             }
+            if self.durability != Durability::None {
+                let _ = self.storage_mgr.sync(inner.current_blk.file_name());
+                let _ = self.sync_control(&mut inner);
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{error::DbResult, storage::FileStorageMgr};
+    use crate::{
+        error::DbResult,
+        storage::{FileStorageMgr, MemStorageMgr},
+    };
 
     use super::*;
     use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use tempfile::TempDir;
 
+    /// Storage which counts how many times it was asked to sync.
+    struct SyncCountingStorageMgr {
+        inner: MemStorageMgr,
+        syncs: AtomicUsize,
+        dir_syncs: AtomicUsize,
+        sync_time: Duration,
+    }
+
+    impl SyncCountingStorageMgr {
+        fn new(block_size: usize) -> Self {
+            Self {
+                inner: MemStorageMgr::new(block_size),
+                syncs: AtomicUsize::new(0),
+                dir_syncs: AtomicUsize::new(0),
+                sync_time: Duration::ZERO,
+            }
+        }
+
+        /// Makes every sync take a while, like on a disk.
+        fn with_sync_time(block_size: usize, sync_time: Duration) -> Self {
+            Self {
+                sync_time,
+                ..Self::new(block_size)
+            }
+        }
+
+        fn syncs(&self) -> usize {
+            self.syncs.load(Ordering::SeqCst)
+        }
+
+        fn dir_syncs(&self) -> usize {
+            self.dir_syncs.load(Ordering::SeqCst)
+        }
+    }
+
+    impl StorageMgr for SyncCountingStorageMgr {
+        fn read(&self, blk: &BlockId, page: &mut Page) -> io::Result<()> {
+            self.inner.read(blk, page)
+        }
+
+        fn write(&self, blk: &BlockId, page: &Page) -> io::Result<()> {
+            self.inner.write(blk, page)
+        }
+
+        fn append(&self, filename: &str) -> io::Result<BlockId> {
+            self.inner.append(filename)
+        }
+
+        fn block_cnt(&self, filename: &str) -> io::Result<i32> {
+            self.inner.block_cnt(filename)
+        }
+
+        fn sync(&self, filename: &str) -> io::Result<()> {
+            self.syncs.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(self.sync_time);
+            self.inner.sync(filename)
+        }
+
+        fn sync_dir(&self) -> io::Result<()> {
+            self.dir_syncs.fetch_add(1, Ordering::SeqCst);
+            self.inner.sync_dir()
+        }
+
+        fn delete_file(&self, filename: &str) -> io::Result<()> {
+            self.inner.delete_file(filename)
+        }
+
+        fn rename_file(&self, from: &str, to: &str) -> io::Result<()> {
+            self.inner.rename_file(from, to)
+        }
+
+        fn is_new(&self) -> bool {
+            self.inner.is_new()
+        }
+
+        fn block_size(&self) -> usize {
+            self.inner.block_size()
+        }
+    }
+
     struct TestEnvironment {
         _temp_dir: TempDir,
         storage_mgr: Arc<dyn StorageMgr>,
@@ -234,6 +624,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_log_manager_lsn_survives_restart() -> DbResult<()> {
+        let env = TestEnvironment::new_with_block_size(100)?;
+
+        {
+            let log_mgr = LogMgr::new(Arc::clone(&env.storage_mgr), "lsnlog")?;
+            for i in 0..50 {
+                let lsn = log_mgr.append(format!("Record #{}", i).as_bytes())?;
+                assert_eq!(lsn, i + 1);
+            }
+        }
+
+        let log_mgr = LogMgr::new(Arc::clone(&env.storage_mgr), "lsnlog")?;
+        assert_eq!(log_mgr.latest_lsn(), 50);
+        assert_eq!(log_mgr.append(b"Record #50")?, 51);
+
+        let mut iter = log_mgr.iterator()?;
+        let mut expected_lsn = 51;
+        while iter.has_next() {
+            let rec = iter.next()?;
+            assert_eq!(iter.current_lsn(), expected_lsn);
+            assert_eq!(rec, format!("Record #{}", expected_lsn - 1).into_bytes());
+            expected_lsn -= 1;
+        }
+        assert_eq!(expected_lsn, 0);
+
+        Ok(())
+    }
+
     #[test]
     fn test_log_manager_multiple_records() -> DbResult<()> {
         let env = TestEnvironment::new()?;
@@ -298,6 +717,162 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_log_manager_crosses_segments() -> DbResult<()> {
+        let env = TestEnvironment::new_with_block_size(100)?;
+
+        {
+            let log_mgr = LogMgr::with_segment_blocks(Arc::clone(&env.storage_mgr), "seglog", 3)?;
+            for i in 0..100 {
+                log_mgr.append(format!("Record #{}", i).as_bytes())?;
+            }
+        }
+        assert!(env._temp_dir.path().join("seglog.0").exists());
+        assert!(env._temp_dir.path().join("seglog.5").exists());
+
+        let log_mgr = LogMgr::with_segment_blocks(Arc::clone(&env.storage_mgr), "seglog", 3)?;
+        for i in 100..200 {
+            assert_eq!(log_mgr.append(format!("Record #{}", i).as_bytes())?, i + 1);
+        }
+
+        let mut iter = log_mgr.iterator()?;
+        let mut expected_lsn = 200;
+        while iter.has_next() {
+            let rec = iter.next()?;
+            assert_eq!(iter.current_lsn(), expected_lsn);
+            assert_eq!(rec, format!("Record #{}", expected_lsn - 1).into_bytes());
+            expected_lsn -= 1;
+        }
+        assert_eq!(expected_lsn, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_manager_truncate() -> DbResult<()> {
+        let env = TestEnvironment::new_with_block_size(100)?;
+
+        {
+            let log_mgr = LogMgr::with_segment_blocks(Arc::clone(&env.storage_mgr), "seglog", 3)?;
+            for i in 0..200 {
+                log_mgr.append(format!("Record #{}", i).as_bytes())?;
+            }
+            log_mgr.truncate(150)?;
+        }
+        assert!(!env._temp_dir.path().join("seglog.0").exists());
+
+        let log_mgr = LogMgr::with_segment_blocks(Arc::clone(&env.storage_mgr), "seglog", 3)?;
+        let mut iter = log_mgr.iterator()?;
+        let mut expected_lsn = 200;
+        while iter.has_next() {
+            let rec = iter.next()?;
+            assert_eq!(iter.current_lsn(), expected_lsn);
+            assert_eq!(rec, format!("Record #{}", expected_lsn - 1).into_bytes());
+            expected_lsn -= 1;
+        }
+        // the segment holding record 150 is kept, the ones before it are gone
+        assert!(expected_lsn < 150);
+        assert!(expected_lsn > 100);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_manager_truncate_syncs_control_block() -> DbResult<()> {
+        let storage_mgr = Arc::new(SyncCountingStorageMgr::new(100));
+        let log_mgr = LogMgr::with_segment_blocks(
+            Arc::clone(&storage_mgr) as Arc<dyn StorageMgr>,
+            "seglog",
+            3,
+        )?;
+        for i in 0..200 {
+            log_mgr.append(format!("Record #{}", i).as_bytes())?;
+        }
+        let (syncs, dir_syncs) = (storage_mgr.syncs(), storage_mgr.dir_syncs());
+
+        log_mgr.truncate(150)?;
+        // the control block is synced before the segments are deleted, then the deletions
+        assert_eq!(storage_mgr.syncs(), syncs + 1);
+        assert_eq!(storage_mgr.dir_syncs(), dir_syncs + 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_manager_durability_levels() -> DbResult<()> {
+        // the first sync of a new log syncs its control block as well
+        for (durability, expected_syncs) in [(Durability::None, 0), (Durability::PerCommit, 4)] {
+            let storage_mgr = Arc::new(SyncCountingStorageMgr::new(400));
+            let log_mgr = LogMgr::new(Arc::clone(&storage_mgr) as Arc<dyn StorageMgr>, "testlog")?
+                .durability(durability);
+
+            for i in 0..3 {
+                let lsn = log_mgr.append(format!("Record #{}", i).as_bytes())?;
+                log_mgr.flush(lsn)?;
+                // already flushed records don't cause another sync
+                log_mgr.flush(lsn)?;
+            }
+            assert_eq!(storage_mgr.syncs(), expected_syncs);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_manager_group_commit() -> DbResult<()> {
+        use std::sync::Barrier;
+        use std::thread;
+
+        let storage_mgr = Arc::new(SyncCountingStorageMgr::with_sync_time(
+            400,
+            Duration::from_millis(20),
+        ));
+        let log_mgr = Arc::new(
+            LogMgr::new(Arc::clone(&storage_mgr) as Arc<dyn StorageMgr>, "testlog")?.durability(
+                Durability::Grouped {
+                    max_delay: Duration::from_millis(100),
+                },
+            ),
+        );
+
+        let thread_cnt = 8;
+        let barrier = Arc::new(Barrier::new(thread_cnt));
+        let handles: Vec<_> = (0..thread_cnt)
+            .map(|i| {
+                let log_mgr = Arc::clone(&log_mgr);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || -> io::Result<()> {
+                    barrier.wait();
+                    let lsn = log_mgr.append(format!("Commit #{}", i).as_bytes())?;
+                    log_mgr.flush(lsn)
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap()?;
+        }
+
+        let syncs = storage_mgr.syncs();
+        assert!(syncs >= 1);
+        assert!(syncs < thread_cnt, "{syncs} syncs for {thread_cnt} commits");
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_manager_group_commit_alone_does_not_wait() -> DbResult<()> {
+        let storage_mgr = Arc::new(SyncCountingStorageMgr::new(400));
+        let log_mgr = LogMgr::new(Arc::clone(&storage_mgr) as Arc<dyn StorageMgr>, "testlog")?
+            .durability(Durability::Grouped {
+                max_delay: Duration::from_secs(10),
+            });
+
+        // nobody else appended a record, so there is no batch to wait for
+        let start = Instant::now();
+        let lsn = log_mgr.append(b"Commit")?;
+        log_mgr.flush(lsn)?;
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(storage_mgr.syncs() >= 1);
+        Ok(())
+    }
+
     #[test]
     fn test_log_manager_persistence() -> DbResult<()> {
         let env = TestEnvironment::new()?;
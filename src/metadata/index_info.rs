@@ -1,6 +1,6 @@
 use crate::{
     index::BTreeIndex,
-    record::{Layout, Schema, schema::FieldType},
+    record::{Layout, Schema},
     tx::Transaction,
 };
 
@@ -40,15 +40,10 @@ impl IndexInfo {
         schema.add_int_field(IndexInfo::BLOCK_NUM_FIELD);
         schema.add_int_field(IndexInfo::ID_FIELD);
 
-        match table_schema.field_type(field_name).unwrap() {
-            FieldType::Integer => {
-                schema.add_int_field(IndexInfo::DATA_FIELD);
-            }
-            FieldType::Varchar => {
-                let field_len = table_schema.length(field_name).unwrap();
-                schema.add_string_field(IndexInfo::DATA_FIELD, field_len);
-            }
-        }
+        //  the key is stored with the type and length of the indexed field
+        let field_type = table_schema.field_type(field_name).unwrap();
+        let field_len = table_schema.length(field_name).unwrap();
+        schema.add_field(IndexInfo::DATA_FIELD, field_type, field_len);
         Layout::new(schema)
     }
 
@@ -1,7 +1,8 @@
 use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    DbResult,
+    DbError, DbResult,
+    index::Index,
     metadata::{IndexInfo, TableMgr},
     query::{Scan, UpdateScan},
     record::{Layout, Schema, TableScan},
@@ -40,6 +41,7 @@ impl IndexMgr {
         })
     }
 
+    /// Registers the index in the catalog and builds it from the records already in the table.
     pub fn create_index(
         &self,
         index_name: &str,
@@ -47,11 +49,106 @@ impl IndexMgr {
         field_name: &str,
         tx: Transaction<'_>,
     ) -> DbResult<()> {
+        let table_layout = self.table_mgr.get_layout(table_name, tx.clone())?;
+        if !table_layout.schema().has_field(field_name) {
+            return Err(DbError::FieldNotFound(field_name.to_string()));
+        }
+        if let Some(field_type) = table_layout.schema().field_type(field_name)
+            && field_type.is_overflow()
+        {
+            return Err(DbError::Schema(format!(
+                "{} field {} cannot be indexed",
+                field_type, field_name
+            )));
+        }
+
         let mut scan = TableScan::new(tx.clone(), IndexMgr::INDEX_TABLE, self.layout.clone())?;
         scan.insert()?;
         scan.set_string(IndexMgr::INDEX_NAME, index_name)?;
         scan.set_string(IndexMgr::TABLE_NAME, table_name)?;
         scan.set_string(IndexMgr::FIELD_NAME, field_name)?;
+
+        let index_info = IndexInfo::new(
+            index_name.to_string(),
+            field_name.to_string(),
+            table_layout.schema().clone(),
+        );
+        let mut entries = Vec::new();
+        let mut table_scan = TableScan::new(tx.clone(), table_name, table_layout)?;
+        while table_scan.next()? {
+            entries.push((table_scan.get_val(field_name)?, table_scan.get_rid()?));
+        }
+        let mut index = index_info.open(tx)?;
+        index.bulk_load(entries)?;
+        index.close();
+        Ok(())
+    }
+
+    /// Removes the index from the catalog and deletes its files once the transaction commits.
+    pub fn drop_index(&self, index_name: &str, tx: Transaction<'_>) -> DbResult<()> {
+        let mut found = false;
+        {
+            let mut scan = TableScan::new(tx.clone(), IndexMgr::INDEX_TABLE, self.layout.clone())?;
+            while scan.next()? {
+                if scan.get_string(IndexMgr::INDEX_NAME)? == index_name {
+                    scan.delete()?;
+                    found = true;
+                }
+            }
+        }
+        if !found {
+            return Err(DbError::Schema(format!(
+                "Index {} does not exist",
+                index_name
+            )));
+        }
+
+        for file_name in Self::index_files(index_name) {
+            tx.delete_file(&file_name)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the names of the leaf and internal files of the index.
+    pub fn index_files(index_name: &str) -> [String; 2] {
+        [
+            format!("{}leaf", index_name),
+            format!("{}internal", index_name),
+        ]
+    }
+
+    /// Moves the catalog entries of the table's indexes to its new name.
+    pub fn rename_table(
+        &self,
+        table_name: &str,
+        new_table_name: &str,
+        tx: Transaction<'_>,
+    ) -> DbResult<()> {
+        let mut scan = TableScan::new(tx, IndexMgr::INDEX_TABLE, self.layout.clone())?;
+        while scan.next()? {
+            if scan.get_string(IndexMgr::TABLE_NAME)? == table_name {
+                scan.set_string(IndexMgr::TABLE_NAME, new_table_name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the catalog entries of the indexes on a field to its new name.
+    pub fn rename_field(
+        &self,
+        table_name: &str,
+        field_name: &str,
+        new_field_name: &str,
+        tx: Transaction<'_>,
+    ) -> DbResult<()> {
+        let mut scan = TableScan::new(tx, IndexMgr::INDEX_TABLE, self.layout.clone())?;
+        while scan.next()? {
+            if scan.get_string(IndexMgr::TABLE_NAME)? == table_name
+                && scan.get_string(IndexMgr::FIELD_NAME)? == field_name
+            {
+                scan.set_string(IndexMgr::FIELD_NAME, new_field_name)?;
+            }
+        }
         Ok(())
     }
 
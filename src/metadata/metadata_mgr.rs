@@ -1,9 +1,18 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use crate::{
-    error::DbResult,
+    error::{DbError, DbResult},
+    index::Index,
     metadata::{IndexInfo, IndexMgr},
-    record::{Layout, Schema},
+    query::{Constant, Scan, UpdateScan},
+    record::{
+        FreeSpaceMap, Layout, RID, RecordFormat, Schema, TableScan, overflow::OverflowFile,
+        record_page, schema::FieldType,
+    },
+    storage::BlockId,
     tx::Transaction,
 };
 
@@ -15,6 +24,9 @@ pub struct MetadataMgr {
 }
 
 impl MetadataMgr {
+    /// Appended to the names of a table and its indexes for the files written by a vacuum.
+    const VACUUM_SUFFIX: &'static str = ".vacuum";
+
     pub fn new(table_mgr: Arc<TableMgr>, index_mgr: Arc<IndexMgr>) -> DbResult<Self> {
         Ok(Self {
             table_mgr,
@@ -23,13 +35,384 @@ impl MetadataMgr {
     }
 
     pub fn create_table(&self, tblname: &str, schema: &Schema, tx: Transaction) -> DbResult<()> {
-        self.table_mgr.create_table(tblname, schema, tx)
+        self.create_table_with_format(tblname, schema, RecordFormat::Fixed, tx)
+    }
+
+    /// Creates the table together with an index on each of its PRIMARY KEY and UNIQUE
+    /// fields, which inserts and updates use to find duplicate values.
+    pub fn create_table_with_format(
+        &self,
+        tblname: &str,
+        schema: &Schema,
+        format: RecordFormat,
+        tx: Transaction,
+    ) -> DbResult<()> {
+        let mut indexes = Vec::new();
+        for fldname in schema.fields().iter().filter(|f| schema.is_unique(f)) {
+            let index_name = Self::constraint_index_name(tblname, fldname, schema);
+            if index_name.len() > TableMgr::MAX_NAME {
+                return Err(DbError::Schema(format!(
+                    "Index name {} for field {} is longer than {} characters",
+                    index_name,
+                    fldname,
+                    TableMgr::MAX_NAME
+                )));
+            }
+            indexes.push((index_name, fldname));
+        }
+
+        self.table_mgr
+            .create_table_with_format(tblname, schema, format, tx.clone())?;
+        for (index_name, fldname) in indexes {
+            self.index_mgr
+                .create_index(&index_name, tblname, fldname, tx.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the name of the index created for a PRIMARY KEY or UNIQUE field,
+    /// `<table>_pkey` or `<table>_<field>_key`.
+    fn constraint_index_name(tblname: &str, fldname: &str, schema: &Schema) -> String {
+        if schema.primary_key() == Some(fldname) {
+            format!("{}_pkey", tblname)
+        } else {
+            format!("{}_{}_key", tblname, fldname)
+        }
+    }
+
+    pub fn table_exists(&self, tblname: &str, tx: Transaction) -> DbResult<bool> {
+        self.table_mgr.table_exists(tblname, tx)
+    }
+
+    /// Drops the table together with its indexes.
+    pub fn drop_table(&self, tblname: &str, tx: Transaction) -> DbResult<()> {
+        if !self.table_mgr.table_exists(tblname, tx.clone())? {
+            return Err(DbError::Schema(format!("Table {} does not exist", tblname)));
+        }
+        let indexes = self.index_mgr.get_index_info(tblname, tx.clone())?;
+        let index_names: HashSet<&str> = indexes.values().map(|i| i.index_name()).collect();
+        for index_name in index_names {
+            self.index_mgr.drop_index(index_name, tx.clone())?;
+        }
+        self.table_mgr.drop_table(tblname, tx)
     }
 
     pub fn get_layout(&self, tblname: &str, tx: Transaction) -> DbResult<Layout> {
         self.table_mgr.get_layout(tblname, tx)
     }
 
+    /// Adds a field to the table. The table file is rewritten for the new layout
+    /// and the field of every existing record is set to the default value.
+    pub fn add_column(
+        &self,
+        tblname: &str,
+        field_name: &str,
+        field_type: FieldType,
+        length: usize,
+        default: Constant,
+        tx: Transaction,
+    ) -> DbResult<()> {
+        let layout = self.existing_layout(tblname, tx.clone())?;
+        if layout.schema().has_field(field_name) {
+            return Err(DbError::Schema(format!(
+                "Field {} already exists in table {}",
+                field_name, tblname
+            )));
+        }
+        let matches_type = match default.coerce_to(field_type) {
+            Some(Constant::String(s)) if field_type == FieldType::Varchar => {
+                s.chars().count() <= length
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if !matches_type {
+            return Err(DbError::Schema(format!(
+                "Default value {} does not fit field {}",
+                default.to_string(),
+                field_name
+            )));
+        }
+
+        let mut schema = Schema::new();
+        schema.add_all(layout.schema());
+        schema.add_field(field_name, field_type, length);
+        let new_layout = Layout::new(schema).with_format(layout.format());
+
+        self.rewrite_table(
+            tblname,
+            &layout,
+            tblname,
+            &new_layout,
+            &[(field_name, default)],
+            tx.clone(),
+        )?;
+        self.table_mgr
+            .alter_table(tblname, tblname, &new_layout, tx)
+    }
+
+    /// Removes a field together with its indexes and rewrites the table file for the new layout.
+    pub fn drop_column(&self, tblname: &str, field_name: &str, tx: Transaction) -> DbResult<()> {
+        let layout = self.existing_layout(tblname, tx.clone())?;
+        if !layout.schema().has_field(field_name) {
+            return Err(DbError::FieldNotFound(field_name.to_string()));
+        }
+        if layout.schema().fields().len() == 1 {
+            return Err(DbError::Schema(format!(
+                "Cannot drop {}, the only field of table {}",
+                field_name, tblname
+            )));
+        }
+
+        if let Some(index_info) = self
+            .index_mgr
+            .get_index_info(tblname, tx.clone())?
+            .get(field_name)
+        {
+            self.index_mgr
+                .drop_index(index_info.index_name(), tx.clone())?;
+        }
+
+        let mut schema = Schema::new();
+        for fldname in layout.schema().fields().iter().filter(|f| *f != field_name) {
+            schema.add_from_schema(fldname, layout.schema());
+        }
+        let new_layout = Layout::new(schema).with_format(layout.format());
+
+        self.rewrite_table(tblname, &layout, tblname, &new_layout, &[], tx.clone())?;
+        self.table_mgr
+            .alter_table(tblname, tblname, &new_layout, tx)
+    }
+
+    /// Renames a field. The layout does not change, so only the catalog is updated.
+    pub fn rename_column(
+        &self,
+        tblname: &str,
+        field_name: &str,
+        new_field_name: &str,
+        tx: Transaction,
+    ) -> DbResult<()> {
+        let layout = self.existing_layout(tblname, tx.clone())?;
+        let schema = layout.schema();
+        if !schema.has_field(field_name) {
+            return Err(DbError::FieldNotFound(field_name.to_string()));
+        }
+        if schema.has_field(new_field_name) {
+            return Err(DbError::Schema(format!(
+                "Field {} already exists in table {}",
+                new_field_name, tblname
+            )));
+        }
+
+        let mut new_schema = Schema::new();
+        let mut offsets = HashMap::new();
+        for fldname in schema.fields() {
+            let new_name = if fldname == field_name {
+                new_field_name
+            } else {
+                fldname
+            };
+            let field_type = schema.field_type(fldname).expect("Field type not found");
+            new_schema.add_field(new_name, field_type, schema.length(fldname).unwrap_or(0));
+            new_schema.set_constraints(new_name, schema.constraints(fldname));
+            offsets.insert(
+                new_name.to_string(),
+                layout.offset(fldname).expect("Field not found"),
+            );
+        }
+        let new_layout = Layout::with_offsets(new_schema, offsets, layout.slot_size())
+            .with_format(layout.format());
+
+        self.index_mgr
+            .rename_field(tblname, field_name, new_field_name, tx.clone())?;
+        self.table_mgr
+            .alter_table(tblname, tblname, &new_layout, tx)
+    }
+
+    /// Renames a table. The records are moved to the file of the new name,
+    /// the old file is deleted once the transaction commits.
+    pub fn rename_table(&self, tblname: &str, new_tblname: &str, tx: Transaction) -> DbResult<()> {
+        let layout = self.existing_layout(tblname, tx.clone())?;
+        if self.table_mgr.table_exists(new_tblname, tx.clone())? {
+            return Err(DbError::Schema(format!(
+                "Table {} already exists",
+                new_tblname
+            )));
+        }
+
+        self.rewrite_table(tblname, &layout, new_tblname, &layout, &[], tx.clone())?;
+        tx.delete_file(&format!("{}.tbl", tblname))?;
+        tx.delete_file(&OverflowFile::file_name(tblname))?;
+        tx.delete_file(&FreeSpaceMap::file_name(tblname))?;
+        self.index_mgr
+            .rename_table(tblname, new_tblname, tx.clone())?;
+        self.table_mgr
+            .alter_table(tblname, new_tblname, &layout, tx)
+    }
+
+    /// Compacts a table. The live records are written into new files, the indexes are
+    /// rebuilt for the new record ids, and the new files replace the old ones when the
+    /// transaction commits, so the free slots and blocks of deleted records are gone.
+    pub fn vacuum(&self, tblname: &str, tx: Transaction) -> DbResult<()> {
+        let layout = self.existing_layout(tblname, tx.clone())?;
+        let indexes = self.index_mgr.get_index_info(tblname, tx.clone())?;
+        let new_tblname = format!("{}{}", tblname, Self::VACUUM_SUFFIX);
+        let table_files = |tblname: &str| {
+            [
+                format!("{}.tbl", tblname),
+                OverflowFile::file_name(tblname),
+                FreeSpaceMap::file_name(tblname),
+            ]
+        };
+
+        // the files of a vacuum which rolled back are left behind
+        for file_name in table_files(&new_tblname) {
+            tx.remove_file(&file_name)?;
+        }
+        for index_info in indexes.values() {
+            let new_index_name = format!("{}{}", index_info.index_name(), Self::VACUUM_SUFFIX);
+            for file_name in IndexMgr::index_files(&new_index_name) {
+                tx.remove_file(&file_name)?;
+            }
+        }
+
+        let mut entries: HashMap<&str, Vec<(Constant, RID)>> = HashMap::new();
+        {
+            let mut scan = TableScan::new(tx.clone(), tblname, layout.clone())?;
+            let mut new_scan = TableScan::new(tx.clone(), &new_tblname, layout.clone())?;
+            while scan.next()? {
+                new_scan.insert()?;
+                for fldname in layout.schema().fields() {
+                    new_scan.set_val(fldname, scan.get_val(fldname)?)?;
+                }
+                for fldname in indexes.keys() {
+                    entries
+                        .entry(fldname)
+                        .or_default()
+                        .push((scan.get_val(fldname)?, new_scan.get_rid()?));
+                }
+            }
+        }
+
+        for (fldname, index_info) in &indexes {
+            let new_index_name = format!("{}{}", index_info.index_name(), Self::VACUUM_SUFFIX);
+            let new_index_info = IndexInfo::new(
+                new_index_name.clone(),
+                fldname.clone(),
+                layout.schema().clone(),
+            );
+            let mut index = new_index_info.open(tx.clone())?;
+            index.bulk_load(entries.remove(fldname.as_str()).unwrap_or_default())?;
+            index.close();
+            let new_files = IndexMgr::index_files(&new_index_name);
+            for (from, to) in new_files
+                .iter()
+                .zip(IndexMgr::index_files(index_info.index_name()))
+            {
+                tx.replace_file(from, &to)?;
+            }
+        }
+
+        // the new table has no free space map and only has an overflow file if it needs one
+        let [new_tbl, new_ovf, new_fsm] = table_files(&new_tblname);
+        let [tbl, ovf, fsm] = table_files(tblname);
+        tx.replace_file(&new_tbl, &tbl)?;
+        if tx.size(&new_ovf)? > 0 {
+            tx.replace_file(&new_ovf, &ovf)?;
+        } else {
+            tx.delete_file(&new_ovf)?;
+            tx.delete_file(&ovf)?;
+        }
+        tx.delete_file(&new_fsm)?;
+        tx.delete_file(&fsm)
+    }
+
+    fn existing_layout(&self, tblname: &str, tx: Transaction) -> DbResult<Layout> {
+        if !self.table_mgr.table_exists(tblname, tx.clone())? {
+            return Err(DbError::Schema(format!("Table {} does not exist", tblname)));
+        }
+        self.table_mgr.get_layout(tblname, tx)
+    }
+
+    /// Copies the records of a table into the file of `new_tblname`, laid out by `new_layout`.
+    /// Fields missing from the old layout get the given values. When both names are the same
+    /// the records are read into memory and the file is cleared with logged writes first,
+    /// so that a rollback restores the old contents, after deleting the records to free the
+    /// overflow blocks of their values. The index entries are moved to the new record ids.
+    fn rewrite_table(
+        &self,
+        tblname: &str,
+        layout: &Layout,
+        new_tblname: &str,
+        new_layout: &Layout,
+        defaults: &[(&str, Constant)],
+        tx: Transaction,
+    ) -> DbResult<()> {
+        let mut records = Vec::new();
+        {
+            let mut scan = TableScan::new(tx.clone(), tblname, layout.clone())?;
+            while scan.next()? {
+                let mut vals = Vec::with_capacity(new_layout.schema().fields().len());
+                for fldname in new_layout.schema().fields() {
+                    let val = match defaults.iter().find(|(f, _)| f == fldname) {
+                        Some((_, default)) => default.clone(),
+                        None => scan.get_val(fldname)?,
+                    };
+                    vals.push(val);
+                }
+                records.push((scan.get_rid()?, vals));
+            }
+        }
+
+        if tblname == new_tblname {
+            if layout.schema().fields().iter().any(|f| {
+                layout
+                    .schema()
+                    .field_type(f)
+                    .is_some_and(|t| t.is_overflow())
+            }) {
+                let mut scan = TableScan::new(tx.clone(), tblname, layout.clone())?;
+                while scan.next()? {
+                    scan.delete()?;
+                }
+            }
+            let file_name = format!("{}.tbl", tblname);
+            for blk_num in 0..tx.size(&file_name)? {
+                let blk = BlockId::new(file_name.clone(), blk_num);
+                record_page::open(tx.clone(), blk, new_layout.clone())?.clear()?;
+            }
+        }
+
+        let indexes = self.index_mgr.get_index_info(tblname, tx.clone())?;
+        let mut scan = TableScan::new(tx.clone(), new_tblname, new_layout.clone())?;
+        for (rid, vals) in records {
+            scan.insert()?;
+            for (fldname, val) in new_layout.schema().fields().iter().zip(vals.iter()) {
+                scan.set_val(fldname, val.clone())?;
+            }
+
+            let new_rid = scan.get_rid()?;
+            if new_rid == rid {
+                continue;
+            }
+            for (fldname, index_info) in &indexes {
+                let Some(pos) = new_layout
+                    .schema()
+                    .fields()
+                    .iter()
+                    .position(|f| f == fldname)
+                else {
+                    continue;
+                };
+                let mut index = index_info.open(tx.clone())?;
+                index.delete(&vals[pos], &rid)?;
+                index.insert(&vals[pos], &new_rid)?;
+                index.close();
+            }
+        }
+        Ok(())
+    }
+
     pub fn create_index(
         &self,
         index_name: &str,
@@ -41,6 +424,10 @@ impl MetadataMgr {
             .create_index(index_name, table_name, field_name, tx)
     }
 
+    pub fn drop_index(&self, index_name: &str, tx: Transaction<'_>) -> DbResult<()> {
+        self.index_mgr.drop_index(index_name, tx)
+    }
+
     pub fn get_index_info<'tx>(
         &self,
         table_name: &str,
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
 use crate::{
-    error::DbResult,
+    error::{DbError, DbResult},
     query::{Scan, UpdateScan},
-    record::{Layout, Schema, TableScan, schema::FieldType},
+    record::{
+        FreeSpaceMap, Layout, RecordFormat, Schema, TableScan, overflow::OverflowFile,
+        schema::FieldType,
+    },
+    storage::BlockId,
     tx::Transaction,
 };
 
@@ -15,10 +19,17 @@ pub struct TableMgr {
 impl TableMgr {
     pub const MAX_NAME: usize = 16;
 
+    /// Version of the on-disk format of the catalog and the records, stored in the first
+    /// block of [Self::VERSION_FILE] when the database is created. It must be raised whenever
+    /// the layout of records or of the catalog tables changes.
+    pub const FORMAT_VERSION: i32 = 1;
+    const VERSION_FILE: &str = "dbversion";
+
     pub fn new(is_new: bool, tx: Transaction) -> DbResult<Self> {
         let mut tcat_schema = Schema::new();
         tcat_schema.add_string_field("tblname", Self::MAX_NAME);
         tcat_schema.add_int_field("slotsize");
+        tcat_schema.add_int_field("format");
         let tcat_layout = Layout::new(tcat_schema.clone());
 
         let mut fcat_schema = Schema::new();
@@ -27,6 +38,7 @@ impl TableMgr {
         fcat_schema.add_int_field("type");
         fcat_schema.add_int_field("length");
         fcat_schema.add_int_field("offset");
+        fcat_schema.add_int_field("constraints");
         let fcat_layout = Layout::new(fcat_schema.clone());
 
         let table_mgr = Self {
@@ -35,21 +47,96 @@ impl TableMgr {
         };
 
         if is_new {
+            Self::write_format_version(&tx)?;
             table_mgr.create_table("tblcat", &tcat_schema, tx.clone())?;
             table_mgr.create_table("fldcat", &fcat_schema, tx.clone())?;
+        } else {
+            Self::check_format_version(&tx)?;
         }
 
         Ok(table_mgr)
     }
 
+    fn write_format_version(tx: &Transaction) -> DbResult<()> {
+        let blk = tx.append(Self::VERSION_FILE)?;
+        tx.pin(&blk)?;
+        tx.set_int(&blk, 0, Self::FORMAT_VERSION, true)?;
+        tx.unpin(&blk);
+        Ok(())
+    }
+
+    /// Fails if the database was created with another format version. Databases created
+    /// before the version was stored have version 0.
+    fn check_format_version(tx: &Transaction) -> DbResult<()> {
+        let version = if tx.size(Self::VERSION_FILE)? == 0 {
+            0
+        } else {
+            let blk = BlockId::new(Self::VERSION_FILE.to_string(), 0);
+            tx.pin(&blk)?;
+            let version = tx.get_int(&blk, 0)?;
+            tx.unpin(&blk);
+            version
+        };
+        if version != Self::FORMAT_VERSION {
+            return Err(DbError::UnsupportedFormat(version, Self::FORMAT_VERSION));
+        }
+        Ok(())
+    }
+
     pub fn create_table(&self, tblname: &str, sch: &Schema, tx: Transaction) -> DbResult<()> {
-        let layout = Layout::new(sch.clone());
+        self.create_table_with_format(tblname, sch, RecordFormat::Fixed, tx)
+    }
+
+    pub fn create_table_with_format(
+        &self,
+        tblname: &str,
+        sch: &Schema,
+        format: RecordFormat,
+        tx: Transaction,
+    ) -> DbResult<()> {
+        let layout = Layout::new(sch.clone()).with_format(format);
+        self.write_catalog(tblname, &layout, tx)
+    }
+
+    pub fn table_exists(&self, tblname: &str, tx: Transaction) -> DbResult<bool> {
+        let mut tcat = TableScan::new(tx, "tblcat", self.tcat_layout.clone())?;
+        while tcat.next()? {
+            if tcat.get_string("tblname")? == tblname {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Removes the table from the catalog and deletes its files once the transaction commits.
+    pub fn drop_table(&self, tblname: &str, tx: Transaction) -> DbResult<()> {
+        self.delete_catalog(tblname, tx.clone())?;
+        tx.delete_file(&format!("{}.tbl", tblname))?;
+        tx.delete_file(&OverflowFile::file_name(tblname))?;
+        tx.delete_file(&FreeSpaceMap::file_name(tblname))
+    }
 
+    /// Replaces the catalog entries of a table with the given name and layout.
+    /// The records in the table file are left to the caller.
+    pub fn alter_table(
+        &self,
+        tblname: &str,
+        new_tblname: &str,
+        layout: &Layout,
+        tx: Transaction,
+    ) -> DbResult<()> {
+        self.delete_catalog(tblname, tx.clone())?;
+        self.write_catalog(new_tblname, layout, tx)
+    }
+
+    fn write_catalog(&self, tblname: &str, layout: &Layout, tx: Transaction) -> DbResult<()> {
+        let sch = layout.schema();
         {
             let mut tcat = TableScan::new(tx.clone(), "tblcat", self.tcat_layout.clone())?;
             tcat.insert()?;
             tcat.set_string("tblname", tblname)?;
             tcat.set_int("slotsize", layout.slot_size() as i32)?;
+            tcat.set_int("format", layout.format() as i32)?;
         }
 
         {
@@ -58,27 +145,50 @@ impl TableMgr {
                 fcat.insert()?;
                 fcat.set_string("tblname", tblname)?;
                 fcat.set_string("fldname", &fldname)?;
-                let type_value = match sch.field_type(&fldname).unwrap() {
-                    FieldType::Integer => 0,
-                    FieldType::Varchar => 1,
-                };
-                fcat.set_int("type", type_value)?;
+                fcat.set_int("type", sch.field_type(&fldname).unwrap() as i32)?;
                 fcat.set_int("length", sch.length(&fldname).unwrap_or(0) as i32)?;
                 fcat.set_int("offset", layout.offset(&fldname).unwrap_or(0) as i32)?;
+                fcat.set_int("constraints", sch.constraints(fldname))?;
             }
         }
 
         Ok(())
     }
 
+    fn delete_catalog(&self, tblname: &str, tx: Transaction) -> DbResult<()> {
+        let mut found = false;
+        {
+            let mut tcat = TableScan::new(tx.clone(), "tblcat", self.tcat_layout.clone())?;
+            while tcat.next()? {
+                if tcat.get_string("tblname")? == tblname {
+                    tcat.delete()?;
+                    found = true;
+                }
+            }
+        }
+        if !found {
+            return Err(DbError::Schema(format!("Table {} does not exist", tblname)));
+        }
+
+        let mut fcat = TableScan::new(tx, "fldcat", self.fcat_layout.clone())?;
+        while fcat.next()? {
+            if fcat.get_string("tblname")? == tblname {
+                fcat.delete()?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_layout(&self, tblname: &str, tx: Transaction) -> DbResult<Layout> {
         let mut size = -1;
+        let mut format = RecordFormat::Fixed;
         {
             let mut tcat = TableScan::new(tx.clone(), "tblcat", self.tcat_layout.clone())?;
 
             while tcat.next()? {
                 if tcat.get_string("tblname")? == tblname {
                     size = tcat.get_int("slotsize")?;
+                    format = RecordFormat::from(tcat.get_int("format")?);
                     break;
                 }
             }
@@ -94,24 +204,27 @@ impl TableMgr {
                 let fldtype = fcat.get_int("type")?;
                 let fldlen = fcat.get_int("length")?;
                 let offset = fcat.get_int("offset")?;
+                let constraints = fcat.get_int("constraints")?;
 
                 offsets.insert(fldname.clone(), offset as usize);
-                let field_type = match fldtype {
-                    0 => FieldType::Integer,
-                    1 => FieldType::Varchar,
-                    _ => panic!("Unknown field type: {}", fldtype),
-                };
-                sch.add_field(&fldname, field_type, fldlen as usize);
+                sch.add_field(&fldname, FieldType::from(fldtype), fldlen as usize);
+                sch.set_constraints(&fldname, constraints);
             }
         }
 
-        Ok(Layout::with_offsets(sch, offsets, size as usize))
+        Ok(Layout::with_offsets(sch, offsets, size as usize).with_format(format))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{error::DbResult, record::Schema, utils::testing_utils::temp_db};
+    use super::TableMgr;
+    use crate::{
+        error::{DbError, DbResult},
+        record::{Schema, schema::Constraint},
+        storage::BlockId,
+        utils::testing_utils::temp_db,
+    };
 
     #[test]
     fn test_table_mgr() -> DbResult<()> {
@@ -144,4 +257,77 @@ mod tests {
         tx2.commit()?;
         Ok(())
     }
+
+    #[test]
+    fn test_table_mgr_rejects_other_format_version() -> DbResult<()> {
+        let db = temp_db()?;
+        let db = db.reopen()?;
+
+        // a database created before the version was stored
+        let tx = db.new_tx()?;
+        let blk = BlockId::new(TableMgr::VERSION_FILE.to_string(), 0);
+        tx.pin(&blk)?;
+        tx.set_int(&blk, 0, 0, true)?;
+        tx.unpin(&blk);
+        tx.commit()?;
+
+        match db.reopen() {
+            Err(DbError::UnsupportedFormat(0, version)) => {
+                assert_eq!(version, TableMgr::FORMAT_VERSION)
+            }
+            Err(err) => panic!("Unexpected error {err}"),
+            Ok(_) => panic!("Database with format version 0 was opened"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_mgr_stores_constraints() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        schema.add_string_field("name", 20);
+        schema.add_constraint("id", Constraint::PrimaryKey);
+        schema.add_constraint("name", Constraint::NotNull);
+        db.metadata_mgr()
+            .create_table("test_table", &schema, tx.clone())?;
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        let layout = db.metadata_mgr().get_layout("test_table", tx.clone())?;
+        assert_eq!(layout.schema().primary_key(), Some("id"));
+        assert!(layout.schema().is_not_null("name"));
+        assert!(!layout.schema().is_unique("name"));
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_drop_table() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        db.metadata_mgr()
+            .create_table("dropped", &schema, tx.clone())?;
+        db.metadata_mgr()
+            .create_table("kept", &schema, tx.clone())?;
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        db.metadata_mgr().drop_table("dropped", tx.clone())?;
+        assert!(db.metadata_mgr().drop_table("dropped", tx.clone()).is_err());
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        assert!(!db.metadata_mgr().table_exists("dropped", tx.clone())?);
+        assert!(db.metadata_mgr().table_exists("kept", tx.clone())?);
+        let layout = db.metadata_mgr().get_layout("kept", tx.clone())?;
+        assert_eq!(layout.schema().fields().len(), 1);
+        tx.commit()?;
+        Ok(())
+    }
 }
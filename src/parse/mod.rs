@@ -1,17 +1,23 @@
-use sqlparser::ast::{CharacterLength, DataType, SetExpr, Statement as SqlStatement, Value};
+use sqlparser::ast::{
+    CharacterLength, ColumnOption, DataType, SetExpr, Statement as SqlStatement, TableConstraint,
+    TimezoneInfo, Value,
+};
 use sqlparser::dialect::GenericDialect;
 use sqlparser::parser::Parser as SqlParser;
+use sqlparser::tokenizer::{Token, Tokenizer};
 
 use crate::error::{DbError, DbResult};
 use crate::query::predicate::Predicate;
-use crate::query::{Constant, Expr, Term};
-use crate::record::schema::Schema;
+use crate::query::{AggregateFn, AggregateKind, CompareOp, Constant, Expr, SortField, Term};
+use crate::record::RecordFormat;
+use crate::record::schema::{Constraint, FieldType, Schema};
 
 #[derive(Debug, Clone)]
 pub enum Statement {
     CreateTable {
         table_name: String,
         schema: Schema,
+        format: RecordFormat,
     },
     CreateIndex {
         name: String,
@@ -29,10 +35,55 @@ pub enum Statement {
         values: Vec<Constant>,
         predicate: Option<Predicate>,
     },
+    Delete {
+        table_name: String,
+        predicate: Option<Predicate>,
+    },
+    AlterTable {
+        table_name: String,
+        action: AlterTableAction,
+    },
+    DropTable {
+        table_name: String,
+        if_exists: bool,
+    },
+    DropIndex {
+        name: String,
+        if_exists: bool,
+    },
+    Vacuum {
+        table_name: String,
+    },
     Query {
         fields: Vec<String>,
         tables: Vec<String>,
         predicate: Option<Predicate>,
+        group_by: Vec<String>,
+        aggregates: Vec<AggregateFn>,
+        having: Option<Predicate>,
+        order_by: Vec<SortField>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum AlterTableAction {
+    AddColumn {
+        field_name: String,
+        field_type: FieldType,
+        length: usize,
+        default: Option<Constant>,
+    },
+    DropColumn {
+        field_name: String,
+    },
+    RenameColumn {
+        field_name: String,
+        new_field_name: String,
+    },
+    RenameTable {
+        new_table_name: String,
     },
 }
 
@@ -48,6 +99,9 @@ impl Parser {
     }
 
     pub fn parse(&self, sql: &str) -> DbResult<Statement> {
+        if let Some(statement) = self.parse_vacuum(sql)? {
+            return Ok(statement);
+        }
         let ast = SqlParser::parse_sql(&self.dialect, sql)
             .map_err(|e| DbError::Schema(format!("Failed to parse SQL: {}", e)))?;
 
@@ -75,11 +129,50 @@ impl Parser {
                 };
                 self.parse_update(&table_name, assignments, selection)
             }
-            SqlStatement::Query(query) => self.parse_select(&query.body),
+            SqlStatement::Delete(delete) => self.parse_delete(delete),
+            SqlStatement::AlterTable {
+                name, operations, ..
+            } => self.parse_alter_table(&name.to_string(), operations),
+            SqlStatement::Drop {
+                object_type,
+                if_exists,
+                names,
+                ..
+            } => self.parse_drop(object_type, *if_exists, names),
+            SqlStatement::Query(query) => self.parse_select(query),
             _ => Err(DbError::Schema("Unsupported SQL statement".to_string())),
         }
     }
 
+    /// Parses `VACUUM table`, which the SQL parser does not support. Returns `None` if
+    /// the statement does not start with `VACUUM`.
+    fn parse_vacuum(&self, sql: &str) -> DbResult<Option<Statement>> {
+        let tokens = Tokenizer::new(&self.dialect, sql)
+            .tokenize()
+            .map_err(|e| DbError::Schema(format!("Failed to parse SQL: {}", e)))?;
+        let mut words = tokens
+            .into_iter()
+            .filter(|token| !matches!(token, Token::Whitespace(_)));
+        match words.next() {
+            Some(Token::Word(word)) if word.value.eq_ignore_ascii_case("vacuum") => {}
+            _ => return Ok(None),
+        }
+        let table_name = match words.next() {
+            Some(Token::Word(word)) => word.value,
+            _ => {
+                return Err(DbError::Schema("VACUUM requires a table name".to_string()));
+            }
+        };
+        match (words.next(), words.next()) {
+            (None, _) | (Some(Token::SemiColon), None) => {
+                Ok(Some(Statement::Vacuum { table_name }))
+            }
+            _ => Err(DbError::Schema(
+                "Unexpected tokens after VACUUM table".to_string(),
+            )),
+        }
+    }
+
     fn parse_create_table(
         &self,
         create_table: &sqlparser::ast::CreateTable,
@@ -88,30 +181,214 @@ impl Parser {
         let mut schema = Schema::new();
 
         for col in &create_table.columns {
-            let field_name = col.name.to_string();
-            match col.data_type {
-                DataType::Int(_) => {
-                    schema.add_int_field(&field_name);
-                }
-                DataType::Varchar(Some(CharacterLength::IntegerLength { length, .. })) => {
-                    schema.add_string_field(&field_name, length as usize);
-                }
-                DataType::Varchar(Some(CharacterLength::Max)) => {
-                    return Err(DbError::Schema(format!(
-                        "VARCHAR(MAX) is not supported for column {}",
-                        field_name
-                    )));
-                }
+            let (field_name, field_type, length) = Self::parse_column_def(col)?;
+            schema.add_field(&field_name, field_type, length);
+            for option in &col.options {
+                let constraint = match option.option {
+                    ColumnOption::Null => continue,
+                    ColumnOption::NotNull => Constraint::NotNull,
+                    ColumnOption::Unique {
+                        is_primary: true, ..
+                    } => Constraint::PrimaryKey,
+                    ColumnOption::Unique { .. } => Constraint::Unique,
+                    _ => {
+                        return Err(DbError::Schema(format!(
+                            "Unsupported option for column {}",
+                            field_name
+                        )));
+                    }
+                };
+                Self::add_constraint(&mut schema, &field_name, constraint)?;
+            }
+        }
+        for table_constraint in &create_table.constraints {
+            let (columns, constraint) = match table_constraint {
+                TableConstraint::Unique { columns, .. } => (columns, Constraint::Unique),
+                TableConstraint::PrimaryKey { columns, .. } => (columns, Constraint::PrimaryKey),
                 _ => {
                     return Err(DbError::Schema(format!(
-                        "Unsupported data type for column {}",
-                        field_name
+                        "Unsupported table constraint: {}",
+                        table_constraint
                     )));
                 }
+            };
+            // only a single column is supported, like for indexes
+            let [column] = columns.as_slice() else {
+                return Err(DbError::Schema(format!(
+                    "{} constraint must have exactly one column",
+                    constraint
+                )));
+            };
+            if !schema.has_field(&column.value) {
+                return Err(DbError::FieldNotFound(column.value.clone()));
+            }
+            Self::add_constraint(&mut schema, &column.value, constraint)?;
+        }
+
+        let mut format = RecordFormat::Fixed;
+        for option in &create_table.with_options {
+            format = Self::parse_table_option(option)?;
+        }
+
+        Ok(Statement::CreateTable {
+            table_name,
+            schema,
+            format,
+        })
+    }
+
+    /// Adds the constraint to the field, failing if it would be the second primary key.
+    fn add_constraint(
+        schema: &mut Schema,
+        field_name: &str,
+        constraint: Constraint,
+    ) -> DbResult<()> {
+        if constraint == Constraint::PrimaryKey
+            && let Some(primary_key) = schema.primary_key()
+            && primary_key != field_name
+        {
+            return Err(DbError::Schema(format!(
+                "Multiple primary keys, {} and {}",
+                primary_key, field_name
+            )));
+        }
+        schema.add_constraint(field_name, constraint);
+        Ok(())
+    }
+
+    /// Parses the `format` option of `CREATE TABLE ... WITH (format = 'slotted')`, the only
+    /// supported table option. The format is either `fixed`, the default, or `slotted`.
+    fn parse_table_option(option: &sqlparser::ast::SqlOption) -> DbResult<RecordFormat> {
+        let sqlparser::ast::SqlOption::KeyValue { key, value } = option else {
+            return Err(DbError::Schema(format!(
+                "Unsupported table option: {}",
+                option
+            )));
+        };
+        if !key.value.eq_ignore_ascii_case("format") {
+            return Err(DbError::Schema(format!(
+                "Unsupported table option: {}",
+                key
+            )));
+        }
+        let format = match value {
+            sqlparser::ast::Expr::Identifier(ident) => ident.value.clone(),
+            _ => match Self::parse_constant(value) {
+                Ok(Constant::String(s)) => s,
+                _ => String::new(),
+            },
+        };
+        match format.to_lowercase().as_str() {
+            "fixed" => Ok(RecordFormat::Fixed),
+            "slotted" => Ok(RecordFormat::Slotted),
+            _ => Err(DbError::Schema(format!(
+                "Unsupported record format: {}",
+                value
+            ))),
+        }
+    }
+
+    /// Returns the name, type and length of a column definition.
+    fn parse_column_def(col: &sqlparser::ast::ColumnDef) -> DbResult<(String, FieldType, usize)> {
+        let field_name = col.name.to_string();
+        match col.data_type {
+            DataType::Int(_) | DataType::Integer(_) => Ok((field_name, FieldType::Integer, 0)),
+            DataType::BigInt(_) | DataType::Int8(_) => Ok((field_name, FieldType::BigInt, 0)),
+            DataType::Boolean | DataType::Bool => Ok((field_name, FieldType::Boolean, 0)),
+            DataType::Double(_) | DataType::DoublePrecision | DataType::Float8 => {
+                Ok((field_name, FieldType::Double, 0))
+            }
+            DataType::Date => Ok((field_name, FieldType::Date, 0)),
+            DataType::Text => Ok((field_name, FieldType::Text, 0)),
+            DataType::Blob(_) | DataType::Bytea => Ok((field_name, FieldType::Blob, 0)),
+            DataType::Timestamp(_, TimezoneInfo::None) | DataType::Datetime(_) => {
+                Ok((field_name, FieldType::Timestamp, 0))
             }
+            DataType::Varchar(Some(CharacterLength::IntegerLength { length, .. })) => {
+                Ok((field_name, FieldType::Varchar, length as usize))
+            }
+            DataType::Varchar(Some(CharacterLength::Max)) => Err(DbError::Schema(format!(
+                "VARCHAR(MAX) is not supported for column {}",
+                field_name
+            ))),
+            _ => Err(DbError::Schema(format!(
+                "Unsupported data type for column {}",
+                field_name
+            ))),
+        }
+    }
+
+    fn parse_alter_table(
+        &self,
+        table_name: &str,
+        operations: &[sqlparser::ast::AlterTableOperation],
+    ) -> DbResult<Statement> {
+        use sqlparser::ast::AlterTableOperation;
+
+        if operations.len() != 1 {
+            return Err(DbError::Schema(
+                "Only a single ALTER TABLE operation is supported".to_string(),
+            ));
         }
 
-        Ok(Statement::CreateTable { table_name, schema })
+        let action = match &operations[0] {
+            AlterTableOperation::AddColumn {
+                column_def,
+                column_position: None,
+                ..
+            } => {
+                let (field_name, field_type, length) = Self::parse_column_def(column_def)?;
+                let mut default = None;
+                for option in &column_def.options {
+                    match &option.option {
+                        ColumnOption::Default(expr) => match self.parse_expr(expr)? {
+                            Expr::Constant(val) => default = Some(val),
+                            _ => {
+                                return Err(DbError::Schema(format!(
+                                    "Default value of column {} must be a constant",
+                                    field_name
+                                )));
+                            }
+                        },
+                        _ => {
+                            return Err(DbError::Schema(format!(
+                                "Unsupported option for column {}",
+                                field_name
+                            )));
+                        }
+                    }
+                }
+                AlterTableAction::AddColumn {
+                    field_name,
+                    field_type,
+                    length,
+                    default,
+                }
+            }
+            AlterTableOperation::DropColumn { column_name, .. } => AlterTableAction::DropColumn {
+                field_name: column_name.value.clone(),
+            },
+            AlterTableOperation::RenameColumn {
+                old_column_name,
+                new_column_name,
+            } => AlterTableAction::RenameColumn {
+                field_name: old_column_name.value.clone(),
+                new_field_name: new_column_name.value.clone(),
+            },
+            AlterTableOperation::RenameTable { table_name } => AlterTableAction::RenameTable {
+                new_table_name: table_name.to_string(),
+            },
+            _ => {
+                return Err(DbError::Schema(
+                    "Unsupported ALTER TABLE operation".to_string(),
+                ));
+            }
+        };
+
+        Ok(Statement::AlterTable {
+            table_name: table_name.to_string(),
+            action,
+        })
     }
 
     fn parse_create_index(
@@ -157,6 +434,14 @@ impl Parser {
             .iter()
             .map(|col| col.value.clone())
             .collect::<Vec<String>>();
+        for (i, field_name) in fields.iter().enumerate() {
+            if fields[..i].contains(field_name) {
+                return Err(DbError::Schema(format!(
+                    "Field {} is listed more than once",
+                    field_name
+                )));
+            }
+        }
         let source = insert.source.as_ref();
 
         let values = match source {
@@ -168,20 +453,7 @@ impl Parser {
 
                     let row = &values.rows[0];
                     row.iter()
-                        .map(|expr| match expr {
-                            sqlparser::ast::Expr::Value(value) => match &value.value {
-                                Value::SingleQuotedString(s) => Ok(Constant::String(s.clone())),
-                                Value::Number(n, _) => {
-                                    Ok(Constant::Int(n.parse().map_err(|_| {
-                                        DbError::Schema(format!("Invalid integer value: {}", n))
-                                    })?))
-                                }
-                                _ => Err(DbError::Schema(format!("Unsupported value type"))),
-                            },
-                            _ => Err(DbError::Schema(format!(
-                                "Unsupported value type in INSERT statement"
-                            ))),
-                        })
+                        .map(Self::parse_constant)
                         .collect::<DbResult<Vec<Constant>>>()?
                 }
                 _ => {
@@ -215,25 +487,15 @@ impl Parser {
 
         for assignment in assignments {
             let field_name = assignment.target.to_string();
+            if fields.contains(&field_name) {
+                return Err(DbError::Schema(format!(
+                    "Field {} is assigned more than once",
+                    field_name
+                )));
+            }
             fields.push(field_name);
 
-            let value =
-                match &assignment.value {
-                    sqlparser::ast::Expr::Value(value) => match &value.value {
-                        Value::SingleQuotedString(s) => Ok(Constant::String(s.clone())),
-                        Value::Number(n, _) => Ok(Constant::Int(n.parse().map_err(|_| {
-                            DbError::Schema(format!("Invalid integer value: {}", n))
-                        })?)),
-                        _ => Err(DbError::Schema(
-                            "Unsupported value type in UPDATE".to_string(),
-                        )),
-                    },
-                    _ => Err(DbError::Schema(
-                        "Only simple values are supported in UPDATE".to_string(),
-                    )),
-                }?;
-
-            values.push(value);
+            values.push(Self::parse_constant(&assignment.value)?);
         }
 
         let predicate = if let Some(where_clause) = selection {
@@ -250,172 +512,846 @@ impl Parser {
         })
     }
 
-    fn parse_select(&self, query: &SetExpr) -> DbResult<Statement> {
-        return match query {
-            SetExpr::Select(select) => {
-                let fields = select
-                    .projection
-                    .iter()
-                    .map(|item| match item {
-                        sqlparser::ast::SelectItem::UnnamedExpr(
-                            sqlparser::ast::Expr::Identifier(ident),
-                        ) => Ok(ident.value.clone()),
-                        sqlparser::ast::SelectItem::ExprWithAlias { expr, alias } => match expr {
-                            sqlparser::ast::Expr::Identifier(_ident) => Ok(alias.value.clone()),
-                            _ => Err(DbError::Schema(
-                                "Only simple column references are supported".to_string(),
-                            )),
-                        },
-                        _ => Err(DbError::Schema(
-                            "Only simple column references are supported".to_string(),
-                        )),
-                    })
-                    .collect::<DbResult<Vec<String>>>()?;
-
-                let tables = select
-                    .from
-                    .iter()
-                    .map(|table_with_join| match &table_with_join.relation {
-                        sqlparser::ast::TableFactor::Table { name, .. } => Ok(name.to_string()),
-                        _ => Err(DbError::Schema(
-                            "Only simple table references are supported".to_string(),
-                        )),
-                    })
-                    .collect::<DbResult<Vec<String>>>()?;
-
-                let predicate = if let Some(where_clause) = &select.selection {
-                    Some(self.parse_where_clause(where_clause)?)
-                } else {
-                    None
-                };
+    fn parse_delete(&self, delete: &sqlparser::ast::Delete) -> DbResult<Statement> {
+        let from = match &delete.from {
+            sqlparser::ast::FromTable::WithFromKeyword(from) => from,
+            sqlparser::ast::FromTable::WithoutKeyword(from) => from,
+        };
+        if from.len() != 1 || !delete.tables.is_empty() || delete.using.is_some() {
+            return Err(DbError::Schema(
+                "Only single table DELETE is supported".to_string(),
+            ));
+        }
 
-                Ok(Statement::Query {
-                    fields,
-                    tables,
-                    predicate,
-                })
-            }
+        let table_name = match &from[0].relation {
+            sqlparser::ast::TableFactor::Table { name, .. } => name.to_string(),
             _ => {
                 return Err(DbError::Schema(
-                    "Only VALUES clause is supported for INSERT".to_string(),
+                    "Only simple table references are supported in DELETE".to_string(),
                 ));
             }
         };
+
+        let predicate = if let Some(where_clause) = &delete.selection {
+            Some(self.parse_where_clause(where_clause)?)
+        } else {
+            None
+        };
+
+        Ok(Statement::Delete {
+            table_name,
+            predicate,
+        })
     }
 
-    fn parse_where_clause(&self, expr: &sqlparser::ast::Expr) -> DbResult<Predicate> {
-        match expr {
-            sqlparser::ast::Expr::BinaryOp { left, op, right } => match op {
-                sqlparser::ast::BinaryOperator::Eq => {
-                    let field = match &**left {
-                        sqlparser::ast::Expr::Identifier(ident) => ident.value.clone(),
-                        _ => {
-                            return Err(DbError::Schema(
-                                "Left side of = must be a field name".to_string(),
-                            ));
+    fn parse_drop(
+        &self,
+        object_type: &sqlparser::ast::ObjectType,
+        if_exists: bool,
+        names: &[sqlparser::ast::ObjectName],
+    ) -> DbResult<Statement> {
+        if names.len() != 1 {
+            return Err(DbError::Schema(
+                "Only a single object can be dropped at a time".to_string(),
+            ));
+        }
+        let name = names[0].to_string();
+
+        match object_type {
+            sqlparser::ast::ObjectType::Table => Ok(Statement::DropTable {
+                table_name: name,
+                if_exists,
+            }),
+            sqlparser::ast::ObjectType::Index => Ok(Statement::DropIndex { name, if_exists }),
+            _ => Err(DbError::Schema(format!(
+                "DROP {} is not supported",
+                object_type
+            ))),
+        }
+    }
+
+    fn parse_select(&self, query: &sqlparser::ast::Query) -> DbResult<Statement> {
+        let order_by = match &query.order_by {
+            Some(order_by) => self.parse_order_by(order_by)?,
+            None => Vec::new(),
+        };
+        let (limit, offset) = match &query.limit_clause {
+            Some(limit_clause) => self.parse_limit_clause(limit_clause)?,
+            None => (None, None),
+        };
+
+        return match query.body.as_ref() {
+            SetExpr::Select(select) => {
+                let mut aggregates: Vec<AggregateFn> = Vec::new();
+                let mut fields = Vec::with_capacity(select.projection.len());
+                for item in &select.projection {
+                    let aggregate = match item {
+                        sqlparser::ast::SelectItem::UnnamedExpr(
+                            sqlparser::ast::Expr::Identifier(ident),
+                        ) => {
+                            fields.push(ident.value.clone());
+                            continue;
                         }
-                    };
-                    let value = match &**right {
-                        sqlparser::ast::Expr::Value(value) => match &value.value {
-                            Value::SingleQuotedString(s) => Constant::String(s.clone()),
-                            Value::Number(n, _) => Constant::Int(n.parse().map_err(|_| {
-                                DbError::Schema(format!("Invalid integer value: {}", n))
-                            })?),
+                        sqlparser::ast::SelectItem::UnnamedExpr(
+                            sqlparser::ast::Expr::CompoundIdentifier(idents),
+                        ) => {
+                            fields.push(Self::field_name(idents)?);
+                            continue;
+                        }
+                        sqlparser::ast::SelectItem::UnnamedExpr(
+                            sqlparser::ast::Expr::Function(function),
+                        ) => Self::parse_aggregate(function)?,
+                        sqlparser::ast::SelectItem::ExprWithAlias { expr, alias } => match expr {
+                            sqlparser::ast::Expr::Identifier(_ident) => {
+                                fields.push(alias.value.clone());
+                                continue;
+                            }
+                            sqlparser::ast::Expr::Function(function) => {
+                                Self::parse_aggregate(function)?.with_alias(alias.value.clone())
+                            }
                             _ => {
                                 return Err(DbError::Schema(
-                                    "Unsupported value type in WHERE clause".to_string(),
+                                    "Only simple column references are supported".to_string(),
                                 ));
                             }
                         },
                         _ => {
                             return Err(DbError::Schema(
-                                "Right side of = must be a value".to_string(),
+                                "Only simple column references are supported".to_string(),
                             ));
                         }
                     };
-                    Ok(Predicate::default()
-                        .with_term(Term::new(Expr::field_name(field), Expr::constant(value))))
-                }
-                sqlparser::ast::BinaryOperator::And => {
-                    let left_pred = self.parse_where_clause(left)?;
-                    let right_pred = self.parse_where_clause(right)?;
-                    Ok(left_pred.conjoin_with(right_pred))
+                    fields.push(aggregate.output_name().to_string());
+                    if !aggregates.contains(&aggregate) {
+                        aggregates.push(aggregate);
+                    }
                 }
-                _ => Err(DbError::Schema(
-                    "Only = and AND operators are supported in WHERE clause".to_string(),
-                )),
-            },
-            _ => Err(DbError::Schema(
-                "Unsupported expression in WHERE clause".to_string(),
-            )),
-        }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::record::schema::FieldType;
+                let mut tables = Vec::new();
+                let mut predicate: Option<Predicate> = None;
+                for table_with_joins in &select.from {
+                    tables.push(Self::table_name(&table_with_joins.relation)?);
+                    for join in &table_with_joins.joins {
+                        tables.push(Self::table_name(&join.relation)?);
+                        if let Some(on_pred) = self.parse_join_constraint(&join.join_operator)? {
+                            predicate = Some(predicate.unwrap_or_default().conjoin_with(on_pred));
+                        }
+                    }
+                }
 
-    use super::*;
+                if let Some(where_clause) = &select.selection {
+                    let where_pred = self.parse_where_clause(where_clause)?;
+                    predicate = Some(predicate.unwrap_or_default().conjoin_with(where_pred));
+                }
 
-    #[test]
-    fn test_parse_create_table() -> DbResult<()> {
-        let parser = Parser::new();
-        let sql = "CREATE TABLE test_table (id INT, name VARCHAR(20))";
+                let group_by = match &select.group_by {
+                    sqlparser::ast::GroupByExpr::Expressions(exprs, modifiers)
+                        if modifiers.is_empty() =>
+                    {
+                        exprs
+                            .iter()
+                            .map(|expr| match expr {
+                                sqlparser::ast::Expr::Identifier(ident) => Ok(ident.value.clone()),
+                                sqlparser::ast::Expr::CompoundIdentifier(idents) => {
+                                    Self::field_name(idents)
+                                }
+                                _ => Err(DbError::Schema(
+                                    "Only column references are supported in GROUP BY".to_string(),
+                                )),
+                            })
+                            .collect::<DbResult<Vec<_>>>()?
+                    }
+                    _ => {
+                        return Err(DbError::Schema(
+                            "Only column references are supported in GROUP BY".to_string(),
+                        ));
+                    }
+                };
 
-        let stmt = parser.parse(sql)?;
+                // aggregates which are only used by HAVING or ORDER BY are computed as well
+                let having = match &select.having {
+                    Some(having) => {
+                        Self::collect_aggregates(having, &mut aggregates)?;
+                        Some(self.parse_condition(having)?)
+                    }
+                    None => None,
+                };
+                if let Some(sqlparser::ast::OrderByKind::Expressions(exprs)) =
+                    query.order_by.as_ref().map(|order_by| &order_by.kind)
+                {
+                    for order_by_expr in exprs {
+                        Self::collect_aggregates(&order_by_expr.expr, &mut aggregates)?;
+                    }
+                }
 
-        match stmt {
-            Statement::CreateTable { table_name, schema } => {
-                assert_eq!(table_name, "test_table");
-                assert!(schema.has_field("id"));
-                assert!(schema.has_field("name"));
-                assert_eq!(schema.field_type("id"), Some(FieldType::Integer));
-                assert_eq!(schema.field_type("name"), Some(FieldType::Varchar));
-                assert_eq!(schema.length("name"), Some(20));
+                Ok(Statement::Query {
+                    fields,
+                    tables,
+                    predicate,
+                    group_by,
+                    aggregates,
+                    having,
+                    order_by,
+                    limit,
+                    offset,
+                })
             }
-            _ => panic!("Unexpected statement"),
-        }
-
-        Ok(())
+            _ => {
+                return Err(DbError::Schema(
+                    "Only VALUES clause is supported for INSERT".to_string(),
+                ));
+            }
+        };
     }
 
-    #[test]
-    fn test_parse_invalid_sql() {
-        let parser = Parser::new();
-        let sql = "INVALID SQL";
-
-        assert!(parser.parse(sql).is_err());
+    fn parse_order_by(&self, order_by: &sqlparser::ast::OrderBy) -> DbResult<Vec<SortField>> {
+        let exprs = match &order_by.kind {
+            sqlparser::ast::OrderByKind::Expressions(exprs) => exprs,
+            sqlparser::ast::OrderByKind::All(_) => {
+                return Err(DbError::Schema("ORDER BY ALL is not supported".to_string()));
+            }
+        };
+        exprs
+            .iter()
+            .map(|order_by_expr| {
+                let field_name = match &order_by_expr.expr {
+                    sqlparser::ast::Expr::Identifier(ident) => ident.value.clone(),
+                    sqlparser::ast::Expr::CompoundIdentifier(idents) => Self::field_name(idents)?,
+                    sqlparser::ast::Expr::Function(function) => {
+                        Self::parse_aggregate(function)?.output_name().to_string()
+                    }
+                    _ => {
+                        return Err(DbError::Schema(
+                            "Only column references and aggregates are supported in ORDER BY"
+                                .to_string(),
+                        ));
+                    }
+                };
+                if order_by_expr.options.nulls_first.is_some() || order_by_expr.with_fill.is_some()
+                {
+                    return Err(DbError::Schema(
+                        "Only ASC and DESC are supported in ORDER BY".to_string(),
+                    ));
+                }
+                Ok(match order_by_expr.options.asc {
+                    Some(false) => SortField::desc(field_name),
+                    _ => SortField::asc(field_name),
+                })
+            })
+            .collect()
     }
 
-    #[test]
-    fn test_parse_insert() -> DbResult<()> {
-        let parser = Parser::new();
-        let sql = "INSERT INTO test_table (id, name) VALUES (1, 'Alice')";
-
-        let stmt = parser.parse(sql)?;
-
-        match stmt {
-            Statement::Insert {
-                table_name,
-                fields,
-                values,
+    /// Parses the LIMIT and OFFSET clauses, returning the limit and the offset.
+    fn parse_limit_clause(
+        &self,
+        limit_clause: &sqlparser::ast::LimitClause,
+    ) -> DbResult<(Option<usize>, Option<usize>)> {
+        match limit_clause {
+            sqlparser::ast::LimitClause::LimitOffset {
+                limit,
+                offset,
+                limit_by,
             } => {
-                assert_eq!(table_name, "test_table");
-                assert_eq!(fields, vec!["id", "name"]);
-                assert_eq!(values.len(), 2);
-                assert_eq!(values[0], Constant::Int(1));
-                assert_eq!(values[1], Constant::String("Alice".to_string()));
+                if !limit_by.is_empty() {
+                    return Err(DbError::Schema("LIMIT BY is not supported".to_string()));
+                }
+                let limit = limit.as_ref().map(Self::parse_count).transpose()?;
+                let offset = offset
+                    .as_ref()
+                    .map(|offset| Self::parse_count(&offset.value))
+                    .transpose()?;
+                Ok((limit, offset))
             }
-            _ => panic!("Unexpected statement"),
+            sqlparser::ast::LimitClause::OffsetCommaLimit { offset, limit } => Ok((
+                Some(Self::parse_count(limit)?),
+                Some(Self::parse_count(offset)?),
+            )),
         }
-
-        Ok(())
     }
 
-    #[test]
-    fn test_parse_insert_invalid() {
-        let parser = Parser::new();
+    /// Parses the non-negative number of rows given to LIMIT or OFFSET.
+    fn parse_count(expr: &sqlparser::ast::Expr) -> DbResult<usize> {
+        match expr {
+            sqlparser::ast::Expr::Value(value) => match &value.value {
+                Value::Number(n, _) => n
+                    .parse()
+                    .map_err(|_| DbError::Schema(format!("Invalid row count: {}", n))),
+                _ => Err(DbError::Schema(format!("Invalid row count: {}", value))),
+            },
+            _ => Err(DbError::Schema(
+                "Only numbers are supported in LIMIT and OFFSET".to_string(),
+            )),
+        }
+    }
+
+    fn parse_where_clause(&self, expr: &sqlparser::ast::Expr) -> DbResult<Predicate> {
+        let mut aggregates = Vec::new();
+        Self::collect_aggregates(expr, &mut aggregates)?;
+        if !aggregates.is_empty() {
+            return Err(DbError::Schema(
+                "Aggregate functions are not allowed in WHERE clause".to_string(),
+            ));
+        }
+        self.parse_condition(expr)
+    }
+
+    /// Parses a condition of a WHERE, ON or HAVING clause. Aggregate functions
+    /// refer to the fields of their output.
+    fn parse_condition(&self, expr: &sqlparser::ast::Expr) -> DbResult<Predicate> {
+        match expr {
+            sqlparser::ast::Expr::BinaryOp {
+                left,
+                op: sqlparser::ast::BinaryOperator::And,
+                right,
+            } => {
+                let left_pred = self.parse_condition(left)?;
+                let right_pred = self.parse_condition(right)?;
+                Ok(left_pred.conjoin_with(right_pred))
+            }
+            sqlparser::ast::Expr::Nested(expr) => self.parse_condition(expr),
+            _ => Ok(Predicate::new(self.parse_term(expr)?)),
+        }
+    }
+
+    /// Parses a boolean expression into a term. Conjunctions nested in other
+    /// expressions become [Term::And].
+    fn parse_term(&self, expr: &sqlparser::ast::Expr) -> DbResult<Term> {
+        use sqlparser::ast::{BinaryOperator, UnaryOperator};
+
+        match expr {
+            sqlparser::ast::Expr::BinaryOp { left, op, right } => {
+                let op = match op {
+                    BinaryOperator::And => {
+                        return Ok(Term::and(vec![
+                            self.parse_term(left)?,
+                            self.parse_term(right)?,
+                        ]));
+                    }
+                    BinaryOperator::Or => {
+                        return Ok(Term::or(vec![
+                            self.parse_term(left)?,
+                            self.parse_term(right)?,
+                        ]));
+                    }
+                    BinaryOperator::Eq => CompareOp::Eq,
+                    BinaryOperator::NotEq => CompareOp::Ne,
+                    BinaryOperator::Lt => CompareOp::Lt,
+                    BinaryOperator::LtEq => CompareOp::Le,
+                    BinaryOperator::Gt => CompareOp::Gt,
+                    BinaryOperator::GtEq => CompareOp::Ge,
+                    _ => {
+                        return Err(DbError::Schema(format!(
+                            "Unsupported operator in WHERE clause: {}",
+                            op
+                        )));
+                    }
+                };
+                let lhs = self.parse_expr(left)?;
+                let rhs = self.parse_expr(right)?;
+                if !lhs.is_field_name() && !rhs.is_field_name() {
+                    return Err(DbError::Schema(format!(
+                        "One side of {} must be a field name",
+                        op
+                    )));
+                }
+                Ok(Term::compare(lhs, op, rhs))
+            }
+            sqlparser::ast::Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => Ok(Term::negate(self.parse_term(expr)?)),
+            sqlparser::ast::Expr::Nested(expr) => self.parse_term(expr),
+            sqlparser::ast::Expr::InList {
+                expr,
+                list,
+                negated,
+            } => Ok(Term::in_list(
+                self.parse_expr(expr)?,
+                list.iter()
+                    .map(|item| self.parse_expr(item))
+                    .collect::<DbResult<Vec<_>>>()?,
+                *negated,
+            )),
+            sqlparser::ast::Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => Ok(Term::between(
+                self.parse_expr(expr)?,
+                self.parse_expr(low)?,
+                self.parse_expr(high)?,
+                *negated,
+            )),
+            sqlparser::ast::Expr::IsNull(expr) => Ok(Term::is_null(self.parse_expr(expr)?, false)),
+            sqlparser::ast::Expr::IsNotNull(expr) => {
+                Ok(Term::is_null(self.parse_expr(expr)?, true))
+            }
+            _ => Err(DbError::Schema(
+                "Unsupported expression in WHERE clause".to_string(),
+            )),
+        }
+    }
+
+    /// Parses an operand of a comparison, which is either a field name or a value.
+    fn parse_expr(&self, expr: &sqlparser::ast::Expr) -> DbResult<Expr> {
+        match expr {
+            sqlparser::ast::Expr::Identifier(ident) => Ok(Expr::field_name(ident.value.clone())),
+            sqlparser::ast::Expr::CompoundIdentifier(idents) => {
+                Ok(Expr::field_name(Self::field_name(idents)?))
+            }
+            sqlparser::ast::Expr::Function(function) => Ok(Expr::field_name(
+                Self::parse_aggregate(function)?.output_name(),
+            )),
+            sqlparser::ast::Expr::Value(_)
+            | sqlparser::ast::Expr::TypedString { .. }
+            | sqlparser::ast::Expr::UnaryOp { .. } => {
+                Ok(Expr::constant(Self::parse_constant(expr)?))
+            }
+            _ => Err(DbError::Schema(
+                "Only field names and values can be compared in WHERE clause".to_string(),
+            )),
+        }
+    }
+
+    fn parse_hex(hex: &str) -> DbResult<Constant> {
+        let invalid = || DbError::Schema(format!("Invalid hex string: X'{}'", hex));
+        if !hex.len().is_multiple_of(2) {
+            return Err(invalid());
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(invalid)
+            })
+            .collect::<DbResult<_>>()
+            .map(Constant::Bytes)
+    }
+
+    /// Parses a literal value such as `42`, `-1.5`, `'text'`, `TRUE`, `NULL`, `DATE '2024-01-31'`,
+    /// `TIMESTAMP '2024-01-31 12:00:00'` or the bytes `X'CAFE'`. Integers which don't fit an INT are BIGINT values
+    /// and numbers with a fraction or exponent are DOUBLE values.
+    fn parse_constant(expr: &sqlparser::ast::Expr) -> DbResult<Constant> {
+        match expr {
+            sqlparser::ast::Expr::Value(value) => match &value.value {
+                Value::SingleQuotedString(s) => Ok(Constant::String(s.clone())),
+                Value::Null => Ok(Constant::Null),
+                Value::Boolean(b) => Ok(Constant::Bool(*b)),
+                Value::HexStringLiteral(hex) => Self::parse_hex(hex),
+                Value::Number(n, _) if n.contains(['.', 'e', 'E']) => n
+                    .parse()
+                    .map(Constant::Double)
+                    .map_err(|_| DbError::Schema(format!("Invalid number: {}", n))),
+                Value::Number(n, _) => match n.parse::<i64>() {
+                    Ok(i) => Ok(i32::try_from(i).map_or(Constant::BigInt(i), Constant::Int)),
+                    Err(_) => Err(DbError::Schema(format!("Invalid integer value: {}", n))),
+                },
+                _ => Err(DbError::Schema(format!("Unsupported value: {}", value))),
+            },
+            sqlparser::ast::Expr::TypedString { data_type, value } => {
+                let Value::SingleQuotedString(s) = value else {
+                    return Err(DbError::Schema(format!("Unsupported value: {}", expr)));
+                };
+                match data_type {
+                    DataType::Date => Constant::parse_date(s),
+                    DataType::Timestamp(_, _) | DataType::Datetime(_) => {
+                        Constant::parse_timestamp(s)
+                    }
+                    _ => Err(DbError::Schema(format!("Unsupported value: {}", expr))),
+                }
+            }
+            sqlparser::ast::Expr::UnaryOp {
+                op: sqlparser::ast::UnaryOperator::Minus,
+                expr,
+            } => match Self::parse_constant(expr)? {
+                Constant::Int(n) => Ok(Constant::Int(-n)),
+                //  the magnitude of i32::MIN only fits a BIGINT
+                Constant::BigInt(n) => {
+                    Ok(i32::try_from(-n).map_or(Constant::BigInt(-n), Constant::Int))
+                }
+                Constant::Double(d) => Ok(Constant::Double(-d)),
+                _ => Err(DbError::Schema(
+                    "Only numeric values can be negated".to_string(),
+                )),
+            },
+            _ => Err(DbError::Schema(format!("Unsupported value: {}", expr))),
+        }
+    }
+
+    /// Parses a call of an aggregate function, e.g. `COUNT(*)` or `SUM(salary)`.
+    fn parse_aggregate(function: &sqlparser::ast::Function) -> DbResult<AggregateFn> {
+        use sqlparser::ast::{DuplicateTreatment, FunctionArg, FunctionArgExpr, FunctionArguments};
+
+        let name = function.name.to_string().to_lowercase();
+        let kind = match name.as_str() {
+            "count" => AggregateKind::Count,
+            "sum" => AggregateKind::Sum,
+            "min" => AggregateKind::Min,
+            "max" => AggregateKind::Max,
+            "avg" => AggregateKind::Avg,
+            _ => return Err(DbError::Schema(format!("Unsupported function: {}", name))),
+        };
+        let unsupported = || DbError::Schema(format!("Unsupported arguments of {}", name));
+        if function.filter.is_some()
+            || function.over.is_some()
+            || function.null_treatment.is_some()
+            || !function.within_group.is_empty()
+        {
+            return Err(unsupported());
+        }
+
+        let args = match &function.args {
+            FunctionArguments::List(list)
+                if list.clauses.is_empty()
+                    && matches!(
+                        list.duplicate_treatment,
+                        None | Some(DuplicateTreatment::All)
+                    ) =>
+            {
+                &list.args
+            }
+            _ => return Err(unsupported()),
+        };
+        let field_name = match args.as_slice() {
+            [FunctionArg::Unnamed(FunctionArgExpr::Wildcard)] if kind == AggregateKind::Count => {
+                None
+            }
+            [FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))] => match expr {
+                sqlparser::ast::Expr::Identifier(ident) => Some(ident.value.clone()),
+                sqlparser::ast::Expr::CompoundIdentifier(idents) => Some(Self::field_name(idents)?),
+                _ => return Err(unsupported()),
+            },
+            _ => return Err(unsupported()),
+        };
+        Ok(AggregateFn::new(kind, field_name))
+    }
+
+    /// Adds the aggregate functions called in an expression to `aggregates`, unless they are already there.
+    fn collect_aggregates(
+        expr: &sqlparser::ast::Expr,
+        aggregates: &mut Vec<AggregateFn>,
+    ) -> DbResult<()> {
+        match expr {
+            sqlparser::ast::Expr::Function(function) => {
+                let aggregate = Self::parse_aggregate(function)?;
+                if !aggregates.contains(&aggregate) {
+                    aggregates.push(aggregate);
+                }
+            }
+            sqlparser::ast::Expr::BinaryOp { left, right, .. } => {
+                Self::collect_aggregates(left, aggregates)?;
+                Self::collect_aggregates(right, aggregates)?;
+            }
+            sqlparser::ast::Expr::UnaryOp { expr, .. }
+            | sqlparser::ast::Expr::Nested(expr)
+            | sqlparser::ast::Expr::IsNull(expr)
+            | sqlparser::ast::Expr::IsNotNull(expr) => {
+                Self::collect_aggregates(expr, aggregates)?;
+            }
+            sqlparser::ast::Expr::InList { expr, list, .. } => {
+                Self::collect_aggregates(expr, aggregates)?;
+                for item in list {
+                    Self::collect_aggregates(item, aggregates)?;
+                }
+            }
+            sqlparser::ast::Expr::Between {
+                expr, low, high, ..
+            } => {
+                Self::collect_aggregates(expr, aggregates)?;
+                Self::collect_aggregates(low, aggregates)?;
+                Self::collect_aggregates(high, aggregates)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Parses the condition of an inner join. Returns None for joins without a condition,
+    /// whose rows are then filtered by the WHERE clause only.
+    fn parse_join_constraint(
+        &self,
+        join_operator: &sqlparser::ast::JoinOperator,
+    ) -> DbResult<Option<Predicate>> {
+        use sqlparser::ast::{JoinConstraint, JoinOperator};
+
+        match join_operator {
+            JoinOperator::Join(constraint) | JoinOperator::Inner(constraint) => match constraint {
+                JoinConstraint::On(expr) => Ok(Some(self.parse_where_clause(expr)?)),
+                JoinConstraint::None => Ok(None),
+                _ => Err(DbError::Schema(
+                    "Only ON conditions are supported for joins".to_string(),
+                )),
+            },
+            JoinOperator::CrossJoin => Ok(None),
+            _ => Err(DbError::Schema(
+                "Only inner and cross joins are supported".to_string(),
+            )),
+        }
+    }
+
+    fn table_name(relation: &sqlparser::ast::TableFactor) -> DbResult<String> {
+        match relation {
+            sqlparser::ast::TableFactor::Table { name, .. } => Ok(name.to_string()),
+            _ => Err(DbError::Schema(
+                "Only simple table references are supported".to_string(),
+            )),
+        }
+    }
+
+    /// A qualified name such as `emp.dept_id` refers to its last part. The planner
+    /// rejects the names which belong to more than one table of a query.
+    fn field_name(idents: &[sqlparser::ast::Ident]) -> DbResult<String> {
+        idents
+            .last()
+            .map(|ident| ident.value.clone())
+            .ok_or_else(|| DbError::Schema("Empty field name".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::record::schema::FieldType;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_create_table() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql = "CREATE TABLE test_table (id INT, name VARCHAR(20))";
+
+        let stmt = parser.parse(sql)?;
+
+        match stmt {
+            Statement::CreateTable {
+                table_name,
+                schema,
+                format,
+            } => {
+                assert_eq!(table_name, "test_table");
+                assert_eq!(format, RecordFormat::Fixed);
+                assert!(schema.has_field("id"));
+                assert!(schema.has_field("name"));
+                assert_eq!(schema.field_type("id"), Some(FieldType::Integer));
+                assert_eq!(schema.field_type("name"), Some(FieldType::Varchar));
+                assert_eq!(schema.length("name"), Some(20));
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_create_table_format() -> DbResult<()> {
+        let parser = Parser::new();
+
+        let sql = "CREATE TABLE t (id INT, name VARCHAR(200)) WITH (format = 'slotted')";
+        let Statement::CreateTable { format, .. } = parser.parse(sql)? else {
+            panic!("Unexpected statement");
+        };
+        assert_eq!(format, RecordFormat::Slotted);
+
+        let sql = "CREATE TABLE t (id INT) WITH (FORMAT = fixed)";
+        let Statement::CreateTable { format, .. } = parser.parse(sql)? else {
+            panic!("Unexpected statement");
+        };
+        assert_eq!(format, RecordFormat::Fixed);
+
+        assert!(
+            parser
+                .parse("CREATE TABLE t (id INT) WITH (format = 'columnar')")
+                .is_err()
+        );
+        assert!(
+            parser
+                .parse("CREATE TABLE t (id INT) WITH (fillfactor = 70)")
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_create_table_constraints() -> DbResult<()> {
+        let parser = Parser::new();
+
+        let sql = "CREATE TABLE t (id INT PRIMARY KEY, email VARCHAR(30) NOT NULL UNIQUE, \
+                   name VARCHAR(20) NULL)";
+        let Statement::CreateTable { schema, .. } = parser.parse(sql)? else {
+            panic!("Unexpected statement");
+        };
+        assert_eq!(schema.primary_key(), Some("id"));
+        assert!(schema.has_constraint("email", Constraint::NotNull));
+        assert!(schema.has_constraint("email", Constraint::Unique));
+        assert_eq!(schema.constraints("name"), 0);
+
+        let sql = "CREATE TABLE t (id INT, code INT, PRIMARY KEY (id), UNIQUE (code))";
+        let Statement::CreateTable { schema, .. } = parser.parse(sql)? else {
+            panic!("Unexpected statement");
+        };
+        assert_eq!(schema.primary_key(), Some("id"));
+        assert!(schema.is_unique("code") && !schema.is_not_null("code"));
+
+        for sql in [
+            "CREATE TABLE t (id INT PRIMARY KEY, code INT PRIMARY KEY)",
+            "CREATE TABLE t (id INT PRIMARY KEY, code INT, PRIMARY KEY (code))",
+            "CREATE TABLE t (id INT, code INT, UNIQUE (id, code))",
+            "CREATE TABLE t (id INT, UNIQUE (missing))",
+            "CREATE TABLE t (id INT CHECK (id > 0))",
+        ] {
+            assert!(parser.parse(sql).is_err(), "{}", sql);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_text_and_blob() -> DbResult<()> {
+        let parser = Parser::new();
+
+        let sql = "CREATE TABLE docs (id INT, body TEXT, data BLOB)";
+        let Statement::CreateTable { schema, .. } = parser.parse(sql)? else {
+            panic!("Unexpected statement");
+        };
+        assert_eq!(schema.field_type("body"), Some(FieldType::Text));
+        assert_eq!(schema.field_type("data"), Some(FieldType::Blob));
+
+        let sql = "INSERT INTO docs (id, data) VALUES (1, X'00ff10')";
+        let Statement::Insert { values, .. } = parser.parse(sql)? else {
+            panic!("Unexpected statement");
+        };
+        assert_eq!(values[1], Constant::Bytes(vec![0x00, 0xff, 0x10]));
+
+        assert!(
+            parser
+                .parse("INSERT INTO docs (data) VALUES (X'abc')")
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_sql() {
+        let parser = Parser::new();
+        let sql = "INVALID SQL";
+
+        assert!(parser.parse(sql).is_err());
+    }
+
+    #[test]
+    fn test_parse_insert() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql = "INSERT INTO test_table (id, name) VALUES (1, 'Alice')";
+
+        let stmt = parser.parse(sql)?;
+
+        match stmt {
+            Statement::Insert {
+                table_name,
+                fields,
+                values,
+            } => {
+                assert_eq!(table_name, "test_table");
+                assert_eq!(fields, vec!["id", "name"]);
+                assert_eq!(values.len(), 2);
+                assert_eq!(values[0], Constant::Int(1));
+                assert_eq!(values[1], Constant::String("Alice".to_string()));
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_column_types() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql = "CREATE TABLE t (a BIGINT, b BOOLEAN, c DOUBLE, d DATE, e TIMESTAMP, f INTEGER)";
+
+        let Statement::CreateTable { schema, .. } = parser.parse(sql)? else {
+            panic!("Unexpected statement");
+        };
+        let types: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|field| schema.field_type(field).unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                FieldType::BigInt,
+                FieldType::Boolean,
+                FieldType::Double,
+                FieldType::Date,
+                FieldType::Timestamp,
+                FieldType::Integer,
+            ]
+        );
+
+        assert!(parser.parse("CREATE TABLE t (a FLOAT)").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_literals() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql = "INSERT INTO t (a, b, c, d, e, f, g, h) VALUES (5000000000, -2147483648, TRUE, \
+                   -1.5, 2e3, DATE '2024-01-31', TIMESTAMP '2024-01-31 08:00:00', NULL)";
+
+        let Statement::Insert { values, .. } = parser.parse(sql)? else {
+            panic!("Unexpected statement");
+        };
+        let types: Vec<_> = values.iter().map(Constant::field_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                Some(FieldType::BigInt),
+                Some(FieldType::Integer),
+                Some(FieldType::Boolean),
+                Some(FieldType::Double),
+                Some(FieldType::Double),
+                Some(FieldType::Date),
+                Some(FieldType::Timestamp),
+                None,
+            ]
+        );
+        assert_eq!(values[0], Constant::BigInt(5_000_000_000));
+        assert_eq!(values[1], Constant::Int(i32::MIN));
+        assert_eq!(values[3], Constant::Double(-1.5));
+        assert_eq!(values[4], Constant::Double(2000.0));
+        assert_eq!(values[5].to_string(), "2024-01-31");
+        assert_eq!(values[6].to_string(), "2024-01-31 08:00:00");
+
+        let stmt = parser.parse("SELECT a FROM t WHERE d < DATE '2024-02-01' AND b = FALSE")?;
+        let Statement::Query { predicate, .. } = stmt else {
+            panic!("Unexpected statement");
+        };
+        assert_eq!(
+            predicate,
+            Some(
+                Predicate::new(Term::compare(
+                    Expr::field_name("d"),
+                    CompareOp::Lt,
+                    Expr::constant(Constant::parse_date("2024-02-01")?),
+                ))
+                .with_term(Term::new(
+                    Expr::field_name("b"),
+                    Expr::constant(Constant::Bool(false)),
+                ))
+            )
+        );
+
+        assert!(
+            parser
+                .parse("INSERT INTO t (d) VALUES (DATE '2024-02-30')")
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_insert_invalid() {
+        let parser = Parser::new();
         let sql = "INSERT INTO test_table VALUES (1, 'Alice')"; // Missing column names
 
         assert!(parser.parse(sql).is_err());
@@ -433,6 +1369,7 @@ mod tests {
                 fields,
                 tables,
                 predicate,
+                ..
             } => {
                 assert_eq!(fields, vec!["id", "name"]);
                 assert_eq!(tables, vec!["test_table"]);
@@ -469,6 +1406,7 @@ mod tests {
                 fields,
                 tables,
                 predicate,
+                ..
             } => {
                 assert_eq!(fields, vec!["id"]);
                 assert_eq!(tables, vec!["test_table"]);
@@ -480,6 +1418,237 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_select_multiple_tables() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql = "SELECT ename, dname FROM emp, dept WHERE emp.edept = dept.did AND eid = 3";
+
+        let stmt = parser.parse(sql)?;
+
+        match stmt {
+            Statement::Query {
+                fields,
+                tables,
+                predicate,
+                ..
+            } => {
+                assert_eq!(fields, vec!["ename", "dname"]);
+                assert_eq!(tables, vec!["emp", "dept"]);
+                assert_eq!(
+                    predicate,
+                    Some(
+                        Predicate::new(Term::new(
+                            Expr::field_name("edept"),
+                            Expr::field_name("did")
+                        ))
+                        .with_term(Term::new(
+                            Expr::field_name("eid"),
+                            Expr::constant(Constant::Int(3))
+                        ))
+                    )
+                );
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_select_join_on() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql = "SELECT emp.ename, dname FROM emp JOIN dept ON edept = did \
+                   CROSS JOIN project WHERE dname = 'eng'";
+
+        let stmt = parser.parse(sql)?;
+
+        match stmt {
+            Statement::Query {
+                fields,
+                tables,
+                predicate,
+                ..
+            } => {
+                assert_eq!(fields, vec!["ename", "dname"]);
+                assert_eq!(tables, vec!["emp", "dept", "project"]);
+                // the ON condition comes first, followed by the WHERE clause
+                assert_eq!(
+                    predicate,
+                    Some(
+                        Predicate::new(Term::new(
+                            Expr::field_name("edept"),
+                            Expr::field_name("did")
+                        ))
+                        .with_term(Term::new(
+                            Expr::field_name("dname"),
+                            Expr::constant(Constant::String("eng".to_string()))
+                        ))
+                    )
+                );
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        assert!(
+            parser
+                .parse("SELECT ename FROM emp LEFT JOIN dept ON edept = did")
+                .is_err()
+        );
+        assert!(parser.parse("SELECT ename FROM emp WHERE 1 = 1").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_select_order_by_limit() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql =
+            "SELECT id, name FROM test_table ORDER BY name DESC, test_table.id LIMIT 10 OFFSET 5";
+
+        match parser.parse(sql)? {
+            Statement::Query {
+                order_by,
+                limit,
+                offset,
+                ..
+            } => {
+                assert_eq!(
+                    order_by,
+                    vec![SortField::desc("name"), SortField::asc("id")]
+                );
+                assert_eq!(limit, Some(10));
+                assert_eq!(offset, Some(5));
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        match parser.parse("SELECT id FROM test_table")? {
+            Statement::Query {
+                order_by,
+                limit,
+                offset,
+                ..
+            } => {
+                assert!(order_by.is_empty());
+                assert_eq!(limit, None);
+                assert_eq!(offset, None);
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        assert!(parser.parse("SELECT id FROM test_table LIMIT -1").is_err());
+        assert!(
+            parser
+                .parse("SELECT id FROM test_table ORDER BY id + 1")
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_where_operators() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql = "SELECT id FROM t WHERE id >= -5 AND (name <> 'a' OR NOT age < 30) \
+                   AND id IN (1, 2) AND age NOT BETWEEN 10 AND 20 AND name IS NOT NULL";
+
+        let predicate = match parser.parse(sql)? {
+            Statement::Query { predicate, .. } => predicate,
+            _ => panic!("Unexpected statement"),
+        };
+        let field = Expr::field_name;
+        let int = |i: i32| Expr::constant(Constant::Int(i));
+        assert_eq!(
+            predicate,
+            Some(
+                Predicate::new(Term::compare(field("id"), CompareOp::Ge, int(-5)))
+                    .with_term(Term::or(vec![
+                        Term::compare(
+                            field("name"),
+                            CompareOp::Ne,
+                            Expr::constant(Constant::string("a"))
+                        ),
+                        Term::negate(Term::compare(field("age"), CompareOp::Lt, int(30))),
+                    ]))
+                    .with_term(Term::in_list(field("id"), vec![int(1), int(2)], false))
+                    .with_term(Term::between(field("age"), int(10), int(20), true))
+                    .with_term(Term::is_null(field("name"), true))
+            )
+        );
+
+        // a conjunction inside a disjunction stays a single term
+        let predicate =
+            match parser.parse("SELECT id FROM t WHERE (id = 1 AND age = 2) OR id = 3")? {
+                Statement::Query { predicate, .. } => predicate,
+                _ => panic!("Unexpected statement"),
+            };
+        assert_eq!(
+            predicate,
+            Some(Predicate::new(Term::or(vec![
+                Term::and(vec![
+                    Term::new(field("id"), int(1)),
+                    Term::new(field("age"), int(2))
+                ]),
+                Term::new(field("id"), int(3)),
+            ])))
+        );
+
+        assert!(parser.parse("SELECT id FROM t WHERE 1 < 2").is_err());
+        assert!(parser.parse("SELECT id FROM t WHERE id LIKE 'a%'").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_select_group_by() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql = "SELECT dept, COUNT(*), SUM(salary) AS total FROM emp \
+                   GROUP BY emp.dept HAVING MAX(salary) = 10 ORDER BY COUNT(*) DESC";
+
+        match parser.parse(sql)? {
+            Statement::Query {
+                fields,
+                group_by,
+                aggregates,
+                having,
+                order_by,
+                ..
+            } => {
+                assert_eq!(fields, vec!["dept", "count(*)", "total"]);
+                assert_eq!(group_by, vec!["dept"]);
+                assert_eq!(
+                    aggregates,
+                    vec![
+                        AggregateFn::new(AggregateKind::Count, None),
+                        AggregateFn::new(AggregateKind::Sum, Some("salary".to_string()))
+                            .with_alias("total"),
+                        AggregateFn::new(AggregateKind::Max, Some("salary".to_string())),
+                    ]
+                );
+                assert_eq!(
+                    having,
+                    Some(Predicate::new(Term::new(
+                        Expr::field_name("max(salary)"),
+                        Expr::constant(Constant::Int(10))
+                    )))
+                );
+                assert_eq!(order_by, vec![SortField::desc("count(*)")]);
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        for sql in [
+            "SELECT COUNT(DISTINCT dept) FROM emp",
+            "SELECT SUM(*) FROM emp",
+            "SELECT UPPER(dept) FROM emp",
+            "SELECT dept FROM emp WHERE COUNT(*) = 1",
+        ] {
+            assert!(parser.parse(sql).is_err(), "{sql}");
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_parse_update() -> DbResult<()> {
         let parser = Parser::new();
@@ -513,6 +1682,20 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_update_rejects_duplicate_fields() {
+        let parser = Parser::new();
+        assert!(parser.parse("UPDATE u SET a = 6, a = 7").is_err());
+        assert!(parser.parse("UPDATE u SET a = 6, b = 7").is_ok());
+    }
+
+    #[test]
+    fn test_parse_insert_rejects_duplicate_fields() {
+        let parser = Parser::new();
+        assert!(parser.parse("INSERT INTO u (a, a) VALUES (6, 7)").is_err());
+        assert!(parser.parse("INSERT INTO u (a, b) VALUES (6, 7)").is_ok());
+    }
+
     #[test]
     fn test_parse_update_no_where() -> DbResult<()> {
         let parser = Parser::new();
@@ -579,6 +1762,177 @@ mod tests {
         assert!(parser.parse(sql).is_err());
     }
 
+    #[test]
+    fn test_parse_delete() -> DbResult<()> {
+        let parser = Parser::new();
+        let sql = "DELETE FROM test_table WHERE id = 1";
+
+        let stmt = parser.parse(sql)?;
+
+        match stmt {
+            Statement::Delete {
+                table_name,
+                predicate,
+            } => {
+                assert_eq!(table_name, "test_table");
+                assert_eq!(
+                    predicate,
+                    Some(Predicate::new(Term::new(
+                        Expr::FieldName("id".to_owned()),
+                        Expr::Constant(Constant::Int(1))
+                    )))
+                );
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_delete_no_where() -> DbResult<()> {
+        let parser = Parser::new();
+        let stmt = parser.parse("DELETE FROM test_table")?;
+
+        match stmt {
+            Statement::Delete {
+                table_name,
+                predicate,
+            } => {
+                assert_eq!(table_name, "test_table");
+                assert!(predicate.is_none());
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_alter_table() -> DbResult<()> {
+        let parser = Parser::new();
+
+        match parser.parse("ALTER TABLE test_table ADD COLUMN name VARCHAR(10) DEFAULT 'none'")? {
+            Statement::AlterTable {
+                table_name,
+                action:
+                    AlterTableAction::AddColumn {
+                        field_name,
+                        field_type,
+                        length,
+                        default,
+                    },
+            } => {
+                assert_eq!(table_name, "test_table");
+                assert_eq!(field_name, "name");
+                assert_eq!(field_type, FieldType::Varchar);
+                assert_eq!(length, 10);
+                assert_eq!(default, Some(Constant::string("none")));
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        match parser.parse("ALTER TABLE test_table ADD age INT DEFAULT -1")? {
+            Statement::AlterTable {
+                action: AlterTableAction::AddColumn { default, .. },
+                ..
+            } => assert_eq!(default, Some(Constant::int(-1))),
+            _ => panic!("Unexpected statement"),
+        }
+
+        match parser.parse("ALTER TABLE test_table DROP COLUMN age")? {
+            Statement::AlterTable {
+                action: AlterTableAction::DropColumn { field_name },
+                ..
+            } => assert_eq!(field_name, "age"),
+            _ => panic!("Unexpected statement"),
+        }
+
+        match parser.parse("ALTER TABLE test_table RENAME COLUMN age TO years")? {
+            Statement::AlterTable {
+                action:
+                    AlterTableAction::RenameColumn {
+                        field_name,
+                        new_field_name,
+                    },
+                ..
+            } => {
+                assert_eq!(field_name, "age");
+                assert_eq!(new_field_name, "years");
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        match parser.parse("ALTER TABLE test_table RENAME TO people")? {
+            Statement::AlterTable {
+                action: AlterTableAction::RenameTable { new_table_name },
+                ..
+            } => assert_eq!(new_table_name, "people"),
+            _ => panic!("Unexpected statement"),
+        }
+
+        assert!(
+            parser
+                .parse("ALTER TABLE test_table ADD COLUMN a INT DEFAULT id")
+                .is_err()
+        );
+        assert!(
+            parser
+                .parse("ALTER TABLE test_table DROP COLUMN a, DROP COLUMN b")
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_drop() -> DbResult<()> {
+        let parser = Parser::new();
+
+        match parser.parse("DROP TABLE test_table")? {
+            Statement::DropTable {
+                table_name,
+                if_exists,
+            } => {
+                assert_eq!(table_name, "test_table");
+                assert!(!if_exists);
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        match parser.parse("DROP TABLE IF EXISTS test_table")? {
+            Statement::DropTable { if_exists, .. } => assert!(if_exists),
+            _ => panic!("Unexpected statement"),
+        }
+
+        match parser.parse("DROP INDEX age_idx")? {
+            Statement::DropIndex { name, if_exists } => {
+                assert_eq!(name, "age_idx");
+                assert!(!if_exists);
+            }
+            _ => panic!("Unexpected statement"),
+        }
+
+        assert!(parser.parse("DROP TABLE t1, t2").is_err());
+        assert!(parser.parse("DROP VIEW v").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_vacuum() -> DbResult<()> {
+        let parser = Parser::new();
+
+        for sql in ["VACUUM test_table", "vacuum test_table;"] {
+            match parser.parse(sql)? {
+                Statement::Vacuum { table_name } => assert_eq!(table_name, "test_table"),
+                _ => panic!("Unexpected statement"),
+            }
+        }
+
+        assert!(parser.parse("VACUUM").is_err());
+        assert!(parser.parse("VACUUM t1, t2").is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_parse_create_index() -> DbResult<()> {
         let parser = Parser::new();
@@ -0,0 +1,143 @@
+use crate::plan::{Plan, SortPlan, TablePlan};
+use crate::query::{AggregateFn, AggregateKind, GroupByScan, HashGroupByScan, Scan, SortField};
+use crate::record::{Layout, TempTable, schema::Schema};
+use crate::tx::Transaction;
+
+/// Groups the output of a plan and computes aggregate functions for every group.
+/// The groups are collected in a hash table. If there are more groups than fit into
+/// the available buffers, the records of the groups which do not fit are spilled to
+/// a temporary table, which is sorted on the group fields and grouped while it is being read.
+/// The input is read only once.
+pub struct GroupByPlan {
+    plan: Box<dyn Plan>,
+    group_fields: Vec<String>,
+    aggregates: Vec<AggregateFn>,
+    schema: Schema,
+}
+
+impl GroupByPlan {
+    pub fn new(
+        plan: Box<dyn Plan>,
+        group_fields: Vec<String>,
+        aggregates: Vec<AggregateFn>,
+    ) -> Self {
+        let input_schema = plan.schema();
+        let mut schema = Schema::new();
+        for field_name in &group_fields {
+            schema.add_from_schema(field_name, &input_schema);
+        }
+        for aggregate in &aggregates {
+            //  only the count is always an integer, the other functions have the type of the field
+            match (aggregate.kind(), aggregate.field_name()) {
+                (AggregateKind::Count, _) | (_, None) => {
+                    schema.add_int_field(aggregate.output_name())
+                }
+                (_, Some(field_name)) => {
+                    let field_type = input_schema
+                        .field_type(field_name)
+                        .expect("Field not found in schema");
+                    let length = input_schema.length(field_name).unwrap_or(0);
+                    schema.add_field(aggregate.output_name(), field_type, length);
+                }
+            }
+        }
+
+        GroupByPlan {
+            plan,
+            group_fields,
+            aggregates,
+            schema,
+        }
+    }
+
+    /// Number of groups which fit into the available buffers.
+    fn max_groups(&self, tx: &Transaction<'_>) -> usize {
+        let slot_size = Layout::new(self.schema.clone()).slot_size();
+        tx.available_buffs().max(1) * (tx.block_size() / slot_size).max(1)
+    }
+}
+
+impl Plan for GroupByPlan {
+    fn open<'tx>(&self, tx: Transaction<'tx>) -> Box<dyn Scan + 'tx> {
+        let mut src = self.plan.open(tx.clone());
+        let spill = TempTable::new(self.plan.schema());
+        let (hashed, spilled) = HashGroupByScan::new(
+            &mut *src,
+            self.group_fields.clone(),
+            self.aggregates.clone(),
+            self.max_groups(&tx),
+            &spill,
+            tx.clone(),
+        )
+        .unwrap();
+        drop(src);
+        if !spilled {
+            return Box::new(hashed);
+        }
+
+        let sort_fields = self.group_fields.iter().map(SortField::asc).collect();
+        let spill_plan = TablePlan::new(spill.table_name().to_string(), spill.layout().clone());
+        let sort_plan = SortPlan::new(Box::new(spill_plan.unwrap()), sort_fields);
+        let sorted = sort_plan.open(tx.clone());
+        spill.delete(&tx).unwrap();
+        let grouped =
+            GroupByScan::new(sorted, self.group_fields.clone(), self.aggregates.clone()).unwrap();
+        Box::new(hashed.followed_by(grouped))
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::DbResult, query::UpdateScan, record::TableScan,
+        utils::testing_utils::temp_db_with_cfg,
+    };
+
+    #[test]
+    fn test_group_by_plan_spills_groups() -> DbResult<()> {
+        let db = temp_db_with_cfg(|cfg| cfg.buffer_capacity(5))?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("num");
+        schema.add_string_field("name", 10);
+        let layout = Layout::new(schema);
+        let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+        for i in 0..1000 {
+            scan.insert()?;
+            scan.set_int("num", (i * 7919) % 500)?;
+            scan.set_string("name", &format!("n{}", i % 7))?;
+        }
+        drop(scan);
+        let table_plan = TablePlan::new("test_table".to_string(), layout)?;
+
+        let aggregates = vec![
+            AggregateFn::new(AggregateKind::Count, None),
+            AggregateFn::new(AggregateKind::Max, Some("name".to_string())),
+        ];
+        let plan = GroupByPlan::new(Box::new(table_plan), vec!["num".to_string()], aggregates);
+        let max_groups = plan.max_groups(&tx);
+        assert!(max_groups < 500);
+        assert!(plan.schema().has_field("max(name)"));
+
+        let mut scan = plan.open(tx.clone());
+        let mut nums = Vec::new();
+        while scan.next()? {
+            assert_eq!(scan.get_int("count(*)")?, 2);
+            nums.push(scan.get_int("num")?);
+        }
+        // the hashed groups come first, the spilled ones are sorted
+        assert!(nums[max_groups..].is_sorted());
+        nums.sort();
+        assert_eq!(nums, (0..500).collect::<Vec<_>>());
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use crate::plan::Plan;
+use crate::query::{HashJoinScan, Scan};
+use crate::record::schema::Schema;
+use crate::tx::Transaction;
+
+/// Joins the outputs of two plans on the equality of a field from each of them
+/// by hashing the records of the smaller one.
+pub struct HashJoinPlan {
+    p1: Box<dyn Plan>,
+    p2: Box<dyn Plan>,
+    field1: String,
+    field2: String,
+    schema: Schema,
+}
+
+impl HashJoinPlan {
+    /// `field1` belongs to `p1`, `field2` to `p2`.
+    pub fn new(p1: Box<dyn Plan>, p2: Box<dyn Plan>, field1: &str, field2: &str) -> Self {
+        let mut schema = Schema::new();
+        schema.add_all(&p1.schema());
+        schema.add_all(&p2.schema());
+        HashJoinPlan {
+            p1,
+            p2,
+            field1: field1.to_string(),
+            field2: field2.to_string(),
+            schema,
+        }
+    }
+}
+
+impl Plan for HashJoinPlan {
+    fn open<'tx>(&self, tx: Transaction<'tx>) -> Box<dyn Scan + 'tx> {
+        let s1 = self.p1.open(tx.clone());
+        let s2 = self.p2.open(tx.clone());
+        Box::new(
+            HashJoinScan::new(
+                tx,
+                s1,
+                s2,
+                self.p1.schema(),
+                self.p2.schema(),
+                &self.field1,
+                &self.field2,
+            )
+            .unwrap(),
+        )
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
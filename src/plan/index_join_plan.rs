@@ -0,0 +1,54 @@
+use crate::metadata::IndexInfo;
+use crate::plan::{Plan, TablePlan};
+use crate::query::{IndexJoinScan, Scan};
+use crate::record::{TableScan, schema::Schema};
+use crate::tx::Transaction;
+
+/// Joins the output of a plan with a table, looking up the matching records of the table
+/// through an index on its join field.
+pub struct IndexJoinPlan {
+    p1: Box<dyn Plan>,
+    p2: TablePlan,
+    index_info: IndexInfo,
+    join_field: String,
+    schema: Schema,
+}
+
+impl IndexJoinPlan {
+    /// `join_field` is the field of `p1` whose values are looked up in the index of `p2`.
+    pub fn new(p1: Box<dyn Plan>, p2: TablePlan, index_info: IndexInfo, join_field: &str) -> Self {
+        let mut schema = Schema::new();
+        schema.add_all(&p1.schema());
+        schema.add_all(&p2.schema());
+        IndexJoinPlan {
+            p1,
+            p2,
+            index_info,
+            join_field: join_field.to_string(),
+            schema,
+        }
+    }
+}
+
+impl Plan for IndexJoinPlan {
+    fn open<'tx>(&self, tx: Transaction<'tx>) -> Box<dyn Scan + 'tx> {
+        let lhs = self.p1.open(tx.clone());
+        let index = self.index_info.open(tx.clone()).unwrap();
+        let rhs = TableScan::new(
+            tx.clone(),
+            self.p2.table_name(),
+            self.p2.table_layout().clone(),
+        )
+        .unwrap();
+        Box::new(IndexJoinScan::new(
+            lhs,
+            Box::new(index),
+            &self.join_field,
+            rhs,
+        ))
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
@@ -0,0 +1,62 @@
+use std::ops::Bound;
+
+use crate::metadata::IndexInfo;
+use crate::plan::{Plan, TablePlan};
+use crate::query::Constant;
+use crate::query::{IndexRangeSelectScan, Scan};
+use crate::record::{TableScan, schema::Schema};
+use crate::tx::Transaction;
+
+/// Selects the records of a table whose indexed field lies between two bounds
+/// by scanning the matching range of the index.
+pub struct IndexRangeSelectPlan {
+    plan: TablePlan,
+    index_info: IndexInfo,
+    lower: Bound<Constant>,
+    upper: Bound<Constant>,
+}
+
+impl IndexRangeSelectPlan {
+    pub fn new(
+        plan: TablePlan,
+        index_info: IndexInfo,
+        lower: Bound<Constant>,
+        upper: Bound<Constant>,
+    ) -> Self {
+        IndexRangeSelectPlan {
+            plan,
+            index_info,
+            lower,
+            upper,
+        }
+    }
+}
+
+impl Plan for IndexRangeSelectPlan {
+    fn open<'tx>(&self, tx: Transaction<'tx>) -> Box<dyn Scan + 'tx> {
+        let index = self.index_info.open(tx.clone()).unwrap();
+        let scan = TableScan::new(
+            tx.clone(),
+            self.plan.table_name(),
+            self.plan.table_layout().clone(),
+        )
+        .unwrap();
+        Box::new(
+            IndexRangeSelectScan::new(
+                scan,
+                Box::new(index),
+                self.lower.clone(),
+                self.upper.clone(),
+            )
+            .unwrap(),
+        )
+    }
+
+    fn schema(&self) -> Schema {
+        self.plan.schema()
+    }
+
+    fn sorted_on(&self) -> Option<&str> {
+        Some(self.index_info.field_name())
+    }
+}
@@ -40,4 +40,8 @@ impl Plan for IndexSelectPlan {
     fn schema(&self) -> Schema {
         self.plan.schema()
     }
+
+    fn sorted_on(&self) -> Option<&str> {
+        Some(self.index_info.field_name())
+    }
 }
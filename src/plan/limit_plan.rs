@@ -0,0 +1,32 @@
+use crate::plan::Plan;
+use crate::query::{LimitScan, Scan};
+use crate::record::schema::Schema;
+use crate::tx::Transaction;
+
+/// Returns at most `limit` records of the underlying plan, after skipping the first `offset`.
+pub struct LimitPlan {
+    plan: Box<dyn Plan>,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+impl LimitPlan {
+    pub fn new(plan: Box<dyn Plan>, limit: Option<usize>, offset: usize) -> Self {
+        LimitPlan {
+            plan,
+            limit,
+            offset,
+        }
+    }
+}
+
+impl Plan for LimitPlan {
+    fn open<'tx>(&self, tx: Transaction<'tx>) -> Box<dyn Scan + 'tx> {
+        let scan = self.plan.open(tx);
+        Box::new(LimitScan::new(scan, self.limit, self.offset))
+    }
+
+    fn schema(&self) -> Schema {
+        self.plan.schema()
+    }
+}
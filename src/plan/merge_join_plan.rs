@@ -0,0 +1,140 @@
+use crate::plan::{Plan, SortPlan};
+use crate::query::{MergeJoinScan, Scan, SortField};
+use crate::record::schema::Schema;
+use crate::tx::Transaction;
+
+/// Joins the outputs of two plans on the equality of a field from each of them
+/// by sorting both on their join field and merging the results.
+/// The first plan is not sorted again when it is already sorted on its join field,
+/// and the output is sorted on the join fields.
+pub struct MergeJoinPlan {
+    p1: Box<dyn Plan>,
+    p2: SortPlan,
+    field1: String,
+    field2: String,
+    schema: Schema,
+}
+
+impl MergeJoinPlan {
+    /// `field1` belongs to `p1`, `field2` to `p2`.
+    pub fn new(p1: Box<dyn Plan>, p2: Box<dyn Plan>, field1: &str, field2: &str) -> Self {
+        let mut schema = Schema::new();
+        schema.add_all(&p1.schema());
+        schema.add_all(&p2.schema());
+        let p1: Box<dyn Plan> = if p1.sorted_on() == Some(field1) {
+            p1
+        } else {
+            Box::new(SortPlan::new(p1, vec![SortField::asc(field1)]))
+        };
+        MergeJoinPlan {
+            p1,
+            p2: SortPlan::new(p2, vec![SortField::asc(field2)]),
+            field1: field1.to_string(),
+            field2: field2.to_string(),
+            schema,
+        }
+    }
+}
+
+impl Plan for MergeJoinPlan {
+    fn open<'tx>(&self, tx: Transaction<'tx>) -> Box<dyn Scan + 'tx> {
+        let s1 = self.p1.open(tx.clone());
+        let s2 = self.p2.open_sorted(tx).unwrap();
+        Box::new(MergeJoinScan::new(s1, s2, &self.field1, &self.field2))
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    fn sorted_on(&self) -> Option<&str> {
+        Some(&self.field1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DbResult,
+        plan::TablePlan,
+        query::UpdateScan,
+        record::{Layout, TableScan},
+        utils::testing_utils::temp_db,
+    };
+
+    fn create_table(
+        tx: &Transaction<'_>,
+        name: &str,
+        fields: [&str; 2],
+        rows: &[(i32, i32)],
+    ) -> DbResult<TablePlan> {
+        let mut schema = Schema::new();
+        schema.add_int_field(fields[0]);
+        schema.add_int_field(fields[1]);
+        let layout = Layout::new(schema);
+        let mut scan = TableScan::new(tx.clone(), name, layout.clone())?;
+        for (a, b) in rows {
+            scan.insert()?;
+            scan.set_int(fields[0], *a)?;
+            scan.set_int(fields[1], *b)?;
+        }
+        TablePlan::new(name.to_string(), layout)
+    }
+
+    #[test]
+    fn test_merge_join_with_duplicates() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        // customer 2 appears twice and has three orders, customer 4 has none
+        let customers = create_table(
+            &tx,
+            "cust",
+            ["cid", "cnum"],
+            &[(3, 30), (2, 20), (1, 10), (4, 40), (2, 21)],
+        )?;
+        let orders = create_table(
+            &tx,
+            "orders",
+            ["oid", "ocust"],
+            &[(100, 2), (101, 3), (102, 2), (103, 5), (104, 1), (105, 2)],
+        )?;
+
+        let plan = MergeJoinPlan::new(Box::new(customers), Box::new(orders), "cid", "ocust");
+        assert!(plan.schema().has_field("cnum"));
+        assert!(plan.schema().has_field("oid"));
+
+        let mut scan = plan.open(tx.clone());
+        let mut rows = Vec::new();
+        while scan.next()? {
+            assert_eq!(scan.get_int("cid")?, scan.get_int("ocust")?);
+            rows.push((scan.get_int("cnum")?, scan.get_int("oid")?));
+        }
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec![
+                (10, 104),
+                (20, 100),
+                (20, 102),
+                (20, 105),
+                (21, 100),
+                (21, 102),
+                (21, 105),
+                (30, 101)
+            ]
+        );
+
+        scan.before_first()?;
+        let mut count = 0;
+        while scan.next()? {
+            count += 1;
+        }
+        assert_eq!(count, 8);
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+}
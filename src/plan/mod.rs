@@ -1,11 +1,27 @@
+pub mod group_by_plan;
+pub mod hash_join_plan;
+pub mod index_join_plan;
+pub mod index_range_select_plan;
 pub mod index_select_plan;
+pub mod limit_plan;
+pub mod merge_join_plan;
 pub mod planner;
+pub mod product_plan;
 pub mod project_plan;
 pub mod select_plan;
+pub mod sort_plan;
 pub mod table_plan;
 
+pub use group_by_plan::GroupByPlan;
+pub use hash_join_plan::HashJoinPlan;
+pub use index_join_plan::IndexJoinPlan;
+pub use index_range_select_plan::IndexRangeSelectPlan;
 pub use index_select_plan::IndexSelectPlan;
+pub use limit_plan::LimitPlan;
+pub use merge_join_plan::MergeJoinPlan;
 pub use planner::Planner;
+pub use product_plan::ProductPlan;
+pub use sort_plan::SortPlan;
 pub use table_plan::TablePlan;
 
 use crate::query::scan::Scan;
@@ -16,4 +32,9 @@ pub trait Plan {
     fn open<'tx>(&self, tx: Transaction<'tx>) -> Box<dyn Scan + 'tx>;
 
     fn schema(&self) -> Schema;
+
+    /// The field on which the records of the plan's scans are sorted in ascending order, if any.
+    fn sorted_on(&self) -> Option<&str> {
+        None
+    }
 }
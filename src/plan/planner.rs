@@ -1,17 +1,16 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    DbResult,
+    DbError, DbResult,
     index::Index,
-    metadata::MetadataMgr,
-    parse::{Parser, Statement},
+    metadata::{IndexInfo, MetadataMgr},
+    parse::{AlterTableAction, Parser, Statement},
     plan::{
-        Plan,
-        project_plan::ProjectPlan,
-        table_plan::{TablePlan, TablePlanner},
+        GroupByPlan, LimitPlan, Plan, SortPlan, project_plan::ProjectPlan, select_plan::SelectPlan,
+        table_plan::TablePlanner,
     },
-    query::{Scan, UpdateScan},
-    record::{Schema, TableScan},
+    query::{AggregateFn, AggregateKind, Constant, Predicate, Scan, SortField, UpdateScan},
+    record::{Layout, RID, RecordFormat, Schema, TableScan, schema::Constraint},
     tx::Transaction,
 };
 
@@ -36,28 +35,86 @@ impl Planner {
                 fields,
                 tables,
                 predicate,
+                group_by,
+                aggregates,
+                having,
+                order_by,
+                limit,
+                offset,
             } => {
-                if tables.len() != 1 {
+                let pred = predicate.unwrap_or_default();
+                let mut table_planners = Vec::with_capacity(tables.len());
+                for table_name in &tables {
+                    table_planners.push(TablePlanner::new(
+                        table_name,
+                        pred.clone(),
+                        tx.clone(),
+                        &self.metadata_mgr,
+                    )?);
+                }
+                if table_planners.is_empty() {
                     return Err(crate::error::DbError::Schema(
-                        "Only single table queries are supported".to_string(),
+                        "No tables specified in query".to_string(),
                     ));
                 }
+                Self::check_ambiguous_fields(
+                    &table_planners,
+                    &fields,
+                    &pred,
+                    &group_by,
+                    &aggregates,
+                    &order_by,
+                )?;
+
+                // Tables are joined in the order they are listed, except that a table which
+                // joins with the tables planned so far is preferred to one which would
+                // produce a product.
+                let mut plan = table_planners.remove(0).make_select_plan();
+                while !table_planners.is_empty() {
+                    let current_schema = plan.schema();
+                    let next = table_planners
+                        .iter()
+                        .position(|tp| tp.joins_with(&current_schema))
+                        .unwrap_or(0);
+                    plan = table_planners.remove(next).make_join_plan(plan);
+                }
 
-                let table_name = &tables[0];
-                let layout = self.metadata_mgr.get_layout(table_name, tx.clone())?;
-                let table_plan = TablePlan::new(table_name.to_string(), layout)?;
-                let mut plan: Box<dyn Plan> = Box::new(table_plan);
+                if !group_by.is_empty() || !aggregates.is_empty() {
+                    Self::check_grouping(&plan.schema(), &fields, &group_by, &aggregates)?;
+                    plan = Box::new(GroupByPlan::new(plan, group_by, aggregates));
+                }
+                if let Some(having) = having {
+                    plan = Box::new(SelectPlan::new(plan, having));
+                }
 
-                if let Some(pred) = predicate {
-                    let table_planner =
-                        TablePlanner::new(table_name, pred, tx.clone(), &self.metadata_mgr)?;
-                    plan = table_planner.make_select_plan();
+                // Sorting comes before the projection, so that the output can be ordered
+                // by fields which are not selected.
+                if !order_by.is_empty() {
+                    let schema = plan.schema();
+                    if let Some(sort_field) =
+                        order_by.iter().find(|f| !schema.has_field(f.field_name()))
+                    {
+                        return Err(crate::error::DbError::FieldNotFound(
+                            sort_field.field_name().to_string(),
+                        ));
+                    }
+                    let sort_plan = SortPlan::new(plan, order_by);
+                    plan = match limit {
+                        Some(limit) => {
+                            Box::new(sort_plan.limit(offset.unwrap_or(0).saturating_add(limit)))
+                        }
+                        None => Box::new(sort_plan),
+                    };
                 }
 
                 if !(fields.len() == 1 && fields[0] == "*") {
                     plan = Box::new(ProjectPlan::new(plan, fields));
                 }
 
+                if limit.is_some() || offset.is_some() {
+                    plan = Box::new(LimitPlan::new(plan, limit, offset.unwrap_or(0)));
+                }
+
                 Ok(plan)
             }
             _ => Err(crate::error::DbError::Schema(
@@ -66,6 +123,88 @@ impl Planner {
         }
     }
 
+    /// Fields are looked up by their name only, also when qualified with a table, so a
+    /// field used by the query must belong to just one of the queried tables.
+    fn check_ambiguous_fields(
+        table_planners: &[TablePlanner<'_>],
+        fields: &[String],
+        pred: &Predicate,
+        group_by: &[String],
+        aggregates: &[AggregateFn],
+        order_by: &[SortField],
+    ) -> DbResult<()> {
+        let select_all = fields.len() == 1 && fields[0] == "*";
+        for (i, tp) in table_planners.iter().enumerate() {
+            for field_name in tp.schema().fields() {
+                let Some(other) = table_planners[i + 1..]
+                    .iter()
+                    .find(|other| other.schema().has_field(field_name))
+                else {
+                    continue;
+                };
+                let used = select_all
+                    || fields.contains(field_name)
+                    || group_by.contains(field_name)
+                    || pred.mentions(field_name)
+                    || aggregates
+                        .iter()
+                        .any(|a| a.field_name() == Some(field_name.as_str()))
+                    || order_by.iter().any(|f| f.field_name() == field_name);
+                if used {
+                    return Err(crate::error::DbError::Schema(format!(
+                        "Field {} is ambiguous, it belongs to {} and {}",
+                        field_name,
+                        tp.table_name(),
+                        other.table_name()
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the grouped fields and the fields of the aggregates exist, and that
+    /// every selected field is either grouped or computed by an aggregate.
+    fn check_grouping(
+        schema: &Schema,
+        fields: &[String],
+        group_by: &[String],
+        aggregates: &[AggregateFn],
+    ) -> DbResult<()> {
+        if let Some(field_name) = group_by.iter().find(|f| !schema.has_field(f)) {
+            return Err(crate::error::DbError::FieldNotFound(field_name.clone()));
+        }
+        for aggregate in aggregates {
+            let Some(field_name) = aggregate.field_name() else {
+                continue;
+            };
+            match schema.field_type(field_name) {
+                None => return Err(crate::error::DbError::FieldNotFound(field_name.to_string())),
+                Some(field_type)
+                    if !field_type.is_numeric()
+                        && matches!(aggregate.kind(), AggregateKind::Sum | AggregateKind::Avg) =>
+                {
+                    return Err(crate::error::DbError::Schema(format!(
+                        "{} requires a numeric field",
+                        aggregate.output_name()
+                    )));
+                }
+                _ => {}
+            }
+        }
+        for field_name in fields {
+            if !group_by.contains(field_name)
+                && !aggregates.iter().any(|a| a.output_name() == field_name)
+            {
+                return Err(crate::error::DbError::Schema(format!(
+                    "Field {} must appear in GROUP BY or be used in an aggregate function",
+                    field_name
+                )));
+            }
+        }
+        Ok(())
+    }
+
     pub fn execute_update(&self, cmd: &str, tx: Transaction<'_>) -> DbResult<i32> {
         let stmt = self.parser.parse(cmd)?;
 
@@ -81,14 +220,36 @@ impl Planner {
                 values,
                 predicate,
             } => self.execute_update_statement(&table_name, &fields, &values, predicate, tx),
-            Statement::CreateTable { table_name, schema } => {
-                self.execute_create_table(&table_name, &schema, tx)
+            Statement::Delete {
+                table_name,
+                predicate,
+            } => self.execute_delete(&table_name, predicate, tx),
+            Statement::CreateTable {
+                table_name,
+                schema,
+                format,
+            } => self.execute_create_table(&table_name, &schema, format, tx),
+            Statement::CreateIndex {
+                name,
+                table_name,
+                column,
+            } => self.execute_create_index(&name, &table_name, &column, tx),
+            Statement::AlterTable { table_name, action } => {
+                self.execute_alter_table(&table_name, action, tx)
+            }
+            Statement::DropTable {
+                table_name,
+                if_exists,
+            } => self.execute_drop_table(&table_name, if_exists, tx),
+            Statement::DropIndex { name, if_exists } => {
+                self.execute_drop_index(&name, if_exists, tx)
             }
-            Statement::CreateIndex { name, table_name, column } => {
-                self.execute_create_index(&name, &table_name, &column, tx)
+            Statement::Vacuum { table_name } => {
+                self.metadata_mgr.vacuum(&table_name, tx)?;
+                Ok(0)
             }
             _ => Err(crate::error::DbError::Schema(
-                "Only INSERT, UPDATE, CREATE TABLE and CREATE INDEX statements are supported for updates"
+                "Only INSERT, UPDATE, DELETE, CREATE, ALTER, DROP and VACUUM statements are supported for updates"
                     .to_string(),
             )),
         }
@@ -102,19 +263,41 @@ impl Planner {
         tx: Transaction<'_>,
     ) -> DbResult<i32> {
         let layout = self.metadata_mgr.get_layout(table_name, tx.clone())?;
-        let mut scan: TableScan<'_> = TableScan::new(tx.clone(), table_name, layout)?;
+        let omitted: Vec<_> = layout
+            .schema()
+            .fields()
+            .iter()
+            .filter(|field| !fields.contains(field))
+            .cloned()
+            .collect();
+        let indexes = self.metadata_mgr.get_index_info(table_name, tx.clone())?;
+        let null_fields = vec![Constant::Null; omitted.len()];
+        for (fields, values) in [(fields, values), (&omitted, &null_fields)] {
+            Self::check_constraints(
+                table_name,
+                &layout,
+                fields,
+                values,
+                None,
+                &indexes,
+                tx.clone(),
+            )?;
+        }
 
-        scan.move_to_last()?;
+        let mut scan: TableScan<'_> = TableScan::new(tx.clone(), table_name, layout)?;
         scan.insert()?;
         let rid = scan.get_rid()?;
-        let indexes = self.metadata_mgr.get_index_info(table_name, tx.clone())?;
 
+        // the fields missing from the column list are null, which is never indexed
+        for field in &omitted {
+            scan.set_val(field, crate::query::Constant::Null)?;
+        }
         for (field, value) in fields.iter().zip(values.iter()) {
             scan.set_val(field, value.clone())?;
 
             if let Some(index_info) = indexes.get(field) {
                 let mut index = index_info.open(tx.clone())?;
-                index.insert(value, &rid)?;
+                index.insert(&scan.get_val(field)?, &rid)?;
                 index.close();
             }
         }
@@ -122,13 +305,158 @@ impl Planner {
         Ok(1)
     }
 
+    /// Fails with a constraint violation if the values would put null into a NOT NULL
+    /// field, or a value of another record into a UNIQUE field. The values go into the
+    /// given records of an update, or into a new record if there are none.
+    fn check_constraints(
+        table_name: &str,
+        layout: &Layout,
+        fields: &[String],
+        values: &[Constant],
+        updated: Option<&[RID]>,
+        indexes: &HashMap<String, IndexInfo>,
+        tx: Transaction<'_>,
+    ) -> DbResult<()> {
+        let schema = layout.schema();
+        for (field, value) in fields.iter().zip(values.iter()) {
+            let Some(field_type) = schema.field_type(field) else {
+                continue;
+            };
+            // the index of the field can only compare values of its type
+            let value = value.coerce_to(field_type).ok_or_else(|| {
+                DbError::Schema(format!(
+                    "Value {} does not fit field {} of type {}",
+                    value.to_string(),
+                    field,
+                    field_type
+                ))
+            })?;
+            let is_primary_key = schema.primary_key() == Some(field.as_str());
+
+            if value.is_null() && schema.is_not_null(field) {
+                let constraint = if is_primary_key {
+                    Constraint::PrimaryKey
+                } else {
+                    Constraint::NotNull
+                };
+                return Err(DbError::ConstraintViolation(format!(
+                    "null value in {} field {}.{}",
+                    constraint, table_name, field
+                )));
+            }
+            if value.is_null() || !schema.is_unique(field) {
+                continue;
+            }
+            let updated = updated.unwrap_or_default();
+            // several updated records would get the same value
+            let duplicate = updated.len() > 1
+                || Self::holds_value(
+                    table_name,
+                    layout,
+                    field,
+                    &value,
+                    updated,
+                    indexes.get(field),
+                    tx.clone(),
+                )?;
+            if duplicate {
+                let constraint = if is_primary_key {
+                    Constraint::PrimaryKey
+                } else {
+                    Constraint::Unique
+                };
+                return Err(DbError::ConstraintViolation(format!(
+                    "duplicate value {} in {} field {}.{}",
+                    value.to_string(),
+                    constraint,
+                    table_name,
+                    field
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if a record of the table other than the excluded ones holds the value
+    /// in the field. The value is looked up in the index of the field, or the table is
+    /// scanned if the index was dropped.
+    fn holds_value(
+        table_name: &str,
+        layout: &Layout,
+        field: &str,
+        value: &Constant,
+        excluded: &[RID],
+        index_info: Option<&IndexInfo>,
+        tx: Transaction<'_>,
+    ) -> DbResult<bool> {
+        let mut found = false;
+        if let Some(index_info) = index_info {
+            let mut index = index_info.open(tx)?;
+            index.before_first(value)?;
+            while !found && index.next()? {
+                found = !excluded.contains(&index.get_data_rid()?);
+            }
+            index.close();
+        } else {
+            let mut scan = TableScan::new(tx, table_name, layout.clone())?;
+            while !found && scan.next()? {
+                found = scan.get_val(field)? == *value && !excluded.contains(&scan.get_rid()?);
+            }
+        }
+        Ok(found)
+    }
+
+    fn execute_delete(
+        &self,
+        table_name: &str,
+        predicate: Option<crate::query::Predicate>,
+        tx: Transaction<'_>,
+    ) -> DbResult<i32> {
+        let pred = predicate.unwrap_or_default();
+        let table_planner =
+            TablePlanner::new(table_name, pred.clone(), tx.clone(), &self.metadata_mgr)?;
+        let indexes = self.metadata_mgr.get_index_info(table_name, tx.clone())?;
+
+        // Collect the victims first: removing index entries while an index scan
+        // is positioned on them would make it skip records.
+        let mut deleted = Vec::new();
+        {
+            let mut scan = table_planner.make_update_scan()?;
+            while scan.next()? {
+                if pred.is_satisfied(&mut *scan)? {
+                    let mut index_vals = Vec::with_capacity(indexes.len());
+                    for field in indexes.keys() {
+                        index_vals.push((field.clone(), scan.get_val(field)?));
+                    }
+                    deleted.push((scan.get_rid()?, index_vals));
+                }
+            }
+        }
+
+        let layout = self.metadata_mgr.get_layout(table_name, tx.clone())?;
+        let mut scan = TableScan::new(tx.clone(), table_name, layout)?;
+        for (rid, index_vals) in &deleted {
+            for (field, val) in index_vals {
+                let mut index = indexes[field].open(tx.clone())?;
+                index.delete(val, rid)?;
+                index.close();
+            }
+            scan.move_to_rid(*rid)?;
+            scan.delete()?;
+        }
+
+        Ok(deleted.len() as i32)
+    }
+
     fn execute_create_table(
         &self,
         table_name: &str,
         schema: &Schema,
+        format: RecordFormat,
         tx: Transaction<'_>,
     ) -> DbResult<i32> {
-        self.metadata_mgr.create_table(table_name, schema, tx)?;
+        self.metadata_mgr
+            .create_table_with_format(table_name, schema, format, tx)?;
         Ok(1)
     }
 
@@ -144,6 +472,75 @@ impl Planner {
         Ok(1)
     }
 
+    /// Applies an ALTER TABLE action. A new field without a default value
+    /// is null in the existing records.
+    fn execute_alter_table(
+        &self,
+        table_name: &str,
+        action: AlterTableAction,
+        tx: Transaction<'_>,
+    ) -> DbResult<i32> {
+        match action {
+            AlterTableAction::AddColumn {
+                field_name,
+                field_type,
+                length,
+                default,
+            } => {
+                let default = default.unwrap_or(crate::query::Constant::Null);
+                self.metadata_mgr.add_column(
+                    table_name,
+                    &field_name,
+                    field_type,
+                    length,
+                    default,
+                    tx,
+                )?;
+            }
+            AlterTableAction::DropColumn { field_name } => {
+                self.metadata_mgr.drop_column(table_name, &field_name, tx)?;
+            }
+            AlterTableAction::RenameColumn {
+                field_name,
+                new_field_name,
+            } => {
+                self.metadata_mgr
+                    .rename_column(table_name, &field_name, &new_field_name, tx)?;
+            }
+            AlterTableAction::RenameTable { new_table_name } => {
+                self.metadata_mgr
+                    .rename_table(table_name, &new_table_name, tx)?;
+            }
+        }
+        Ok(1)
+    }
+
+    /// Drops the table and its indexes. A missing table is an error unless IF EXISTS is given.
+    fn execute_drop_table(
+        &self,
+        table_name: &str,
+        if_exists: bool,
+        tx: Transaction<'_>,
+    ) -> DbResult<i32> {
+        if if_exists && !self.metadata_mgr.table_exists(table_name, tx.clone())? {
+            return Ok(0);
+        }
+        self.metadata_mgr.drop_table(table_name, tx)?;
+        Ok(1)
+    }
+
+    fn execute_drop_index(
+        &self,
+        name: &str,
+        if_exists: bool,
+        tx: Transaction<'_>,
+    ) -> DbResult<i32> {
+        match self.metadata_mgr.drop_index(name, tx) {
+            Err(crate::error::DbError::Schema(_)) if if_exists => Ok(0),
+            result => result.map(|_| 1),
+        }
+    }
+
     fn execute_update_statement(
         &self,
         table_name: &str,
@@ -152,33 +549,60 @@ impl Planner {
         predicate: Option<crate::query::Predicate>,
         tx: Transaction<'_>,
     ) -> DbResult<i32> {
-        let layout = self.metadata_mgr.get_layout(table_name, tx.clone())?;
-        let mut scan = TableScan::new(tx.clone(), table_name, layout)?;
-
-        let mut affected_rows = 0;
+        let pred = predicate.unwrap_or_default();
+        let table_planner =
+            TablePlanner::new(table_name, pred.clone(), tx.clone(), &self.metadata_mgr)?;
+        let indexes = self.metadata_mgr.get_index_info(table_name, tx.clone())?;
 
-        // TODO use index to find by predicate
-        if let Some(pred) = predicate {
-            scan.before_first()?;
+        // Collect the records first: an index scan over a field being modified
+        // would otherwise meet the updated records again under their new values.
+        let mut updated = Vec::new();
+        {
+            let mut scan = table_planner.make_update_scan()?;
             while scan.next()? {
-                if pred.is_satisfied(&mut scan)? {
-                    for (field, value) in fields.iter().zip(values.iter()) {
-                        scan.set_val(field, value.clone())?;
+                if pred.is_satisfied(&mut *scan)? {
+                    let mut old_vals = Vec::new();
+                    for field in fields.iter().filter(|f| indexes.contains_key(*f)) {
+                        old_vals.push((field, scan.get_val(field)?));
                     }
-                    affected_rows += 1;
+                    updated.push((scan.get_rid()?, old_vals));
                 }
             }
-        } else {
-            scan.before_first()?;
-            while scan.next()? {
-                for (field, value) in fields.iter().zip(values.iter()) {
-                    scan.set_val(field, value.clone())?;
+        }
+
+        let layout = self.metadata_mgr.get_layout(table_name, tx.clone())?;
+        if !updated.is_empty() {
+            let rids: Vec<RID> = updated.iter().map(|(rid, _)| *rid).collect();
+            Self::check_constraints(
+                table_name,
+                &layout,
+                fields,
+                values,
+                Some(&rids),
+                &indexes,
+                tx.clone(),
+            )?;
+        }
+
+        let mut scan = TableScan::new(tx.clone(), table_name, layout)?;
+        for (rid, old_vals) in &updated {
+            scan.move_to_rid(*rid)?;
+            for (field, value) in fields.iter().zip(values.iter()) {
+                scan.set_val(field, value.clone())?;
+            }
+            for (field, old_val) in old_vals {
+                let new_val = scan.get_val(field)?;
+                if *old_val == new_val {
+                    continue;
                 }
-                affected_rows += 1;
+                let mut index = indexes[*field].open(tx.clone())?;
+                index.delete(old_val, rid)?;
+                index.insert(&new_val, rid)?;
+                index.close();
             }
         }
 
-        Ok(affected_rows)
+        Ok(updated.len() as i32)
     }
 }
 
@@ -186,10 +610,14 @@ impl Planner {
 mod tests {
     use super::*;
     use crate::{
+        SimpleDB,
         index::Index,
         query::Constant,
-        record::{Layout, schema::Schema},
-        utils::testing_utils::temp_db,
+        record::{
+            Layout,
+            schema::{FieldType, Schema},
+        },
+        utils::testing_utils::{temp_db, temp_db_with_cfg},
     };
 
     #[test]
@@ -345,4 +773,1590 @@ mod tests {
         tx.commit()?;
         Ok(())
     }
+
+    #[test]
+    fn test_execute_create_index_on_populated_table() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        planner.execute_update("CREATE TABLE item (id INT, tag VARCHAR(8))", tx.clone())?;
+        for id in 0..300 {
+            let tag = format!("t{}", id % 7);
+            planner.execute_update(
+                &format!("INSERT INTO item (id, tag) VALUES ({id}, '{tag}')"),
+                tx.clone(),
+            )?;
+        }
+        planner.execute_update("CREATE INDEX tag_idx ON item (tag)", tx.clone())?;
+        planner.execute_update("CREATE INDEX id_idx ON item (id)", tx.clone())?;
+        planner.execute_update("INSERT INTO item (id, tag) VALUES (300, 't3')", tx.clone())?;
+
+        let collect = |sql: &str| -> DbResult<Vec<i32>> {
+            let plan = planner.create_query_plan(sql, tx.clone())?;
+            let mut scan = plan.open(tx.clone());
+            let mut ids = Vec::new();
+            while scan.next()? {
+                ids.push(scan.get_int("id")?);
+            }
+            ids.sort();
+            Ok(ids)
+        };
+        assert_eq!(
+            collect("SELECT id FROM item WHERE tag = 't3'")?,
+            (0..300)
+                .filter(|id| id % 7 == 3)
+                .chain([300])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(collect("SELECT id FROM item WHERE id = 150")?, vec![150]);
+        assert_eq!(
+            collect("SELECT id FROM item WHERE id >= 290")?,
+            (290..=300).collect::<Vec<_>>()
+        );
+
+        assert!(
+            planner
+                .execute_update("CREATE INDEX bad_idx ON item (price)", tx.clone())
+                .is_err()
+        );
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_delete() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let planner = db.planner();
+        planner.execute_update(
+            "CREATE TABLE test_table (id INT, name VARCHAR(20))",
+            tx.clone(),
+        )?;
+        for (id, name) in [(1, "Alice"), (2, "Bob"), (3, "Alice")] {
+            planner.execute_update(
+                &format!("INSERT INTO test_table (id, name) VALUES ({id}, '{name}')"),
+                tx.clone(),
+            )?;
+        }
+
+        let deleted =
+            planner.execute_update("DELETE FROM test_table WHERE name = 'Alice'", tx.clone())?;
+        assert_eq!(deleted, 2);
+
+        let plan = planner.create_query_plan("SELECT id FROM test_table", tx.clone())?;
+        let mut scan = plan.open(tx.clone());
+        assert!(scan.next()?);
+        assert_eq!(scan.get_int("id")?, 2);
+        assert!(!scan.next()?);
+        drop(scan);
+
+        assert_eq!(
+            planner.execute_update("DELETE FROM test_table", tx.clone())?,
+            1
+        );
+        let mut scan = plan.open(tx.clone());
+        assert!(!scan.next()?);
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_delete_with_index_maintenance() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let planner = db.planner();
+        planner.execute_update(
+            "CREATE TABLE test_table (id INT, name VARCHAR(20), age INT)",
+            tx.clone(),
+        )?;
+        planner.execute_update("CREATE INDEX age_idx ON test_table (age)", tx.clone())?;
+        planner.execute_update("CREATE INDEX name_idx ON test_table (name)", tx.clone())?;
+        for id in 0..30 {
+            planner.execute_update(
+                &format!(
+                    "INSERT INTO test_table (id, name, age) VALUES ({id}, 'Name{}', {})",
+                    id % 3,
+                    20 + id % 5
+                ),
+                tx.clone(),
+            )?;
+        }
+
+        // goes through age_idx
+        let deleted =
+            planner.execute_update("DELETE FROM test_table WHERE age = 21", tx.clone())?;
+        assert_eq!(deleted, 6);
+        // full scan, entries must still be removed from both indexes
+        let deleted = planner.execute_update("DELETE FROM test_table WHERE id = 0", tx.clone())?;
+        assert_eq!(deleted, 1);
+
+        let indexes = db.metadata_mgr().get_index_info("test_table", tx.clone())?;
+        let mut age_index = indexes["age"].open(tx.clone())?;
+        age_index.before_first(&Constant::int(21))?;
+        assert!(!age_index.next()?);
+        let mut age_20_cnt = 0;
+        age_index.before_first(&Constant::int(20))?;
+        while age_index.next()? {
+            age_20_cnt += 1;
+        }
+        assert_eq!(age_20_cnt, 5);
+        age_index.close();
+
+        let mut name_index = indexes["name"].open(tx.clone())?;
+        let mut name_0_cnt = 0;
+        name_index.before_first(&Constant::string("Name0"))?;
+        while name_index.next()? {
+            name_0_cnt += 1;
+        }
+        // ids 0, 3, ..., 27 minus id 0 and ids with age 21 (6, 21)
+        assert_eq!(name_0_cnt, 7);
+        name_index.close();
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_update_with_index_maintenance() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let planner = db.planner();
+        planner.execute_update(
+            "CREATE TABLE test_table (id INT, name VARCHAR(20), age INT)",
+            tx.clone(),
+        )?;
+        planner.execute_update("CREATE INDEX age_idx ON test_table (age)", tx.clone())?;
+        for id in 0..30 {
+            planner.execute_update(
+                &format!(
+                    "INSERT INTO test_table (id, name, age) VALUES ({id}, 'Name{}', {})",
+                    id % 3,
+                    20 + id % 5
+                ),
+                tx.clone(),
+            )?;
+        }
+
+        let count_age = |age: i32| -> DbResult<usize> {
+            let indexes = db.metadata_mgr().get_index_info("test_table", tx.clone())?;
+            let mut age_index = indexes["age"].open(tx.clone())?;
+            age_index.before_first(&Constant::int(age))?;
+            let mut cnt = 0;
+            while age_index.next()? {
+                cnt += 1;
+            }
+            age_index.close();
+            Ok(cnt)
+        };
+
+        // found through age_idx, and every record is moved only once although
+        // the new value still lies in the scanned range
+        let updated = planner.execute_update(
+            "UPDATE test_table SET age = 22 WHERE age >= 20 AND age < 22",
+            tx.clone(),
+        )?;
+        assert_eq!(updated, 12);
+        assert_eq!(count_age(20)?, 0);
+        assert_eq!(count_age(21)?, 0);
+        assert_eq!(count_age(22)?, 18);
+
+        // full scan
+        let updated = planner.execute_update(
+            "UPDATE test_table SET age = 30, name = 'Old' WHERE name = 'Name0'",
+            tx.clone(),
+        )?;
+        assert_eq!(updated, 10);
+        assert_eq!(count_age(30)?, 10);
+
+        let plan = planner
+            .create_query_plan("SELECT id, name FROM test_table WHERE age = 30", tx.clone())?;
+        let mut scan = plan.open(tx.clone());
+        let mut ids = Vec::new();
+        while scan.next()? {
+            assert_eq!(scan.get_string("name")?, "Old");
+            ids.push(scan.get_int("id")?);
+        }
+        ids.sort();
+        assert_eq!(ids, (0..30).filter(|id| id % 3 == 0).collect::<Vec<_>>());
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_delete_and_update_with_duplicate_index_keys() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        // far more records of one age than fit into a leaf of the index
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "CREATE TABLE test_table (id INT, age INT, grp INT)",
+            tx.clone(),
+        )?;
+        planner.execute_update("CREATE INDEX age_idx ON test_table (age)", tx.clone())?;
+        for id in 0..150 {
+            let age = if id % 10 == 0 { 5 } else { 7 };
+            planner.execute_update(
+                &format!(
+                    "INSERT INTO test_table (id, age, grp) VALUES ({id}, {age}, {})",
+                    id / 30
+                ),
+                tx.clone(),
+            )?;
+        }
+        tx.commit()?;
+
+        // through the index and by a full scan
+        let tx = db.new_tx()?;
+        let deleted = planner.execute_update(
+            "DELETE FROM test_table WHERE age = 7 AND grp = 0",
+            tx.clone(),
+        )?;
+        assert_eq!(deleted, 27);
+        let deleted = planner.execute_update("DELETE FROM test_table WHERE grp = 2", tx.clone())?;
+        assert_eq!(deleted, 30);
+        let updated =
+            planner.execute_update("UPDATE test_table SET age = 8 WHERE grp = 4", tx.clone())?;
+        assert_eq!(updated, 30);
+        tx.commit()?;
+
+        let query_ids = |sql: &str| -> DbResult<Vec<i32>> {
+            let tx = db.new_tx()?;
+            let mut ids = Vec::new();
+            {
+                let plan = planner.create_query_plan(sql, tx.clone())?;
+                let mut scan = plan.open(tx.clone());
+                while scan.next()? {
+                    ids.push(scan.get_int("id")?);
+                }
+            }
+            tx.commit()?;
+            ids.sort();
+            Ok(ids)
+        };
+        let ids = |filter: &dyn Fn(i32) -> bool| -> Vec<i32> {
+            (0..150)
+                .filter(|id| (id % 10 == 0 || *id >= 30) && !(60..90).contains(id))
+                .filter(|id| filter(*id))
+                .collect()
+        };
+        assert_eq!(query_ids("SELECT id FROM test_table")?, ids(&|_| true));
+        assert_eq!(
+            query_ids("SELECT id FROM test_table WHERE age = 7")?,
+            ids(&|id| id % 10 != 0 && id < 120)
+        );
+        assert_eq!(
+            query_ids("SELECT id FROM test_table WHERE age = 8")?,
+            ids(&|id| id >= 120)
+        );
+        assert_eq!(
+            query_ids("SELECT id FROM test_table WHERE age = 5")?,
+            ids(&|id| id % 10 == 0 && id < 120)
+        );
+        Ok(())
+    }
+
+    fn setup_emp_dept(planner: &Planner, tx: Transaction<'_>) -> DbResult<()> {
+        planner.execute_update("CREATE TABLE dept (did INT, dname VARCHAR(10))", tx.clone())?;
+        planner.execute_update(
+            "CREATE TABLE emp (eid INT, ename VARCHAR(10), edept INT)",
+            tx.clone(),
+        )?;
+        for (did, dname) in [(10, "eng"), (20, "sales"), (30, "hr")] {
+            planner.execute_update(
+                &format!("INSERT INTO dept (did, dname) VALUES ({did}, '{dname}')"),
+                tx.clone(),
+            )?;
+        }
+        for eid in 0..12 {
+            let edept = 10 * (eid % 2 + 1);
+            planner.execute_update(
+                &format!("INSERT INTO emp (eid, ename, edept) VALUES ({eid}, 'e{eid}', {edept})"),
+                tx.clone(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn collect_pairs(plan: &dyn Plan, tx: Transaction<'_>) -> DbResult<Vec<(String, String)>> {
+        let mut scan = plan.open(tx);
+        let mut pairs = Vec::new();
+        while scan.next()? {
+            pairs.push((scan.get_string("ename")?, scan.get_string("dname")?));
+        }
+        pairs.sort();
+        Ok(pairs)
+    }
+
+    #[test]
+    fn test_execute_drop_table_and_index() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update("CREATE TABLE test_table (id INT, age INT)", tx.clone())?;
+        planner.execute_update("CREATE INDEX age_idx ON test_table (age)", tx.clone())?;
+        planner.execute_update("CREATE INDEX id_idx ON test_table (id)", tx.clone())?;
+        for id in 0..20 {
+            planner.execute_update(
+                &format!(
+                    "INSERT INTO test_table (id, age) VALUES ({id}, {})",
+                    20 + id
+                ),
+                tx.clone(),
+            )?;
+        }
+        tx.commit()?;
+
+        // the files are deleted when the dropping transaction commits, which can't use them
+        let tx = db.new_tx()?;
+        assert_eq!(planner.execute_update("DROP INDEX id_idx", tx.clone())?, 1);
+        assert!(tx.size("id_idxleaf").is_err());
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        assert_eq!(tx.size("id_idxleaf")?, 0);
+        let indexes = db.metadata_mgr().get_index_info("test_table", tx.clone())?;
+        assert_eq!(indexes.keys().collect::<Vec<_>>(), vec!["age"]);
+        assert!(
+            planner
+                .execute_update("DROP INDEX id_idx", tx.clone())
+                .is_err()
+        );
+        assert_eq!(
+            planner.execute_update("DROP INDEX IF EXISTS id_idx", tx.clone())?,
+            0
+        );
+
+        assert_eq!(
+            planner.execute_update("DROP TABLE test_table", tx.clone())?,
+            1
+        );
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        assert_eq!(tx.size("test_table.tbl")?, 0);
+        assert_eq!(tx.size("age_idxleaf")?, 0);
+        assert_eq!(tx.size("age_idxinternal")?, 0);
+        assert!(
+            db.metadata_mgr()
+                .get_index_info("test_table", tx.clone())?
+                .is_empty()
+        );
+        assert!(
+            planner
+                .execute_update("DROP TABLE test_table", tx.clone())
+                .is_err()
+        );
+        assert_eq!(
+            planner.execute_update("DROP TABLE IF EXISTS test_table", tx.clone())?,
+            0
+        );
+
+        // the table can be created again, without any of the old rows
+        planner.execute_update("CREATE TABLE test_table (id INT, age INT)", tx.clone())?;
+        planner.execute_update(
+            "INSERT INTO test_table (id, age) VALUES (100, 50)",
+            tx.clone(),
+        )?;
+        let plan = planner.create_query_plan("SELECT id FROM test_table", tx.clone())?;
+        let mut scan = plan.open(tx.clone());
+        assert!(scan.next()?);
+        assert_eq!(scan.get_int("id")?, 100);
+        assert!(!scan.next()?);
+        drop(scan);
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn query_rows(db: &SimpleDB, sql: &str, fields: &[&str]) -> DbResult<Vec<Vec<Constant>>> {
+        let tx = db.new_tx()?;
+        let mut rows = Vec::new();
+        {
+            let plan = db.planner().create_query_plan(sql, tx.clone())?;
+            let mut scan = plan.open(tx.clone());
+            while scan.next()? {
+                rows.push(
+                    fields
+                        .iter()
+                        .map(|f| scan.get_val(f))
+                        .collect::<DbResult<Vec<_>>>()?,
+                );
+            }
+        }
+        tx.commit()?;
+        rows.sort_by_key(|row| row[0].clone());
+        Ok(rows)
+    }
+
+    #[test]
+    fn test_execute_with_nulls() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "CREATE TABLE test_table (id INT, name VARCHAR(10), score INT)",
+            tx.clone(),
+        )?;
+        planner.execute_update("CREATE INDEX score_idx ON test_table (score)", tx.clone())?;
+        planner.execute_update(
+            "INSERT INTO test_table (id, name, score) VALUES (1, 'a', 10)",
+            tx.clone(),
+        )?;
+        planner.execute_update("INSERT INTO test_table (id) VALUES (2)", tx.clone())?;
+        planner.execute_update(
+            "INSERT INTO test_table (id, name, score) VALUES (3, NULL, 30)",
+            tx.clone(),
+        )?;
+        tx.commit()?;
+
+        let fields = ["id", "name", "score"];
+        let rows = query_rows(&db, "SELECT id, name, score FROM test_table", &fields)?;
+        assert_eq!(
+            rows,
+            vec![
+                vec![Constant::int(1), Constant::string("a"), Constant::int(10)],
+                vec![Constant::int(2), Constant::Null, Constant::Null],
+                vec![Constant::int(3), Constant::Null, Constant::int(30)],
+            ]
+        );
+
+        let ids = |sql: &str| -> DbResult<Vec<Constant>> {
+            Ok(query_rows(&db, sql, &["id"])?
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect())
+        };
+        assert_eq!(
+            ids("SELECT id FROM test_table WHERE name IS NULL")?,
+            vec![Constant::int(2), Constant::int(3)]
+        );
+        assert_eq!(
+            ids("SELECT id FROM test_table WHERE score IS NOT NULL")?,
+            vec![Constant::int(1), Constant::int(3)]
+        );
+        // comparisons with null are unknown and match nothing
+        assert!(ids("SELECT id FROM test_table WHERE score = NULL")?.is_empty());
+        assert_eq!(
+            ids("SELECT id FROM test_table WHERE score <> 10")?,
+            vec![Constant::int(3)]
+        );
+        assert_eq!(
+            ids("SELECT id FROM test_table WHERE NOT (score > 20)")?,
+            vec![Constant::int(1)]
+        );
+
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "UPDATE test_table SET score = NULL WHERE id = 1",
+            tx.clone(),
+        )?;
+        tx.commit()?;
+        assert_eq!(
+            ids("SELECT id FROM test_table WHERE score > 0")?,
+            vec![Constant::int(3)]
+        );
+
+        let rows = query_rows(
+            &db,
+            "SELECT COUNT(*), COUNT(score), SUM(score), MIN(name) FROM test_table",
+            &["count(*)", "count(score)", "sum(score)", "min(name)"],
+        )?;
+        assert_eq!(
+            rows,
+            vec![vec![
+                Constant::int(3),
+                Constant::int(1),
+                Constant::int(30),
+                Constant::string("a")
+            ]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_with_column_types() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "CREATE TABLE events (id BIGINT, ok BOOLEAN, score DOUBLE, day DATE, at TIMESTAMP)",
+            tx.clone(),
+        )?;
+        planner.execute_update("CREATE INDEX id_idx ON events (id)", tx.clone())?;
+        planner.execute_update("CREATE INDEX day_idx ON events (day)", tx.clone())?;
+        for i in 0..20 {
+            planner.execute_update(
+                &format!(
+                    "INSERT INTO events (id, ok, score, day, at) VALUES \
+                     ({}, {}, {}.5, DATE '2024-01-{:02}', TIMESTAMP '2024-01-01 00:00:{:02}')",
+                    5_000_000_000_i64 + i,
+                    i % 2 == 0,
+                    i,
+                    i + 1,
+                    i
+                ),
+                tx.clone(),
+            )?;
+        }
+        // an INT literal is widened for a BIGINT field and its index
+        planner.execute_update("INSERT INTO events (id, score) VALUES (7, 1)", tx.clone())?;
+        assert!(
+            planner
+                .execute_update("INSERT INTO events (ok) VALUES (1)", tx.clone())
+                .is_err()
+        );
+        tx.commit()?;
+
+        let rows = query_rows(
+            &db,
+            "SELECT id, ok, score, day, at FROM events WHERE id = 5000000003",
+            &["id", "ok", "score", "day", "at"],
+        )?;
+        assert_eq!(
+            rows,
+            vec![vec![
+                Constant::BigInt(5_000_000_003),
+                Constant::Bool(false),
+                Constant::Double(3.5),
+                Constant::parse_date("2024-01-04")?,
+                Constant::parse_timestamp("2024-01-01 00:00:03")?,
+            ]]
+        );
+        let rows = query_rows(&db, "SELECT id FROM events WHERE id = 7", &["id"])?;
+        assert_eq!(rows, vec![vec![Constant::BigInt(7)]]);
+
+        let rows = query_rows(
+            &db,
+            "SELECT id FROM events WHERE day >= DATE '2024-01-18' AND ok = TRUE",
+            &["id"],
+        )?;
+        assert_eq!(rows, vec![vec![Constant::BigInt(5_000_000_018)]]);
+
+        let rows = query_rows(
+            &db,
+            "SELECT id FROM events WHERE score > 17 AND at < TIMESTAMP '2024-01-01 00:00:19'",
+            &["id"],
+        )?;
+        assert_eq!(
+            rows,
+            vec![
+                vec![Constant::BigInt(5_000_000_017)],
+                vec![Constant::BigInt(5_000_000_018)]
+            ]
+        );
+
+        let rows = query_rows(
+            &db,
+            "SELECT SUM(id), AVG(score), MAX(day) FROM events WHERE id > 100",
+            &["sum(id)", "avg(score)", "max(day)"],
+        )?;
+        assert_eq!(
+            rows,
+            vec![vec![
+                Constant::BigInt(100_000_000_190),
+                Constant::Double(10.0),
+                Constant::parse_date("2024-01-20")?,
+            ]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_with_slotted_table() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "CREATE TABLE notes (id INT, body VARCHAR(300)) WITH (format = 'slotted')",
+            tx.clone(),
+        )?;
+        planner.execute_update("CREATE INDEX id_idx ON notes (id)", tx.clone())?;
+        for id in 0..40 {
+            planner.execute_update(
+                &format!(
+                    "INSERT INTO notes (id, body) VALUES ({id}, '{}')",
+                    "n".repeat(id as usize % 5)
+                ),
+                tx.clone(),
+            )?;
+        }
+        // a slot of the fixed layout would take most of a block
+        assert!(tx.size("notes.tbl")? < 4);
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        // the grown records move to other blocks but keep their ids, so the index still finds them
+        planner.execute_update(
+            &format!(
+                "UPDATE notes SET body = '{}' WHERE id < 3",
+                "long".repeat(70)
+            ),
+            tx.clone(),
+        )?;
+        planner.execute_update("DELETE FROM notes WHERE id >= 10", tx.clone())?;
+        tx.commit()?;
+
+        let rows = query_rows(
+            &db,
+            "SELECT id, body FROM notes WHERE id = 1",
+            &["id", "body"],
+        )?;
+        assert_eq!(
+            rows,
+            vec![vec![Constant::Int(1), Constant::String("long".repeat(70))]]
+        );
+        let rows = query_rows(&db, "SELECT id, body FROM notes", &["id", "body"])?;
+        let expected: Vec<_> = (0..10)
+            .map(|id| {
+                let body = match id {
+                    0..3 => "long".repeat(70),
+                    _ => "n".repeat(id as usize % 5),
+                };
+                vec![Constant::Int(id), Constant::String(body)]
+            })
+            .collect();
+        assert_eq!(rows, expected);
+
+        let tx = db.new_tx()?;
+        let layout = db.metadata_mgr().get_layout("notes", tx.clone())?;
+        assert_eq!(layout.format(), RecordFormat::Slotted);
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_insert_reuses_deleted_space() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update("CREATE TABLE test_table (id INT, age INT)", tx.clone())?;
+        for id in 0..100 {
+            planner.execute_update(
+                &format!("INSERT INTO test_table (id, age) VALUES ({id}, 0)"),
+                tx.clone(),
+            )?;
+        }
+        let size = tx.size("test_table.tbl")?;
+        planner.execute_update("DELETE FROM test_table WHERE id < 50", tx.clone())?;
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        for id in 100..150 {
+            planner.execute_update(
+                &format!("INSERT INTO test_table (id, age) VALUES ({id}, 0)"),
+                tx.clone(),
+            )?;
+        }
+        assert_eq!(tx.size("test_table.tbl")?, size);
+        tx.commit()?;
+
+        let rows = query_rows(&db, "SELECT id FROM test_table", &["id"])?;
+        let expected: Vec<_> = (50..150).map(|id| vec![Constant::Int(id)]).collect();
+        assert_eq!(rows, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_vacuum() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update("CREATE TABLE test_table (id INT, age INT)", tx.clone())?;
+        planner.execute_update("CREATE INDEX age_idx ON test_table (age)", tx.clone())?;
+        for id in 0..200 {
+            planner.execute_update(
+                &format!(
+                    "INSERT INTO test_table (id, age) VALUES ({id}, {})",
+                    id % 10
+                ),
+                tx.clone(),
+            )?;
+        }
+        planner.execute_update("DELETE FROM test_table WHERE id < 180", tx.clone())?;
+        let table_size = tx.size("test_table.tbl")?;
+        let index_size = tx.size("age_idxleaf")?;
+        tx.commit()?;
+
+        // a rolled back vacuum leaves the table as it was
+        let tx = db.new_tx()?;
+        planner.execute_update("VACUUM test_table", tx.clone())?;
+        tx.rollback()?;
+        let tx = db.new_tx()?;
+        assert_eq!(tx.size("test_table.tbl")?, table_size);
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        assert_eq!(planner.execute_update("VACUUM test_table", tx.clone())?, 0);
+        tx.commit()?;
+        let tx = db.new_tx()?;
+        assert!(tx.size("test_table.tbl")? < table_size);
+        assert!(tx.size("age_idxleaf")? < index_size);
+        assert_eq!(tx.size("test_table.vacuum.tbl")?, 0);
+        tx.commit()?;
+
+        let expected = vec![vec![Constant::Int(183)], vec![Constant::Int(193)]];
+        let sql = "SELECT id FROM test_table WHERE age = 3";
+        assert_eq!(query_rows(&db, sql, &["id"])?, expected);
+        let rows = query_rows(&db, "SELECT id FROM test_table", &["id"])?;
+        assert_eq!(rows.len(), 20);
+
+        let db = db.reopen()?;
+        db.vacuum("test_table")?;
+        assert_eq!(query_rows(&db, sql, &["id"])?, expected);
+        assert!(db.vacuum("missing_table").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_with_constraints() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "CREATE TABLE users (id INT PRIMARY KEY, email VARCHAR(20) UNIQUE, \
+             name VARCHAR(20) NOT NULL)",
+            tx.clone(),
+        )?;
+        let indexes = db.metadata_mgr().get_index_info("users", tx.clone())?;
+        assert_eq!(indexes["id"].index_name(), "users_pkey");
+        assert_eq!(indexes["email"].index_name(), "users_email_key");
+        for sql in [
+            "INSERT INTO users (id, email, name) VALUES (1, 'a@x', 'A')",
+            "INSERT INTO users (id, name) VALUES (2, 'B')",
+            "INSERT INTO users (id, email, name) VALUES (3, NULL, 'C')",
+        ] {
+            planner.execute_update(sql, tx.clone())?;
+        }
+        tx.commit()?;
+
+        // the violations are found before anything is logged
+        let tx = db.new_tx()?;
+        let lsn = db.log_mgr().latest_lsn();
+        for sql in [
+            "INSERT INTO users (id, email, name) VALUES (1, 'b@x', 'D')",
+            "INSERT INTO users (email, name) VALUES ('b@x', 'D')",
+            "INSERT INTO users (id, email) VALUES (4, 'b@x')",
+            "INSERT INTO users (id, email, name) VALUES (4, 'a@x', 'D')",
+            "UPDATE users SET email = 'a@x' WHERE id = 2",
+            "UPDATE users SET email = 'b@x' WHERE id > 1",
+            "UPDATE users SET name = NULL WHERE id = 3",
+            "UPDATE users SET id = 1 WHERE id = 3",
+        ] {
+            let result = planner.execute_update(sql, tx.clone());
+            assert!(
+                matches!(result, Err(DbError::ConstraintViolation(_))),
+                "{}",
+                sql
+            );
+        }
+        // values of another type are rejected before the indexes are probed with them
+        for sql in [
+            "INSERT INTO users (id, email, name) VALUES ('abc', 'z', 'G')",
+            "UPDATE users SET id = 'abc' WHERE id = 2",
+            "INSERT INTO users (id, id, name) VALUES (4, 1, 'G')",
+        ] {
+            let result = planner.execute_update(sql, tx.clone());
+            assert!(matches!(result, Err(DbError::Schema(_))), "{}", sql);
+        }
+        assert_eq!(db.log_mgr().latest_lsn(), lsn);
+
+        // a record may keep its own value, and updates matching no record check nothing
+        planner.execute_update("UPDATE users SET email = 'a@x' WHERE id = 1", tx.clone())?;
+        planner.execute_update("UPDATE users SET id = 10 WHERE id = 3", tx.clone())?;
+        planner.execute_update("UPDATE users SET id = 1 WHERE id = 99", tx.clone())?;
+        tx.commit()?;
+        let rows = query_rows(&db, "SELECT id FROM users ORDER BY id", &["id"])?;
+        let ids: Vec<_> = [1, 2, 10]
+            .into_iter()
+            .map(|id| vec![Constant::Int(id)])
+            .collect();
+        assert_eq!(rows, ids);
+
+        // without its index a unique field is checked by scanning the table
+        let tx = db.new_tx()?;
+        planner.execute_update("DROP INDEX users_email_key", tx.clone())?;
+        let result = planner.execute_update(
+            "INSERT INTO users (id, email, name) VALUES (5, 'a@x', 'E')",
+            tx.clone(),
+        );
+        assert!(matches!(result, Err(DbError::ConstraintViolation(_))));
+        tx.commit()?;
+
+        // the constraints are kept in the catalog
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        let result = db
+            .planner()
+            .execute_update("INSERT INTO users (id, name) VALUES (2, 'F')", tx.clone());
+        assert!(matches!(result, Err(DbError::ConstraintViolation(_))));
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_with_text_and_blob() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+        let body = |id: i32| format!("{id}:{}", "text ".repeat(300));
+
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "CREATE TABLE docs (id INT, body TEXT, data BLOB)",
+            tx.clone(),
+        )?;
+        for id in 0..5 {
+            planner.execute_update(
+                &format!(
+                    "INSERT INTO docs (id, body, data) VALUES ({id}, '{}', X'{}')",
+                    body(id),
+                    "c0ffee".repeat(200)
+                ),
+                tx.clone(),
+            )?;
+        }
+        assert!(
+            planner
+                .execute_update("CREATE INDEX body_idx ON docs (body)", tx.clone())
+                .is_err()
+        );
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        planner.execute_update("UPDATE docs SET body = 'short' WHERE id = 1", tx.clone())?;
+        planner.execute_update("DELETE FROM docs WHERE id >= 3", tx.clone())?;
+        tx.commit()?;
+
+        // the values are reassembled from their chains, also through the temp tables of a sort
+        let rows = query_rows(
+            &db,
+            "SELECT id, body, data FROM docs ORDER BY body",
+            &["id", "body", "data"],
+        )?;
+        let data = Constant::Bytes([0xc0, 0xff, 0xee].repeat(200));
+        let expected = vec![
+            vec![Constant::Int(0), Constant::String(body(0)), data.clone()],
+            vec![
+                Constant::Int(1),
+                Constant::String("short".to_string()),
+                data.clone(),
+            ],
+            vec![Constant::Int(2), Constant::String(body(2)), data],
+        ];
+        assert_eq!(rows, expected);
+
+        // a vacuum copies the values into the chains of a new overflow file
+        let tx = db.new_tx()?;
+        let ovf_size = tx.size("docs.ovf")?;
+        tx.commit()?;
+        db.vacuum("docs")?;
+        let tx = db.new_tx()?;
+        assert!(tx.size("docs.ovf")? < ovf_size);
+        tx.commit()?;
+        let rows = query_rows(
+            &db,
+            "SELECT id, body, data FROM docs ORDER BY body",
+            &["id", "body", "data"],
+        )?;
+        assert_eq!(rows, expected);
+
+        let tx = db.new_tx()?;
+        planner.execute_update("ALTER TABLE docs DROP COLUMN data", tx.clone())?;
+        tx.commit()?;
+        let rows = query_rows(
+            &db,
+            "SELECT id, body FROM docs WHERE id = 2",
+            &["id", "body"],
+        )?;
+        assert_eq!(
+            rows,
+            vec![vec![Constant::Int(2), Constant::String(body(2))]]
+        );
+
+        let tx = db.new_tx()?;
+        planner.execute_update("DROP TABLE docs", tx.clone())?;
+        tx.commit()?;
+        let tx = db.new_tx()?;
+        assert_eq!(tx.size("docs.ovf")?, 0);
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_alter_table() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update("CREATE TABLE test_table (id INT, age INT)", tx.clone())?;
+        planner.execute_update("CREATE INDEX age_idx ON test_table (age)", tx.clone())?;
+        // enough records for several blocks, so that they move when the slots grow
+        for id in 0..60 {
+            planner.execute_update(
+                &format!("INSERT INTO test_table (id, age) VALUES ({id}, {})", id % 7),
+                tx.clone(),
+            )?;
+        }
+        planner.execute_update("DELETE FROM test_table WHERE id < 10", tx.clone())?;
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "ALTER TABLE test_table ADD COLUMN name VARCHAR(10) DEFAULT 'none'",
+            tx.clone(),
+        )?;
+        planner.execute_update("ALTER TABLE test_table ADD COLUMN score INT", tx.clone())?;
+        planner.execute_update("UPDATE test_table SET name = 'x' WHERE id = 59", tx.clone())?;
+        tx.commit()?;
+
+        let rows = query_rows(
+            &db,
+            "SELECT id, name, score FROM test_table",
+            &["id", "name", "score"],
+        )?;
+        assert_eq!(rows.len(), 50);
+        assert_eq!(
+            rows[0],
+            vec![Constant::int(10), Constant::string("none"), Constant::Null]
+        );
+        assert_eq!(
+            rows[49],
+            vec![Constant::int(59), Constant::string("x"), Constant::Null]
+        );
+
+        // the index still finds every record after they moved
+        let rows = query_rows(
+            &db,
+            "SELECT id, age FROM test_table WHERE age = 3",
+            &["id", "age"],
+        )?;
+        let expected: Vec<_> = (10..60).filter(|id| id % 7 == 3).collect();
+        assert_eq!(
+            rows.iter().map(|r| r[0].clone()).collect::<Vec<_>>(),
+            expected
+                .iter()
+                .map(|id| Constant::int(*id))
+                .collect::<Vec<_>>()
+        );
+
+        let tx = db.new_tx()?;
+        planner.execute_update("ALTER TABLE test_table DROP COLUMN name", tx.clone())?;
+        planner.execute_update(
+            "ALTER TABLE test_table RENAME COLUMN age TO years",
+            tx.clone(),
+        )?;
+        planner.execute_update("ALTER TABLE test_table RENAME TO people", tx.clone())?;
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        assert!(!db.metadata_mgr().table_exists("test_table", tx.clone())?);
+        assert_eq!(tx.size("test_table.tbl")?, 0);
+        let layout = db.metadata_mgr().get_layout("people", tx.clone())?;
+        assert_eq!(layout.schema().fields(), ["id", "years", "score"]);
+        let indexes = db.metadata_mgr().get_index_info("people", tx.clone())?;
+        assert_eq!(indexes.keys().collect::<Vec<_>>(), vec!["years"]);
+        assert!(
+            planner
+                .execute_update("ALTER TABLE people DROP COLUMN name", tx.clone())
+                .is_err()
+        );
+        assert!(
+            planner
+                .execute_update("ALTER TABLE people ADD COLUMN id INT", tx.clone())
+                .is_err()
+        );
+        tx.commit()?;
+
+        let rows = query_rows(
+            &db,
+            "SELECT id, years FROM people WHERE years = 3",
+            &["id", "years"],
+        )?;
+        assert_eq!(rows.len(), expected.len());
+        let rows = query_rows(&db, "SELECT id, score FROM people", &["id", "score"])?;
+        assert_eq!(rows.len(), 50);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rolled_back_alter_table_keeps_records() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "CREATE TABLE test_table (id INT, name VARCHAR(10))",
+            tx.clone(),
+        )?;
+        for id in 0..30 {
+            planner.execute_update(
+                &format!("INSERT INTO test_table (id, name) VALUES ({id}, 'n{id}')"),
+                tx.clone(),
+            )?;
+        }
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        planner.execute_update(
+            "ALTER TABLE test_table ADD COLUMN age INT DEFAULT 5",
+            tx.clone(),
+        )?;
+        planner.execute_update("ALTER TABLE test_table DROP COLUMN name", tx.clone())?;
+        tx.rollback()?;
+
+        let rows = query_rows(&db, "SELECT id, name FROM test_table", &["id", "name"])?;
+        assert_eq!(rows.len(), 30);
+        for (id, row) in rows.iter().enumerate() {
+            assert_eq!(row[1], Constant::string(format!("n{id}")));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rolled_back_drop_table_keeps_table() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update("CREATE TABLE test_table (id INT)", tx.clone())?;
+        planner.execute_update("INSERT INTO test_table (id) VALUES (1)", tx.clone())?;
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        planner.execute_update("DROP TABLE test_table", tx.clone())?;
+        tx.rollback()?;
+
+        let tx = db.new_tx()?;
+        let plan = planner.create_query_plan("SELECT id FROM test_table", tx.clone())?;
+        let mut scan = plan.open(tx.clone());
+        assert!(scan.next()?);
+        assert_eq!(scan.get_int("id")?, 1);
+        drop(scan);
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_dropped_table_is_not_created_again_in_same_tx() -> DbResult<()> {
+        let db = temp_db()?;
+        let planner = db.planner();
+
+        let tx = db.new_tx()?;
+        planner.execute_update("CREATE TABLE test_table (id INT)", tx.clone())?;
+        planner.execute_update("CREATE TABLE other (id INT)", tx.clone())?;
+        planner.execute_update("INSERT INTO test_table (id) VALUES (1)", tx.clone())?;
+        planner.execute_update("INSERT INTO other (id) VALUES (2)", tx.clone())?;
+        tx.commit()?;
+
+        // the file of the dropped table is deleted when the transaction commits
+        let tx = db.new_tx()?;
+        planner.execute_update("DROP TABLE test_table", tx.clone())?;
+        planner.execute_update("CREATE TABLE test_table (id INT)", tx.clone())?;
+        assert!(
+            planner
+                .execute_update("INSERT INTO test_table (id) VALUES (3)", tx.clone())
+                .is_err()
+        );
+        tx.rollback()?;
+
+        let tx = db.new_tx()?;
+        planner.execute_update("DROP TABLE test_table", tx.clone())?;
+        assert!(
+            planner
+                .execute_update("ALTER TABLE other RENAME TO test_table", tx.clone())
+                .is_err()
+        );
+        tx.rollback()?;
+
+        let rows = query_rows(&db, "SELECT id FROM test_table", &["id"])?;
+        assert_eq!(rows, vec![vec![Constant::Int(1)]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_table_query() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        setup_emp_dept(planner, tx.clone())?;
+
+        let plan = planner.create_query_plan(
+            "SELECT ename, dname FROM emp, dept WHERE edept = did AND eid = 1",
+            tx.clone(),
+        )?;
+        assert_eq!(
+            collect_pairs(&*plan, tx.clone())?,
+            vec![("e1".to_string(), "sales".to_string())]
+        );
+
+        let plan = planner.create_query_plan(
+            "SELECT ename, dname FROM dept JOIN emp ON emp.edept = dept.did WHERE dname = 'sales'",
+            tx.clone(),
+        )?;
+        let mut expected: Vec<_> = (0..12)
+            .filter(|eid| eid % 2 == 1)
+            .map(|eid| (format!("e{eid}"), "sales".to_string()))
+            .collect();
+        expected.sort();
+        assert_eq!(collect_pairs(&*plan, tx.clone())?, expected);
+
+        // without a join predicate every pair of records is returned
+        let plan = planner.create_query_plan("SELECT ename, dname FROM emp, dept", tx.clone())?;
+        assert_eq!(collect_pairs(&*plan, tx.clone())?.len(), 36);
+        assert!(plan.schema().has_field("ename"));
+        assert!(!plan.schema().has_field("did"));
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_table_query_rejects_ambiguous_fields() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        planner.execute_update("CREATE TABLE a (id INT, aval INT)", tx.clone())?;
+        planner.execute_update("CREATE TABLE b (id INT, bval INT)", tx.clone())?;
+        planner.execute_update("INSERT INTO a (id, aval) VALUES (1, 10)", tx.clone())?;
+        planner.execute_update("INSERT INTO b (id, bval) VALUES (2, 20)", tx.clone())?;
+
+        for sql in [
+            "SELECT aval, bval FROM a JOIN b ON a.id = b.id",
+            "SELECT id FROM a, b",
+            "SELECT aval FROM a, b ORDER BY b.id",
+            "SELECT * FROM a, b",
+        ] {
+            assert!(
+                matches!(
+                    planner.create_query_plan(sql, tx.clone()),
+                    Err(crate::error::DbError::Schema(_))
+                ),
+                "{sql}"
+            );
+        }
+
+        // a field in both tables is fine as long as the query doesn't use it
+        let plan =
+            planner.create_query_plan("SELECT aval, bval FROM a, b WHERE aval = 10", tx.clone())?;
+        let mut scan = plan.open(tx.clone());
+        assert!(scan.next()?);
+        assert_eq!(scan.get_int("aval")?, 10);
+        assert_eq!(scan.get_int("bval")?, 20);
+        assert!(!scan.next()?);
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_table_query_with_mismatched_join_types() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        planner.execute_update("CREATE TABLE a (aid INT, x INT)", tx.clone())?;
+        planner.execute_update("CREATE INDEX aid_idx ON a (aid)", tx.clone())?;
+        planner.execute_update("CREATE TABLE c (z VARCHAR(10))", tx.clone())?;
+        for i in 0..10 {
+            planner.execute_update(
+                &format!("INSERT INTO a (aid, x) VALUES ({i}, {i})"),
+                tx.clone(),
+            )?;
+            planner.execute_update(&format!("INSERT INTO c (z) VALUES ('{i}')"), tx.clone())?;
+        }
+
+        // neither the index on aid nor the order of a can be used to look up strings
+        let plan =
+            planner.create_query_plan("SELECT z, aid FROM c, a WHERE z = aid", tx.clone())?;
+        let mut scan = plan.open(tx.clone());
+        assert!(!scan.next()?);
+        drop(scan);
+        let plan = planner.create_query_plan(
+            "SELECT aid, z FROM a, c WHERE aid = z AND aid = 4",
+            tx.clone(),
+        )?;
+        assert_eq!(plan.sorted_on(), None);
+        let mut scan = plan.open(tx.clone());
+        assert!(!scan.next()?);
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_table_query_join_order() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        setup_emp_dept(planner, tx.clone())?;
+        planner.execute_update("CREATE TABLE proj (pid INT, pdept INT)", tx.clone())?;
+        for (pid, pdept) in [(1, 10), (2, 30)] {
+            planner.execute_update(
+                &format!("INSERT INTO proj (pid, pdept) VALUES ({pid}, {pdept})"),
+                tx.clone(),
+            )?;
+        }
+
+        // proj and emp are only related through dept, which is therefore joined second
+        let plan = planner.create_query_plan(
+            "SELECT ename, dname, pid FROM proj, emp, dept WHERE pdept = did AND edept = did",
+            tx.clone(),
+        )?;
+        let mut scan = plan.open(tx.clone());
+        let mut count = 0;
+        while scan.next()? {
+            assert_eq!(scan.get_int("pid")?, 1);
+            assert_eq!(scan.get_string("dname")?, "eng");
+            count += 1;
+        }
+        assert_eq!(count, 6);
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_table_query_uses_index_join() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        setup_emp_dept(planner, tx.clone())?;
+        planner.execute_update("CREATE INDEX edept_idx ON emp (edept)", tx.clone())?;
+        for eid in 0..12 {
+            let edept = 10 * (eid % 2 + 1);
+            planner.execute_update(
+                &format!("INSERT INTO emp (eid, ename, edept) VALUES ({eid}, 'i{eid}', {edept})"),
+                tx.clone(),
+            )?;
+        }
+
+        // the index covers the records inserted before it was created as well
+        let mut expected: Vec<_> = (0..12)
+            .filter(|eid| eid % 2 == 0)
+            .flat_map(|eid| [format!("e{eid}"), format!("i{eid}")])
+            .map(|ename| (ename, "eng".to_string()))
+            .collect();
+        expected.sort();
+        for sql in [
+            "SELECT ename, dname FROM dept, emp WHERE edept = did AND dname = 'eng'",
+            "SELECT ename, dname FROM emp, dept WHERE did = edept AND dname = 'eng'",
+        ] {
+            let plan = planner.create_query_plan(sql, tx.clone())?;
+            let pairs = collect_pairs(&*plan, tx.clone())?;
+            assert_eq!(pairs, expected, "{sql}");
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_table_query_uses_merge_join() -> DbResult<()> {
+        let mut db_dir = std::path::PathBuf::new();
+        let db = temp_db_with_cfg(|cfg| {
+            db_dir = cfg.storage_mgr.db_directory().unwrap().to_path_buf();
+            cfg
+        })?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        planner.execute_update("CREATE TABLE a (aid INT, x INT)", tx.clone())?;
+        planner.execute_update("CREATE INDEX aid_idx ON a (aid)", tx.clone())?;
+        planner.execute_update("CREATE TABLE b (bid INT, y INT)", tx.clone())?;
+        for i in 0..30 {
+            let aid = (i * 7) % 30;
+            planner.execute_update(
+                &format!("INSERT INTO a (aid, x) VALUES ({aid}, {i})"),
+                tx.clone(),
+            )?;
+            let bid = (i * 11) % 15;
+            planner.execute_update(
+                &format!("INSERT INTO b (bid, y) VALUES ({bid}, {i})"),
+                tx.clone(),
+            )?;
+        }
+
+        // the index range returns the records of a sorted on aid, so only b is sorted
+        let plan = planner.create_query_plan(
+            "SELECT aid, bid FROM a, b WHERE aid = bid AND aid > 4",
+            tx.clone(),
+        )?;
+        assert_eq!(plan.sorted_on(), Some("aid"));
+        let mut scan = plan.open(tx.clone());
+        let mut aids = Vec::new();
+        while scan.next()? {
+            assert_eq!(scan.get_int("aid")?, scan.get_int("bid")?);
+            aids.push(scan.get_int("aid")?);
+        }
+        drop(scan);
+        assert_eq!(aids, (5..15).flat_map(|aid| [aid, aid]).collect::<Vec<_>>());
+
+        // the sorted runs are deleted with the scan
+        let temp_files = std::fs::read_dir(&db_dir)?
+            .filter(|entry| {
+                entry.as_ref().is_ok_and(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with(crate::storage::TEMP_FILE_PREFIX)
+                })
+            })
+            .count();
+        assert_eq!(temp_files, 0);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_order_by_limit() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        setup_emp_dept(planner, tx.clone())?;
+
+        let collect = |sql: &str| -> DbResult<Vec<String>> {
+            let plan = planner.create_query_plan(sql, tx.clone())?;
+            let mut scan = plan.open(tx.clone());
+            let mut names = Vec::new();
+            while scan.next()? {
+                names.push(scan.get_string("ename")?);
+            }
+            Ok(names)
+        };
+
+        // sorted by a field which is not selected
+        assert_eq!(
+            collect("SELECT ename FROM emp WHERE edept = 10 ORDER BY eid DESC")?,
+            vec!["e10", "e8", "e6", "e4", "e2", "e0"]
+        );
+        assert_eq!(
+            collect("SELECT ename FROM emp ORDER BY edept DESC, eid LIMIT 3 OFFSET 2")?,
+            vec!["e5", "e7", "e9"]
+        );
+        assert_eq!(
+            collect(
+                "SELECT ename, dname FROM emp, dept WHERE edept = did ORDER BY dname, eid LIMIT 2"
+            )?,
+            vec!["e0", "e2"]
+        );
+        assert_eq!(collect("SELECT ename FROM emp LIMIT 4")?.len(), 4);
+        assert_eq!(collect("SELECT ename FROM emp OFFSET 10")?.len(), 2);
+        assert_eq!(
+            collect(&format!(
+                "SELECT ename FROM emp ORDER BY eid LIMIT {} OFFSET 10",
+                usize::MAX
+            ))?,
+            vec!["e10", "e11"]
+        );
+        assert!(collect("SELECT ename FROM emp ORDER BY salary").is_err());
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_group_by() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        setup_emp_dept(planner, tx.clone())?;
+
+        let plan = planner.create_query_plan(
+            "SELECT edept, COUNT(*), SUM(eid), MIN(ename), AVG(eid) AS average FROM emp \
+             GROUP BY edept ORDER BY edept DESC",
+            tx.clone(),
+        )?;
+        let schema = plan.schema();
+        assert_eq!(
+            schema.fields(),
+            &["edept", "count(*)", "sum(eid)", "min(ename)", "average"]
+        );
+        assert_eq!(schema.field_type("min(ename)"), Some(FieldType::Varchar));
+        let mut scan = plan.open(tx.clone());
+        let mut groups = Vec::new();
+        while scan.next()? {
+            groups.push((
+                scan.get_int("edept")?,
+                scan.get_int("count(*)")?,
+                scan.get_int("sum(eid)")?,
+                scan.get_string("min(ename)")?,
+                scan.get_int("average")?,
+            ));
+        }
+        assert_eq!(
+            groups,
+            vec![
+                (20, 6, 36, "e1".to_string(), 6),
+                (10, 6, 30, "e0".to_string(), 5)
+            ]
+        );
+        drop(scan);
+
+        // HAVING may use an aggregate which is not selected
+        let plan = planner.create_query_plan(
+            "SELECT dname, COUNT(eid) AS n FROM emp, dept WHERE edept = did \
+             GROUP BY dname HAVING MAX(eid) = 11",
+            tx.clone(),
+        )?;
+        let mut scan = plan.open(tx.clone());
+        assert!(scan.next()?);
+        assert_eq!(scan.get_string("dname")?, "sales");
+        assert_eq!(scan.get_int("n")?, 6);
+        assert!(!scan.next()?);
+        drop(scan);
+
+        let plan =
+            planner.create_query_plan("SELECT COUNT(*) FROM emp WHERE eid = 100", tx.clone())?;
+        let mut scan = plan.open(tx.clone());
+        assert!(scan.next()?);
+        assert_eq!(scan.get_int("count(*)")?, 0);
+        drop(scan);
+
+        for sql in [
+            "SELECT ename, COUNT(*) FROM emp GROUP BY edept",
+            "SELECT SUM(ename) FROM emp",
+            "SELECT edept FROM emp GROUP BY salary",
+            "SELECT ename FROM emp WHERE COUNT(*) = 1",
+        ] {
+            assert!(planner.create_query_plan(sql, tx.clone()).is_err(), "{sql}");
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_where_operators() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        setup_emp_dept(planner, tx.clone())?;
+
+        let collect = |sql: &str| -> DbResult<Vec<i32>> {
+            let plan = planner.create_query_plan(sql, tx.clone())?;
+            let mut scan = plan.open(tx.clone());
+            let mut ids = Vec::new();
+            while scan.next()? {
+                ids.push(scan.get_int("eid")?);
+            }
+            ids.sort();
+            Ok(ids)
+        };
+
+        assert_eq!(
+            collect("SELECT eid FROM emp WHERE eid > 8")?,
+            vec![9, 10, 11]
+        );
+        assert_eq!(
+            collect("SELECT eid FROM emp WHERE eid <= 2 OR eid >= 11")?,
+            vec![0, 1, 2, 11]
+        );
+        assert_eq!(
+            collect("SELECT eid FROM emp WHERE eid BETWEEN 3 AND 6 AND NOT edept = 10")?,
+            vec![3, 5]
+        );
+        assert_eq!(
+            collect("SELECT eid FROM emp WHERE ename IN ('e1', 'e4', 'x') AND eid <> 4")?,
+            vec![1]
+        );
+        assert_eq!(
+            collect("SELECT eid FROM emp WHERE ename IS NULL")?,
+            Vec::<i32>::new()
+        );
+        assert_eq!(
+            collect("SELECT eid FROM emp WHERE eid IS NOT NULL")?.len(),
+            12
+        );
+
+        // a disjunction over the fields of both tables is checked after the join
+        assert_eq!(
+            collect(
+                "SELECT eid, dname FROM emp, dept WHERE edept = did \
+                 AND (dname = 'sales' OR eid < 3) AND eid < 6"
+            )?,
+            vec![0, 1, 2, 3, 5]
+        );
+
+        assert!(collect("SELECT eid FROM emp WHERE ename < 5").is_err());
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_uses_index_range() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let planner = db.planner();
+        planner.execute_update("CREATE TABLE item (id INT, price INT)", tx.clone())?;
+        planner.execute_update("CREATE INDEX price_idx ON item (price)", tx.clone())?;
+        for id in 0..200 {
+            let price = (id * 7) % 100;
+            planner.execute_update(
+                &format!("INSERT INTO item (id, price) VALUES ({id}, {price})"),
+                tx.clone(),
+            )?;
+        }
+
+        let collect = |sql: &str| -> DbResult<Vec<i32>> {
+            let plan = planner.create_query_plan(sql, tx.clone())?;
+            let mut scan = plan.open(tx.clone());
+            let mut ids = Vec::new();
+            while scan.next()? {
+                ids.push(scan.get_int("id")?);
+            }
+            ids.sort();
+            Ok(ids)
+        };
+        let expected =
+            |f: fn(i32) -> bool| -> Vec<i32> { (0..200).filter(|id| f((id * 7) % 100)).collect() };
+
+        assert_eq!(
+            collect("SELECT id FROM item WHERE price > 90")?,
+            expected(|p| p > 90)
+        );
+        assert_eq!(
+            collect("SELECT id FROM item WHERE 10 >= price")?,
+            expected(|p| p <= 10)
+        );
+        assert_eq!(
+            collect("SELECT id FROM item WHERE price BETWEEN 40 AND 45 AND price <> 42")?,
+            expected(|p| (40..=45).contains(&p) && p != 42)
+        );
+        assert_eq!(
+            collect("SELECT id FROM item WHERE price >= 20 AND price < 25 AND id < 100")?,
+            (0..100)
+                .filter(|id| (20..25).contains(&((id * 7) % 100)))
+                .collect::<Vec<_>>()
+        );
+
+        // deletes find their victims through the range as well
+        assert_eq!(
+            planner.execute_update("DELETE FROM item WHERE price < 50", tx.clone())?,
+            expected(|p| p < 50).len() as i32
+        );
+        assert_eq!(
+            collect("SELECT id FROM item WHERE price < 60")?,
+            expected(|p| (50..60).contains(&p))
+        );
+
+        tx.commit()?;
+        Ok(())
+    }
 }
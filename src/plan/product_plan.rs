@@ -0,0 +1,31 @@
+use crate::plan::Plan;
+use crate::query::{ProductScan, Scan};
+use crate::record::schema::Schema;
+use crate::tx::Transaction;
+
+pub struct ProductPlan {
+    p1: Box<dyn Plan>,
+    p2: Box<dyn Plan>,
+    schema: Schema,
+}
+
+impl ProductPlan {
+    pub fn new(p1: Box<dyn Plan>, p2: Box<dyn Plan>) -> Self {
+        let mut schema = Schema::new();
+        schema.add_all(&p1.schema());
+        schema.add_all(&p2.schema());
+        ProductPlan { p1, p2, schema }
+    }
+}
+
+impl Plan for ProductPlan {
+    fn open<'tx>(&self, tx: Transaction<'tx>) -> Box<dyn Scan + 'tx> {
+        let s1 = self.p1.open(tx.clone());
+        let s2 = self.p2.open(tx);
+        Box::new(ProductScan::new(s1, s2))
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
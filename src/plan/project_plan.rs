@@ -33,4 +33,10 @@ impl Plan for ProjectPlan {
     fn schema(&self) -> Schema {
         self.schema.clone()
     }
+
+    fn sorted_on(&self) -> Option<&str> {
+        self.plan
+            .sorted_on()
+            .filter(|field_name| self.schema.has_field(field_name))
+    }
 }
@@ -23,4 +23,8 @@ impl Plan for SelectPlan {
     fn schema(&self) -> Schema {
         self.plan.schema()
     }
+
+    fn sorted_on(&self) -> Option<&str> {
+        self.plan.sorted_on()
+    }
 }
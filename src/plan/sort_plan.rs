@@ -0,0 +1,347 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::error::DbResult;
+use crate::plan::Plan;
+use crate::query::sort_scan::RecordComparator;
+use crate::query::{Constant, Scan, SortField, SortScan, UpdateScan};
+use crate::record::{Layout, TempTable, schema::Schema};
+use crate::tx::Transaction;
+
+/// Sorts the output of a plan with an external merge sort. The input is split into runs
+/// which are sorted in memory and written to temporary tables, and the runs are then merged
+/// until few enough are left for [SortScan] to merge them while it is being read.
+///
+/// When only the first records of the output are needed (see [SortPlan::limit]) and they fit
+/// into the available buffers, they are picked with a bounded heap in a single pass instead.
+pub struct SortPlan {
+    plan: Box<dyn Plan>,
+    schema: Schema,
+    comparator: RecordComparator,
+    limit: Option<usize>,
+}
+
+impl SortPlan {
+    pub fn new(plan: Box<dyn Plan>, sort_fields: Vec<SortField>) -> Self {
+        let schema = plan.schema();
+        SortPlan {
+            plan,
+            schema,
+            comparator: RecordComparator::new(sort_fields),
+            limit: None,
+        }
+    }
+
+    /// Declares that only the first `limit` records of the sorted output will be read.
+    /// Records past the limit may be left out of the result.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sorts the input and opens a scan over the result.
+    pub fn open_sorted<'tx>(&self, tx: Transaction<'tx>) -> DbResult<SortScan<'tx>> {
+        let mut src = self.plan.open(tx.clone());
+        let mut runs = match self.limit {
+            Some(limit) if limit <= self.max_run_records(&tx) => {
+                vec![self.top_n(&mut *src, limit, &tx)?]
+            }
+            _ => self.split_into_runs(&mut *src, &tx)?,
+        };
+        drop(src);
+
+        let fan_in = Self::fan_in(&tx);
+        while runs.len() > fan_in {
+            runs = runs
+                .chunks(fan_in)
+                .map(|group| self.merge_runs(group.to_vec(), &tx))
+                .collect::<DbResult<Vec<_>>>()?;
+        }
+        SortScan::new(runs, self.comparator.clone(), tx)
+    }
+
+    /// Reads as many records as fit into the available buffers, sorts them and writes
+    /// them to a new run, until the input is exhausted.
+    fn split_into_runs(
+        &self,
+        src: &mut dyn Scan,
+        tx: &Transaction<'_>,
+    ) -> DbResult<Vec<TempTable>> {
+        let max_run_records = self.max_run_records(tx);
+        let keys = self.sort_keys();
+
+        let mut runs = Vec::new();
+        src.before_first()?;
+        let mut has_more = true;
+        while has_more {
+            let mut rows: Vec<Vec<Constant>> = Vec::new();
+            while rows.len() < max_run_records {
+                has_more = src.next()?;
+                if !has_more {
+                    break;
+                }
+                rows.push(self.read_row(src)?);
+            }
+            if rows.is_empty() {
+                break;
+            }
+
+            rows.sort_by(|r1, r2| compare_rows(&keys, r1, r2));
+            runs.push(self.write_run(rows, tx)?);
+        }
+        Ok(runs)
+    }
+
+    /// Reads the whole input keeping only the `limit` smallest records in a max-heap,
+    /// and writes them to a single run.
+    fn top_n(&self, src: &mut dyn Scan, limit: usize, tx: &Transaction<'_>) -> DbResult<TempTable> {
+        let keys = self.sort_keys();
+        let mut heap: BinaryHeap<HeapRow> = BinaryHeap::with_capacity(limit);
+
+        src.before_first()?;
+        while limit > 0 && src.next()? {
+            let row = HeapRow {
+                row: self.read_row(src)?,
+                keys: &keys,
+            };
+            if heap.len() < limit {
+                heap.push(row);
+            } else if heap.peek().is_some_and(|largest| row < *largest) {
+                heap.pop();
+                heap.push(row);
+            }
+        }
+
+        let rows = heap.into_sorted_vec().into_iter().map(|r| r.row).collect();
+        self.write_run(rows, tx)
+    }
+
+    /// Merges the runs into a new one, deleting them.
+    fn merge_runs(&self, runs: Vec<TempTable>, tx: &Transaction<'_>) -> DbResult<TempTable> {
+        let mut src = SortScan::new(runs, self.comparator.clone(), tx.clone())?;
+        let result = TempTable::new(self.schema.clone());
+        let mut dest = result.open(tx.clone())?;
+        while src.next()? {
+            dest.insert()?;
+            for field_name in self.schema.fields() {
+                dest.set_val(field_name, src.get_val(field_name)?)?;
+            }
+        }
+        Ok(result)
+    }
+
+    fn read_row(&self, src: &mut dyn Scan) -> DbResult<Vec<Constant>> {
+        self.schema
+            .fields()
+            .iter()
+            .map(|field_name| src.get_val(field_name))
+            .collect()
+    }
+
+    fn write_run(&self, rows: Vec<Vec<Constant>>, tx: &Transaction<'_>) -> DbResult<TempTable> {
+        let run = TempTable::new(self.schema.clone());
+        let mut dest = run.open(tx.clone())?;
+        for row in rows {
+            dest.insert()?;
+            for (field_name, val) in self.schema.fields().iter().zip(row) {
+                dest.set_val(field_name, val)?;
+            }
+        }
+        Ok(run)
+    }
+
+    /// Positions of the sort fields in a row read by [SortPlan::read_row].
+    fn sort_keys(&self) -> Vec<(usize, &SortField)> {
+        self.comparator
+            .fields()
+            .iter()
+            .map(|sort_field| {
+                let pos = self
+                    .schema
+                    .fields()
+                    .iter()
+                    .position(|f| f == sort_field.field_name())
+                    .expect("Sort field not found in schema");
+                (pos, sort_field)
+            })
+            .collect()
+    }
+
+    /// Number of records which fit into the available buffers.
+    fn max_run_records(&self, tx: &Transaction<'_>) -> usize {
+        let slot_size = Layout::new(self.schema.clone()).slot_size();
+        tx.available_buffs().max(1) * (tx.block_size() / slot_size).max(1)
+    }
+
+    /// Number of runs merged at once. Every run being merged pins a buffer,
+    /// and one more is needed for the output.
+    fn fan_in(tx: &Transaction<'_>) -> usize {
+        tx.available_buffs().saturating_sub(1).clamp(2, 16)
+    }
+}
+
+fn compare_rows(keys: &[(usize, &SortField)], r1: &[Constant], r2: &[Constant]) -> Ordering {
+    keys.iter()
+        .map(|(pos, sort_field)| sort_field.compare(&r1[*pos], &r2[*pos]))
+        .find(|ord| *ord != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// A row ordered by the sort fields, so that it can be kept in a [BinaryHeap].
+struct HeapRow<'a> {
+    row: Vec<Constant>,
+    keys: &'a [(usize, &'a SortField)],
+}
+
+impl PartialEq for HeapRow<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapRow<'_> {}
+
+impl PartialOrd for HeapRow<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapRow<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_rows(self.keys, &self.row, &other.row)
+    }
+}
+
+impl Plan for SortPlan {
+    fn open<'tx>(&self, tx: Transaction<'tx>) -> Box<dyn Scan + 'tx> {
+        Box::new(self.open_sorted(tx).unwrap())
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        plan::TablePlan,
+        record::TableScan,
+        utils::testing_utils::{temp_db, temp_db_with_cfg},
+    };
+
+    fn setup_table(tx: &Transaction<'_>, records: &[(i32, &str)]) -> DbResult<TablePlan> {
+        let mut schema = Schema::new();
+        schema.add_int_field("num");
+        schema.add_string_field("name", 10);
+        let layout = Layout::new(schema);
+        let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+        for (num, name) in records {
+            scan.insert()?;
+            scan.set_int("num", *num)?;
+            scan.set_string("name", name)?;
+        }
+        TablePlan::new("test_table".to_string(), layout)
+    }
+
+    #[test]
+    fn test_sort_plan_multiple_fields() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let table_plan = setup_table(&tx, &[(3, "c"), (1, "b"), (3, "a"), (2, "z"), (1, "a")])?;
+        let plan = SortPlan::new(
+            Box::new(table_plan),
+            vec![SortField::asc("num"), SortField::desc("name")],
+        );
+        assert!(plan.schema().has_field("name"));
+
+        let mut scan = plan.open(tx.clone());
+        let mut records = Vec::new();
+        while scan.next()? {
+            records.push((scan.get_int("num")?, scan.get_string("name")?));
+        }
+        let expected: Vec<_> = [(1, "b"), (1, "a"), (2, "z"), (3, "c"), (3, "a")]
+            .iter()
+            .map(|(num, name)| (*num, name.to_string()))
+            .collect();
+        assert_eq!(records, expected);
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_plan_larger_than_buffers() -> DbResult<()> {
+        let db = temp_db_with_cfg(|cfg| cfg.buffer_capacity(5))?;
+        let tx = db.new_tx()?;
+
+        let nums: Vec<i32> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let records: Vec<_> = nums.iter().map(|num| (*num, "x")).collect();
+        let table_plan = setup_table(&tx, &records)?;
+        let plan = SortPlan::new(Box::new(table_plan), vec![SortField::asc("num")]);
+
+        let mut scan = plan.open(tx.clone());
+        let mut sorted = Vec::new();
+        while scan.next()? {
+            sorted.push(scan.get_int("num")?);
+        }
+        assert_eq!(sorted, (0..1000).collect::<Vec<_>>());
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_plan_empty_input() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let table_plan = setup_table(&tx, &[])?;
+        let plan = SortPlan::new(Box::new(table_plan), vec![SortField::asc("num")]);
+        let mut scan = plan.open(tx.clone());
+        assert!(!scan.next()?);
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_plan_with_limit() -> DbResult<()> {
+        let db = temp_db_with_cfg(|cfg| cfg.buffer_capacity(5))?;
+        let tx = db.new_tx()?;
+
+        let nums: Vec<i32> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let records: Vec<_> = nums.iter().map(|num| (*num, "x")).collect();
+        let table_plan = setup_table(&tx, &records)?;
+
+        // fits into the buffers, so the records are picked with a heap
+        let plan =
+            SortPlan::new(Box::new(table_plan.clone()), vec![SortField::desc("num")]).limit(5);
+        let mut scan = plan.open(tx.clone());
+        let mut sorted = Vec::new();
+        while scan.next()? {
+            sorted.push(scan.get_int("num")?);
+        }
+        assert_eq!(sorted, vec![999, 998, 997, 996, 995]);
+        drop(scan);
+
+        // too large for the buffers, falls back to the merge sort
+        let plan = SortPlan::new(Box::new(table_plan), vec![SortField::desc("num")]).limit(900);
+        let mut scan = plan.open(tx.clone());
+        let mut sorted = Vec::new();
+        while scan.next()? {
+            sorted.push(scan.get_int("num")?);
+        }
+        assert_eq!(sorted[..900], (100..1000).rev().collect::<Vec<_>>()[..]);
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -1,15 +1,18 @@
 use super::Plan;
 use crate::DbResult;
 use crate::metadata::{IndexInfo, MetadataMgr};
-use crate::plan::IndexSelectPlan;
 use crate::plan::select_plan::SelectPlan;
-use crate::query::Predicate;
-use crate::query::Scan;
+use crate::plan::{
+    HashJoinPlan, IndexJoinPlan, IndexRangeSelectPlan, IndexSelectPlan, MergeJoinPlan, ProductPlan,
+};
+use crate::query::{Constant, Predicate};
+use crate::query::{IndexRangeSelectScan, IndexSelectScan, Scan, UpdateScan};
 use crate::record::TableScan;
 use crate::record::layout::Layout;
 use crate::record::schema::Schema;
 use crate::tx::transaction::Transaction;
 use std::collections::HashMap;
+use std::ops::Bound;
 
 #[derive(Clone)]
 pub struct TablePlan {
@@ -76,6 +79,14 @@ impl<'tx> TablePlanner<'tx> {
         })
     }
 
+    pub fn table_name(&self) -> &str {
+        self.plan.table_name()
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
     /// Creates a select plan for the table, using indexes when possible for better performance.
     pub fn make_select_plan(&self) -> Box<dyn Plan> {
         let mut plan = self.try_index_select();
@@ -85,11 +96,92 @@ impl<'tx> TablePlanner<'tx> {
         self.add_select_pred(plan.unwrap())
     }
 
+    /// Returns true if the predicate has terms which join this table with the given schema.
+    pub fn joins_with(&self, sch: &Schema) -> bool {
+        self.pred.join_sub_pred(&self.schema, sch).is_some()
+    }
+
+    /// Creates a plan which joins the given plan with this table. An index join is used
+    /// when the predicate equates an indexed field of this table with a field of the given plan.
+    /// When the equated field is not indexed, a merge join is used if the given plan is already
+    /// sorted on its equated field, and a hash join otherwise. Otherwise the join is a product.
+    /// The result is filtered by the join terms of the predicate, if there are any.
+    pub fn make_join_plan(&self, current: Box<dyn Plan>) -> Box<dyn Plan> {
+        let current_schema = current.schema();
+        let plan: Box<dyn Plan> =
+            if let Some((index_info, outer_field)) = self.try_index_join(&current_schema) {
+                let plan = Box::new(IndexJoinPlan::new(
+                    current,
+                    self.plan.clone(),
+                    index_info.clone(),
+                    outer_field,
+                ));
+                self.add_select_pred(plan)
+            } else if let Some((outer_field, field)) = self.try_merge_join(current.as_ref()) {
+                Box::new(MergeJoinPlan::new(
+                    current,
+                    self.make_select_plan(),
+                    &outer_field,
+                    field,
+                ))
+            } else if let Some((outer_field, field)) = self.try_equi_join(&current_schema) {
+                Box::new(HashJoinPlan::new(
+                    current,
+                    self.make_select_plan(),
+                    outer_field,
+                    field,
+                ))
+            } else {
+                Box::new(ProductPlan::new(current, self.make_select_plan()))
+            };
+        match self.pred.join_sub_pred(&self.schema, &current_schema) {
+            Some(join_pred) => Box::new(SelectPlan::new(plan, join_pred)),
+            None => plan,
+        }
+    }
+
+    /// Opens an updatable scan over the records which may satisfy the predicate, going
+    /// through an index when the predicate equates an indexed field with a constant
+    /// or restricts it to a range.
+    /// The scan is not filtered by the predicate, callers must check it for every record.
+    pub fn make_update_scan(&self) -> DbResult<Box<dyn UpdateScan + 'tx>> {
+        let table_scan = TableScan::new(
+            self.tx.clone(),
+            self.plan.table_name(),
+            self.plan.table_layout().clone(),
+        )?;
+
+        for (fldname, index_info) in &self.indexes {
+            if let Some(val) = self.pred.equates_with_constant(fldname)
+                && self.is_searchable(fldname, val)
+            {
+                let index = index_info.open(self.tx.clone())?;
+                return Ok(Box::new(IndexSelectScan::new(
+                    table_scan,
+                    Box::new(index),
+                    val.clone(),
+                )?));
+            }
+        }
+        if let Some((index_info, lower, upper)) = self.try_index_range() {
+            let index = index_info.open(self.tx.clone())?;
+            return Ok(Box::new(IndexRangeSelectScan::new(
+                table_scan,
+                Box::new(index),
+                lower,
+                upper,
+            )?));
+        }
+        Ok(Box::new(table_scan))
+    }
+
     /// Attempts to create an index-based select plan if the predicate can use an index.
     /// Returns None if no suitable index is found.
     fn try_index_select(&self) -> Option<Box<dyn Plan>> {
         for (fldname, index) in &self.indexes {
-            if let Some(val) = self.pred.equates_with_constant(fldname) {
+            if let Some(val) = self.pred.equates_with_constant(fldname)
+                && self.is_searchable(fldname, val)
+            {
                 return Some(Box::new(IndexSelectPlan::new(
                     self.plan.clone(),
                     index.clone(),
@@ -97,9 +189,98 @@ impl<'tx> TablePlanner<'tx> {
                 )));
             }
         }
+        if let Some((index_info, lower, upper)) = self.try_index_range() {
+            return Some(Box::new(IndexRangeSelectPlan::new(
+                self.plan.clone(),
+                index_info.clone(),
+                lower,
+                upper,
+            )));
+        }
+        None
+    }
+
+    /// Finds an index on a field which the predicate restricts to a range of values,
+    /// as in `age > 30`. Bounds which are not of the type of the field are not usable.
+    /// Returns the index together with the bounds of the range.
+    fn try_index_range(&self) -> Option<(&IndexInfo, Bound<Constant>, Bound<Constant>)> {
+        for (fldname, index) in &self.indexes {
+            let Some((lower, upper)) = self.pred.range_for(fldname) else {
+                continue;
+            };
+            let matches_type = |bound: &Bound<&Constant>| match bound {
+                Bound::Included(val) | Bound::Excluded(val) => self.is_searchable(fldname, val),
+                Bound::Unbounded => true,
+            };
+            if matches_type(&lower) && matches_type(&upper) {
+                return Some((index, lower.cloned(), upper.cloned()));
+            }
+        }
+        None
+    }
+
+    /// Returns true if the index on the field can be searched for the value,
+    /// which must be of the type of the field or a number if the field is numeric.
+    fn is_searchable(&self, fldname: &str, val: &Constant) -> bool {
+        match (self.schema.field_type(fldname), val.field_type()) {
+            (Some(field_type), Some(val_type)) => field_type.is_comparable_with(val_type),
+            _ => false,
+        }
+    }
+
+    /// Finds an index on a field of this table which the predicate equates with a field
+    /// of the given schema. Returns the index together with the name of the outer field.
+    fn try_index_join(&self, current_schema: &Schema) -> Option<(&IndexInfo, &str)> {
+        for (fldname, index) in &self.indexes {
+            if let Some(outer_field) = self.pred.equates_with_field(fldname)
+                && self.is_comparable(fldname, current_schema, outer_field)
+            {
+                return Some((index, outer_field));
+            }
+        }
+        None
+    }
+
+    /// Finds a field of this table which the predicate equates with a field of the given schema.
+    /// Returns the outer field together with the field of this table.
+    fn try_equi_join<'a>(&'a self, current_schema: &Schema) -> Option<(&'a str, &'a str)> {
+        for fldname in self.schema.fields() {
+            if let Some(outer_field) = self.pred.equates_with_field(fldname)
+                && current_schema.has_field(outer_field)
+            {
+                return Some((outer_field, fldname));
+            }
+        }
         None
     }
 
+    /// Finds a field of this table which the predicate equates with the field on which
+    /// the given plan is sorted. Returns the outer field together with the field of this table.
+    fn try_merge_join(&self, current: &dyn Plan) -> Option<(String, &str)> {
+        let outer_field = current.sorted_on()?;
+        let current_schema = current.schema();
+        self.schema
+            .fields()
+            .iter()
+            .find(|fldname| {
+                self.pred.equates_with_field(fldname) == Some(outer_field)
+                    && self.is_comparable(fldname, &current_schema, outer_field)
+            })
+            .map(|fldname| (outer_field.to_string(), fldname.as_str()))
+    }
+
+    /// Returns true if the values of the field of this table can be compared with those of
+    /// the field of the other schema, which index and merge joins need to look them up.
+    fn is_comparable(&self, fldname: &str, other_schema: &Schema, other_field: &str) -> bool {
+        match (
+            self.schema.field_type(fldname),
+            other_schema.field_type(other_field),
+        ) {
+            (Some(field_type), Some(other_type)) => field_type.is_comparable_with(other_type),
+            _ => false,
+        }
+    }
+
     /// Adds a select predicate to the given plan if the predicate applies to the table schema.
     fn add_select_pred(&self, plan: Box<dyn Plan>) -> Box<dyn Plan> {
         if let Some(select_pred) = self.pred.select_sub_pred(&self.schema) {
@@ -0,0 +1,314 @@
+use std::fmt;
+
+use crate::error::{DbError, DbResult};
+use crate::query::{Constant, Scan};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateKind {
+    Count,
+    Sum,
+    Min,
+    Max,
+    /// Average of an integer field, truncated towards zero.
+    Avg,
+}
+
+impl fmt::Display for AggregateKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AggregateKind::Count => "count",
+            AggregateKind::Sum => "sum",
+            AggregateKind::Min => "min",
+            AggregateKind::Max => "max",
+            AggregateKind::Avg => "avg",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An aggregate function such as `COUNT(*)` or `SUM(field)`, computed over the records of a group.
+/// Its value is output as a field named after the function, e.g. `sum(field)`, unless an alias is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateFn {
+    kind: AggregateKind,
+    field_name: Option<String>,
+    output_name: String,
+}
+
+/// State of an aggregate function while the records of a group are read.
+#[derive(Debug, Clone, Default)]
+pub struct Accumulator {
+    count: i64,
+    sum: i64,
+    /// Set once a value of a double field is summed, the sum of integers is kept in `sum`.
+    double_sum: Option<f64>,
+    /// Whether the summed integers are of a BIGINT field.
+    is_big: bool,
+    extreme: Option<Constant>,
+}
+
+impl AggregateFn {
+    /// Creates an aggregate over a field, or over whole records (`COUNT(*)`) if no field is given.
+    pub fn new(kind: AggregateKind, field_name: Option<String>) -> Self {
+        let output_name = format!("{}({})", kind, field_name.as_deref().unwrap_or("*"));
+        AggregateFn {
+            kind,
+            field_name,
+            output_name,
+        }
+    }
+
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.output_name = alias.into();
+        self
+    }
+
+    pub fn kind(&self) -> AggregateKind {
+        self.kind
+    }
+
+    pub fn field_name(&self) -> Option<&str> {
+        self.field_name.as_deref()
+    }
+
+    pub fn output_name(&self) -> &str {
+        &self.output_name
+    }
+
+    /// Adds the current record of the scan to the accumulator.
+    /// Null values are skipped, so `COUNT(field)` counts the records where the field is not null.
+    pub fn accumulate(&self, acc: &mut Accumulator, scan: &mut dyn Scan) -> DbResult<()> {
+        let Some(field_name) = &self.field_name else {
+            acc.count += 1;
+            return Ok(());
+        };
+        let val = scan.get_val(field_name)?;
+        if val.is_null() {
+            return Ok(());
+        }
+        acc.count += 1;
+        match self.kind {
+            AggregateKind::Count => {}
+            AggregateKind::Sum | AggregateKind::Avg => match val {
+                Constant::Double(d) => *acc.double_sum.get_or_insert(0.0) += d,
+                Constant::BigInt(i) => {
+                    acc.is_big = true;
+                    acc.sum = acc.sum.checked_add(i).ok_or_else(|| self.overflow())?;
+                }
+                val => acc.sum += val.int_value(field_name)? as i64,
+            },
+            AggregateKind::Min | AggregateKind::Max => {
+                let replace = match &acc.extreme {
+                    None => true,
+                    Some(extreme) if self.kind == AggregateKind::Min => val < *extreme,
+                    Some(extreme) => val > *extreme,
+                };
+                if replace {
+                    acc.extreme = Some(val);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value of the aggregate, which is null if no non-null values were
+    /// accumulated, except for `COUNT` which is 0.
+    /// The sum and average are of the type of the field.
+    pub fn value(&self, acc: &Accumulator) -> DbResult<Constant> {
+        let to_int = |val: i64| {
+            if acc.is_big {
+                return Ok(Constant::BigInt(val));
+            }
+            i32::try_from(val)
+                .map(Constant::Int)
+                .map_err(|_| self.overflow())
+        };
+        match self.kind {
+            AggregateKind::Count => to_int(acc.count),
+            _ if acc.count == 0 => Ok(Constant::Null),
+            AggregateKind::Sum => match acc.double_sum {
+                Some(sum) => Ok(Constant::Double(sum)),
+                None => to_int(acc.sum),
+            },
+            AggregateKind::Avg => match acc.double_sum {
+                Some(sum) => Ok(Constant::Double(sum / acc.count as f64)),
+                None => to_int(acc.sum / acc.count),
+            },
+            AggregateKind::Min | AggregateKind::Max => {
+                Ok(acc.extreme.clone().unwrap_or(Constant::Null))
+            }
+        }
+    }
+
+    fn overflow(&self) -> DbError {
+        DbError::Schema(format!("Integer overflow in {}", self.output_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        query::UpdateScan,
+        record::{Layout, Schema, TableScan, schema::FieldType},
+        utils::testing_utils::temp_db,
+    };
+
+    #[test]
+    fn test_aggregate_fns() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("a");
+        schema.add_string_field("b", 10);
+        let mut scan = TableScan::new(tx.clone(), "test_table", Layout::new(schema))?;
+        for (a, b) in [(4, "x"), (-3, "z"), (10, "y")] {
+            scan.insert()?;
+            scan.set_int("a", a)?;
+            scan.set_string("b", b)?;
+        }
+
+        let fns = [
+            AggregateFn::new(AggregateKind::Count, None),
+            AggregateFn::new(AggregateKind::Sum, Some("a".to_string())),
+            AggregateFn::new(AggregateKind::Avg, Some("a".to_string())),
+            AggregateFn::new(AggregateKind::Min, Some("a".to_string())),
+            AggregateFn::new(AggregateKind::Max, Some("b".to_string())).with_alias("last"),
+        ];
+        assert_eq!(fns[1].output_name(), "sum(a)");
+        assert_eq!(fns[4].output_name(), "last");
+
+        let mut accs = vec![Accumulator::default(); fns.len()];
+        for (f, acc) in fns.iter().zip(&accs) {
+            let expected = match f.kind() {
+                AggregateKind::Count => Constant::Int(0),
+                _ => Constant::Null,
+            };
+            assert_eq!(f.value(acc)?, expected);
+        }
+
+        scan.before_first()?;
+        while scan.next()? {
+            for (f, acc) in fns.iter().zip(accs.iter_mut()) {
+                f.accumulate(acc, &mut scan)?;
+            }
+        }
+        let values = fns
+            .iter()
+            .zip(&accs)
+            .map(|(f, acc)| f.value(acc))
+            .collect::<DbResult<Vec<_>>>()?;
+        assert_eq!(
+            values,
+            vec![
+                Constant::Int(3),
+                Constant::Int(11),
+                Constant::Int(3),
+                Constant::Int(-3),
+                Constant::string("z"),
+            ]
+        );
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate_fns_keep_field_type() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_field("big", FieldType::BigInt, 0);
+        schema.add_field("ratio", FieldType::Double, 0);
+        let mut scan = TableScan::new(tx.clone(), "test_table", Layout::new(schema))?;
+        for (big, ratio) in [(4_000_000_000, 0.5), (3_000_000_000, 1.75)] {
+            scan.insert()?;
+            scan.set_val("big", Constant::BigInt(big))?;
+            scan.set_val("ratio", Constant::Double(ratio))?;
+        }
+
+        let fns = [
+            AggregateFn::new(AggregateKind::Sum, Some("big".to_string())),
+            AggregateFn::new(AggregateKind::Avg, Some("big".to_string())),
+            AggregateFn::new(AggregateKind::Sum, Some("ratio".to_string())),
+            AggregateFn::new(AggregateKind::Avg, Some("ratio".to_string())),
+        ];
+        let mut accs = vec![Accumulator::default(); fns.len()];
+        scan.before_first()?;
+        while scan.next()? {
+            for (f, acc) in fns.iter().zip(accs.iter_mut()) {
+                f.accumulate(acc, &mut scan)?;
+            }
+        }
+        let values = fns
+            .iter()
+            .zip(&accs)
+            .map(|(f, acc)| f.value(acc))
+            .collect::<DbResult<Vec<_>>>()?;
+        assert_eq!(
+            values,
+            vec![
+                Constant::BigInt(7_000_000_000),
+                Constant::BigInt(3_500_000_000),
+                Constant::Double(2.25),
+                Constant::Double(1.125),
+            ]
+        );
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate_fns_skip_nulls() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("a");
+        let mut scan = TableScan::new(tx.clone(), "test_table", Layout::new(schema))?;
+        for a in [Constant::int(4), Constant::Null, Constant::int(8)] {
+            scan.insert()?;
+            scan.set_val("a", a)?;
+        }
+
+        let a = || Some("a".to_string());
+        let fns = [
+            AggregateFn::new(AggregateKind::Count, None),
+            AggregateFn::new(AggregateKind::Count, a()),
+            AggregateFn::new(AggregateKind::Sum, a()),
+            AggregateFn::new(AggregateKind::Avg, a()),
+            AggregateFn::new(AggregateKind::Min, a()),
+        ];
+        let mut accs = vec![Accumulator::default(); fns.len()];
+        scan.before_first()?;
+        while scan.next()? {
+            for (f, acc) in fns.iter().zip(accs.iter_mut()) {
+                f.accumulate(acc, &mut scan)?;
+            }
+        }
+        let values = fns
+            .iter()
+            .zip(&accs)
+            .map(|(f, acc)| f.value(acc))
+            .collect::<DbResult<Vec<_>>>()?;
+        assert_eq!(
+            values,
+            vec![
+                Constant::Int(3),
+                Constant::Int(2),
+                Constant::Int(12),
+                Constant::Int(6),
+                Constant::Int(4),
+            ]
+        );
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -1,7 +1,30 @@
-#[derive(Debug, Clone, Eq, PartialEq)]
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use crate::error::{DbError, DbResult};
+use crate::record::schema::FieldType;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+/// A field value. `Null` stands for a missing value; it is ordered before all other values
+/// and equal to itself, so that sorting and grouping keep nulls together. Comparisons in
+/// predicates treat it as unknown instead, see [super::Term].
+/// The numeric variants are compared by value, so `Int(1)` equals `BigInt(1)` and `Double(1.0)`.
+#[derive(Debug, Clone)]
 pub enum Constant {
     Int(i32),
     String(String),
+    Null,
+    BigInt(i64),
+    Bool(bool),
+    Double(f64),
+    /// Days since 1970-01-01.
+    Date(i32),
+    /// Microseconds since 1970-01-01 00:00:00.
+    Timestamp(i64),
+    /// The value of a BLOB field, ordered byte by byte.
+    Bytes(Vec<u8>),
 }
 
 impl Constant {
@@ -13,6 +36,35 @@ impl Constant {
         Constant::String(val.into())
     }
 
+    /// Parses a date of the form `YYYY-MM-DD`.
+    pub fn parse_date(s: &str) -> DbResult<Self> {
+        parse_date(s.trim())
+            .map(|days| Constant::Date(days as i32))
+            .ok_or_else(|| DbError::Schema(format!("Invalid date: {}", s)))
+    }
+
+    /// Parses a timestamp of the form `YYYY-MM-DD HH:MM:SS[.ffffff]`. The time may be left out.
+    pub fn parse_timestamp(s: &str) -> DbResult<Self> {
+        parse_timestamp(s.trim())
+            .map(Constant::Timestamp)
+            .ok_or_else(|| DbError::Schema(format!("Invalid timestamp: {}", s)))
+    }
+
+    /// Returns the type of the value, or None for `Null`.
+    pub fn field_type(&self) -> Option<FieldType> {
+        match self {
+            Constant::Int(_) => Some(FieldType::Integer),
+            Constant::String(_) => Some(FieldType::Varchar),
+            Constant::Null => None,
+            Constant::BigInt(_) => Some(FieldType::BigInt),
+            Constant::Bool(_) => Some(FieldType::Boolean),
+            Constant::Double(_) => Some(FieldType::Double),
+            Constant::Date(_) => Some(FieldType::Date),
+            Constant::Timestamp(_) => Some(FieldType::Timestamp),
+            Constant::Bytes(_) => Some(FieldType::Blob),
+        }
+    }
+
     pub fn is_integer(&self) -> bool {
         matches!(self, Constant::Int(_))
     }
@@ -21,6 +73,53 @@ impl Constant {
         matches!(self, Constant::String(_))
     }
 
+    pub fn is_null(&self) -> bool {
+        matches!(self, Constant::Null)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Constant::Int(_) | Constant::BigInt(_) | Constant::Double(_)
+        )
+    }
+
+    /// Returns true if the values can be ordered: either one is null, both are numbers
+    /// or both are of the same type.
+    pub fn is_comparable_with(&self, other: &Constant) -> bool {
+        self.is_null()
+            || other.is_null()
+            || (self.is_numeric() && other.is_numeric())
+            || std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    /// Converts the value to the type of a field. Integers are widened to the larger numeric
+    /// types and a big integer is narrowed if it fits. A string fits a TEXT field and becomes
+    /// the bytes of its UTF-8 encoding for a BLOB field. Returns None if the value doesn't fit.
+    pub fn coerce_to(&self, field_type: FieldType) -> Option<Constant> {
+        match (self, field_type) {
+            (Constant::Null, _)
+            | (Constant::Int(_), FieldType::Integer)
+            | (Constant::String(_), FieldType::Varchar)
+            | (Constant::BigInt(_), FieldType::BigInt)
+            | (Constant::Bool(_), FieldType::Boolean)
+            | (Constant::Double(_), FieldType::Double)
+            | (Constant::Date(_), FieldType::Date)
+            | (Constant::Timestamp(_), FieldType::Timestamp)
+            | (Constant::String(_), FieldType::Text)
+            | (Constant::Bytes(_), FieldType::Blob) => Some(self.clone()),
+            (Constant::String(s), FieldType::Blob) => Some(Constant::Bytes(s.as_bytes().to_vec())),
+            (Constant::Int(i), FieldType::BigInt) => Some(Constant::BigInt(*i as i64)),
+            (Constant::Int(i), FieldType::Double) => Some(Constant::Double(*i as f64)),
+            (Constant::BigInt(i), FieldType::Integer) => i32::try_from(*i).ok().map(Constant::Int),
+            (Constant::BigInt(i), FieldType::Double) => Some(Constant::Double(*i as f64)),
+            (Constant::Date(days), FieldType::Timestamp) => {
+                Some(Constant::Timestamp(*days as i64 * MICROS_PER_DAY))
+            }
+            _ => None,
+        }
+    }
+
     pub fn as_integer(&self) -> i32 {
         match self {
             Constant::Int(i) => *i,
@@ -35,21 +134,135 @@ impl Constant {
         }
     }
 
+    /// Returns the integer value of a field read from a scan, failing if it is null.
+    pub fn int_value(&self, field_name: &str) -> DbResult<i32> {
+        match self {
+            Constant::Int(i) => Ok(*i),
+            Constant::Null => Err(DbError::Schema(format!("Field {} is null", field_name))),
+            _ => Err(DbError::Schema(format!(
+                "Field {} is not an integer",
+                field_name
+            ))),
+        }
+    }
+
+    /// Returns the string value of a field read from a scan, failing if it is null.
+    pub fn string_value(&self, field_name: &str) -> DbResult<String> {
+        match self {
+            Constant::String(s) => Ok(s.clone()),
+            Constant::Null => Err(DbError::Schema(format!("Field {} is null", field_name))),
+            _ => Err(DbError::Schema(format!(
+                "Field {} is not a string",
+                field_name
+            ))),
+        }
+    }
+
+    /// Returns the bytes of a BLOB field read from a scan, failing if it is null.
+    pub fn bytes_value(&self, field_name: &str) -> DbResult<Vec<u8>> {
+        match self {
+            Constant::Bytes(bytes) => Ok(bytes.clone()),
+            Constant::Null => Err(DbError::Schema(format!("Field {} is null", field_name))),
+            _ => Err(DbError::Schema(format!(
+                "Field {} is not a blob",
+                field_name
+            ))),
+        }
+    }
+
+    /// Bytes are written as hex digits after `\x`.
     pub fn to_string(&self) -> String {
         match self {
             Constant::Int(i) => i.to_string(),
             Constant::String(s) => s.clone(),
+            Constant::Null => "NULL".to_string(),
+            Constant::BigInt(i) => i.to_string(),
+            Constant::Bool(b) => b.to_string(),
+            Constant::Double(d) => d.to_string(),
+            Constant::Date(days) => format_date(*days as i64),
+            Constant::Timestamp(micros) => format_timestamp(*micros),
+            Constant::Bytes(bytes) => {
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                format!("\\x{}", hex)
+            }
         }
     }
 
-    /// Compare this constant with another constant.
-    pub fn compare_to(&self, other: &Constant) -> std::cmp::Ordering {
+    /// Compare this constant with another constant. Null comes before any other value.
+    /// Panics if the values are not comparable, see [Constant::is_comparable_with].
+    pub fn compare_to(&self, other: &Constant) -> Ordering {
         match (self, other) {
             (Constant::Int(a), Constant::Int(b)) => a.cmp(b),
             (Constant::String(a), Constant::String(b)) => a.cmp(b),
+            (Constant::Null, Constant::Null) => Ordering::Equal,
+            (Constant::Null, _) => Ordering::Less,
+            (_, Constant::Null) => Ordering::Greater,
+            (Constant::Bool(a), Constant::Bool(b)) => a.cmp(b),
+            (Constant::Date(a), Constant::Date(b)) => a.cmp(b),
+            (Constant::Timestamp(a), Constant::Timestamp(b)) => a.cmp(b),
+            (Constant::Bytes(a), Constant::Bytes(b)) => a.cmp(b),
+            (a, b) if a.is_numeric() && b.is_numeric() => match (a.as_long(), b.as_long()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(a), None) => compare_long_with_double(a, b.as_double()),
+                (None, Some(b)) => compare_long_with_double(b, a.as_double()).reverse(),
+                // zeros of either sign are equal, as they are to the integer 0
+                (None, None) => {
+                    let (a, b) = (a.as_double(), b.as_double());
+                    a.partial_cmp(&b).unwrap_or_else(|| a.total_cmp(&b))
+                }
+            },
             _ => panic!("Cannot compare different constant types"),
         }
     }
+
+    /// The value of an integer of either size.
+    fn as_long(&self) -> Option<i64> {
+        match self {
+            Constant::Int(i) => Some(*i as i64),
+            Constant::BigInt(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    fn as_double(&self) -> f64 {
+        match self {
+            Constant::Double(d) => *d,
+            val => val.as_long().expect("Not a numeric constant") as f64,
+        }
+    }
+}
+
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_comparable_with(other) && self.compare_to(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Constant {}
+
+/// Equal numbers hash alike whatever their type.
+impl Hash for Constant {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Constant::Int(_) | Constant::BigInt(_) => {
+                state.write_u8(0);
+                self.as_long().hash(state);
+            }
+            Constant::Double(d)
+                if d.fract() == 0.0 && *d >= i64::MIN as f64 && *d < i64::MAX as f64 =>
+            {
+                state.write_u8(0);
+                Some(*d as i64).hash(state);
+            }
+            Constant::Double(d) => d.to_bits().hash(state),
+            Constant::String(s) => s.hash(state),
+            Constant::Null => state.write_u8(1),
+            Constant::Bool(b) => b.hash(state),
+            Constant::Date(days) => days.hash(state),
+            Constant::Timestamp(micros) => micros.hash(state),
+            Constant::Bytes(bytes) => bytes.hash(state),
+        }
+    }
 }
 
 impl From<i32> for Constant {
@@ -65,17 +278,130 @@ impl From<String> for Constant {
 }
 
 impl PartialOrd for Constant {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
 impl Ord for Constant {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> Ordering {
         self.compare_to(other)
     }
 }
 
+/// Compares an integer with a double exactly, without rounding the integer to a double.
+/// NaN is ordered as by [f64::total_cmp].
+fn compare_long_with_double(a: i64, b: f64) -> Ordering {
+    // i64::MIN as f64 is exactly -2^63, i64::MAX as f64 rounds up to 2^63
+    if b.is_nan() {
+        return (a as f64).total_cmp(&b);
+    }
+    if b >= i64::MAX as f64 {
+        return Ordering::Less;
+    }
+    if b < i64::MIN as f64 {
+        return Ordering::Greater;
+    }
+    a.cmp(&(b.trunc() as i64))
+        .then_with(|| 0.0.partial_cmp(&b.fract()).unwrap())
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar,
+/// see <http://howardhinnant.github.io/date_algorithms.html>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The inverse of [days_from_civil], returning the year, month and day.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Parses the digits of a date or time component, which must have the given length.
+fn parse_digits(s: &str, len: usize) -> Option<i64> {
+    if s.len() != len || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn parse_date(s: &str) -> Option<i64> {
+    let mut parts = s.split('-');
+    let year = parse_digits(parts.next()?, 4)?;
+    let month = parse_digits(parts.next()?, 2)?;
+    let day = parse_digits(parts.next()?, 2)?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || day < 1 {
+        return None;
+    }
+    //  a day past the end of the month would come back as a day of the next month
+    let days = days_from_civil(year, month, day);
+    (civil_from_days(days) == (year, month, day)).then_some(days)
+}
+
+fn parse_timestamp(s: &str) -> Option<i64> {
+    let (date, time) = match s.split_once([' ', 'T']) {
+        Some((date, time)) => (date, time),
+        None => (s, "00:00:00"),
+    };
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) if (1..=6).contains(&fraction.len()) => (
+            time,
+            parse_digits(fraction, fraction.len())? * 10_i64.pow(6 - fraction.len() as u32),
+        ),
+        Some(_) => return None,
+        None => (time, 0),
+    };
+    let mut parts = time.split(':');
+    let hour = parse_digits(parts.next()?, 2)?;
+    let minute = parse_digits(parts.next()?, 2)?;
+    let second = parse_digits(parts.next()?, 2)?;
+    if parts.next().is_some() || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let seconds = hour * 3600 + minute * 60 + second;
+    Some(parse_date(date)? * MICROS_PER_DAY + seconds * MICROS_PER_SECOND + fraction)
+}
+
+fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn format_timestamp(micros: i64) -> String {
+    let date = format_date(micros.div_euclid(MICROS_PER_DAY));
+    let micros_of_day = micros.rem_euclid(MICROS_PER_DAY);
+    let seconds = micros_of_day / MICROS_PER_SECOND;
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    match micros_of_day % MICROS_PER_SECOND {
+        0 => format!("{} {}", date, time),
+        fraction => format!("{} {}.{:06}", date, time, fraction),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,6 +443,111 @@ mod tests {
             constants,
             vec![Constant::int(1), Constant::int(2), Constant::int(3),]
         );
+
+        // Null sorts first
+        let mut constants = vec![Constant::string("a"), Constant::Null, Constant::string("")];
+        constants.sort();
+        assert_eq!(
+            constants,
+            vec![Constant::Null, Constant::string(""), Constant::string("a")]
+        );
+    }
+
+    #[test]
+    fn test_numeric_constants_compare_by_value() {
+        assert_eq!(Constant::int(5), Constant::BigInt(5));
+        assert_eq!(Constant::BigInt(5), Constant::Double(5.0));
+        assert!(Constant::int(5) < Constant::Double(5.5));
+        assert!(Constant::BigInt(i64::MAX) > Constant::BigInt(i64::MAX - 1));
+        assert_ne!(Constant::int(1), Constant::Bool(true));
+        assert!(!Constant::int(1).is_comparable_with(&Constant::string("1")));
+        assert!(Constant::Null.is_comparable_with(&Constant::Date(0)));
+
+        let hash = |val: &Constant| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            val.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&Constant::int(7)), hash(&Constant::BigInt(7)));
+        assert_eq!(hash(&Constant::int(7)), hash(&Constant::Double(7.0)));
+
+        // large integers are not rounded to a double
+        let big = 1i64 << 60;
+        assert_ne!(Constant::BigInt(big + 1), Constant::Double(big as f64));
+        assert!(Constant::BigInt(big + 1) > Constant::Double(big as f64));
+        assert!(Constant::Double(big as f64) < Constant::BigInt(big + 1));
+        assert_eq!(Constant::BigInt(big), Constant::Double(big as f64));
+        assert!(Constant::BigInt(i64::MAX) < Constant::Double(i64::MAX as f64));
+        assert_eq!(
+            Constant::BigInt(i64::MIN),
+            Constant::Double(i64::MIN as f64)
+        );
+        assert_eq!(
+            hash(&Constant::BigInt(i64::MIN)),
+            hash(&Constant::Double(i64::MIN as f64))
+        );
+        assert!(Constant::BigInt(-3) > Constant::Double(-3.5));
+        assert!(Constant::BigInt(-3) < Constant::Double(f64::NAN));
+        assert_eq!(Constant::Double(-0.0), Constant::int(0));
+        assert_eq!(Constant::Double(-0.0), Constant::Double(0.0));
+    }
+
+    #[test]
+    fn test_coerce_to() {
+        assert_eq!(
+            Constant::int(3).coerce_to(FieldType::BigInt),
+            Some(Constant::BigInt(3))
+        );
+        assert_eq!(
+            Constant::BigInt(3).coerce_to(FieldType::Integer),
+            Some(Constant::int(3))
+        );
+        assert_eq!(
+            Constant::BigInt(1 << 40).coerce_to(FieldType::Integer),
+            None
+        );
+        assert_eq!(Constant::Double(1.5).coerce_to(FieldType::Integer), None);
+        assert_eq!(Constant::string("x").coerce_to(FieldType::Date), None);
+        assert_eq!(
+            Constant::Date(1).coerce_to(FieldType::Timestamp),
+            Some(Constant::Timestamp(86_400_000_000))
+        );
+        assert_eq!(
+            Constant::Null.coerce_to(FieldType::Boolean),
+            Some(Constant::Null)
+        );
+    }
+
+    #[test]
+    fn test_dates_and_timestamps() -> DbResult<()> {
+        assert_eq!(Constant::parse_date("1970-01-01")?, Constant::Date(0));
+        assert_eq!(Constant::parse_date("2000-03-01")?, Constant::Date(11017));
+        assert_eq!(Constant::parse_date("1969-12-31")?, Constant::Date(-1));
+        assert!(Constant::parse_date("2023-02-29").is_err());
+        assert!(Constant::parse_date("2024-13-01").is_err());
+        assert!(Constant::parse_date("24-01-01").is_err());
+        for date in ["2024-02-29", "1900-12-31", "0001-01-01", "9999-12-31"] {
+            assert_eq!(Constant::parse_date(date)?.to_string(), date);
+        }
+
+        assert_eq!(
+            Constant::parse_timestamp("1970-01-02 00:00:01")?,
+            Constant::Timestamp(86_401_000_000)
+        );
+        assert_eq!(
+            Constant::parse_timestamp("1970-01-01")?,
+            Constant::Timestamp(0)
+        );
+        assert_eq!(
+            Constant::parse_timestamp("1969-12-31 23:59:59.5")?,
+            Constant::Timestamp(-500_000)
+        );
+        assert!(Constant::parse_timestamp("2024-01-01 24:00:00").is_err());
+        for timestamp in ["2024-02-29 13:05:09", "1950-06-15 00:00:00.000250"] {
+            assert_eq!(Constant::parse_timestamp(timestamp)?.to_string(), timestamp);
+        }
+        assert!(Constant::parse_date("2024-01-01")? < Constant::parse_date("2024-01-02")?);
+        Ok(())
     }
 
     #[test]
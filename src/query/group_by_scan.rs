@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+
+use crate::error::{DbError, DbResult};
+use crate::query::aggregate::{Accumulator, AggregateFn};
+use crate::query::{Constant, Scan, UpdateScan};
+use crate::record::{TableScan, TempTable};
+use crate::tx::Transaction;
+
+/// The group fields and aggregate functions of a grouping, and the values of one group.
+struct Group {
+    key: Vec<Constant>,
+    accumulators: Vec<Accumulator>,
+}
+
+impl Group {
+    fn new(key: Vec<Constant>, aggregates: &[AggregateFn]) -> Self {
+        Group {
+            key,
+            accumulators: vec![Accumulator::default(); aggregates.len()],
+        }
+    }
+
+    fn accumulate(&mut self, aggregates: &[AggregateFn], scan: &mut dyn Scan) -> DbResult<()> {
+        for (aggregate, acc) in aggregates.iter().zip(self.accumulators.iter_mut()) {
+            aggregate.accumulate(acc, scan)?;
+        }
+        Ok(())
+    }
+
+    fn get_val(
+        &self,
+        group_fields: &[String],
+        aggregates: &[AggregateFn],
+        field_name: &str,
+    ) -> DbResult<Constant> {
+        if let Some(pos) = group_fields.iter().position(|f| f == field_name) {
+            return Ok(self.key[pos].clone());
+        }
+        let pos = aggregates
+            .iter()
+            .position(|a| a.output_name() == field_name)
+            .ok_or_else(|| DbError::FieldNotFound(field_name.to_string()))?;
+        aggregates[pos].value(&self.accumulators[pos])
+    }
+}
+
+fn read_key(scan: &mut dyn Scan, group_fields: &[String]) -> DbResult<Vec<Constant>> {
+    group_fields
+        .iter()
+        .map(|field_name| scan.get_val(field_name))
+        .collect()
+}
+
+fn has_output_field(group_fields: &[String], aggregates: &[AggregateFn], field_name: &str) -> bool {
+    group_fields.iter().any(|f| f == field_name)
+        || aggregates.iter().any(|a| a.output_name() == field_name)
+}
+
+/// `GroupByScan` computes aggregates over an input which is sorted on the group fields,
+/// so that the records of each group are adjacent. Each call to `next` reads one group.
+/// Without group fields the whole input forms a single group, even if it is empty.
+pub struct GroupByScan<'a> {
+    scan: Box<dyn Scan + 'a>,
+    group_fields: Vec<String>,
+    aggregates: Vec<AggregateFn>,
+    group: Option<Group>,
+    more_groups: bool,
+    started: bool,
+}
+
+impl<'a> GroupByScan<'a> {
+    pub fn new(
+        scan: Box<dyn Scan + 'a>,
+        group_fields: Vec<String>,
+        aggregates: Vec<AggregateFn>,
+    ) -> DbResult<Self> {
+        let mut scan = GroupByScan {
+            scan,
+            group_fields,
+            aggregates,
+            group: None,
+            more_groups: false,
+            started: false,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+
+    fn current_group(&self) -> DbResult<&Group> {
+        self.group
+            .as_ref()
+            .ok_or_else(|| DbError::Schema("No current group".to_string()))
+    }
+}
+
+impl<'a> Scan for GroupByScan<'a> {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.scan.before_first()?;
+        self.more_groups = self.scan.next()?;
+        self.group = None;
+        self.started = false;
+        Ok(())
+    }
+
+    /// Reads the records of the next group, stopping at the first record of the group after it.
+    fn next(&mut self) -> DbResult<bool> {
+        let first = !self.started;
+        self.started = true;
+        if !self.more_groups {
+            self.group = (first && self.group_fields.is_empty())
+                .then(|| Group::new(Vec::new(), &self.aggregates));
+            return Ok(self.group.is_some());
+        }
+
+        let key = read_key(&mut *self.scan, &self.group_fields)?;
+        let mut group = Group::new(key, &self.aggregates);
+        loop {
+            group.accumulate(&self.aggregates, &mut *self.scan)?;
+            self.more_groups = self.scan.next()?;
+            if !self.more_groups || read_key(&mut *self.scan, &self.group_fields)? != group.key {
+                break;
+            }
+        }
+        self.group = Some(group);
+        Ok(true)
+    }
+
+    fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+        self.get_val(field_name)?.int_value(field_name)
+    }
+
+    fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+        self.get_val(field_name)?.string_value(field_name)
+    }
+
+    fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+        self.current_group()?
+            .get_val(&self.group_fields, &self.aggregates, field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        has_output_field(&self.group_fields, &self.aggregates, field_name)
+    }
+}
+
+/// `HashGroupByScan` computes aggregates over an unsorted input by keeping the groups in
+/// a hash table. The whole input is read when the scan is created, and groups are returned
+/// in the order their first records were read.
+///
+/// The records of the groups which do not fit into the table are copied to a spill table
+/// instead. Their groups are computed by a [GroupByScan] over the sorted spill table, and
+/// are returned after the hashed ones (see [HashGroupByScan::followed_by]).
+pub struct HashGroupByScan<'a> {
+    group_fields: Vec<String>,
+    aggregates: Vec<AggregateFn>,
+    groups: Vec<Group>,
+    current: Option<usize>,
+    spilled: Option<GroupByScan<'a>>,
+}
+
+impl<'a> HashGroupByScan<'a> {
+    /// Reads the input and groups its records, keeping at most `max_groups` groups.
+    /// The records of the other groups are inserted into the spill table.
+    /// Returns the scan and whether records were spilled.
+    pub fn new(
+        scan: &mut dyn Scan,
+        group_fields: Vec<String>,
+        aggregates: Vec<AggregateFn>,
+        max_groups: usize,
+        spill: &TempTable,
+        tx: Transaction<'_>,
+    ) -> DbResult<(Self, bool)> {
+        let mut positions: HashMap<Vec<Constant>, usize> = HashMap::new();
+        let mut groups: Vec<Group> = Vec::new();
+        let mut spill_scan: Option<TableScan<'_>> = None;
+
+        scan.before_first()?;
+        while scan.next()? {
+            let key = read_key(scan, &group_fields)?;
+            let pos = match positions.get(&key) {
+                Some(pos) => *pos,
+                None if groups.len() == max_groups => {
+                    if spill_scan.is_none() {
+                        spill_scan = Some(spill.open(tx.clone())?);
+                    }
+                    let dest = spill_scan.as_mut().unwrap();
+                    dest.insert()?;
+                    for field_name in spill.layout().schema().fields() {
+                        dest.set_val(field_name, scan.get_val(field_name)?)?;
+                    }
+                    continue;
+                }
+                None => {
+                    positions.insert(key.clone(), groups.len());
+                    groups.push(Group::new(key, &aggregates));
+                    groups.len() - 1
+                }
+            };
+            groups[pos].accumulate(&aggregates, scan)?;
+        }
+        if groups.is_empty() && group_fields.is_empty() {
+            groups.push(Group::new(Vec::new(), &aggregates));
+        }
+
+        let scan = HashGroupByScan {
+            group_fields,
+            aggregates,
+            groups,
+            current: None,
+            spilled: None,
+        };
+        Ok((scan, spill_scan.is_some()))
+    }
+
+    /// Returns the groups of the given scan after the hashed ones.
+    /// The scan groups the spilled records, which belong to none of the hashed groups.
+    pub fn followed_by(mut self, spilled: GroupByScan<'a>) -> Self {
+        self.spilled = Some(spilled);
+        self
+    }
+
+    fn current_group(&self) -> DbResult<&Group> {
+        self.current
+            .and_then(|current| self.groups.get(current))
+            .ok_or_else(|| DbError::Schema("No current group".to_string()))
+    }
+}
+
+impl<'a> Scan for HashGroupByScan<'a> {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.current = None;
+        if let Some(spilled) = &mut self.spilled {
+            spilled.before_first()?;
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        let next = self.current.map_or(0, |current| current + 1);
+        if next < self.groups.len() {
+            self.current = Some(next);
+            return Ok(true);
+        }
+        self.current = Some(self.groups.len());
+        match &mut self.spilled {
+            Some(spilled) => spilled.next(),
+            None => Ok(false),
+        }
+    }
+
+    fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+        self.get_val(field_name)?.int_value(field_name)
+    }
+
+    fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+        self.get_val(field_name)?.string_value(field_name)
+    }
+
+    fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+        if self.current == Some(self.groups.len())
+            && let Some(spilled) = &mut self.spilled
+        {
+            return spilled.get_val(field_name);
+        }
+        self.current_group()?
+            .get_val(&self.group_fields, &self.aggregates, field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        has_output_field(&self.group_fields, &self.aggregates, field_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        query::{UpdateScan, aggregate::AggregateKind},
+        record::{Layout, Schema},
+        utils::testing_utils::temp_db,
+    };
+
+    fn scan_schema() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_string_field("dept", 10);
+        schema.add_int_field("salary");
+        schema
+    }
+
+    fn setup_table<'tx>(
+        tx: &Transaction<'tx>,
+        records: &[(&str, i32)],
+    ) -> DbResult<TableScan<'tx>> {
+        let mut scan = TableScan::new(tx.clone(), "test_table", Layout::new(scan_schema()))?;
+        for (dept, salary) in records {
+            scan.insert()?;
+            scan.set_string("dept", dept)?;
+            scan.set_int("salary", *salary)?;
+        }
+        Ok(scan)
+    }
+
+    fn aggregates() -> Vec<AggregateFn> {
+        vec![
+            AggregateFn::new(AggregateKind::Count, None),
+            AggregateFn::new(AggregateKind::Sum, Some("salary".to_string())),
+        ]
+    }
+
+    fn collect(scan: &mut dyn Scan) -> DbResult<Vec<(String, i32, i32)>> {
+        let mut groups = Vec::new();
+        scan.before_first()?;
+        while scan.next()? {
+            groups.push((
+                scan.get_string("dept")?,
+                scan.get_int("count(*)")?,
+                scan.get_int("sum(salary)")?,
+            ));
+        }
+        Ok(groups)
+    }
+
+    #[test]
+    fn test_group_by_scan_sorted_input() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let scan = setup_table(&tx, &[("a", 1), ("a", 2), ("b", 5), ("c", 1), ("c", 1)])?;
+        let mut scan = GroupByScan::new(Box::new(scan), vec!["dept".to_string()], aggregates())?;
+        assert!(scan.has_field("sum(salary)"));
+        assert!(!scan.has_field("salary"));
+        assert_eq!(
+            collect(&mut scan)?,
+            vec![
+                ("a".to_string(), 2, 3),
+                ("b".to_string(), 1, 5),
+                ("c".to_string(), 2, 2)
+            ]
+        );
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_by_scan_without_groups() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        // an empty input still has a count
+        let scan = setup_table(&tx, &[])?;
+        let mut scan = GroupByScan::new(Box::new(scan), Vec::new(), aggregates())?;
+        assert!(scan.next()?);
+        assert_eq!(scan.get_int("count(*)")?, 0);
+        assert_eq!(scan.get_val("sum(salary)")?, Constant::Null);
+        assert!(scan.get_int("sum(salary)").is_err());
+        assert!(!scan.next()?);
+        drop(scan);
+
+        let mut scan = setup_table(&tx, &[])?;
+        let spill = TempTable::new(Schema::new());
+        let (mut scan, spilled) =
+            HashGroupByScan::new(&mut scan, Vec::new(), aggregates(), 1, &spill, tx.clone())?;
+        assert!(!spilled);
+        assert!(scan.next()?);
+        assert_eq!(scan.get_int("count(*)")?, 0);
+        assert!(!scan.next()?);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_group_by_scan() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut scan = setup_table(&tx, &[("b", 5), ("a", 1), ("c", 1), ("a", 2), ("c", 1)])?;
+        let spill = TempTable::new(scan_schema());
+        let (mut group_scan, spilled) = HashGroupByScan::new(
+            &mut scan,
+            vec!["dept".to_string()],
+            aggregates(),
+            3,
+            &spill,
+            tx.clone(),
+        )?;
+        assert!(!spilled);
+        assert_eq!(
+            collect(&mut group_scan)?,
+            vec![
+                ("b".to_string(), 1, 5),
+                ("a".to_string(), 2, 3),
+                ("c".to_string(), 2, 2)
+            ]
+        );
+
+        // the records of the group which does not fit are spilled
+        let (group_scan, spilled) = HashGroupByScan::new(
+            &mut scan,
+            vec!["dept".to_string()],
+            aggregates(),
+            2,
+            &spill,
+            tx.clone(),
+        )?;
+        assert!(spilled);
+        let mut spill_scan = spill.open(tx.clone())?;
+        let mut spilled_records = Vec::new();
+        while spill_scan.next()? {
+            spilled_records.push((
+                spill_scan.get_string("dept")?,
+                spill_scan.get_int("salary")?,
+            ));
+        }
+        assert_eq!(
+            spilled_records,
+            vec![("c".to_string(), 1), ("c".to_string(), 1)]
+        );
+
+        let mut group_scan = group_scan.followed_by(GroupByScan::new(
+            Box::new(spill_scan),
+            vec!["dept".to_string()],
+            aggregates(),
+        )?);
+        assert_eq!(
+            collect(&mut group_scan)?,
+            vec![
+                ("b".to_string(), 1, 5),
+                ("a".to_string(), 2, 3),
+                ("c".to_string(), 2, 2)
+            ]
+        );
+        assert!(group_scan.get_val("dept").is_err());
+        drop(group_scan);
+        drop(scan);
+        spill.delete(&tx)?;
+
+        tx.commit()?;
+        Ok(())
+    }
+}
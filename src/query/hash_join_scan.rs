@@ -0,0 +1,572 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use crate::error::{DbError, DbResult};
+use crate::query::{Constant, Scan, UpdateScan};
+use crate::record::{Layout, Schema, TableScan, TempTable};
+use crate::tx::Transaction;
+
+/// Field values of a record in the order of its schema.
+type Row = Vec<Constant>;
+
+/// Inputs are partitioned at most this many times. Records which share a join value
+/// always end up in the same partition, so repartitioning stops helping at some point.
+const MAX_PARTITION_DEPTH: usize = 3;
+
+const MAX_PARTITIONS: usize = 16;
+
+/// `HashJoinScan` joins two scans on the equality of a field from each of them.
+/// The smaller input is loaded into an in-memory hash table which is probed with the
+/// records of the other one. When both inputs exceed the memory budget, they are
+/// partitioned by the hash of the join value into temporary tables, and every pair of
+/// partitions is joined the same way. A partition is deleted once it is joined.
+pub struct HashJoinScan<'tx> {
+    tx: Transaction<'tx>,
+    lhs: Box<dyn Scan + 'tx>,
+    rhs: Box<dyn Scan + 'tx>,
+    lhs_side: JoinSide,
+    rhs_side: JoinSide,
+    max_build_records: usize,
+    inputs_consumed: bool,
+    pending: Vec<Partition>,
+    // the partition the probe reads, deleted when the probe is done
+    probed: Option<Partition>,
+    probe: Option<Probe<'tx>>,
+    current: Option<(Row, Row)>,
+}
+
+struct JoinSide {
+    schema: Schema,
+    join_field_pos: usize,
+}
+
+impl JoinSide {
+    fn new(schema: Schema, join_field: &str) -> DbResult<Self> {
+        let join_field_pos = schema
+            .fields()
+            .iter()
+            .position(|f| f == join_field)
+            .ok_or_else(|| DbError::FieldNotFound(join_field.to_string()))?;
+        Ok(JoinSide {
+            schema,
+            join_field_pos,
+        })
+    }
+
+    fn position(&self, field_name: &str) -> Option<usize> {
+        self.schema.fields().iter().position(|f| f == field_name)
+    }
+}
+
+/// A pair of partitions which contain the records of both inputs with the same join value hashes.
+struct Partition {
+    lhs: TempTable,
+    rhs: TempTable,
+    depth: usize,
+}
+
+impl Partition {
+    fn delete(&self, tx: &Transaction<'_>) -> DbResult<()> {
+        self.lhs.delete(tx)?;
+        self.rhs.delete(tx)
+    }
+}
+
+enum RowSource<'tx> {
+    /// The scan the join was created with.
+    Input,
+    Temp(Box<TableScan<'tx>>),
+}
+
+/// The hash table built on one side and the state of probing it with the other side.
+struct Probe<'tx> {
+    table: HashMap<Constant, Vec<Row>>,
+    build_is_lhs: bool,
+    buffered: VecDeque<Row>,
+    source: RowSource<'tx>,
+    probe_row: Option<Row>,
+    match_idx: usize,
+}
+
+impl<'tx> HashJoinScan<'tx> {
+    pub fn new(
+        tx: Transaction<'tx>,
+        lhs: Box<dyn Scan + 'tx>,
+        rhs: Box<dyn Scan + 'tx>,
+        lhs_schema: Schema,
+        rhs_schema: Schema,
+        lhs_field: &str,
+        rhs_field: &str,
+    ) -> DbResult<Self> {
+        // the hash table may use as many records as fit into the currently available buffers
+        let slot_size = Layout::new(lhs_schema.clone())
+            .slot_size()
+            .max(Layout::new(rhs_schema.clone()).slot_size());
+        let max_build_records = tx.available_buffs().max(1) * (tx.block_size() / slot_size).max(1);
+
+        Ok(HashJoinScan {
+            tx,
+            lhs,
+            rhs,
+            lhs_side: JoinSide::new(lhs_schema, lhs_field)?,
+            rhs_side: JoinSide::new(rhs_schema, rhs_field)?,
+            max_build_records,
+            inputs_consumed: false,
+            pending: Vec::new(),
+            probed: None,
+            probe: None,
+            current: None,
+        })
+    }
+
+    /// Overrides the number of records the in-memory hash table may hold.
+    pub fn max_build_records(mut self, max_build_records: usize) -> Self {
+        self.max_build_records = max_build_records.max(1);
+        self
+    }
+
+    /// Reads both sides in lockstep until one of them is exhausted, which makes it the smaller one
+    /// and the build side. If both exceed the budget instead, they are partitioned and None is returned.
+    fn load(
+        &mut self,
+        mut lhs_source: RowSource<'tx>,
+        mut rhs_source: RowSource<'tx>,
+        depth: usize,
+    ) -> DbResult<Option<Probe<'tx>>> {
+        let budget = self.max_build_records;
+        let (mut lhs_rows, mut rhs_rows) = (Vec::new(), Vec::new());
+        let (mut lhs_done, mut rhs_done) = (false, false);
+
+        while !lhs_done && !rhs_done && (lhs_rows.len() <= budget || rhs_rows.len() <= budget) {
+            if lhs_rows.len() <= budget {
+                match self.read(&mut lhs_source, true)? {
+                    Some(row) => lhs_rows.push(row),
+                    None => lhs_done = true,
+                }
+            }
+            if rhs_rows.len() <= budget {
+                match self.read(&mut rhs_source, false)? {
+                    Some(row) => rhs_rows.push(row),
+                    None => rhs_done = true,
+                }
+            }
+        }
+
+        if lhs_done && (!rhs_done || lhs_rows.len() <= rhs_rows.len()) {
+            Ok(Some(self.build(lhs_rows, true, rhs_rows, rhs_source)))
+        } else if rhs_done {
+            Ok(Some(self.build(rhs_rows, false, lhs_rows, lhs_source)))
+        } else if depth < MAX_PARTITION_DEPTH {
+            let count = self
+                .tx
+                .available_buffs()
+                .saturating_sub(2)
+                .clamp(2, MAX_PARTITIONS);
+            let lhs_parts = self.partition(lhs_rows, lhs_source, true, count, depth)?;
+            let rhs_parts = self.partition(rhs_rows, rhs_source, false, count, depth)?;
+            for (lhs, rhs) in lhs_parts.into_iter().zip(rhs_parts) {
+                self.pending.push(Partition {
+                    lhs,
+                    rhs,
+                    depth: depth + 1,
+                });
+            }
+            Ok(None)
+        } else {
+            while let Some(row) = self.read(&mut lhs_source, true)? {
+                lhs_rows.push(row);
+            }
+            Ok(Some(self.build(lhs_rows, true, rhs_rows, rhs_source)))
+        }
+    }
+
+    fn build(
+        &self,
+        build_rows: Vec<Row>,
+        build_is_lhs: bool,
+        probe_rows: Vec<Row>,
+        source: RowSource<'tx>,
+    ) -> Probe<'tx> {
+        let key_pos = self.side(build_is_lhs).join_field_pos;
+        let mut table: HashMap<Constant, Vec<Row>> = HashMap::new();
+        for row in build_rows {
+            table.entry(row[key_pos].clone()).or_default().push(row);
+        }
+        Probe {
+            table,
+            build_is_lhs,
+            buffered: probe_rows.into(),
+            source,
+            probe_row: None,
+            match_idx: 0,
+        }
+    }
+
+    /// Distributes the rows already read and the rest of the source among `count` temporary tables.
+    fn partition(
+        &mut self,
+        rows: Vec<Row>,
+        mut source: RowSource<'tx>,
+        is_lhs: bool,
+        count: usize,
+        depth: usize,
+    ) -> DbResult<Vec<TempTable>> {
+        let side = self.side(is_lhs);
+        let schema = side.schema.clone();
+        let key_pos = side.join_field_pos;
+
+        let tables: Vec<_> = (0..count).map(|_| TempTable::new(schema.clone())).collect();
+        let mut scans = tables
+            .iter()
+            .map(|table| table.open(self.tx.clone()))
+            .collect::<DbResult<Vec<_>>>()?;
+
+        let mut write = |row: Row| -> DbResult<()> {
+            let scan = &mut scans[Self::partition_of(&row[key_pos], depth, count)];
+            scan.insert()?;
+            for (field_name, val) in schema.fields().iter().zip(row) {
+                scan.set_val(field_name, val)?;
+            }
+            Ok(())
+        };
+        for row in rows {
+            write(row)?;
+        }
+        while let Some(row) = self.read(&mut source, is_lhs)? {
+            write(row)?;
+        }
+        Ok(tables)
+    }
+
+    /// The depth is part of the hash, so that a partition which is still too large
+    /// is split differently the next time.
+    fn partition_of(key: &Constant, depth: usize, count: usize) -> usize {
+        let mut hasher = DefaultHasher::new();
+        depth.hash(&mut hasher);
+        key.hash(&mut hasher);
+        (hasher.finish() % count as u64) as usize
+    }
+
+    fn next_match(&mut self) -> DbResult<Option<(Row, Row)>> {
+        let mut probe = self.probe.take().expect("No hash table is built");
+        let probe_key_pos = self.side(!probe.build_is_lhs).join_field_pos;
+
+        let result = loop {
+            if let Some(probe_row) = &probe.probe_row
+                && let Some(matches) = probe.table.get(&probe_row[probe_key_pos])
+                && probe.match_idx < matches.len()
+            {
+                let build_row = matches[probe.match_idx].clone();
+                probe.match_idx += 1;
+                break Some(if probe.build_is_lhs {
+                    (build_row, probe_row.clone())
+                } else {
+                    (probe_row.clone(), build_row)
+                });
+            }
+
+            let next_row = match probe.buffered.pop_front() {
+                Some(row) => Some(row),
+                None => self.read(&mut probe.source, !probe.build_is_lhs)?,
+            };
+            if next_row.is_none() {
+                break None;
+            }
+            probe.probe_row = next_row;
+            probe.match_idx = 0;
+        };
+
+        self.probe = Some(probe);
+        Ok(result)
+    }
+
+    fn read(&mut self, source: &mut RowSource<'tx>, is_lhs: bool) -> DbResult<Option<Row>> {
+        let (input, side) = if is_lhs {
+            (&mut self.lhs, &self.lhs_side)
+        } else {
+            (&mut self.rhs, &self.rhs_side)
+        };
+        let scan: &mut dyn Scan = match source {
+            RowSource::Input => &mut **input,
+            RowSource::Temp(scan) => &mut **scan,
+        };
+
+        // a null join field equals nothing, so such rows are skipped
+        while scan.next()? {
+            let row = side
+                .schema
+                .fields()
+                .iter()
+                .map(|field_name| scan.get_val(field_name))
+                .collect::<DbResult<Row>>()?;
+            if !row[side.join_field_pos].is_null() {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Deletes the partitions which are written, the probe must be done with them.
+    fn delete_partitions(&mut self) -> DbResult<()> {
+        for partition in self.probed.take().into_iter().chain(self.pending.drain(..)) {
+            partition.delete(&self.tx)?;
+        }
+        Ok(())
+    }
+
+    fn side(&self, is_lhs: bool) -> &JoinSide {
+        if is_lhs {
+            &self.lhs_side
+        } else {
+            &self.rhs_side
+        }
+    }
+}
+
+impl<'tx> Scan for HashJoinScan<'tx> {
+    /// Restarts the join. Partitions written so far are discarded and the inputs are read again.
+    fn before_first(&mut self) -> DbResult<()> {
+        self.probe = None;
+        self.delete_partitions()?;
+        self.current = None;
+        self.inputs_consumed = false;
+        self.lhs.before_first()?;
+        self.rhs.before_first()
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        loop {
+            if self.probe.is_some() {
+                if let Some(rows) = self.next_match()? {
+                    self.current = Some(rows);
+                    return Ok(true);
+                }
+                self.probe = None;
+            }
+            // the partition was read completely, by the probe or when it got partitioned again
+            if let Some(partition) = self.probed.take() {
+                partition.delete(&self.tx)?;
+            }
+
+            if !self.inputs_consumed {
+                self.inputs_consumed = true;
+                self.probe = self.load(RowSource::Input, RowSource::Input, 0)?;
+            } else if let Some(partition) = self.pending.pop() {
+                let lhs = RowSource::Temp(Box::new(partition.lhs.open(self.tx.clone())?));
+                let rhs = RowSource::Temp(Box::new(partition.rhs.open(self.tx.clone())?));
+                let depth = partition.depth;
+                self.probed = Some(partition);
+                self.probe = self.load(lhs, rhs, depth)?;
+            } else {
+                self.current = None;
+                return Ok(false);
+            }
+        }
+    }
+
+    fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+        self.get_val(field_name)?.int_value(field_name)
+    }
+
+    fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+        self.get_val(field_name)?.string_value(field_name)
+    }
+
+    fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+        let (lhs_row, rhs_row) = self.current.as_ref().expect("No current record");
+        if let Some(pos) = self.lhs_side.position(field_name) {
+            Ok(lhs_row[pos].clone())
+        } else if let Some(pos) = self.rhs_side.position(field_name) {
+            Ok(rhs_row[pos].clone())
+        } else {
+            Err(DbError::FieldNotFound(field_name.to_string()))
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.lhs_side.schema.has_field(field_name) || self.rhs_side.schema.has_field(field_name)
+    }
+}
+
+impl<'tx> Drop for HashJoinScan<'tx> {
+    fn drop(&mut self) {
+        self.probe = None;
+        let _ = self.delete_partitions();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::TEMP_FILE_PREFIX;
+    use crate::utils::testing_utils::{temp_db, temp_db_with_cfg};
+
+    fn create_table<'tx>(
+        tx: &Transaction<'tx>,
+        name: &str,
+        fields: [&str; 2],
+        rows: impl IntoIterator<Item = (i32, i32)>,
+    ) -> DbResult<(TableScan<'tx>, Schema)> {
+        let mut schema = Schema::new();
+        schema.add_int_field(fields[0]);
+        schema.add_int_field(fields[1]);
+        let mut scan = TableScan::new(tx.clone(), name, Layout::new(schema.clone()))?;
+        for (a, b) in rows {
+            scan.insert()?;
+            scan.set_int(fields[0], a)?;
+            scan.set_int(fields[1], b)?;
+        }
+        scan.before_first()?;
+        Ok((scan, schema))
+    }
+
+    /// Joins customers (cid, cregion) with orders (oid, ocust) on cid = ocust.
+    fn join_results(join: &mut HashJoinScan) -> DbResult<Vec<(i32, i32)>> {
+        let mut rows = Vec::new();
+        while join.next()? {
+            assert_eq!(join.get_int("cid")?, join.get_int("ocust")?);
+            assert_eq!(join.get_int("cregion")?, join.get_int("cid")? % 3);
+            rows.push((join.get_int("cid")?, join.get_int("oid")?));
+        }
+        rows.sort();
+        Ok(rows)
+    }
+
+    fn expected_results(customers: i32, orders: i32) -> Vec<(i32, i32)> {
+        let mut rows: Vec<_> = (0..orders)
+            .map(|oid| (oid % 50, oid))
+            .filter(|(cid, _)| *cid < customers)
+            .collect();
+        rows.sort();
+        rows
+    }
+
+    fn setup_join<'tx>(
+        tx: &Transaction<'tx>,
+        customers: i32,
+        orders: i32,
+    ) -> DbResult<HashJoinScan<'tx>> {
+        let (cust_scan, cust_schema) = create_table(
+            tx,
+            "cust",
+            ["cid", "cregion"],
+            (0..customers).map(|cid| (cid, cid % 3)),
+        )?;
+        let (order_scan, order_schema) = create_table(
+            tx,
+            "orders",
+            ["oid", "ocust"],
+            (0..orders).map(|oid| (oid, oid % 50)),
+        )?;
+        HashJoinScan::new(
+            tx.clone(),
+            Box::new(cust_scan),
+            Box::new(order_scan),
+            cust_schema,
+            order_schema,
+            "cid",
+            "ocust",
+        )
+    }
+
+    #[test]
+    fn test_hash_join_in_memory() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut join = setup_join(&tx, 40, 200)?;
+        assert!(join.has_field("cregion"));
+        assert!(join.has_field("oid"));
+        assert!(!join.has_field("dname"));
+        assert_eq!(join_results(&mut join)?, expected_results(40, 200));
+
+        join.before_first()?;
+        assert_eq!(join_results(&mut join)?, expected_results(40, 200));
+        drop(join);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_join_builds_on_smaller_input() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        // only the customers fit into the budget, so no partitions are needed
+        let mut join = setup_join(&tx, 20, 300)?.max_build_records(25);
+        assert_eq!(join_results(&mut join)?, expected_results(20, 300));
+        assert!(join.pending.is_empty());
+        drop(join);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_join_spills_partitions() -> DbResult<()> {
+        let mut db_dir = std::path::PathBuf::new();
+        let db = temp_db_with_cfg(|cfg| {
+            db_dir = cfg.storage_mgr.db_directory().unwrap().to_path_buf();
+            cfg.buffer_capacity(12)
+        })?;
+        let tx = db.new_tx()?;
+        let temp_files = || -> DbResult<usize> {
+            let mut count = 0;
+            for entry in std::fs::read_dir(&db_dir)? {
+                if entry?
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(TEMP_FILE_PREFIX)
+                {
+                    count += 1;
+                }
+            }
+            Ok(count)
+        };
+
+        let mut join = setup_join(&tx, 50, 400)?.max_build_records(10);
+        assert_eq!(join_results(&mut join)?, expected_results(50, 400));
+        // the partitions are deleted once they are joined
+        assert_eq!(temp_files()?, 0);
+
+        join.before_first()?;
+        assert_eq!(join_results(&mut join)?, expected_results(50, 400));
+
+        join.before_first()?;
+        assert!(join.next()?);
+        assert!(temp_files()? > 0);
+        drop(join);
+        assert_eq!(temp_files()?, 0);
+
+        // all orders share a single customer, which partitioning can not split up
+        let (cust_scan, cust_schema) =
+            create_table(&tx, "cust2", ["cid", "cregion"], (0..30).map(|_| (7, 1)))?;
+        let (order_scan, order_schema) = create_table(
+            &tx,
+            "orders2",
+            ["oid", "ocust"],
+            (0..30).map(|oid| (oid, 7)),
+        )?;
+        let mut join = HashJoinScan::new(
+            tx.clone(),
+            Box::new(cust_scan),
+            Box::new(order_scan),
+            cust_schema,
+            order_schema,
+            "cid",
+            "ocust",
+        )?
+        .max_build_records(5);
+        let mut count = 0;
+        while join.next()? {
+            count += 1;
+        }
+        assert_eq!(count, 30 * 30);
+        drop(join);
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::error::DbResult;
+use crate::index::Index;
+use crate::query::{Constant, Scan, UpdateScan};
+use crate::record::TableScan;
+
+/// `IndexJoinScan` joins an outer scan with a table through an index on the table's join field.
+/// For every outer record the index is searched for the value of the outer join field,
+/// and the table scan is moved to each matching data record.
+pub struct IndexJoinScan<'tx> {
+    lhs: Box<dyn Scan + 'tx>,
+    index: Box<dyn Index + 'tx>,
+    join_field: String,
+    rhs: TableScan<'tx>,
+    lhs_valid: bool,
+}
+
+impl<'tx> IndexJoinScan<'tx> {
+    pub fn new(
+        lhs: Box<dyn Scan + 'tx>,
+        index: Box<dyn Index + 'tx>,
+        join_field: &str,
+        rhs: TableScan<'tx>,
+    ) -> Self {
+        IndexJoinScan {
+            lhs,
+            index,
+            join_field: join_field.to_string(),
+            rhs,
+            lhs_valid: false,
+        }
+    }
+}
+
+impl<'tx> Scan for IndexJoinScan<'tx> {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.lhs.before_first()?;
+        self.lhs_valid = false;
+        Ok(())
+    }
+
+    /// Moves to the next index entry for the current outer record. Once there are none left,
+    /// the outer scan advances and the index is positioned on the new join value.
+    fn next(&mut self) -> DbResult<bool> {
+        loop {
+            if self.lhs_valid && self.index.next()? {
+                let rid = self.index.get_data_rid()?;
+                self.rhs.move_to_rid(rid)?;
+                return Ok(true);
+            }
+            self.lhs_valid = self.lhs.next()?;
+            if !self.lhs_valid {
+                return Ok(false);
+            }
+            let search_key = self.lhs.get_val(&self.join_field)?;
+            self.index.before_first(&search_key)?;
+        }
+    }
+
+    fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+        if self.rhs.has_field(field_name) {
+            self.rhs.get_int(field_name)
+        } else {
+            self.lhs.get_int(field_name)
+        }
+    }
+
+    fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+        if self.rhs.has_field(field_name) {
+            self.rhs.get_string(field_name)
+        } else {
+            self.lhs.get_string(field_name)
+        }
+    }
+
+    fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+        if self.rhs.has_field(field_name) {
+            self.rhs.get_val(field_name)
+        } else {
+            self.lhs.get_val(field_name)
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.rhs.has_field(field_name) || self.lhs.has_field(field_name)
+    }
+}
+
+impl<'tx> Drop for IndexJoinScan<'tx> {
+    fn drop(&mut self) {
+        self.index.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        index::BTreeIndex,
+        metadata::IndexInfo,
+        record::{Layout, Schema},
+        utils::testing_utils::temp_db,
+    };
+
+    #[test]
+    fn test_index_join_scan() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut cust_schema = Schema::new();
+        cust_schema.add_int_field("cid");
+        cust_schema.add_string_field("cname", 10);
+        let cust_layout = Layout::new(cust_schema);
+
+        let mut order_schema = Schema::new();
+        order_schema.add_int_field("oid");
+        order_schema.add_int_field("ocust");
+        let order_layout = Layout::new(order_schema);
+
+        let mut cust_scan = TableScan::new(tx.clone(), "cust", cust_layout)?;
+        for (cid, cname) in [(1, "ann"), (2, "bob"), (3, "cid")] {
+            cust_scan.insert()?;
+            cust_scan.set_int("cid", cid)?;
+            cust_scan.set_string("cname", cname)?;
+        }
+        cust_scan.before_first()?;
+
+        // bob has no orders
+        let index_layout = IndexInfo::create_idx_layout("ocust", order_layout.schema());
+        let mut index = BTreeIndex::new(tx.clone(), "ocust_idx", index_layout)?;
+        let mut order_scan = TableScan::new(tx.clone(), "orders", order_layout.clone())?;
+        for (oid, ocust) in [(100, 3), (101, 1), (102, 3), (103, 3)] {
+            order_scan.insert()?;
+            order_scan.set_int("oid", oid)?;
+            order_scan.set_int("ocust", ocust)?;
+            index.insert(&Constant::int(ocust), &order_scan.get_rid()?)?;
+        }
+
+        let mut join_scan = IndexJoinScan::new(
+            Box::new(cust_scan),
+            Box::new(index),
+            "cid",
+            TableScan::new(tx.clone(), "orders", order_layout)?,
+        );
+        assert!(join_scan.has_field("cname"));
+        assert!(join_scan.has_field("oid"));
+
+        let mut rows = Vec::new();
+        while join_scan.next()? {
+            assert_eq!(join_scan.get_int("cid")?, join_scan.get_int("ocust")?);
+            rows.push((join_scan.get_string("cname")?, join_scan.get_int("oid")?));
+        }
+        rows.sort();
+        let expected: Vec<_> = [("ann", 101), ("cid", 100), ("cid", 102), ("cid", 103)]
+            .iter()
+            .map(|(cname, oid)| (cname.to_string(), *oid))
+            .collect();
+        assert_eq!(rows, expected);
+
+        join_scan.before_first()?;
+        let mut count = 0;
+        while join_scan.next()? {
+            count += 1;
+        }
+        assert_eq!(count, 4);
+        drop(join_scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use std::ops::Bound;
+
+use crate::error::DbResult;
+use crate::index::Index;
+use crate::query::{Constant, Scan, UpdateScan};
+use crate::record::{RID, TableScan};
+
+/// `IndexRangeSelectScan` uses an index to find the records whose indexed value lies
+/// between a lower and an upper bound, such as the records matching `age > 30`.
+pub struct IndexRangeSelectScan<'tx> {
+    table_scan: TableScan<'tx>,
+    index: Box<dyn Index + 'tx>,
+    lower: Bound<Constant>,
+    upper: Bound<Constant>,
+}
+
+impl<'tx> IndexRangeSelectScan<'tx> {
+    pub fn new(
+        table_scan: TableScan<'tx>,
+        index: Box<dyn Index + 'tx>,
+        lower: Bound<Constant>,
+        upper: Bound<Constant>,
+    ) -> DbResult<Self> {
+        let mut scan = IndexRangeSelectScan {
+            table_scan,
+            index,
+            lower,
+            upper,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+}
+
+impl<'tx> Scan for IndexRangeSelectScan<'tx> {
+    /// Positions the index before the first entry within the bounds.
+    fn before_first(&mut self) -> DbResult<()> {
+        self.index.seek(self.lower.as_ref(), self.upper.as_ref())
+    }
+
+    /// Moves to the next index entry within the bounds and the table scan to its data record.
+    /// Returns false if there are no more such entries.
+    fn next(&mut self) -> DbResult<bool> {
+        let has_next = self.index.next()?;
+        if has_next {
+            let rid = self.index.get_data_rid()?;
+            self.table_scan.move_to_rid(rid)?;
+        }
+        Ok(has_next)
+    }
+
+    fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+        self.table_scan.get_int(field_name)
+    }
+
+    fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+        self.table_scan.get_string(field_name)
+    }
+
+    fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+        self.table_scan.get_val(field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.table_scan.has_field(field_name)
+    }
+}
+
+/// Updates go to the current data record. Changing the indexed field or deleting the
+/// record does not touch the index, so callers must maintain it themselves.
+impl<'tx> UpdateScan for IndexRangeSelectScan<'tx> {
+    fn set_val(&mut self, field_name: &str, val: Constant) -> DbResult<()> {
+        self.table_scan.set_val(field_name, val)
+    }
+
+    fn set_int(&mut self, field_name: &str, val: i32) -> DbResult<()> {
+        self.table_scan.set_int(field_name, val)
+    }
+
+    fn set_string(&mut self, field_name: &str, val: &str) -> DbResult<()> {
+        self.table_scan.set_string(field_name, val)
+    }
+
+    fn insert(&mut self) -> DbResult<()> {
+        self.table_scan.insert()
+    }
+
+    fn delete(&mut self) -> DbResult<()> {
+        self.table_scan.delete()
+    }
+
+    fn get_rid(&self) -> DbResult<RID> {
+        self.table_scan.get_rid()
+    }
+
+    fn move_to_rid(&mut self, rid: RID) -> DbResult<()> {
+        self.table_scan.move_to_rid(rid)
+    }
+}
+
+impl<'tx> Drop for IndexRangeSelectScan<'tx> {
+    fn drop(&mut self) {
+        self.index.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        index::BTreeIndex,
+        metadata::IndexInfo,
+        record::{layout::Layout, schema::Schema},
+        utils::testing_utils::temp_db,
+    };
+
+    #[test]
+    fn test_index_range_select_scan() -> DbResult<()> {
+        let db = temp_db()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        schema.add_int_field("age");
+        let layout = Layout::new(schema);
+
+        let tx = db.new_tx()?;
+
+        let index_layout = IndexInfo::create_idx_layout("age", layout.schema());
+        let mut index = BTreeIndex::new(tx.clone(), "age_idx", index_layout)?;
+
+        let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+        for id in 0..50 {
+            scan.insert()?;
+            scan.set_int("id", id)?;
+            scan.set_int("age", 20 + id % 10)?;
+            index.insert(&Constant::int(20 + id % 10), &scan.get_rid()?)?;
+        }
+
+        {
+            // 25 < age <= 27
+            let mut index_scan = IndexRangeSelectScan::new(
+                TableScan::new(tx.clone(), "test_table", layout.clone())?,
+                Box::new(index),
+                Bound::Excluded(Constant::int(25)),
+                Bound::Included(Constant::int(27)),
+            )?;
+
+            let mut ids = Vec::new();
+            while index_scan.next()? {
+                let age = index_scan.get_int("age")?;
+                assert!(age > 25 && age <= 27);
+                ids.push(index_scan.get_int("id")?);
+            }
+            ids.sort();
+            let expected: Vec<_> = (0..50).filter(|id| matches!(id % 10, 6 | 7)).collect();
+            assert_eq!(ids, expected);
+
+            // the scan can be restarted
+            index_scan.before_first()?;
+            let mut count = 0;
+            while index_scan.next()? {
+                count += 1;
+            }
+            assert_eq!(count, expected.len());
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+}
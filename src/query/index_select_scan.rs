@@ -1,7 +1,7 @@
 use crate::error::DbResult;
 use crate::index::Index;
 use crate::query::{Constant, Scan, UpdateScan};
-use crate::record::TableScan;
+use crate::record::{RID, TableScan};
 
 /// `IndexSelectScan` uses an index to efficiently find records matching a specific value.
 /// It combines an index scan with a table scan to retrieve the actual record data.
@@ -64,6 +64,38 @@ impl<'tx> Scan for IndexSelectScan<'tx> {
     }
 }
 
+/// Updates go to the current data record. Changing the indexed field or deleting the
+/// record does not touch the index, so callers must maintain it themselves.
+impl<'tx> UpdateScan for IndexSelectScan<'tx> {
+    fn set_val(&mut self, field_name: &str, val: Constant) -> DbResult<()> {
+        self.table_scan.set_val(field_name, val)
+    }
+
+    fn set_int(&mut self, field_name: &str, val: i32) -> DbResult<()> {
+        self.table_scan.set_int(field_name, val)
+    }
+
+    fn set_string(&mut self, field_name: &str, val: &str) -> DbResult<()> {
+        self.table_scan.set_string(field_name, val)
+    }
+
+    fn insert(&mut self) -> DbResult<()> {
+        self.table_scan.insert()
+    }
+
+    fn delete(&mut self) -> DbResult<()> {
+        self.table_scan.delete()
+    }
+
+    fn get_rid(&self) -> DbResult<RID> {
+        self.table_scan.get_rid()
+    }
+
+    fn move_to_rid(&mut self, rid: RID) -> DbResult<()> {
+        self.table_scan.move_to_rid(rid)
+    }
+}
+
 impl<'tx> Drop for IndexSelectScan<'tx> {
     fn drop(&mut self) {
         self.index.close();
@@ -0,0 +1,153 @@
+use crate::DbResult;
+use crate::query::scan::Scan;
+
+use super::Constant;
+
+/// `LimitScan` skips the first `offset` records of the underlying scan and returns at most
+/// `limit` records after them. Once the limit is reached, no more records are pulled from
+/// the underlying scan.
+pub struct LimitScan<'a> {
+    scan: Box<dyn Scan + 'a>,
+    limit: Option<usize>,
+    offset: usize,
+    returned: usize,
+    skipped: bool,
+}
+
+impl<'a> LimitScan<'a> {
+    pub fn new(scan: Box<dyn Scan + 'a>, limit: Option<usize>, offset: usize) -> Self {
+        LimitScan {
+            scan,
+            limit,
+            offset,
+            returned: 0,
+            skipped: false,
+        }
+    }
+}
+
+impl<'a> Scan for LimitScan<'a> {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.returned = 0;
+        self.skipped = false;
+        self.scan.before_first()
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        if self.limit.is_some_and(|limit| self.returned >= limit) {
+            return Ok(false);
+        }
+        if !self.skipped {
+            self.skipped = true;
+            for _ in 0..self.offset {
+                if !self.scan.next()? {
+                    return Ok(false);
+                }
+            }
+        }
+        if !self.scan.next()? {
+            return Ok(false);
+        }
+        self.returned += 1;
+        Ok(true)
+    }
+
+    fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+        self.scan.get_int(field_name)
+    }
+
+    fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+        self.scan.get_string(field_name)
+    }
+
+    fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+        self.scan.get_val(field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.scan.has_field(field_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        query::UpdateScan,
+        record::{Layout, Schema, TableScan},
+        utils::testing_utils::temp_db,
+    };
+
+    /// Counts the calls to `next` of the underlying scan.
+    struct CountingScan<'a> {
+        scan: TableScan<'a>,
+        pulled: std::rc::Rc<std::cell::Cell<usize>>,
+    }
+
+    impl<'a> Scan for CountingScan<'a> {
+        fn before_first(&mut self) -> DbResult<()> {
+            self.scan.before_first()
+        }
+
+        fn next(&mut self) -> DbResult<bool> {
+            self.pulled.set(self.pulled.get() + 1);
+            self.scan.next()
+        }
+
+        fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+            self.scan.get_int(field_name)
+        }
+
+        fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+            self.scan.get_string(field_name)
+        }
+
+        fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+            self.scan.get_val(field_name)
+        }
+
+        fn has_field(&self, field_name: &str) -> bool {
+            self.scan.has_field(field_name)
+        }
+    }
+
+    #[test]
+    fn test_limit_scan() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("a");
+        let layout = Layout::new(schema);
+        let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+        for i in 0..10 {
+            scan.insert()?;
+            scan.set_int("a", i)?;
+        }
+        drop(scan);
+
+        let collect = |limit: Option<usize>, offset: usize| -> DbResult<(Vec<i32>, usize)> {
+            let pulled = std::rc::Rc::new(std::cell::Cell::new(0));
+            let counting = CountingScan {
+                scan: TableScan::new(tx.clone(), "test_table", layout.clone())?,
+                pulled: pulled.clone(),
+            };
+            let mut scan = LimitScan::new(Box::new(counting), limit, offset);
+            let mut values = Vec::new();
+            while scan.next()? {
+                values.push(scan.get_int("a")?);
+            }
+            Ok((values, pulled.get()))
+        };
+
+        assert_eq!(collect(Some(3), 0)?, (vec![0, 1, 2], 3));
+        assert_eq!(collect(Some(3), 4)?, (vec![4, 5, 6], 7));
+        assert_eq!(collect(None, 8)?, (vec![8, 9], 11));
+        assert_eq!(collect(Some(5), 8)?, (vec![8, 9], 11));
+        assert_eq!(collect(Some(0), 0)?, (vec![], 0));
+        assert_eq!(collect(Some(2), 20)?.0, Vec::<i32>::new());
+
+        tx.commit()?;
+        Ok(())
+    }
+}
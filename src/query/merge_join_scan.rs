@@ -0,0 +1,106 @@
+use crate::error::DbResult;
+use crate::query::sort_scan::SortPosition;
+use crate::query::{Constant, Scan, SortScan};
+
+/// `MergeJoinScan` joins two scans which are sorted in ascending order on their join fields.
+/// Both scans are advanced in step. When the right scan has several records with the same
+/// join value, its position at the first of them is saved and restored for every left record
+/// with that value.
+pub struct MergeJoinScan<'tx> {
+    s1: Box<dyn Scan + 'tx>,
+    s2: SortScan<'tx>,
+    field1: String,
+    field2: String,
+    join_val: Option<Constant>,
+    pos: Option<SortPosition>,
+}
+
+impl<'tx> MergeJoinScan<'tx> {
+    pub fn new(s1: Box<dyn Scan + 'tx>, s2: SortScan<'tx>, field1: &str, field2: &str) -> Self {
+        MergeJoinScan {
+            s1,
+            s2,
+            field1: field1.to_string(),
+            field2: field2.to_string(),
+            join_val: None,
+            pos: None,
+        }
+    }
+}
+
+impl<'tx> Scan for MergeJoinScan<'tx> {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.s1.before_first()?;
+        self.s2.before_first()?;
+        self.join_val = None;
+        self.pos = None;
+        Ok(())
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        let mut has_more2 = self.s2.next()?;
+        if has_more2
+            && let Some(join_val) = &self.join_val
+            && self.s2.get_val(&self.field2)? == *join_val
+        {
+            return Ok(true);
+        }
+
+        let mut has_more1 = self.s1.next()?;
+        if has_more1
+            && let Some(join_val) = &self.join_val
+            && self.s1.get_val(&self.field1)? == *join_val
+        {
+            let pos = self
+                .pos
+                .as_ref()
+                .expect("Position of the join value not saved");
+            self.s2.restore_position(pos)?;
+            return Ok(true);
+        }
+
+        while has_more1 && has_more2 {
+            let v1 = self.s1.get_val(&self.field1)?;
+            let v2 = self.s2.get_val(&self.field2)?;
+            // nulls are sorted first and equal no value, so they are skipped
+            if v1.is_null() || v1 < v2 {
+                has_more1 = self.s1.next()?;
+            } else if v2.is_null() || v1 > v2 {
+                has_more2 = self.s2.next()?;
+            } else {
+                self.pos = Some(self.s2.save_position()?);
+                self.join_val = Some(v2);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_int(field_name)
+        } else {
+            self.s2.get_int(field_name)
+        }
+    }
+
+    fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_string(field_name)
+        } else {
+            self.s2.get_string(field_name)
+        }
+    }
+
+    fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_val(field_name)
+        } else {
+            self.s2.get_val(field_name)
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.s1.has_field(field_name) || self.s2.has_field(field_name)
+    }
+}
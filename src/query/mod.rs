@@ -1,18 +1,36 @@
+pub mod aggregate;
 pub mod constant;
 pub mod expr;
+pub mod group_by_scan;
+pub mod hash_join_scan;
+pub mod index_join_scan;
+pub mod index_range_select_scan;
 pub mod index_select_scan;
+pub mod limit_scan;
+pub mod merge_join_scan;
 pub mod predicate;
+pub mod product_scan;
 pub mod project_scan;
 pub mod scan;
 pub mod select_scan;
+pub mod sort_scan;
 pub mod term;
 pub mod update_scan;
 
+pub use aggregate::{AggregateFn, AggregateKind};
 pub use constant::Constant;
 pub use expr::Expr;
+pub use group_by_scan::{GroupByScan, HashGroupByScan};
+pub use hash_join_scan::HashJoinScan;
+pub use index_join_scan::IndexJoinScan;
+pub use index_range_select_scan::IndexRangeSelectScan;
 pub use index_select_scan::IndexSelectScan;
+pub use limit_scan::LimitScan;
+pub use merge_join_scan::MergeJoinScan;
 pub use predicate::Predicate;
+pub use product_scan::ProductScan;
 pub use scan::Scan;
 pub use select_scan::SelectScan;
-pub use term::Term;
+pub use sort_scan::{SortField, SortScan};
+pub use term::{CompareOp, Term};
 pub use update_scan::UpdateScan;
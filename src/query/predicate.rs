@@ -1,3 +1,6 @@
+use std::cmp::Ordering;
+use std::ops::Bound;
+
 use crate::error::DbResult;
 use crate::query::{Constant, Scan};
 use crate::record::Schema;
@@ -46,6 +49,41 @@ impl Predicate {
         None
     }
 
+    /// Returns the range of values the predicate allows for the specified field, combining
+    /// the bounds of all terms comparing the field with a constant.
+    /// Returns None if no term restricts the field to a range.
+    pub fn range_for(&self, fldname: &str) -> Option<(Bound<&Constant>, Bound<&Constant>)> {
+        let mut range: Option<(Bound<&Constant>, Bound<&Constant>)> = None;
+        for term in &self.terms {
+            if let Some((lower, upper)) = term.bounds_for(fldname) {
+                range = Some(match range {
+                    Some((cur_lower, cur_upper)) => (
+                        tighter_bound(cur_lower, lower, Ordering::Greater),
+                        tighter_bound(cur_upper, upper, Ordering::Less),
+                    ),
+                    None => (lower, upper),
+                });
+            }
+        }
+        range
+    }
+
+    /// Returns the first field name that equates with the specified field name.
+    /// Returns None if no term equates the field with another field.
+    pub fn equates_with_field(&self, fldname: &str) -> Option<&str> {
+        for term in &self.terms {
+            if let Some(field) = term.equates_with_field(fldname) {
+                return Some(field);
+            }
+        }
+        None
+    }
+
+    /// Returns true if any term refers to the specified field.
+    pub fn mentions(&self, fldname: &str) -> bool {
+        self.terms.iter().any(|term| term.mentions(fldname))
+    }
+
     /// Creates a new predicate containing only the terms that apply to the given schema.
     /// Returns None if no terms apply to the schema.
     pub fn select_sub_pred(&self, sch: &Schema) -> Option<Predicate> {
@@ -63,6 +101,55 @@ impl Predicate {
             Some(result)
         }
     }
+
+    /// Creates a new predicate containing only the terms that apply to the union of
+    /// the two schemas, but not to either schema alone.
+    /// Returns None if there are no such terms.
+    pub fn join_sub_pred(&self, sch1: &Schema, sch2: &Schema) -> Option<Predicate> {
+        let mut result = Predicate::default();
+        let mut new_sch = Schema::new();
+        new_sch.add_all(sch1);
+        new_sch.add_all(sch2);
+
+        for term in &self.terms {
+            if !term.applies_to(sch1) && !term.applies_to(sch2) && term.applies_to(&new_sch) {
+                result.terms.push(term.clone());
+            }
+        }
+
+        if result.terms.is_empty() {
+            None
+        } else {
+            Some(result)
+        }
+    }
+}
+
+/// Picks the more restrictive of two bounds: for lower bounds that is the one with the
+/// greater key (`Ordering::Greater`), for upper bounds the one with the lesser key.
+/// Bounds on values of different types can't be compared and the current one is kept.
+fn tighter_bound<'a>(
+    current: Bound<&'a Constant>,
+    other: Bound<&'a Constant>,
+    tighter: Ordering,
+) -> Bound<&'a Constant> {
+    let (cur_val, other_val) = match (current, other) {
+        (Bound::Unbounded, _) => return other,
+        (_, Bound::Unbounded) => return current,
+        (
+            Bound::Included(cur_val) | Bound::Excluded(cur_val),
+            Bound::Included(other_val) | Bound::Excluded(other_val),
+        ) => (cur_val, other_val),
+    };
+    if !cur_val.is_comparable_with(other_val) {
+        return current;
+    }
+    match other_val.cmp(cur_val) {
+        Ordering::Equal if matches!(other, Bound::Excluded(_)) => other,
+        Ordering::Equal => current,
+        ordering if ordering == tighter => other,
+        _ => current,
+    }
 }
 
 impl std::fmt::Display for Predicate {
@@ -85,7 +172,7 @@ impl std::fmt::Display for Predicate {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::query::{Constant, Expr, Term};
+    use crate::query::{CompareOp, Constant, Expr, Term};
 
     #[test]
     fn test_equates_with_constant() {
@@ -130,6 +217,70 @@ mod tests {
         assert_eq!(empty_predicate.equates_with_constant("age"), None);
     }
 
+    #[test]
+    fn test_range_for() {
+        let (c10, c20, c30) = (Constant::int(10), Constant::int(20), Constant::int(30));
+        let field = || Expr::field_name("age");
+
+        // age > 10 and 30 >= age and age >= 20
+        let predicate = Predicate::new(Term::compare(
+            field(),
+            CompareOp::Gt,
+            Expr::constant(c10.clone()),
+        ))
+        .with_term(Term::compare(
+            Expr::constant(c30.clone()),
+            CompareOp::Ge,
+            field(),
+        ))
+        .with_term(Term::compare(
+            field(),
+            CompareOp::Ge,
+            Expr::constant(c20.clone()),
+        ));
+        assert_eq!(
+            predicate.range_for("age"),
+            Some((Bound::Included(&c20), Bound::Included(&c30)))
+        );
+        assert_eq!(predicate.range_for("name"), None);
+
+        // age between 10 and 30 and age < 30
+        let predicate = Predicate::new(Term::between(
+            field(),
+            Expr::constant(c10.clone()),
+            Expr::constant(c30.clone()),
+            false,
+        ))
+        .with_term(Term::compare(
+            field(),
+            CompareOp::Lt,
+            Expr::constant(c30.clone()),
+        ));
+        assert_eq!(
+            predicate.range_for("age"),
+            Some((Bound::Included(&c10), Bound::Excluded(&c30)))
+        );
+
+        // neither inequality, negated ranges nor comparisons with fields bound a field
+        let predicate = Predicate::new(Term::compare(
+            field(),
+            CompareOp::Ne,
+            Expr::constant(c10.clone()),
+        ))
+        .with_term(Term::between(
+            field(),
+            Expr::constant(c10),
+            Expr::constant(c30),
+            true,
+        ))
+        .with_term(Term::compare(
+            field(),
+            CompareOp::Lt,
+            Expr::field_name("salary"),
+        ));
+        assert_eq!(predicate.range_for("age"), None);
+    }
+
     #[test]
     fn test_select_sub_pred() {
         use crate::record::Schema;
@@ -184,4 +335,45 @@ mod tests {
         let sub_pred = empty_predicate.select_sub_pred(&schema);
         assert!(sub_pred.is_none());
     }
+
+    #[test]
+    fn test_join_sub_pred() {
+        let mut emp_schema = Schema::new();
+        emp_schema.add_int_field("eid");
+        emp_schema.add_int_field("edept");
+
+        let mut dept_schema = Schema::new();
+        dept_schema.add_int_field("did");
+        dept_schema.add_string_field("dname", 20);
+
+        // "edept = did and eid = 1 and dname = 'eng'"
+        let join_term = Term::new(Expr::field_name("edept"), Expr::field_name("did"));
+        let eid_term = Term::new(Expr::field_name("eid"), Expr::constant(Constant::int(1)));
+        let dname_term = Term::new(
+            Expr::field_name("dname"),
+            Expr::constant(Constant::string("eng")),
+        );
+        let predicate = Predicate::new(eid_term)
+            .with_term(join_term.clone())
+            .with_term(dname_term);
+
+        let join_pred = predicate.join_sub_pred(&emp_schema, &dept_schema);
+        assert_eq!(join_pred, Some(Predicate::new(join_term.clone())));
+        let join_pred = predicate.join_sub_pred(&dept_schema, &emp_schema);
+        assert_eq!(join_pred, Some(Predicate::new(join_term)));
+
+        // selection terms alone never form a join predicate
+        let mut other_schema = Schema::new();
+        other_schema.add_int_field("salary");
+        assert!(
+            predicate
+                .join_sub_pred(&emp_schema, &other_schema)
+                .is_none()
+        );
+        assert!(
+            Predicate::default()
+                .join_sub_pred(&emp_schema, &dept_schema)
+                .is_none()
+        );
+    }
 }
@@ -0,0 +1,164 @@
+use crate::error::DbResult;
+use crate::query::{Constant, Scan};
+
+/// The scan class corresponding to the product relational algebra operator.
+/// For every record of the left scan it iterates over all records of the right scan.
+/// Fields are looked up in the left scan first, so field names are expected to be
+/// distinct between the two scans.
+pub struct ProductScan<'a> {
+    s1: Box<dyn Scan + 'a>,
+    s2: Box<dyn Scan + 'a>,
+    lhs_valid: bool,
+}
+
+impl<'a> ProductScan<'a> {
+    pub fn new(s1: Box<dyn Scan + 'a>, s2: Box<dyn Scan + 'a>) -> Self {
+        ProductScan {
+            s1,
+            s2,
+            lhs_valid: false,
+        }
+    }
+}
+
+impl<'a> Scan for ProductScan<'a> {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.s1.before_first()?;
+        self.lhs_valid = false;
+        Ok(())
+    }
+
+    /// Moves to the next record of the right scan. Once it is exhausted, the left scan
+    /// advances and the right scan is rewound.
+    fn next(&mut self) -> DbResult<bool> {
+        loop {
+            if self.lhs_valid && self.s2.next()? {
+                return Ok(true);
+            }
+            self.lhs_valid = self.s1.next()?;
+            if !self.lhs_valid {
+                return Ok(false);
+            }
+            self.s2.before_first()?;
+        }
+    }
+
+    fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_int(field_name)
+        } else {
+            self.s2.get_int(field_name)
+        }
+    }
+
+    fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_string(field_name)
+        } else {
+            self.s2.get_string(field_name)
+        }
+    }
+
+    fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_val(field_name)
+        } else {
+            self.s2.get_val(field_name)
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.s1.has_field(field_name) || self.s2.has_field(field_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        query::UpdateScan,
+        record::{Layout, Schema, TableScan},
+        utils::testing_utils::temp_db,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_product_scan() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut dept_schema = Schema::new();
+        dept_schema.add_int_field("did");
+        dept_schema.add_string_field("dname", 10);
+        let mut emp_schema = Schema::new();
+        emp_schema.add_int_field("eid");
+        emp_schema.add_int_field("edept");
+
+        let mut dept_scan = TableScan::new(tx.clone(), "dept", Layout::new(dept_schema))?;
+        for (did, dname) in [(1, "eng"), (2, "sales")] {
+            dept_scan.insert()?;
+            dept_scan.set_int("did", did)?;
+            dept_scan.set_string("dname", dname)?;
+        }
+        let mut emp_scan = TableScan::new(tx.clone(), "emp", Layout::new(emp_schema))?;
+        for eid in 0..3 {
+            emp_scan.insert()?;
+            emp_scan.set_int("eid", eid)?;
+            emp_scan.set_int("edept", eid % 2 + 1)?;
+        }
+        dept_scan.before_first()?;
+        emp_scan.before_first()?;
+
+        let mut product = ProductScan::new(Box::new(dept_scan), Box::new(emp_scan));
+        assert!(product.has_field("dname"));
+        assert!(product.has_field("eid"));
+
+        let mut pairs = Vec::new();
+        while product.next()? {
+            pairs.push((product.get_int("did")?, product.get_int("eid")?));
+        }
+        assert_eq!(pairs, vec![(1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+
+        // the scan can be restarted
+        product.before_first()?;
+        assert!(product.next()?);
+        assert_eq!(product.get_string("dname")?, "eng");
+        assert_eq!(product.get_int("edept")?, 1);
+        drop(product);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_product_scan_empty_side() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema1 = Schema::new();
+        schema1.add_int_field("a");
+        let mut schema2 = Schema::new();
+        schema2.add_int_field("b");
+
+        let mut scan1 = TableScan::new(tx.clone(), "t1", Layout::new(schema1))?;
+        scan1.insert()?;
+        scan1.set_int("a", 1)?;
+        scan1.before_first()?;
+        let scan2 = TableScan::new(tx.clone(), "t2", Layout::new(schema2.clone()))?;
+
+        let mut product = ProductScan::new(Box::new(scan1), Box::new(scan2));
+        assert!(!product.next()?);
+        drop(product);
+
+        let scan1 = TableScan::new(tx.clone(), "t2", Layout::new(schema2))?;
+        let mut schema3 = Schema::new();
+        schema3.add_int_field("a");
+        let scan2 = TableScan::new(tx.clone(), "t1", Layout::new(schema3))?;
+        let mut product = ProductScan::new(Box::new(scan1), Box::new(scan2));
+        assert!(!product.next()?);
+        drop(product);
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -11,5 +11,11 @@ pub trait Scan {
     fn get_int(&mut self, field_name: &str) -> DbResult<i32>;
     fn get_string(&mut self, field_name: &str) -> DbResult<String>;
     fn get_val(&mut self, field_name: &str) -> DbResult<Constant>;
+
+    /// Reads the value of a BLOB field.
+    fn get_bytes(&mut self, field_name: &str) -> DbResult<Vec<u8>> {
+        self.get_val(field_name)?.bytes_value(field_name)
+    }
+
     fn has_field(&self, field_name: &str) -> bool;
 }
@@ -0,0 +1,277 @@
+use std::cmp::Ordering;
+
+use crate::error::DbResult;
+use crate::query::{Constant, Scan, UpdateScan};
+use crate::record::{RID, TableScan, TempTable};
+use crate::tx::Transaction;
+
+/// A field to sort by, in ascending or descending order.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SortField {
+    field_name: String,
+    descending: bool,
+}
+
+impl SortField {
+    pub fn asc(field_name: impl Into<String>) -> Self {
+        SortField {
+            field_name: field_name.into(),
+            descending: false,
+        }
+    }
+
+    pub fn desc(field_name: impl Into<String>) -> Self {
+        SortField {
+            field_name: field_name.into(),
+            descending: true,
+        }
+    }
+
+    pub fn field_name(&self) -> &str {
+        &self.field_name
+    }
+
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    /// Orders two values of the field according to the sort direction.
+    pub fn compare(&self, v1: &Constant, v2: &Constant) -> Ordering {
+        let ord = v1.compare_to(v2);
+        if self.descending { ord.reverse() } else { ord }
+    }
+}
+
+/// Compares records by the values of a list of fields, the first field being the most significant.
+#[derive(Clone)]
+pub struct RecordComparator {
+    fields: Vec<SortField>,
+}
+
+impl RecordComparator {
+    pub fn new(fields: Vec<SortField>) -> Self {
+        RecordComparator { fields }
+    }
+
+    pub fn fields(&self) -> &[SortField] {
+        &self.fields
+    }
+
+    /// Compares the current records of two scans.
+    pub fn compare(&self, s1: &mut dyn Scan, s2: &mut dyn Scan) -> DbResult<Ordering> {
+        for field in &self.fields {
+            let ord = field.compare(
+                &s1.get_val(field.field_name())?,
+                &s2.get_val(field.field_name())?,
+            );
+            if ord != Ordering::Equal {
+                return Ok(ord);
+            }
+        }
+        Ok(Ordering::Equal)
+    }
+}
+
+/// `SortScan` merges sorted runs stored in temporary tables.
+/// Each call to `next` returns the smallest of the current records of the runs.
+/// The runs are deleted when the scan is dropped.
+pub struct SortScan<'tx> {
+    tx: Transaction<'tx>,
+    runs: Vec<TempTable>,
+    scans: Vec<TableScan<'tx>>,
+    has_more: Vec<bool>,
+    current: Option<usize>,
+    comparator: RecordComparator,
+}
+
+/// Position of a [SortScan] saved with [SortScan::save_position].
+#[derive(Debug, Clone)]
+pub struct SortPosition {
+    rids: Vec<Option<RID>>,
+    current: Option<usize>,
+}
+
+impl<'tx> SortScan<'tx> {
+    pub fn new(
+        runs: Vec<TempTable>,
+        comparator: RecordComparator,
+        tx: Transaction<'tx>,
+    ) -> DbResult<Self> {
+        let scans = runs
+            .iter()
+            .map(|run| run.open(tx.clone()))
+            .collect::<DbResult<Vec<_>>>()?;
+        let mut scan = SortScan {
+            tx,
+            runs,
+            has_more: vec![false; scans.len()],
+            scans,
+            current: None,
+            comparator,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+
+    /// Saves the position of every run, so that the scan can return to the current record.
+    pub fn save_position(&self) -> DbResult<SortPosition> {
+        let mut rids = Vec::with_capacity(self.scans.len());
+        for (scan, has_more) in self.scans.iter().zip(&self.has_more) {
+            rids.push(if *has_more {
+                Some(scan.get_rid()?)
+            } else {
+                None
+            });
+        }
+        Ok(SortPosition {
+            rids,
+            current: self.current,
+        })
+    }
+
+    pub fn restore_position(&mut self, pos: &SortPosition) -> DbResult<()> {
+        for (i, rid) in pos.rids.iter().enumerate() {
+            self.has_more[i] = rid.is_some();
+            if let Some(rid) = rid {
+                self.scans[i].move_to_rid(*rid)?;
+            }
+        }
+        self.current = pos.current;
+        Ok(())
+    }
+
+    fn current_scan(&mut self) -> &mut TableScan<'tx> {
+        let current = self.current.expect("No current record");
+        &mut self.scans[current]
+    }
+}
+
+impl<'tx> Scan for SortScan<'tx> {
+    /// Positions every run on its first record.
+    fn before_first(&mut self) -> DbResult<()> {
+        self.current = None;
+        for (scan, has_more) in self.scans.iter_mut().zip(self.has_more.iter_mut()) {
+            scan.before_first()?;
+            *has_more = scan.next()?;
+        }
+        Ok(())
+    }
+
+    /// Advances the run of the current record and picks the smallest record among all runs.
+    fn next(&mut self) -> DbResult<bool> {
+        if let Some(current) = self.current {
+            self.has_more[current] = self.scans[current].next()?;
+        }
+
+        self.current = None;
+        for i in 0..self.scans.len() {
+            if !self.has_more[i] {
+                continue;
+            }
+            let is_smaller = match self.current {
+                None => true,
+                Some(current) => {
+                    let (s1, s2) = if i < current {
+                        let (left, right) = self.scans.split_at_mut(current);
+                        (&mut left[i], &mut right[0])
+                    } else {
+                        let (left, right) = self.scans.split_at_mut(i);
+                        (&mut right[0], &mut left[current])
+                    };
+                    self.comparator.compare(s1, s2)? == Ordering::Less
+                }
+            };
+            if is_smaller {
+                self.current = Some(i);
+            }
+        }
+        Ok(self.current.is_some())
+    }
+
+    fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
+        self.current_scan().get_int(field_name)
+    }
+
+    fn get_string(&mut self, field_name: &str) -> DbResult<String> {
+        self.current_scan().get_string(field_name)
+    }
+
+    fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
+        self.current_scan().get_val(field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.scans
+            .first()
+            .is_some_and(|scan| scan.has_field(field_name))
+    }
+}
+
+impl<'tx> Drop for SortScan<'tx> {
+    fn drop(&mut self) {
+        self.scans.clear();
+        for run in &self.runs {
+            let _ = run.delete(&self.tx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{record::Schema, utils::testing_utils::temp_db};
+
+    #[test]
+    fn test_sort_scan_merges_runs() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("a");
+        schema.add_int_field("b");
+
+        let run_values = [vec![1, 4, 7, 7], vec![2, 3, 9], vec![], vec![0, 7, 8]];
+        let mut runs = Vec::new();
+        for values in &run_values {
+            let run = TempTable::new(schema.clone());
+            let mut scan = run.open(tx.clone())?;
+            for (i, a) in values.iter().enumerate() {
+                scan.insert()?;
+                scan.set_int("a", *a)?;
+                scan.set_int("b", i as i32)?;
+            }
+            runs.push(run);
+        }
+
+        let comparator = RecordComparator::new(vec![SortField::asc("a")]);
+        let mut sort_scan = SortScan::new(runs.clone(), comparator, tx.clone())?;
+        assert!(sort_scan.has_field("b"));
+
+        let mut values = Vec::new();
+        let mut saved = None;
+        while sort_scan.next()? {
+            let a = sort_scan.get_int("a")?;
+            if a == 7 && saved.is_none() {
+                saved = Some(sort_scan.save_position()?);
+            }
+            values.push(a);
+        }
+        assert_eq!(values, vec![0, 1, 2, 3, 4, 7, 7, 7, 8, 9]);
+
+        sort_scan.restore_position(&saved.unwrap())?;
+        assert_eq!(sort_scan.get_int("a")?, 7);
+        let mut rest = Vec::new();
+        while sort_scan.next()? {
+            rest.push(sort_scan.get_int("a")?);
+        }
+        assert_eq!(rest, vec![7, 7, 8, 9]);
+
+        sort_scan.before_first()?;
+        assert!(sort_scan.next()?);
+        assert_eq!(sort_scan.get_int("a")?, 0);
+        drop(sort_scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -1,62 +1,419 @@
-use crate::error::DbResult;
+use std::cmp::Ordering;
+use std::ops::Bound;
+
+use crate::error::{DbError, DbResult};
 use crate::query::{Constant, Scan};
 use crate::record::Schema;
 
 use super::Expr;
 
-/// Represents a term that compares two expressions for equality.
+/// Comparison operator of a [Term::Compare].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    /// Returns None, standing for unknown, if either value is null.
+    fn holds(&self, lhs: &Constant, rhs: &Constant) -> DbResult<Option<bool>> {
+        if lhs.is_null() || rhs.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(match self {
+            CompareOp::Eq => lhs == rhs,
+            CompareOp::Ne => lhs != rhs,
+            CompareOp::Lt => compare(lhs, rhs)? == Ordering::Less,
+            CompareOp::Le => compare(lhs, rhs)? != Ordering::Greater,
+            CompareOp::Gt => compare(lhs, rhs)? == Ordering::Greater,
+            CompareOp::Ge => compare(lhs, rhs)? != Ordering::Less,
+        }))
+    }
+}
+
+impl std::fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "<>",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// Orders two values, failing if they are of types which can't be compared.
+fn compare(lhs: &Constant, rhs: &Constant) -> DbResult<Ordering> {
+    if !lhs.is_comparable_with(rhs) {
+        return Err(DbError::Schema(format!(
+            "Cannot compare {} with {}",
+            lhs.to_string(),
+            rhs.to_string()
+        )));
+    }
+    Ok(lhs.compare_to(rhs))
+}
+
+/// A boolean condition over the fields of a record. A [super::Predicate] is a conjunction of terms;
+/// a term is either a comparison or a combination of other terms.
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Term {
-    lhs: Expr,
-    rhs: Expr,
+pub enum Term {
+    Compare {
+        lhs: Expr,
+        op: CompareOp,
+        rhs: Expr,
+    },
+    And(Vec<Term>),
+    Or(Vec<Term>),
+    Not(Box<Term>),
+    InList {
+        expr: Expr,
+        list: Vec<Expr>,
+        negated: bool,
+    },
+    Between {
+        expr: Expr,
+        low: Expr,
+        high: Expr,
+        negated: bool,
+    },
+    IsNull {
+        expr: Expr,
+        negated: bool,
+    },
 }
 
 impl Term {
+    /// Creates a term that compares two expressions for equality.
     pub fn new(lhs: Expr, rhs: Expr) -> Self {
-        Term { lhs, rhs }
+        Term::compare(lhs, CompareOp::Eq, rhs)
+    }
+
+    pub fn compare(lhs: Expr, op: CompareOp, rhs: Expr) -> Self {
+        Term::Compare { lhs, op, rhs }
     }
 
+    /// Creates the conjunction of the terms. Nested conjunctions are flattened.
+    pub fn and(terms: Vec<Term>) -> Self {
+        let mut flattened = Vec::with_capacity(terms.len());
+        for term in terms {
+            match term {
+                Term::And(inner) => flattened.extend(inner),
+                term => flattened.push(term),
+            }
+        }
+        Term::And(flattened)
+    }
+
+    /// Creates the disjunction of the terms. Nested disjunctions are flattened.
+    pub fn or(terms: Vec<Term>) -> Self {
+        let mut flattened = Vec::with_capacity(terms.len());
+        for term in terms {
+            match term {
+                Term::Or(inner) => flattened.extend(inner),
+                term => flattened.push(term),
+            }
+        }
+        Term::Or(flattened)
+    }
+
+    pub fn negate(term: Term) -> Self {
+        Term::Not(Box::new(term))
+    }
+
+    pub fn in_list(expr: Expr, list: Vec<Expr>, negated: bool) -> Self {
+        Term::InList {
+            expr,
+            list,
+            negated,
+        }
+    }
+
+    pub fn between(expr: Expr, low: Expr, high: Expr, negated: bool) -> Self {
+        Term::Between {
+            expr,
+            low,
+            high,
+            negated,
+        }
+    }
+
+    pub fn is_null(expr: Expr, negated: bool) -> Self {
+        Term::IsNull { expr, negated }
+    }
+
+    /// A record satisfies the term only if it evaluates to true, not if it is unknown.
     pub fn is_satisfied(&self, scan: &mut dyn Scan) -> DbResult<bool> {
-        let lhs_val = self.lhs.evaluate(scan)?;
-        let rhs_val = self.rhs.evaluate(scan)?;
-        Ok(lhs_val == rhs_val)
+        Ok(self.evaluate(scan)? == Some(true))
+    }
+
+    /// Evaluates the term with three-valued logic: a comparison involving a null value
+    /// is unknown (None) rather than true or false.
+    fn evaluate(&self, scan: &mut dyn Scan) -> DbResult<Option<bool>> {
+        match self {
+            Term::Compare { lhs, op, rhs } => {
+                let lhs_val = lhs.evaluate(scan)?;
+                let rhs_val = rhs.evaluate(scan)?;
+                op.holds(&lhs_val, &rhs_val)
+            }
+            Term::And(terms) => {
+                let mut result = Some(true);
+                for term in terms {
+                    match term.evaluate(scan)? {
+                        Some(false) => return Ok(Some(false)),
+                        None => result = None,
+                        Some(true) => {}
+                    }
+                }
+                Ok(result)
+            }
+            Term::Or(terms) => {
+                let mut result = Some(false);
+                for term in terms {
+                    match term.evaluate(scan)? {
+                        Some(true) => return Ok(Some(true)),
+                        None => result = None,
+                        Some(false) => {}
+                    }
+                }
+                Ok(result)
+            }
+            Term::Not(term) => Ok(term.evaluate(scan)?.map(|satisfied| !satisfied)),
+            Term::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let val = expr.evaluate(scan)?;
+                if val.is_null() {
+                    return Ok(None);
+                }
+                // without a match, a null in the list makes the result unknown
+                let mut result = Some(*negated);
+                for item in list {
+                    let item_val = item.evaluate(scan)?;
+                    if item_val.is_null() {
+                        result = None;
+                    } else if item_val == val {
+                        return Ok(Some(!*negated));
+                    }
+                }
+                Ok(result)
+            }
+            Term::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let val = expr.evaluate(scan)?;
+                let above_low = CompareOp::Ge.holds(&val, &low.evaluate(scan)?)?;
+                let below_high = CompareOp::Le.holds(&val, &high.evaluate(scan)?)?;
+                let in_range = match (above_low, below_high) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                };
+                Ok(in_range.map(|in_range| in_range != *negated))
+            }
+            Term::IsNull { expr, negated } => {
+                let val = expr.evaluate(scan)?;
+                Ok(Some(val.is_null() != *negated))
+            }
+        }
     }
 
     pub fn applies_to(&self, sch: &Schema) -> bool {
-        self.lhs.applies_to(sch) && self.rhs.applies_to(sch)
+        match self {
+            Term::Compare { lhs, rhs, .. } => lhs.applies_to(sch) && rhs.applies_to(sch),
+            Term::And(terms) | Term::Or(terms) => terms.iter().all(|term| term.applies_to(sch)),
+            Term::Not(term) => term.applies_to(sch),
+            Term::InList { expr, list, .. } => {
+                expr.applies_to(sch) && list.iter().all(|item| item.applies_to(sch))
+            }
+            Term::Between {
+                expr, low, high, ..
+            } => expr.applies_to(sch) && low.applies_to(sch) && high.applies_to(sch),
+            Term::IsNull { expr, .. } => expr.applies_to(sch),
+        }
+    }
+
+    /// Returns true if the term refers to the specified field.
+    pub fn mentions(&self, fldname: &str) -> bool {
+        let is_field = |expr: &Expr| expr.as_field_name() == Some(fldname);
+        match self {
+            Term::Compare { lhs, rhs, .. } => is_field(lhs) || is_field(rhs),
+            Term::And(terms) | Term::Or(terms) => terms.iter().any(|term| term.mentions(fldname)),
+            Term::Not(term) => term.mentions(fldname),
+            Term::InList { expr, list, .. } => is_field(expr) || list.iter().any(is_field),
+            Term::Between {
+                expr, low, high, ..
+            } => is_field(expr) || is_field(low) || is_field(high),
+            Term::IsNull { expr, .. } => is_field(expr),
+        }
     }
 
     /// Returns the constant value if this term equates the specified field with a constant.
     /// Returns None if the term doesn't equate the field with a constant.
     pub fn equates_with_constant(&self, fldname: &str) -> Option<&Constant> {
-        if let Some(lhs_field) = self.lhs.as_field_name()
-            && lhs_field == fldname
-            && !self.rhs.is_field_name()
-        {
-            return self.rhs.as_constant();
-        }
+        let Term::Compare {
+            lhs,
+            op: CompareOp::Eq,
+            rhs,
+        } = self
+        else {
+            return None;
+        };
+
+        let val = if lhs.as_field_name() == Some(fldname) {
+            rhs.as_constant()
+        } else if rhs.as_field_name() == Some(fldname) {
+            lhs.as_constant()
+        } else {
+            None
+        };
+        // `field = null` matches no record, so there is nothing to look up
+        val.filter(|val| !val.is_null())
+    }
 
-        if let Some(rhs_field) = self.rhs.as_field_name()
-            && rhs_field == fldname
-            && !self.lhs.is_field_name()
-        {
-            return self.lhs.as_constant();
+    /// Returns the lower and upper bound this term puts on the specified field, if it compares
+    /// the field with constants, as in `age > 30`, `30 <= age` or `age between 20 and 30`.
+    /// Returns None if the term doesn't restrict the field to a range.
+    pub fn bounds_for(&self, fldname: &str) -> Option<(Bound<&Constant>, Bound<&Constant>)> {
+        match self {
+            Term::Compare { lhs, op, rhs } => {
+                let (val, op) = if lhs.as_field_name() == Some(fldname) {
+                    (rhs.as_constant()?, *op)
+                } else if rhs.as_field_name() == Some(fldname) {
+                    // `c < field` bounds the field like `field > c`
+                    let flipped = match op {
+                        CompareOp::Lt => CompareOp::Gt,
+                        CompareOp::Le => CompareOp::Ge,
+                        CompareOp::Gt => CompareOp::Lt,
+                        CompareOp::Ge => CompareOp::Le,
+                        op => *op,
+                    };
+                    (lhs.as_constant()?, flipped)
+                } else {
+                    return None;
+                };
+                if val.is_null() {
+                    return None;
+                }
+                match op {
+                    CompareOp::Eq => Some((Bound::Included(val), Bound::Included(val))),
+                    CompareOp::Ne => None,
+                    CompareOp::Lt => Some((Bound::Unbounded, Bound::Excluded(val))),
+                    CompareOp::Le => Some((Bound::Unbounded, Bound::Included(val))),
+                    CompareOp::Gt => Some((Bound::Excluded(val), Bound::Unbounded)),
+                    CompareOp::Ge => Some((Bound::Included(val), Bound::Unbounded)),
+                }
+            }
+            Term::Between {
+                expr,
+                low,
+                high,
+                negated: false,
+            } if expr.as_field_name() == Some(fldname) => {
+                let (low, high) = (low.as_constant()?, high.as_constant()?);
+                if low.is_null() || high.is_null() {
+                    return None;
+                }
+                Some((Bound::Included(low), Bound::Included(high)))
+            }
+            _ => None,
         }
+    }
+
+    /// Returns the name of the other field if this term equates the specified field with a field.
+    /// Returns None if the term doesn't equate the field with another field.
+    pub fn equates_with_field(&self, fldname: &str) -> Option<&str> {
+        let Term::Compare {
+            lhs,
+            op: CompareOp::Eq,
+            rhs,
+        } = self
+        else {
+            return None;
+        };
 
+        if let (Some(lhs_field), Some(rhs_field)) = (lhs.as_field_name(), rhs.as_field_name()) {
+            if lhs_field == fldname {
+                return Some(rhs_field);
+            }
+            if rhs_field == fldname {
+                return Some(lhs_field);
+            }
+        }
         None
     }
 }
 
+fn write_joined(
+    f: &mut std::fmt::Formatter<'_>,
+    items: &[impl std::fmt::Display],
+    separator: &str,
+) -> std::fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, "{}", separator)?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
 impl std::fmt::Display for Term {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}={}", self.lhs, self.rhs)
+        let not = |negated: &bool| if *negated { "not " } else { "" };
+        match self {
+            Term::Compare { lhs, op, rhs } => write!(f, "{}{}{}", lhs, op, rhs),
+            Term::And(terms) => {
+                write!(f, "(")?;
+                write_joined(f, terms, " and ")?;
+                write!(f, ")")
+            }
+            Term::Or(terms) => {
+                write!(f, "(")?;
+                write_joined(f, terms, " or ")?;
+                write!(f, ")")
+            }
+            Term::Not(term) => write!(f, "not {}", term),
+            Term::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                write!(f, "{} {}in (", expr, not(negated))?;
+                write_joined(f, list, ", ")?;
+                write!(f, ")")
+            }
+            Term::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => write!(f, "{} {}between {} and {}", expr, not(negated), low, high),
+            Term::IsNull { expr, negated } => write!(f, "{} is {}null", expr, not(negated)),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::query::{Constant, Expr};
+    use crate::{
+        query::{Constant, Expr, UpdateScan},
+        record::{Layout, TableScan},
+        utils::testing_utils::temp_db,
+    };
 
     #[test]
     fn test_equates_with_constant() {
@@ -96,4 +453,163 @@ mod tests {
             Some(&Constant::string("Bob"))
         );
     }
+
+    #[test]
+    fn test_equates_with_field() {
+        let term = Term::new(Expr::field_name("edept"), Expr::field_name("did"));
+        assert_eq!(term.equates_with_field("edept"), Some("did"));
+        assert_eq!(term.equates_with_field("did"), Some("edept"));
+        assert_eq!(term.equates_with_field("eid"), None);
+
+        let term = Term::new(Expr::field_name("did"), Expr::constant(Constant::int(10)));
+        assert_eq!(term.equates_with_field("did"), None);
+
+        let term = Term::compare(
+            Expr::field_name("edept"),
+            CompareOp::Lt,
+            Expr::field_name("did"),
+        );
+        assert_eq!(term.equates_with_field("edept"), None);
+    }
+
+    #[test]
+    fn test_is_satisfied() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("a");
+        schema.add_string_field("b", 10);
+        let mut scan = TableScan::new(tx.clone(), "test_table", Layout::new(schema.clone()))?;
+        scan.insert()?;
+        scan.set_int("a", 5)?;
+        scan.set_string("b", "mid")?;
+
+        let a = || Expr::field_name("a");
+        let int = |i: i32| Expr::constant(Constant::int(i));
+        let cases = [
+            (Term::compare(a(), CompareOp::Lt, int(6)), true),
+            (Term::compare(a(), CompareOp::Le, int(5)), true),
+            (Term::compare(a(), CompareOp::Gt, int(5)), false),
+            (Term::compare(a(), CompareOp::Ge, int(5)), true),
+            (Term::compare(a(), CompareOp::Ne, int(5)), false),
+            (
+                Term::compare(
+                    Expr::field_name("b"),
+                    CompareOp::Gt,
+                    Expr::constant(Constant::string("low")),
+                ),
+                true,
+            ),
+            (
+                Term::or(vec![Term::new(a(), int(1)), Term::new(a(), int(5))]),
+                true,
+            ),
+            (
+                Term::and(vec![Term::new(a(), int(1)), Term::new(a(), int(5))]),
+                false,
+            ),
+            (Term::negate(Term::new(a(), int(1))), true),
+            (Term::in_list(a(), vec![int(1), int(5)], false), true),
+            (Term::in_list(a(), vec![int(1), int(5)], true), false),
+            (Term::between(a(), int(5), int(7), false), true),
+            (Term::between(a(), int(6), int(7), false), false),
+            (Term::between(a(), int(6), int(7), true), true),
+            (Term::is_null(a(), false), false),
+            (Term::is_null(a(), true), true),
+        ];
+        for (term, expected) in cases {
+            assert_eq!(term.is_satisfied(&mut scan)?, expected, "{term}");
+            assert!(term.applies_to(&schema));
+        }
+
+        // numbers of different types compare by value
+        let big = Expr::constant(Constant::BigInt(5));
+        assert!(Term::new(a(), big.clone()).is_satisfied(&mut scan)?);
+        let half = Expr::constant(Constant::Double(5.5));
+        assert!(Term::compare(a(), CompareOp::Lt, half).is_satisfied(&mut scan)?);
+
+        let mismatched = Term::compare(a(), CompareOp::Lt, Expr::constant(Constant::string("x")));
+        assert!(mismatched.is_satisfied(&mut scan).is_err());
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_satisfied_with_nulls() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("a");
+        schema.add_int_field("b");
+        let mut scan = TableScan::new(tx.clone(), "test_table", Layout::new(schema))?;
+        scan.insert()?;
+        scan.set_int("a", 5)?;
+        scan.set_val("b", Constant::Null)?;
+
+        let a = || Expr::field_name("a");
+        let b = || Expr::field_name("b");
+        let int = |i: i32| Expr::constant(Constant::int(i));
+        let null = || Expr::constant(Constant::Null);
+        let cases = [
+            (Term::new(b(), int(1)), None),
+            (Term::compare(b(), CompareOp::Ne, int(1)), None),
+            (Term::new(b(), null()), None),
+            (Term::new(a(), null()), None),
+            (Term::negate(Term::new(b(), int(1))), None),
+            (
+                Term::and(vec![Term::new(a(), int(5)), Term::new(b(), int(1))]),
+                None,
+            ),
+            (
+                Term::and(vec![Term::new(a(), int(1)), Term::new(b(), int(1))]),
+                Some(false),
+            ),
+            (
+                Term::or(vec![Term::new(a(), int(5)), Term::new(b(), int(1))]),
+                Some(true),
+            ),
+            (
+                Term::or(vec![Term::new(a(), int(1)), Term::new(b(), int(1))]),
+                None,
+            ),
+            (Term::in_list(b(), vec![int(1)], false), None),
+            (Term::in_list(a(), vec![int(5), null()], false), Some(true)),
+            (Term::in_list(a(), vec![int(1), null()], false), None),
+            (Term::in_list(a(), vec![int(1), null()], true), None),
+            (Term::between(b(), int(1), int(7), false), None),
+            (Term::between(a(), int(6), null(), false), Some(false)),
+            (Term::between(a(), int(1), null(), true), None),
+            (Term::is_null(b(), false), Some(true)),
+            (Term::is_null(b(), true), Some(false)),
+            (Term::is_null(a(), false), Some(false)),
+        ];
+        for (term, expected) in cases {
+            assert_eq!(term.evaluate(&mut scan)?, expected, "{term}");
+            assert_eq!(term.is_satisfied(&mut scan)?, expected == Some(true));
+        }
+        drop(scan);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_constants_do_not_bound_fields() {
+        let null = || Expr::constant(Constant::Null);
+        let term = Term::new(Expr::field_name("a"), null());
+        assert_eq!(term.equates_with_constant("a"), None);
+        assert_eq!(term.bounds_for("a"), None);
+
+        let term = Term::between(
+            Expr::field_name("a"),
+            Expr::constant(Constant::int(1)),
+            null(),
+            false,
+        );
+        assert_eq!(term.bounds_for("a"), None);
+    }
 }
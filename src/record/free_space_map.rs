@@ -0,0 +1,144 @@
+use crate::error::DbResult;
+use crate::storage::BlockId;
+use crate::tx::Transaction;
+
+const INT_BYTES: usize = std::mem::size_of::<i32>();
+
+/// Tracks which blocks of a table have room for another record, so that inserts reuse
+/// the space of deleted records instead of growing the table. The file holds a word per
+/// block of the table, 1 if the block has room. Blocks past the end of the file, such as
+/// blocks appended for inserts, are assumed to be full.
+///
+/// The map is a hint: a block is listed when a record is deleted from it and unlisted
+/// when an insert into it fails. Its blocks are shared by all the transactions using the
+/// table, so they are only latched while an entry is read or written, and the entries are
+/// not logged. An entry left stale by a rollback or a crash costs a failed insert or
+/// some unused space, nothing more.
+pub struct FreeSpaceMap<'tx> {
+    tx: Transaction<'tx>,
+    file_name: String,
+}
+
+impl<'tx> FreeSpaceMap<'tx> {
+    /// Opens the free space map of the table stored in the given file, `<table>.fsm`.
+    pub fn new(tx: Transaction<'tx>, table_file_name: &str) -> Self {
+        let table_name = table_file_name
+            .strip_suffix(".tbl")
+            .unwrap_or(table_file_name);
+        FreeSpaceMap {
+            tx,
+            file_name: Self::file_name(table_name),
+        }
+    }
+
+    pub fn file_name(table_name: &str) -> String {
+        format!("{}.fsm", table_name)
+    }
+
+    /// Records whether the block of the table has room for another record.
+    /// The entry is only written if it changes.
+    pub fn set_free(&self, blk_num: i32, free: bool) -> DbResult<()> {
+        let (blk, pos) = self.entry(blk_num);
+        if !free && blk.number() >= self.tx.size_unlocked(&self.file_name)? {
+            return Ok(());
+        }
+        // other transactions may grow the map meanwhile
+        while self.tx.size_unlocked(&self.file_name)? <= blk.number() {
+            self.tx.append_unlocked(&self.file_name)?;
+        }
+
+        self.tx.pin(&blk)?;
+        if (self.tx.get_int_unlocked(&blk, pos) != 0) != free {
+            self.tx.set_int_unlocked(&blk, pos, free as i32);
+        }
+        self.tx.unpin(&blk);
+        Ok(())
+    }
+
+    /// Returns the first block of the table listed as having room for another record.
+    pub fn find_free(&self) -> DbResult<Option<i32>> {
+        let entries_per_block = self.entries_per_block();
+        for blk_num in 0..self.tx.size_unlocked(&self.file_name)? {
+            let blk = BlockId::new(self.file_name.clone(), blk_num);
+            self.tx.pin(&blk)?;
+            for i in 0..entries_per_block {
+                if self.tx.get_int_unlocked(&blk, i * INT_BYTES) != 0 {
+                    self.tx.unpin(&blk);
+                    return Ok(Some(blk_num * entries_per_block as i32 + i as i32));
+                }
+            }
+            self.tx.unpin(&blk);
+        }
+        Ok(None)
+    }
+
+    /// Returns the block of the map and the position within it of the entry of a table block.
+    fn entry(&self, blk_num: i32) -> (BlockId, usize) {
+        let entries_per_block = self.entries_per_block() as i32;
+        let blk = BlockId::new(self.file_name.clone(), blk_num / entries_per_block);
+        let pos = (blk_num % entries_per_block) as usize * INT_BYTES;
+        (blk, pos)
+    }
+
+    fn entries_per_block(&self) -> usize {
+        self.tx.block_size() / INT_BYTES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing_utils::temp_db;
+
+    #[test]
+    fn test_free_space_map() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let map = FreeSpaceMap::new(tx.clone(), "test_table.tbl");
+
+        assert_eq!(map.find_free()?, None);
+        // unlisting a block past the end of the map does not grow it
+        map.set_free(3, false)?;
+        assert_eq!(tx.size("test_table.fsm")?, 0);
+
+        let far = 2 * map.entries_per_block() as i32 + 5;
+        map.set_free(far, true)?;
+        assert_eq!(tx.size("test_table.fsm")?, 3);
+        assert_eq!(map.find_free()?, Some(far));
+        map.set_free(7, true)?;
+        assert_eq!(map.find_free()?, Some(7));
+        map.set_free(7, false)?;
+        map.set_free(far, false)?;
+        assert_eq!(map.find_free()?, None);
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_free_space_map_is_shared_and_not_rolled_back() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx1 = db.new_tx()?;
+        let map1 = FreeSpaceMap::new(tx1.clone(), "test_table.tbl");
+        map1.set_free(4, true)?;
+
+        // the entry is not locked, so another transaction sees and changes it right away
+        let tx2 = db.new_tx()?;
+        let map2 = FreeSpaceMap::new(tx2.clone(), "test_table.tbl");
+        assert_eq!(map2.find_free()?, Some(4));
+        map2.set_free(9, true)?;
+        assert_eq!(map1.find_free()?, Some(4));
+        map1.set_free(4, false)?;
+        assert_eq!(map2.find_free()?, Some(9));
+
+        // the entries are not logged, so the rollback leaves them as they are
+        tx1.rollback()?;
+        tx2.rollback()?;
+        let tx = db.new_tx()?;
+        assert_eq!(
+            FreeSpaceMap::new(tx.clone(), "test_table.tbl").find_free()?,
+            Some(9)
+        );
+        tx.commit()?;
+        Ok(())
+    }
+}
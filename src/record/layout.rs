@@ -5,38 +5,77 @@ use std::collections::HashMap;
 /// Description of the structure of a record.
 /// It contains the name, type, length and offset of
 /// each field of the table.
+/// A slot starts with the in-use flag, followed by a null bitmap with one bit per field
+/// and then the fields. The bits are assigned to the fields in the order of their offsets.
 #[derive(Debug, Clone)]
 pub struct Layout {
     schema: Schema,
     offsets: HashMap<String, usize>,
+    null_bits: HashMap<String, usize>,
     slot_size: usize,
+    format: RecordFormat,
 }
 
+/// How the records of a table are stored in its blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Every record takes a slot of `slot_size` bytes at the offsets of the layout,
+    /// see [super::FixedRecordPage].
+    Fixed = 0,
+    /// Records take only the bytes of their values and are found through a slot directory,
+    /// see [super::SlottedRecordPage]. The offsets of the layout only give the order of the fields.
+    Slotted = 1,
+}
+
+impl From<i32> for RecordFormat {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => RecordFormat::Fixed,
+            1 => RecordFormat::Slotted,
+            _ => panic!("Invalid record format"),
+        }
+    }
+}
+
+const INT_BYTES: usize = std::mem::size_of::<i32>();
+const BITS_PER_INT: usize = 32;
+
 impl Layout {
+    /// The slot layout is part of the on-disk format, see [crate::metadata::TableMgr::FORMAT_VERSION].
     pub fn new(schema: Schema) -> Self {
         let mut offsets = HashMap::new();
-        let mut pos = std::mem::size_of::<i32>();
+        let mut pos = INT_BYTES + Self::null_bitmap_size(&schema);
 
         for field_name in schema.fields() {
             offsets.insert(field_name.clone(), pos);
             pos += Self::length_in_bytes(&schema, field_name);
         }
 
-        Layout {
-            schema,
-            offsets,
-            slot_size: pos,
-        }
+        Self::with_offsets(schema, offsets, pos)
     }
 
     pub fn with_offsets(schema: Schema, offsets: HashMap<String, usize>, slot_size: usize) -> Self {
+        let mut fields: Vec<_> = offsets.iter().collect();
+        fields.sort_by_key(|(_, offset)| **offset);
+        let null_bits = fields
+            .into_iter()
+            .enumerate()
+            .map(|(bit, (field_name, _))| (field_name.clone(), bit))
+            .collect();
         Layout {
             schema,
             offsets,
+            null_bits,
             slot_size,
+            format: RecordFormat::Fixed,
         }
     }
 
+    pub fn with_format(mut self, format: RecordFormat) -> Self {
+        self.format = format;
+        self
+    }
+
     pub fn schema(&self) -> &Schema {
         &self.schema
     }
@@ -49,9 +88,35 @@ impl Layout {
         self.slot_size
     }
 
+    pub fn format(&self) -> RecordFormat {
+        self.format
+    }
+
+    /// Returns the offset of the bitmap word holding the null bit of the field,
+    /// and the mask of the bit within that word.
+    pub fn null_bit(&self, field_name: &str) -> Option<(usize, i32)> {
+        self.null_bits.get(field_name).map(|bit| {
+            let word = INT_BYTES + (bit / BITS_PER_INT) * INT_BYTES;
+            (word, 1 << (bit % BITS_PER_INT))
+        })
+    }
+
+    /// Returns the offsets of the words of the null bitmap.
+    pub fn null_bitmap_offsets(&self) -> impl Iterator<Item = usize> {
+        let words = self.null_bits.len().div_ceil(BITS_PER_INT);
+        (0..words).map(|word| INT_BYTES + word * INT_BYTES)
+    }
+
+    fn null_bitmap_size(schema: &Schema) -> usize {
+        schema.fields().len().div_ceil(BITS_PER_INT) * INT_BYTES
+    }
+
     fn length_in_bytes(schema: &Schema, field_name: &str) -> usize {
         match schema.field_type(field_name).expect("Field not found") {
-            FieldType::Integer => std::mem::size_of::<i32>(),
+            FieldType::Integer | FieldType::Boolean | FieldType::Date => INT_BYTES,
+            FieldType::BigInt | FieldType::Double | FieldType::Timestamp => 2 * INT_BYTES,
+            //  the first block and length of the value in the overflow file
+            FieldType::Text | FieldType::Blob => 2 * INT_BYTES,
             FieldType::Varchar => {
                 Page::max_length(schema.length(field_name).expect("Field length not found"))
             }
@@ -71,11 +136,47 @@ mod tests {
 
         let layout = Layout::new(schema);
 
-        assert_eq!(layout.offset("id"), Some(4));
+        // the flag and a single word of null bits come first
+        assert_eq!(layout.offset("id"), Some(8));
 
         let name_offset = layout.offset("name").unwrap();
-        assert!(name_offset > 4);
+        assert!(name_offset > 8);
 
         assert!(layout.slot_size() > name_offset);
+        assert_eq!(layout.null_bit("id"), Some((4, 1)));
+        assert_eq!(layout.null_bit("name"), Some((4, 2)));
+        assert_eq!(layout.null_bitmap_offsets().collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn test_layout_fixed_width_types() {
+        let mut schema = Schema::new();
+        schema.add_field("big", FieldType::BigInt, 0);
+        schema.add_field("flag", FieldType::Boolean, 0);
+        schema.add_field("ratio", FieldType::Double, 0);
+        schema.add_field("day", FieldType::Date, 0);
+        schema.add_field("at", FieldType::Timestamp, 0);
+        let layout = Layout::new(schema);
+
+        assert_eq!(layout.offset("big"), Some(8));
+        assert_eq!(layout.offset("flag"), Some(16));
+        assert_eq!(layout.offset("ratio"), Some(20));
+        assert_eq!(layout.offset("day"), Some(28));
+        assert_eq!(layout.offset("at"), Some(32));
+        assert_eq!(layout.slot_size(), 40);
+    }
+
+    #[test]
+    fn test_layout_null_bitmap_spans_words() {
+        let mut schema = Schema::new();
+        for i in 0..40 {
+            schema.add_int_field(&format!("f{i}"));
+        }
+        let layout = Layout::new(schema);
+
+        assert_eq!(layout.offset("f0"), Some(12));
+        assert_eq!(layout.null_bit("f31"), Some((4, i32::MIN)));
+        assert_eq!(layout.null_bit("f32"), Some((8, 1)));
+        assert_eq!(layout.null_bitmap_offsets().collect::<Vec<_>>(), vec![4, 8]);
     }
 }
@@ -1,11 +1,18 @@
+pub mod free_space_map;
 pub mod layout;
+pub mod overflow;
 pub mod record_page;
 pub mod rid;
 pub mod schema;
+pub mod slotted_record_page;
 pub mod table_scan;
+pub mod temp_table;
 
-pub use layout::Layout;
-pub use record_page::RecordPage;
+pub use free_space_map::FreeSpaceMap;
+pub use layout::{Layout, RecordFormat};
+pub use record_page::{FixedRecordPage, RecordPage};
 pub use rid::RID;
 pub use schema::Schema;
+pub use slotted_record_page::SlottedRecordPage;
 pub use table_scan::TableScan;
+pub use temp_table::TempTable;
@@ -0,0 +1,387 @@
+use super::schema::FieldType;
+use crate::error::DbResult;
+use crate::query::Constant;
+use crate::storage::BlockId;
+use crate::tx::Transaction;
+
+const INT_BYTES: usize = std::mem::size_of::<i32>();
+
+/// Holds the values of the TEXT and BLOB fields of a table, which may be larger than a block.
+/// A value is stored in a chain of blocks, each starting with the number of the next block,
+/// and its field holds the number of the first block followed by the length of the value.
+/// Block 0 holds the head of the list of freed blocks, which are reused before the file grows.
+/// Block number 0 ends a chain and the free list, and is the first block of an empty value.
+///
+/// The free list is shared by all the transactions writing values of the table, so it is
+/// only changed by system transactions which commit right away, instead of being locked
+/// until the writing transaction finishes. A chain freed by a transaction is put on the list
+/// once it commits, and the blocks taken by a transaction are put back if it rolls back,
+/// so the blocks of a value are not reused while a rollback may still restore it.
+pub struct OverflowFile<'tx> {
+    tx: Transaction<'tx>,
+    file_name: String,
+}
+
+impl<'tx> OverflowFile<'tx> {
+    /// Opens the overflow file of the table stored in the given file, `<table>.ovf`.
+    pub fn new(tx: Transaction<'tx>, table_file_name: &str) -> Self {
+        let table_name = table_file_name
+            .strip_suffix(".tbl")
+            .unwrap_or(table_file_name);
+        OverflowFile {
+            tx,
+            file_name: Self::file_name(table_name),
+        }
+    }
+
+    pub fn file_name(table_name: &str) -> String {
+        format!("{}.ovf", table_name)
+    }
+
+    /// Reads the value of the given length which starts at the block.
+    pub fn read(&self, first: i32, length: usize) -> DbResult<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(length);
+        let mut blk_num = first;
+        while bytes.len() < length {
+            let blk = self.block(blk_num);
+            self.tx.pin(&blk)?;
+            let chunk_len = (length - bytes.len()).min(self.chunk_size());
+            bytes.extend(self.tx.read_bytes(&blk, INT_BYTES, chunk_len)?);
+            blk_num = self.tx.get_int(&blk, 0)?;
+            self.tx.unpin(&blk);
+        }
+        Ok(bytes)
+    }
+
+    /// Writes the value into a new chain and returns its first block.
+    pub fn write(&self, bytes: &[u8]) -> DbResult<i32> {
+        let mut first = 0;
+        let mut prev: Option<BlockId> = None;
+        for chunk in bytes.chunks(self.chunk_size()) {
+            let blk = self.allocate()?;
+            self.tx.pin(&blk)?;
+            self.tx.set_int(&blk, 0, 0, true)?;
+            self.tx.write_bytes(&blk, INT_BYTES, chunk, true)?;
+            match prev.replace(blk.clone()) {
+                Some(prev) => {
+                    self.tx.set_int(&prev, 0, blk.number(), true)?;
+                    self.tx.unpin(&prev);
+                }
+                None => first = blk.number(),
+            }
+        }
+        if let Some(prev) = prev {
+            self.tx.unpin(&prev);
+        }
+        Ok(first)
+    }
+
+    /// Puts the blocks of the chain on the free list once the transaction commits.
+    pub fn free(&self, first: i32) -> DbResult<()> {
+        if first == 0 {
+            return Ok(());
+        }
+        if self.is_temp() {
+            // temporary tables are private to the transaction and their writes are not undone
+            return self.push_free(first);
+        }
+        self.tx.free_on_commit(&self.file_name, first);
+        Ok(())
+    }
+
+    /// Puts a chain of the overflow file on its free list in the given system transaction,
+    /// on behalf of a transaction which freed it and committed or took it and rolled back.
+    pub(crate) fn release(tx: Transaction<'tx>, file_name: &str, first: i32) -> DbResult<()> {
+        OverflowFile {
+            tx,
+            file_name: file_name.to_string(),
+        }
+        .push_free(first)
+    }
+
+    /// Takes a block from the free list, or appends one if the list is empty.
+    fn allocate(&self) -> DbResult<BlockId> {
+        let blk = if self.is_temp() {
+            self.pop_free()?
+        } else if self.has_free()? {
+            let list_tx = self.tx.new_system_tx()?;
+            let blk = OverflowFile {
+                tx: list_tx.clone(),
+                file_name: self.file_name.clone(),
+            }
+            .pop_free()?;
+            list_tx.commit()?;
+            blk
+        } else {
+            None
+        };
+
+        let blk = match blk {
+            Some(blk) => blk,
+            None => {
+                let mut blk = self.tx.append_unlocked(&self.file_name)?;
+                if blk.number() == 0 {
+                    // block 0 holds the head of the free list
+                    blk = self.tx.append_unlocked(&self.file_name)?;
+                }
+                blk
+            }
+        };
+        if !self.is_temp() {
+            self.tx.free_on_rollback(&self.file_name, blk.number());
+        }
+        Ok(blk)
+    }
+
+    /// Returns whether the free list has a block, reading its head without locking it.
+    fn has_free(&self) -> DbResult<bool> {
+        if self.tx.size_unlocked(&self.file_name)? == 0 {
+            return Ok(false);
+        }
+        let header = self.block(0);
+        self.tx.pin(&header)?;
+        let free_head = self.tx.get_int_unlocked(&header, 0);
+        self.tx.unpin(&header);
+        Ok(free_head != 0)
+    }
+
+    /// Puts the blocks of the chain on the free list.
+    fn push_free(&self, first: i32) -> DbResult<()> {
+        let mut last = self.block(first);
+        self.tx.pin(&last)?;
+        loop {
+            let next = self.tx.get_int(&last, 0)?;
+            if next == 0 {
+                break;
+            }
+            self.tx.unpin(&last);
+            last = self.block(next);
+            self.tx.pin(&last)?;
+        }
+
+        let header = self.block(0);
+        self.tx.pin(&header)?;
+        let free_head = self.tx.get_int(&header, 0)?;
+        self.tx.set_int(&last, 0, free_head, true)?;
+        self.tx.set_int(&header, 0, first, true)?;
+        self.tx.unpin(&header);
+        self.tx.unpin(&last);
+        Ok(())
+    }
+
+    /// Takes the first block of the free list.
+    fn pop_free(&self) -> DbResult<Option<BlockId>> {
+        if self.tx.size_unlocked(&self.file_name)? == 0 {
+            return Ok(None);
+        }
+        let header = self.block(0);
+        self.tx.pin(&header)?;
+        let free_head = self.tx.get_int(&header, 0)?;
+        let blk = if free_head == 0 {
+            None
+        } else {
+            let blk = self.block(free_head);
+            self.tx.pin(&blk)?;
+            let next = self.tx.get_int(&blk, 0)?;
+            self.tx.unpin(&blk);
+            self.tx.set_int(&header, 0, next, true)?;
+            Some(blk)
+        };
+        self.tx.unpin(&header);
+        Ok(blk)
+    }
+
+    fn is_temp(&self) -> bool {
+        self.block(0).is_temp()
+    }
+
+    fn block(&self, blk_num: i32) -> BlockId {
+        BlockId::new(self.file_name.clone(), blk_num)
+    }
+
+    fn chunk_size(&self) -> usize {
+        (self.tx.block_size() - INT_BYTES) / INT_BYTES * INT_BYTES
+    }
+}
+
+/// Reads the TEXT or BLOB value whose first block and length are stored at the position.
+pub(super) fn read_field(
+    tx: &Transaction,
+    blk: &BlockId,
+    pos: usize,
+    field_type: FieldType,
+) -> DbResult<Constant> {
+    let first = tx.get_int(blk, pos)?;
+    let length = tx.get_int(blk, pos + INT_BYTES)? as usize;
+    let bytes = OverflowFile::new(tx.clone(), blk.file_name()).read(first, length)?;
+    Ok(match field_type {
+        FieldType::Text => Constant::String(String::from_utf8_lossy(&bytes).to_string()),
+        _ => Constant::Bytes(bytes),
+    })
+}
+
+/// Writes a string or bytes into a new chain, freeing the chain of the previous value.
+pub(super) fn write_field(
+    tx: &Transaction,
+    blk: &BlockId,
+    pos: usize,
+    val: &Constant,
+) -> DbResult<()> {
+    let bytes = match val {
+        Constant::String(s) => s.as_bytes(),
+        Constant::Bytes(bytes) => bytes.as_slice(),
+        _ => panic!("Not a TEXT or BLOB value"),
+    };
+    free_field(tx, blk, pos)?;
+    let first = OverflowFile::new(tx.clone(), blk.file_name()).write(bytes)?;
+    tx.set_int(blk, pos, first, true)?;
+    tx.set_int(blk, pos + INT_BYTES, bytes.len() as i32, true)
+}
+
+/// Frees the chain of the value stored at the position and clears the field.
+pub(super) fn free_field(tx: &Transaction, blk: &BlockId, pos: usize) -> DbResult<()> {
+    let first = tx.get_int(blk, pos)?;
+    if first == 0 {
+        return Ok(());
+    }
+    OverflowFile::new(tx.clone(), blk.file_name()).free(first)?;
+    tx.set_int(blk, pos, 0, true)?;
+    tx.set_int(blk, pos + INT_BYTES, 0, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::{Scan, UpdateScan};
+    use crate::record::{Layout, RecordFormat, Schema, TableScan};
+    use crate::utils::testing_utils::temp_db;
+
+    #[test]
+    fn test_overflow_file_reuses_freed_blocks() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+        let ovf = OverflowFile::new(tx.clone(), "docs.tbl");
+
+        let long: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let first = ovf.write(&long)?;
+        let short = ovf.write(b"abc")?;
+        assert_eq!(ovf.read(first, long.len())?, long);
+        assert_eq!(ovf.read(short, 3)?, b"abc");
+        assert_eq!(ovf.write(b"")?, 0);
+        assert!(ovf.read(0, 0)?.is_empty());
+
+        // the header and the chains of both values
+        let size = tx.size("docs.ovf")?;
+        assert_eq!(size, 1 + long.len().div_ceil(ovf.chunk_size()) as i32 + 1);
+        ovf.free(first)?;
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        let ovf = OverflowFile::new(tx.clone(), "docs.tbl");
+        let again = ovf.write(&long)?;
+        assert_eq!(again, first);
+        assert_eq!(ovf.read(again, long.len())?, long);
+        assert_eq!(tx.size("docs.ovf")?, size);
+        assert_eq!(ovf.read(short, 3)?, b"abc");
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow_free_list_is_shared() -> DbResult<()> {
+        let db = temp_db()?;
+        let long: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let tx = db.new_tx()?;
+        let first = OverflowFile::new(tx.clone(), "docs.tbl").write(&long)?;
+        tx.commit()?;
+
+        // the freed blocks are not reused before the transaction freeing them commits
+        let tx1 = db.new_tx()?;
+        let tx2 = db.new_tx()?;
+        OverflowFile::new(tx1.clone(), "docs.tbl").free(first)?;
+        let ovf2 = OverflowFile::new(tx2.clone(), "docs.tbl");
+        let other = ovf2.write(&long)?;
+        assert_ne!(other, first);
+        let size = tx2.size("docs.ovf")?;
+        tx1.commit()?;
+
+        // nor do they wait for the other writer to finish
+        let tx3 = db.new_tx()?;
+        let ovf3 = OverflowFile::new(tx3.clone(), "docs.tbl");
+        assert_eq!(ovf3.write(&long)?, first);
+        assert_eq!(ovf3.read(first, long.len())?, long);
+        tx3.commit()?;
+
+        // the blocks taken by a transaction which rolls back are put back on the list
+        tx2.rollback()?;
+        let tx4 = db.new_tx()?;
+        let ovf4 = OverflowFile::new(tx4.clone(), "docs.tbl");
+        let again = ovf4.write(&long)?;
+        assert_eq!(ovf4.read(again, long.len())?, long);
+        assert_eq!(tx4.size("docs.ovf")?, size);
+        tx4.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow_fields() -> DbResult<()> {
+        let db = temp_db()?;
+        for format in [RecordFormat::Fixed, RecordFormat::Slotted] {
+            let mut schema = Schema::new();
+            schema.add_int_field("id");
+            schema.add_field("body", FieldType::Text, 0);
+            schema.add_field("data", FieldType::Blob, 0);
+            let layout = Layout::new(schema).with_format(format);
+            let table_name = format!("docs{}", format as i32);
+            let ovf_name = OverflowFile::file_name(&table_name);
+
+            let body = "lorem ipsum ".repeat(100);
+            let data: Vec<u8> = (0..=255).cycle().take(900).collect();
+            let tx = db.new_tx()?;
+            let mut scan = TableScan::new(tx.clone(), &table_name, layout.clone())?;
+            for id in 0..3 {
+                scan.insert()?;
+                scan.set_int("id", id)?;
+                scan.set_string("body", &body)?;
+                scan.set_val("data", Constant::Bytes(data.clone()))?;
+            }
+            tx.commit()?;
+
+            let tx = db.new_tx()?;
+            let mut scan = TableScan::new(tx.clone(), &table_name, layout.clone())?;
+            while scan.next()? {
+                assert_eq!(scan.get_string("body")?, body);
+                assert_eq!(scan.get_val("body")?, Constant::String(body.clone()));
+                assert_eq!(scan.get_bytes("data")?, data);
+            }
+            let size = tx.size(&ovf_name)?;
+
+            // deleting and nulling values frees their blocks, which are only reused for
+            // new values once the transaction commits
+            scan.before_first()?;
+            scan.next()?;
+            scan.delete()?;
+            scan.next()?;
+            scan.set_val("data", Constant::Null)?;
+            scan.set_string("body", "short")?;
+            scan.insert()?;
+            scan.set_val("body", Constant::String(body.clone()))?;
+            scan.set_val("data", Constant::Bytes(data.clone()))?;
+            assert!(tx.size(&ovf_name)? > size);
+            tx.rollback()?;
+
+            // a rollback restores the values and their chains
+            let tx = db.new_tx()?;
+            let mut scan = TableScan::new(tx.clone(), &table_name, layout)?;
+            let mut count = 0;
+            while scan.next()? {
+                assert_eq!(scan.get_string("body")?, body);
+                assert_eq!(scan.get_bytes("data")?, data);
+                count += 1;
+            }
+            assert_eq!(count, 3);
+            tx.commit()?;
+        }
+        Ok(())
+    }
+}
@@ -1,57 +1,255 @@
 use super::Layout;
+use super::SlottedRecordPage;
+use super::free_space_map::FreeSpaceMap;
+use super::layout::RecordFormat;
+use super::overflow;
 use super::schema::FieldType;
 use crate::error::DbResult;
+use crate::query::Constant;
 use crate::storage::BlockId;
 use crate::tx::Transaction;
 
 const EMPTY: i32 = 0;
 const USED: i32 = 1;
 
-pub struct RecordPage<'a> {
+/// The records stored in a single block of a table. A record is identified by its slot,
+/// slot 0 is never used so that `next_after(0)` and `insert_after(0)` start at the first record.
+pub trait RecordPage {
+    fn get_int(&self, slot: usize, field_name: &str) -> DbResult<i32>;
+
+    fn get_string(&self, slot: usize, field_name: &str) -> DbResult<String>;
+
+    fn set_int(&self, slot: usize, field_name: &str, val: i32) -> DbResult<()>;
+
+    fn set_string(&self, slot: usize, field_name: &str, val: &str) -> DbResult<()>;
+
+    /// Reads the field as a value of its type, or `Null` if the field is null.
+    fn get_val(&self, slot: usize, field_name: &str) -> DbResult<Constant>;
+
+    /// Writes the value into the field, which must be of the type of the value,
+    /// or marks the field as null.
+    fn set_val(&self, slot: usize, field_name: &str, val: &Constant) -> DbResult<()>;
+
+    fn is_null(&self, slot: usize, field_name: &str) -> DbResult<bool>;
+
+    /// Marks the field as null. The stored value is left as it is,
+    /// except that the overflow blocks of a TEXT or BLOB value are freed.
+    fn set_null(&self, slot: usize, field_name: &str) -> DbResult<()>;
+
+    /// Deletes the record and lists the block in the free space map of the table.
+    fn delete(&self, slot: usize) -> DbResult<()>;
+
+    /// Prepares a newly appended block, without logging.
+    fn format(&self) -> DbResult<()>;
+
+    /// Zeroes the whole page with logged writes, which leaves an empty page for any layout.
+    /// Unlike `format`, the previous contents are restored if the transaction rolls back,
+    /// so a page can be reused for a different layout.
+    fn clear(&self) -> DbResult<()>;
+
+    fn next_after(&self, slot: usize) -> DbResult<Option<usize>>;
+
+    /// Returns `None` if there is no room for a new record after the slot. With slot 0
+    /// the page is full, and the block is removed from the free space map of the table.
+    fn insert_after(&self, slot: usize) -> DbResult<Option<usize>>;
+
+    fn block(&self) -> &BlockId;
+}
+
+/// Pins the block and opens it as a record page of the format of the layout.
+pub fn open<'tx>(
+    tx: Transaction<'tx>,
+    blk: BlockId,
+    layout: Layout,
+) -> DbResult<Box<dyn RecordPage + 'tx>> {
+    Ok(match layout.format() {
+        RecordFormat::Fixed => Box::new(FixedRecordPage::new(tx, blk, layout)?),
+        RecordFormat::Slotted => Box::new(SlottedRecordPage::new(tx, blk, layout)?),
+    })
+}
+
+pub(super) fn clear(tx: &Transaction, blk: &BlockId) -> DbResult<()> {
+    let int_size = std::mem::size_of::<i32>();
+    let mut offset = 0;
+    while offset + int_size <= tx.block_size() {
+        tx.set_int(blk, offset, 0, true)?;
+        offset += int_size;
+    }
+    set_free(tx, blk, true)
+}
+
+/// Lists or unlists the block in the free space map of its table.
+pub(super) fn set_free(tx: &Transaction, blk: &BlockId, free: bool) -> DbResult<()> {
+    FreeSpaceMap::new(tx.clone(), blk.file_name()).set_free(blk.number(), free)
+}
+
+/// Stores every record in a slot of `slot_size` bytes, with the fields at the offsets
+/// of the layout.
+pub struct FixedRecordPage<'a> {
     tx: Transaction<'a>,
     blk: BlockId,
     layout: Layout,
 }
 
-impl<'a> RecordPage<'a> {
+impl<'a> FixedRecordPage<'a> {
     pub fn new(tx: Transaction<'a>, blk: BlockId, layout: Layout) -> DbResult<Self> {
         tx.pin(&blk)?;
-        Ok(RecordPage { tx, blk, layout })
+        Ok(FixedRecordPage { tx, blk, layout })
+    }
+
+    /// Updates the null bit of the field, writing the bitmap only if the bit changes.
+    fn set_null_bit(&self, slot: usize, field_name: &str, is_null: bool) -> DbResult<()> {
+        let (word_pos, mask) = self.layout.null_bit(field_name).expect("Field not found");
+        let pos = self.offset(slot) + word_pos;
+        let word = self.tx.get_int(&self.blk, pos)?;
+        let new_word = if is_null { word | mask } else { word & !mask };
+        if new_word != word {
+            self.tx.set_int(&self.blk, pos, new_word, true)?;
+        }
+        Ok(())
+    }
+
+    fn set_flag(&self, slot: usize, flag: i32) -> DbResult<()> {
+        self.tx.set_int(&self.blk, self.offset(slot), flag, true)
+    }
+
+    fn search_after(&self, mut slot: usize, flag: i32) -> DbResult<Option<usize>> {
+        slot += 1;
+        while self.is_valid_slot(slot) {
+            if self.tx.get_int(&self.blk, self.offset(slot))? == flag {
+                return Ok(Some(slot));
+            }
+            slot += 1;
+        }
+        Ok(None)
+    }
+
+    fn is_valid_slot(&self, slot: usize) -> bool {
+        self.offset(slot + 1) <= self.tx.block_size()
+    }
+
+    fn offset(&self, slot: usize) -> usize {
+        slot * self.layout.slot_size()
+    }
+
+    fn field_pos(&self, slot: usize, field_name: &str) -> usize {
+        self.offset(slot) + self.layout.offset(field_name).expect("Field not found")
+    }
+
+    fn field_type(&self, field_name: &str) -> FieldType {
+        self.layout
+            .schema()
+            .field_type(field_name)
+            .expect("Field type not found")
     }
+}
 
-    pub fn get_int(&self, slot: usize, field_name: &str) -> DbResult<i32> {
+impl<'a> RecordPage for FixedRecordPage<'a> {
+    fn get_int(&self, slot: usize, field_name: &str) -> DbResult<i32> {
         let field_pos =
             self.offset(slot) + self.layout.offset(field_name).expect("Field not found");
         self.tx.get_int(&self.blk, field_pos)
     }
 
-    pub fn get_string(&self, slot: usize, field_name: &str) -> DbResult<String> {
+    fn get_string(&self, slot: usize, field_name: &str) -> DbResult<String> {
+        if self.field_type(field_name) == FieldType::Text {
+            return self.get_val(slot, field_name)?.string_value(field_name);
+        }
         let field_pos =
             self.offset(slot) + self.layout.offset(field_name).expect("Field not found");
         self.tx.get_string(&self.blk, field_pos)
     }
 
-    pub fn set_int(&self, slot: usize, field_name: &str, val: i32) -> DbResult<()> {
+    fn set_int(&self, slot: usize, field_name: &str, val: i32) -> DbResult<()> {
         let field_pos =
             self.offset(slot) + self.layout.offset(field_name).expect("Field not found");
-        self.tx.set_int(&self.blk, field_pos, val, true)
+        self.tx.set_int(&self.blk, field_pos, val, true)?;
+        self.set_null_bit(slot, field_name, false)
     }
 
-    pub fn set_string(&self, slot: usize, field_name: &str, val: &str) -> DbResult<()> {
+    fn set_string(&self, slot: usize, field_name: &str, val: &str) -> DbResult<()> {
+        if self.field_type(field_name) == FieldType::Text {
+            return self.set_val(slot, field_name, &Constant::String(val.to_string()));
+        }
         let field_pos =
             self.offset(slot) + self.layout.offset(field_name).expect("Field not found");
-        self.tx.set_string(&self.blk, field_pos, val, true)
+        self.tx.set_string(&self.blk, field_pos, val, true)?;
+        self.set_null_bit(slot, field_name, false)
     }
 
-    pub fn delete(&self, slot: usize) -> DbResult<()> {
-        self.set_flag(slot, EMPTY)
+    fn get_val(&self, slot: usize, field_name: &str) -> DbResult<Constant> {
+        if self.is_null(slot, field_name)? {
+            return Ok(Constant::Null);
+        }
+        let field_pos = self.field_pos(slot, field_name);
+        Ok(match self.field_type(field_name) {
+            FieldType::Integer => Constant::Int(self.tx.get_int(&self.blk, field_pos)?),
+            FieldType::Varchar => Constant::String(self.tx.get_string(&self.blk, field_pos)?),
+            FieldType::BigInt => Constant::BigInt(self.tx.get_long(&self.blk, field_pos)?),
+            FieldType::Boolean => Constant::Bool(self.tx.get_bool(&self.blk, field_pos)?),
+            FieldType::Double => Constant::Double(self.tx.get_double(&self.blk, field_pos)?),
+            FieldType::Date => Constant::Date(self.tx.get_int(&self.blk, field_pos)?),
+            FieldType::Timestamp => Constant::Timestamp(self.tx.get_long(&self.blk, field_pos)?),
+            field_type @ (FieldType::Text | FieldType::Blob) => {
+                overflow::read_field(&self.tx, &self.blk, field_pos, field_type)?
+            }
+        })
+    }
+
+    fn set_val(&self, slot: usize, field_name: &str, val: &Constant) -> DbResult<()> {
+        let field_pos = self.field_pos(slot, field_name);
+        match val {
+            Constant::Null => return self.set_null(slot, field_name),
+            _ if self.field_type(field_name).is_overflow() => {
+                overflow::write_field(&self.tx, &self.blk, field_pos, val)?
+            }
+            Constant::Int(i) | Constant::Date(i) => {
+                self.tx.set_int(&self.blk, field_pos, *i, true)?
+            }
+            Constant::String(s) => self.tx.set_string(&self.blk, field_pos, s, true)?,
+            Constant::BigInt(i) | Constant::Timestamp(i) => {
+                self.tx.set_long(&self.blk, field_pos, *i, true)?
+            }
+            Constant::Bool(b) => self.tx.set_bool(&self.blk, field_pos, *b, true)?,
+            Constant::Double(d) => self.tx.set_double(&self.blk, field_pos, *d, true)?,
+            Constant::Bytes(_) => panic!("BLOB value for a field of another type"),
+        }
+        self.set_null_bit(slot, field_name, false)
     }
 
-    pub fn format(&self) -> DbResult<()> {
+    fn is_null(&self, slot: usize, field_name: &str) -> DbResult<bool> {
+        let (word_pos, mask) = self.layout.null_bit(field_name).expect("Field not found");
+        let word = self.tx.get_int(&self.blk, self.offset(slot) + word_pos)?;
+        Ok(word & mask != 0)
+    }
+
+    fn set_null(&self, slot: usize, field_name: &str) -> DbResult<()> {
+        if self.field_type(field_name).is_overflow() {
+            overflow::free_field(&self.tx, &self.blk, self.field_pos(slot, field_name))?;
+        }
+        self.set_null_bit(slot, field_name, true)
+    }
+
+    /// Empties the slot and frees the overflow blocks of its TEXT and BLOB values.
+    fn delete(&self, slot: usize) -> DbResult<()> {
+        for field_name in self.layout.schema().fields() {
+            if self.field_type(field_name).is_overflow() {
+                overflow::free_field(&self.tx, &self.blk, self.field_pos(slot, field_name))?;
+            }
+        }
+        self.set_flag(slot, EMPTY)?;
+        set_free(&self.tx, &self.blk, true)
+    }
+
+    fn format(&self) -> DbResult<()> {
         let mut slot = 0;
         while self.is_valid_slot(slot) {
             self.tx
                 .set_int(&self.blk, self.offset(slot), EMPTY, false)?;
+            for word_pos in self.layout.null_bitmap_offsets() {
+                self.tx
+                    .set_int(&self.blk, self.offset(slot) + word_pos, 0, false)?;
+            }
 
             for field_name in self.layout.schema().fields() {
                 let field_pos =
@@ -63,9 +261,16 @@ impl<'a> RecordPage<'a> {
                     .field_type(field_name)
                     .expect("Field type not found")
                 {
-                    FieldType::Integer => {
+                    FieldType::Integer | FieldType::Boolean | FieldType::Date => {
                         self.tx.set_int(&self.blk, field_pos, 0, false)?;
                     }
+                    FieldType::BigInt
+                    | FieldType::Double
+                    | FieldType::Timestamp
+                    | FieldType::Text
+                    | FieldType::Blob => {
+                        self.tx.set_long(&self.blk, field_pos, 0, false)?;
+                    }
                     FieldType::Varchar => {
                         self.tx.set_string(&self.blk, field_pos, "", false)?;
                     }
@@ -76,48 +281,39 @@ impl<'a> RecordPage<'a> {
         Ok(())
     }
 
-    pub fn next_after(&self, slot: usize) -> DbResult<Option<usize>> {
+    fn clear(&self) -> DbResult<()> {
+        clear(&self.tx, &self.blk)
+    }
+
+    fn next_after(&self, slot: usize) -> DbResult<Option<usize>> {
         self.search_after(slot, USED)
     }
 
-    pub fn insert_after(&self, slot: usize) -> DbResult<Option<usize>> {
+    fn insert_after(&self, slot: usize) -> DbResult<Option<usize>> {
         if let Some(new_slot) = self.search_after(slot, EMPTY)? {
             self.set_flag(new_slot, USED)?;
+            // a reused slot may still carry the null bits of a deleted record
+            for word_pos in self.layout.null_bitmap_offsets() {
+                let pos = self.offset(new_slot) + word_pos;
+                if self.tx.get_int(&self.blk, pos)? != 0 {
+                    self.tx.set_int(&self.blk, pos, 0, true)?;
+                }
+            }
             Ok(Some(new_slot))
         } else {
+            if slot == 0 {
+                set_free(&self.tx, &self.blk, false)?;
+            }
             Ok(None)
         }
     }
 
-    pub fn block(&self) -> &BlockId {
+    fn block(&self) -> &BlockId {
         &self.blk
     }
-
-    fn set_flag(&self, slot: usize, flag: i32) -> DbResult<()> {
-        self.tx.set_int(&self.blk, self.offset(slot), flag, true)
-    }
-
-    fn search_after(&self, mut slot: usize, flag: i32) -> DbResult<Option<usize>> {
-        slot += 1;
-        while self.is_valid_slot(slot) {
-            if self.tx.get_int(&self.blk, self.offset(slot))? == flag {
-                return Ok(Some(slot));
-            }
-            slot += 1;
-        }
-        Ok(None)
-    }
-
-    fn is_valid_slot(&self, slot: usize) -> bool {
-        self.offset(slot + 1) <= self.tx.block_size()
-    }
-
-    fn offset(&self, slot: usize) -> usize {
-        slot * self.layout.slot_size()
-    }
 }
 
-impl<'a> Drop for RecordPage<'a> {
+impl<'a> Drop for FixedRecordPage<'a> {
     fn drop(&mut self) {
         self.tx.unpin(&self.blk);
     }
@@ -128,7 +324,7 @@ mod tests {
     use super::*;
     use crate::error::DbResult;
     use crate::record::schema::Schema;
-    use crate::utils::testing_utils::temp_db_with_cfg;
+    use crate::utils::testing_utils::{temp_db, temp_db_with_cfg};
 
     #[test]
     fn test_record_page_basic() -> DbResult<()> {
@@ -146,7 +342,7 @@ mod tests {
         assert_eq!(3, buffer_mgr.available());
 
         {
-            let record_page = RecordPage::new(tx.clone(), blk.clone(), layout)?;
+            let record_page = FixedRecordPage::new(tx.clone(), blk.clone(), layout)?;
 
             assert_eq!(2, buffer_mgr.available()); // one buffer is pinned by record_page
 
@@ -156,7 +352,114 @@ mod tests {
             record_page.set_string(slot, "name", "test")?;
             assert_eq!(record_page.get_int(slot, "id")?, 123);
             assert_eq!(record_page.get_string(slot, "name")?, "test");
+            assert!(!record_page.is_null(slot, "name")?);
+
+            record_page.set_null(slot, "name")?;
+            assert!(record_page.is_null(slot, "name")?);
+            assert!(!record_page.is_null(slot, "id")?);
+            record_page.set_string(slot, "name", "again")?;
+            assert!(!record_page.is_null(slot, "name")?);
+
+            record_page.set_null(slot, "id")?;
             record_page.delete(slot)?;
+            // the slot is reused without the null bits of the deleted record
+            assert_eq!(record_page.insert_after(0)?, Some(slot));
+            assert!(!record_page.is_null(slot, "id")?);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_page_typed_values() -> DbResult<()> {
+        let db = temp_db()?;
+
+        let mut schema = Schema::new();
+        schema.add_field("big", FieldType::BigInt, 0);
+        schema.add_field("flag", FieldType::Boolean, 0);
+        schema.add_field("ratio", FieldType::Double, 0);
+        schema.add_field("day", FieldType::Date, 0);
+        schema.add_field("at", FieldType::Timestamp, 0);
+        let layout = Layout::new(schema);
+        // a formatted slot holds the zero value of every type
+        let values = [
+            ("big", "0", Constant::BigInt(-5_000_000_000)),
+            ("flag", "false", Constant::Bool(true)),
+            ("ratio", "0", Constant::Double(0.125)),
+            ("day", "1970-01-01", Constant::parse_date("2024-02-29")?),
+            (
+                "at",
+                "1970-01-01 00:00:00",
+                Constant::parse_timestamp("2024-02-29 12:30:00")?,
+            ),
+        ];
+
+        let tx = db.new_tx()?;
+        let blk = tx.append("testfile")?;
+        let slot = {
+            let record_page = FixedRecordPage::new(tx.clone(), blk.clone(), layout.clone())?;
+            record_page.format()?;
+            let slot = record_page.insert_after(0)?.expect("Failed to insert");
+            for (field_name, zero, val) in &values {
+                assert_eq!(record_page.get_val(slot, field_name)?.to_string(), *zero);
+                record_page.set_val(slot, field_name, val)?;
+            }
+            record_page.set_val(slot, "ratio", &Constant::Null)?;
+            slot
+        };
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        {
+            let record_page = FixedRecordPage::new(tx.clone(), blk.clone(), layout)?;
+            for (field_name, _, val) in &values {
+                let expected = match *field_name {
+                    "ratio" => Constant::Null,
+                    _ => val.clone(),
+                };
+                assert_eq!(record_page.get_val(slot, field_name)?, expected);
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_page_clear_is_undone_by_rollback() -> DbResult<()> {
+        let db = temp_db()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        schema.add_string_field("name", 20);
+        let layout = Layout::new(schema);
+
+        let tx = db.new_tx()?;
+        let blk = tx.append("testfile")?;
+        let slot = {
+            let record_page = FixedRecordPage::new(tx.clone(), blk.clone(), layout.clone())?;
+            record_page.format()?;
+            let slot = record_page.insert_after(0)?.expect("Failed to insert");
+            record_page.set_int(slot, "id", 123)?;
+            record_page.set_string(slot, "name", "test")?;
+            slot
+        };
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        {
+            let record_page = FixedRecordPage::new(tx.clone(), blk.clone(), layout.clone())?;
+            record_page.clear()?;
+            assert_eq!(record_page.next_after(0)?, None);
+            assert_eq!(record_page.get_string(slot, "name")?, "");
+        }
+        tx.rollback()?;
+
+        let tx = db.new_tx()?;
+        {
+            let record_page = FixedRecordPage::new(tx.clone(), blk.clone(), layout)?;
+            assert_eq!(record_page.next_after(0)?, Some(slot));
+            assert_eq!(record_page.get_int(slot, "id")?, 123);
+            assert_eq!(record_page.get_string(slot, "name")?, "test");
         }
         tx.commit()?;
         Ok(())
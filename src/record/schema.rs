@@ -1,9 +1,46 @@
 use std::collections::HashMap;
+use std::fmt;
 
+/// The type of a field. The discriminant is the type code stored in the catalog.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FieldType {
     Integer = 0,
     Varchar = 1,
+    BigInt = 2,
+    Boolean = 3,
+    Double = 4,
+    /// Days since 1970-01-01.
+    Date = 5,
+    /// Microseconds since 1970-01-01 00:00:00.
+    Timestamp = 6,
+    /// A string of any length, stored in the overflow file of the table.
+    Text = 7,
+    /// Bytes of any length, stored in the overflow file of the table.
+    Blob = 8,
+}
+
+impl FieldType {
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            FieldType::Integer | FieldType::BigInt | FieldType::Double
+        )
+    }
+
+    /// Returns true if values of the two types can be compared with each other: values of
+    /// the same type, numbers, or strings of either kind.
+    pub fn is_comparable_with(&self, other: FieldType) -> bool {
+        let is_string = |t: FieldType| matches!(t, FieldType::Varchar | FieldType::Text);
+        *self == other
+            || (self.is_numeric() && other.is_numeric())
+            || (is_string(*self) && is_string(other))
+    }
+
+    /// Returns true for the types whose values are kept in the overflow file of the table,
+    /// see [super::overflow::OverflowFile]. The record only holds a pointer to the value.
+    pub fn is_overflow(&self) -> bool {
+        matches!(self, FieldType::Text | FieldType::Blob)
+    }
 }
 
 impl From<i32> for FieldType {
@@ -11,15 +48,61 @@ impl From<i32> for FieldType {
         match value {
             0 => FieldType::Integer,
             1 => FieldType::Varchar,
+            2 => FieldType::BigInt,
+            3 => FieldType::Boolean,
+            4 => FieldType::Double,
+            5 => FieldType::Date,
+            6 => FieldType::Timestamp,
+            7 => FieldType::Text,
+            8 => FieldType::Blob,
             _ => panic!("Invalid field type"),
         }
     }
 }
 
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FieldType::Integer => "INT",
+            FieldType::Varchar => "VARCHAR",
+            FieldType::BigInt => "BIGINT",
+            FieldType::Boolean => "BOOLEAN",
+            FieldType::Double => "DOUBLE",
+            FieldType::Date => "DATE",
+            FieldType::Timestamp => "TIMESTAMP",
+            FieldType::Text => "TEXT",
+            FieldType::Blob => "BLOB",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A constraint on the values of a field. The discriminant is the bit of the constraint
+/// in the flags stored in the catalog.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constraint {
+    NotNull = 1,
+    Unique = 2,
+    /// Both NOT NULL and UNIQUE. A table has at most one primary key.
+    PrimaryKey = 4,
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Constraint::NotNull => "NOT NULL",
+            Constraint::Unique => "UNIQUE",
+            Constraint::PrimaryKey => "PRIMARY KEY",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Debug, Clone)]
 struct FieldInfo {
     field_type: FieldType,
     length: usize,
+    constraints: i32,
 }
 
 #[derive(Debug, Clone)]
@@ -38,8 +121,14 @@ impl Schema {
 
     pub fn add_field(&mut self, field_name: &str, field_type: FieldType, length: usize) {
         self.fields.push(field_name.to_string());
-        self.info
-            .insert(field_name.to_string(), FieldInfo { field_type, length });
+        self.info.insert(
+            field_name.to_string(),
+            FieldInfo {
+                field_type,
+                length,
+                constraints: 0,
+            },
+        );
     }
 
     pub fn add_int_field(&mut self, field_name: &str) {
@@ -56,6 +145,7 @@ impl Schema {
             .get(field_name)
             .expect("Field not found in schema");
         self.add_field(field_name, field_info.field_type, field_info.length);
+        self.set_constraints(field_name, field_info.constraints);
     }
 
     pub fn add_all(&mut self, other: &Schema) {
@@ -79,6 +169,46 @@ impl Schema {
     pub fn length(&self, field_name: &str) -> Option<usize> {
         self.info.get(field_name).map(|info| info.length)
     }
+
+    pub fn add_constraint(&mut self, field_name: &str, constraint: Constraint) {
+        let flags = self.constraints(field_name) | constraint as i32;
+        self.set_constraints(field_name, flags);
+    }
+
+    /// Returns the constraints of the field as the flags stored in the catalog.
+    pub fn constraints(&self, field_name: &str) -> i32 {
+        self.info.get(field_name).map_or(0, |info| info.constraints)
+    }
+
+    pub fn set_constraints(&mut self, field_name: &str, flags: i32) {
+        if let Some(info) = self.info.get_mut(field_name) {
+            info.constraints = flags;
+        }
+    }
+
+    pub fn has_constraint(&self, field_name: &str, constraint: Constraint) -> bool {
+        self.constraints(field_name) & constraint as i32 != 0
+    }
+
+    /// Returns true if the field may not hold null, being NOT NULL or the primary key.
+    pub fn is_not_null(&self, field_name: &str) -> bool {
+        self.has_constraint(field_name, Constraint::NotNull)
+            || self.has_constraint(field_name, Constraint::PrimaryKey)
+    }
+
+    /// Returns true if no two records may hold the same value in the field, being UNIQUE or
+    /// the primary key. Any number of records may hold null.
+    pub fn is_unique(&self, field_name: &str) -> bool {
+        self.has_constraint(field_name, Constraint::Unique)
+            || self.has_constraint(field_name, Constraint::PrimaryKey)
+    }
+
+    pub fn primary_key(&self) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|f| self.has_constraint(f, Constraint::PrimaryKey))
+            .map(|f| f.as_str())
+    }
 }
 
 #[cfg(test)]
@@ -114,4 +244,39 @@ mod tests {
         assert_eq!(schema2.field_type("name"), Some(FieldType::Varchar));
         assert_eq!(schema2.length("name"), Some(20));
     }
+
+    #[test]
+    fn test_schema_constraints() {
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        schema.add_string_field("email", 20);
+        schema.add_string_field("name", 20);
+        schema.add_constraint("id", Constraint::PrimaryKey);
+        schema.add_constraint("email", Constraint::Unique);
+        schema.add_constraint("email", Constraint::NotNull);
+
+        assert!(schema.is_not_null("id") && schema.is_unique("id"));
+        assert!(schema.is_not_null("email") && schema.is_unique("email"));
+        assert!(!schema.is_not_null("name") && !schema.is_unique("name"));
+        assert_eq!(schema.primary_key(), Some("id"));
+        assert_eq!(schema.constraints("email"), 3);
+
+        // the constraints are copied with the field
+        let mut copy = Schema::new();
+        copy.add_from_schema("email", &schema);
+        assert_eq!(copy.constraints("email"), 3);
+        assert_eq!(copy.primary_key(), None);
+    }
+
+    #[test]
+    fn test_field_type_codes() {
+        for code in 0..9 {
+            assert_eq!(FieldType::from(code) as i32, code);
+        }
+        assert!(FieldType::Double.is_numeric());
+        assert!(!FieldType::Date.is_numeric());
+        assert_eq!(FieldType::Timestamp.to_string(), "TIMESTAMP");
+        assert!(FieldType::Blob.is_overflow());
+        assert!(!FieldType::Varchar.is_overflow());
+    }
 }
@@ -0,0 +1,686 @@
+use super::Layout;
+use super::RID;
+use super::overflow;
+use super::record_page::{self, RecordPage};
+use super::schema::FieldType;
+use crate::error::{DbError, DbResult};
+use crate::query::Constant;
+use crate::storage::BlockId;
+use crate::tx::Transaction;
+
+const INT_BYTES: usize = std::mem::size_of::<i32>();
+const ENTRY_BYTES: usize = 2 * INT_BYTES;
+
+/// A record of the slot.
+const USED: i32 = 1;
+/// The record of the slot has been moved to another block, the stub holds its block and slot.
+const FORWARDED: i32 = 2;
+/// A record moved here from another block. It is only reached through its stub.
+const MOVED: i32 = 3;
+
+/// Stores records of variable length. The page starts with a slot directory, whose entries
+/// hold the offset and length of the record of each slot, and the records are packed at
+/// the end of the page. Entry 0 is the header with the number of slots and the start of
+/// the records, 0 standing for the end of the page, so a zeroed page is empty.
+///
+/// A record starts with its flag and null bitmap like a fixed slot, followed by the fields
+/// in the order of their layout offsets. A string takes its length and bytes rounded up to
+/// whole words. The records are kept packed, so a record which changes size and a deleted
+/// record move the records below them. The slot of a record never changes; a record which
+/// outgrows the page is moved to another block and leaves a stub pointing to it.
+pub struct SlottedRecordPage<'a> {
+    tx: Transaction<'a>,
+    blk: BlockId,
+    layout: Layout,
+    fields: Vec<String>,
+}
+
+impl<'a> SlottedRecordPage<'a> {
+    pub fn new(tx: Transaction<'a>, blk: BlockId, layout: Layout) -> DbResult<Self> {
+        tx.pin(&blk)?;
+        let mut fields = layout.schema().fields().to_vec();
+        fields.sort_by_key(|field_name| layout.offset(field_name).expect("Field not found"));
+        Ok(SlottedRecordPage {
+            tx,
+            blk,
+            layout,
+            fields,
+        })
+    }
+
+    /// Returns the number of bytes left for new records and directory entries.
+    pub fn free_space(&self) -> DbResult<usize> {
+        Ok(self.free_end()? - (self.num_slots()? + 1) * ENTRY_BYTES)
+    }
+
+    /// Runs `f` on the page and slot holding the record of the slot, following its stub.
+    fn with_record<T>(
+        &self,
+        slot: usize,
+        f: impl FnOnce(&SlottedRecordPage<'a>, usize) -> DbResult<T>,
+    ) -> DbResult<T> {
+        match self.forwarded_to(slot)? {
+            Some(rid) => f(&self.page(rid.block_number())?, rid.slot()),
+            None => f(self, slot),
+        }
+    }
+
+    fn forwarded_to(&self, slot: usize) -> DbResult<Option<RID>> {
+        let offset = self.record_offset(slot)?;
+        if self.tx.get_int(&self.blk, offset)? != FORWARDED {
+            return Ok(None);
+        }
+        let blk_num = self.tx.get_int(&self.blk, offset + INT_BYTES)?;
+        let target = self.tx.get_int(&self.blk, offset + 2 * INT_BYTES)?;
+        Ok(Some(RID::new(blk_num, target as usize)))
+    }
+
+    fn page(&self, blk_num: i32) -> DbResult<SlottedRecordPage<'a>> {
+        let blk = BlockId::new(self.blk.file_name().to_string(), blk_num);
+        SlottedRecordPage::new(self.tx.clone(), blk, self.layout.clone())
+    }
+
+    /// Moves the record of the slot, with the string written into the field, to where it fits:
+    /// back into this page in place of its stub or into another block.
+    fn relocate(&self, slot: usize, field_name: &str, val: &str) -> DbResult<()> {
+        let mut record = self.with_record(slot, |page, slot| {
+            page.record_with_string(slot, field_name, val)
+        })?;
+        record[0] = USED;
+        if let Some(rid) = self.forwarded_to(slot)? {
+            self.page(rid.block_number())?.remove(rid.slot())?;
+        }
+        if self.replace(slot, &record)? {
+            return Ok(());
+        }
+
+        record[0] = MOVED;
+        let rid = self.insert_elsewhere(&record)?;
+        let stub = [FORWARDED, rid.block_number(), rid.slot() as i32];
+        //  a stub is never larger than a record, so it fits in place of the record
+        self.replace(slot, &stub)?;
+        Ok(())
+    }
+
+    /// Inserts the record into the last block of the file or, if it does not fit there,
+    /// into a new block.
+    fn insert_elsewhere(&self, record: &[i32]) -> DbResult<RID> {
+        let last = self.tx.size(self.blk.file_name())? - 1;
+        if last != self.blk.number()
+            && let Some(slot) = self.page(last)?.insert_record(0, record)?
+        {
+            return Ok(RID::new(last, slot));
+        }
+
+        let blk = self.tx.append(self.blk.file_name())?;
+        let page = SlottedRecordPage::new(self.tx.clone(), blk.clone(), self.layout.clone())?;
+        page.format()?;
+        match page.insert_record(0, record)? {
+            Some(slot) => Ok(RID::new(blk.number(), slot)),
+            None => Err(DbError::Schema(format!(
+                "Record of {} bytes does not fit in a block",
+                record.len() * INT_BYTES
+            ))),
+        }
+    }
+
+    /// Stores the record in the first empty slot after `slot`, or in a new slot.
+    /// Returns `None` if the page has no room for it.
+    fn insert_record(&self, slot: usize, record: &[i32]) -> DbResult<Option<usize>> {
+        let num_slots = self.num_slots()?;
+        let mut new_slot = slot + 1;
+        while new_slot <= num_slots && self.entry(new_slot)?.0 != 0 {
+            new_slot += 1;
+        }
+
+        let new_entries = new_slot.saturating_sub(num_slots) * ENTRY_BYTES;
+        if record.len() * INT_BYTES + new_entries > self.free_space()? {
+            return Ok(None);
+        }
+        if new_slot > num_slots {
+            //  the directory grows into the free space, which may hold stale bytes
+            for empty_slot in num_slots + 1..=new_slot {
+                self.set_entry(empty_slot, 0, 0)?;
+            }
+            self.tx.set_int(&self.blk, 0, new_slot as i32, true)?;
+        }
+        self.replace(new_slot, record)?;
+        Ok(Some(new_slot))
+    }
+
+    /// Replaces the record of the slot, which may be empty, with the given words.
+    /// Returns false, leaving the page as it is, if the page has no room for them.
+    fn replace(&self, slot: usize, record: &[i32]) -> DbResult<bool> {
+        let (offset, length) = self.entry(slot)?;
+        let new_length = record.len() * INT_BYTES;
+        if new_length == length {
+            self.write_words(offset, record)?;
+            return Ok(true);
+        }
+        if new_length > length + self.free_space()? {
+            return Ok(false);
+        }
+
+        self.remove(slot)?;
+        let new_offset = self.free_end()? - new_length;
+        self.write_words(new_offset, record)?;
+        self.set_entry(slot, new_offset, new_length)?;
+        self.tx
+            .set_int(&self.blk, INT_BYTES, new_offset as i32, true)?;
+        Ok(true)
+    }
+
+    /// Empties the slot and moves the records stored below its record up to close the gap.
+    /// The moved records and the updated directory are logged as one write each.
+    fn remove(&self, slot: usize) -> DbResult<()> {
+        let (offset, length) = self.entry(slot)?;
+        if offset == 0 {
+            return Ok(());
+        }
+
+        let free_end = self.free_end()?;
+        let below = self.read_words(free_end, offset - free_end)?;
+        self.write_words(free_end + length, &below)?;
+
+        //  the directory holds the header followed by the offset and length of every slot
+        let num_slots = self.num_slots()?;
+        let mut directory = self.read_words(0, (num_slots + 1) * ENTRY_BYTES)?;
+        for entry in directory.chunks_mut(2).skip(1) {
+            if entry[0] != 0 && (entry[0] as usize) < offset {
+                entry[0] += length as i32;
+            }
+        }
+        directory[2 * slot] = 0;
+        directory[2 * slot + 1] = 0;
+        directory[1] = (free_end + length) as i32;
+        self.write_words(0, &directory)
+    }
+
+    /// Returns the words of the record of the slot with the string written into the field.
+    fn record_with_string(&self, slot: usize, field_name: &str, val: &str) -> DbResult<Vec<i32>> {
+        let (offset, length) = self.entry(slot)?;
+        let field_pos = self.field_pos(offset, field_name)?;
+        let field_end = field_pos + self.field_size(field_pos, field_name)?;
+
+        let mut record = self.read_words(offset, field_pos - offset)?;
+        let mut bytes = vec![0; Self::string_size(val.len())];
+        bytes[..INT_BYTES].copy_from_slice(&(val.len() as i32).to_be_bytes());
+        bytes[INT_BYTES..INT_BYTES + val.len()].copy_from_slice(val.as_bytes());
+        record.extend(
+            bytes
+                .chunks(INT_BYTES)
+                .map(|word| i32::from_be_bytes(word.try_into().unwrap())),
+        );
+        record.extend(self.read_words(field_end, offset + length - field_end)?);
+
+        let (word_pos, mask) = self.layout.null_bit(field_name).expect("Field not found");
+        record[word_pos / INT_BYTES] &= !mask;
+        Ok(record)
+    }
+
+    /// Writes the string into the field of a record stored in this page.
+    /// Returns false, leaving the record as it is, if the page has no room for it.
+    fn write_string(&self, slot: usize, field_name: &str, val: &str) -> DbResult<bool> {
+        let offset = self.record_offset(slot)?;
+        let field_pos = self.field_pos(offset, field_name)?;
+        if self.field_size(field_pos, field_name)? == Self::string_size(val.len()) {
+            self.tx.set_string(&self.blk, field_pos, val, true)?;
+            self.set_null_bit(offset, field_name, false)?;
+            return Ok(true);
+        }
+        let record = self.record_with_string(slot, field_name, val)?;
+        self.replace(slot, &record)
+    }
+
+    /// Returns the record of a new slot, with every field set to the zero value of its type.
+    fn new_record(&self) -> Vec<i32> {
+        let length = self.fields_offset()
+            + self
+                .fields
+                .iter()
+                .map(|field_name| match self.field_type(field_name) {
+                    FieldType::Varchar => INT_BYTES,
+                    field_type => Self::fixed_size(field_type),
+                })
+                .sum::<usize>();
+        let mut record = vec![0; length / INT_BYTES];
+        record[0] = USED;
+        record
+    }
+
+    /// Returns the position of the field of the record at the offset.
+    fn field_pos(&self, offset: usize, field_name: &str) -> DbResult<usize> {
+        let mut pos = offset + self.fields_offset();
+        for other in &self.fields {
+            if other == field_name {
+                return Ok(pos);
+            }
+            pos += self.field_size(pos, other)?;
+        }
+        panic!("Field not found")
+    }
+
+    fn field_size(&self, pos: usize, field_name: &str) -> DbResult<usize> {
+        Ok(match self.field_type(field_name) {
+            FieldType::Varchar => Self::string_size(self.tx.get_int(&self.blk, pos)? as usize),
+            field_type => Self::fixed_size(field_type),
+        })
+    }
+
+    fn field_type(&self, field_name: &str) -> FieldType {
+        self.layout
+            .schema()
+            .field_type(field_name)
+            .expect("Field type not found")
+    }
+
+    fn fixed_size(field_type: FieldType) -> usize {
+        match field_type {
+            FieldType::BigInt
+            | FieldType::Double
+            | FieldType::Timestamp
+            | FieldType::Text
+            | FieldType::Blob => 2 * INT_BYTES,
+            _ => INT_BYTES,
+        }
+    }
+
+    fn string_size(len: usize) -> usize {
+        INT_BYTES + len.next_multiple_of(INT_BYTES)
+    }
+
+    /// Returns the offset of the first field within a record, after the flag and null bitmap.
+    fn fields_offset(&self) -> usize {
+        INT_BYTES + self.layout.null_bitmap_offsets().count() * INT_BYTES
+    }
+
+    fn set_null_bit(&self, offset: usize, field_name: &str, is_null: bool) -> DbResult<()> {
+        let (word_pos, mask) = self.layout.null_bit(field_name).expect("Field not found");
+        let pos = offset + word_pos;
+        let word = self.tx.get_int(&self.blk, pos)?;
+        let new_word = if is_null { word | mask } else { word & !mask };
+        if new_word != word {
+            self.tx.set_int(&self.blk, pos, new_word, true)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the offset of the record of the slot, failing if the slot is empty.
+    fn record_offset(&self, slot: usize) -> DbResult<usize> {
+        match self.entry(slot)? {
+            (0, _) => Err(DbError::NotFound),
+            (offset, _) => Ok(offset),
+        }
+    }
+
+    fn entry(&self, slot: usize) -> DbResult<(usize, usize)> {
+        if slot == 0 || slot > self.num_slots()? {
+            return Ok((0, 0));
+        }
+        let pos = slot * ENTRY_BYTES;
+        let offset = self.tx.get_int(&self.blk, pos)?;
+        let length = self.tx.get_int(&self.blk, pos + INT_BYTES)?;
+        Ok((offset as usize, length as usize))
+    }
+
+    fn set_entry(&self, slot: usize, offset: usize, length: usize) -> DbResult<()> {
+        let pos = slot * ENTRY_BYTES;
+        self.write_words(pos, &[offset as i32, length as i32])
+    }
+
+    fn num_slots(&self) -> DbResult<usize> {
+        Ok(self.tx.get_int(&self.blk, 0)? as usize)
+    }
+
+    fn free_end(&self) -> DbResult<usize> {
+        match self.tx.get_int(&self.blk, INT_BYTES)? {
+            0 => Ok(self.tx.block_size() / INT_BYTES * INT_BYTES),
+            free_end => Ok(free_end as usize),
+        }
+    }
+
+    fn read_words(&self, offset: usize, length: usize) -> DbResult<Vec<i32>> {
+        let bytes = self.tx.read_bytes(&self.blk, offset, length)?;
+        Ok(bytes
+            .chunks(INT_BYTES)
+            .map(|word| i32::from_be_bytes(word.try_into().unwrap()))
+            .collect())
+    }
+
+    /// Writes the words with a single logged write of the words from the first to the last
+    /// one which changes.
+    fn write_words(&self, offset: usize, words: &[i32]) -> DbResult<()> {
+        let old_words = self.read_words(offset, words.len() * INT_BYTES)?;
+        let changed = |i: &usize| old_words[*i] != words[*i];
+        let Some(first) = (0..words.len()).find(changed) else {
+            return Ok(());
+        };
+        let last = (0..words.len()).rfind(changed).unwrap();
+        let bytes: Vec<u8> = words[first..=last]
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect();
+        self.tx
+            .write_bytes(&self.blk, offset + first * INT_BYTES, &bytes, true)
+    }
+}
+
+impl<'a> RecordPage for SlottedRecordPage<'a> {
+    fn get_int(&self, slot: usize, field_name: &str) -> DbResult<i32> {
+        self.with_record(slot, |page, slot| {
+            let field_pos = page.field_pos(page.record_offset(slot)?, field_name)?;
+            page.tx.get_int(&page.blk, field_pos)
+        })
+    }
+
+    fn get_string(&self, slot: usize, field_name: &str) -> DbResult<String> {
+        if self.field_type(field_name) == FieldType::Text {
+            return self.get_val(slot, field_name)?.string_value(field_name);
+        }
+        self.with_record(slot, |page, slot| {
+            let field_pos = page.field_pos(page.record_offset(slot)?, field_name)?;
+            page.tx.get_string(&page.blk, field_pos)
+        })
+    }
+
+    fn set_int(&self, slot: usize, field_name: &str, val: i32) -> DbResult<()> {
+        self.set_val(slot, field_name, &Constant::Int(val))
+    }
+
+    /// A string of another length changes the size of the record, which may move it
+    /// to another block.
+    fn set_string(&self, slot: usize, field_name: &str, val: &str) -> DbResult<()> {
+        if self.field_type(field_name) == FieldType::Text {
+            return self.set_val(slot, field_name, &Constant::String(val.to_string()));
+        }
+        if !self.with_record(slot, |page, slot| page.write_string(slot, field_name, val))? {
+            self.relocate(slot, field_name, val)?;
+        }
+        Ok(())
+    }
+
+    fn get_val(&self, slot: usize, field_name: &str) -> DbResult<Constant> {
+        if self.is_null(slot, field_name)? {
+            return Ok(Constant::Null);
+        }
+        self.with_record(slot, |page, slot| {
+            let field_pos = page.field_pos(page.record_offset(slot)?, field_name)?;
+            let (tx, blk) = (&page.tx, &page.blk);
+            Ok(match page.field_type(field_name) {
+                FieldType::Integer => Constant::Int(tx.get_int(blk, field_pos)?),
+                FieldType::Varchar => Constant::String(tx.get_string(blk, field_pos)?),
+                FieldType::BigInt => Constant::BigInt(tx.get_long(blk, field_pos)?),
+                FieldType::Boolean => Constant::Bool(tx.get_bool(blk, field_pos)?),
+                FieldType::Double => Constant::Double(tx.get_double(blk, field_pos)?),
+                FieldType::Date => Constant::Date(tx.get_int(blk, field_pos)?),
+                FieldType::Timestamp => Constant::Timestamp(tx.get_long(blk, field_pos)?),
+                field_type @ (FieldType::Text | FieldType::Blob) => {
+                    overflow::read_field(tx, blk, field_pos, field_type)?
+                }
+            })
+        })
+    }
+
+    fn set_val(&self, slot: usize, field_name: &str, val: &Constant) -> DbResult<()> {
+        match val {
+            Constant::Null => return self.set_null(slot, field_name),
+            Constant::String(s) if self.field_type(field_name) == FieldType::Varchar => {
+                return self.set_string(slot, field_name, s);
+            }
+            _ => {}
+        }
+        self.with_record(slot, |page, slot| {
+            let offset = page.record_offset(slot)?;
+            let field_pos = page.field_pos(offset, field_name)?;
+            let (tx, blk) = (&page.tx, &page.blk);
+            match val {
+                _ if page.field_type(field_name).is_overflow() => {
+                    overflow::write_field(tx, blk, field_pos, val)?
+                }
+                Constant::Int(i) | Constant::Date(i) => tx.set_int(blk, field_pos, *i, true)?,
+                Constant::BigInt(i) | Constant::Timestamp(i) => {
+                    tx.set_long(blk, field_pos, *i, true)?
+                }
+                Constant::Bool(b) => tx.set_bool(blk, field_pos, *b, true)?,
+                Constant::Double(d) => tx.set_double(blk, field_pos, *d, true)?,
+                Constant::Bytes(_) => panic!("BLOB value for a field of another type"),
+                Constant::Null | Constant::String(_) => unreachable!(),
+            }
+            page.set_null_bit(offset, field_name, false)
+        })
+    }
+
+    fn is_null(&self, slot: usize, field_name: &str) -> DbResult<bool> {
+        self.with_record(slot, |page, slot| {
+            let (word_pos, mask) = page.layout.null_bit(field_name).expect("Field not found");
+            let word = page
+                .tx
+                .get_int(&page.blk, page.record_offset(slot)? + word_pos)?;
+            Ok(word & mask != 0)
+        })
+    }
+
+    fn set_null(&self, slot: usize, field_name: &str) -> DbResult<()> {
+        self.with_record(slot, |page, slot| {
+            let offset = page.record_offset(slot)?;
+            if page.field_type(field_name).is_overflow() {
+                overflow::free_field(&page.tx, &page.blk, page.field_pos(offset, field_name)?)?;
+            }
+            page.set_null_bit(offset, field_name, true)
+        })
+    }
+
+    /// Removes the record and its stub, the space is reclaimed right away.
+    /// The overflow blocks of its TEXT and BLOB values are freed.
+    fn delete(&self, slot: usize) -> DbResult<()> {
+        self.with_record(slot, |page, slot| {
+            let offset = page.record_offset(slot)?;
+            for field_name in &page.fields {
+                if page.field_type(field_name).is_overflow() {
+                    overflow::free_field(&page.tx, &page.blk, page.field_pos(offset, field_name)?)?;
+                }
+            }
+            Ok(())
+        })?;
+        if let Some(rid) = self.forwarded_to(slot)? {
+            self.page(rid.block_number())?.remove(rid.slot())?;
+        }
+        self.remove(slot)?;
+        record_page::set_free(&self.tx, &self.blk, true)
+    }
+
+    fn format(&self) -> DbResult<()> {
+        self.tx.set_int(&self.blk, 0, 0, false)?;
+        self.tx.set_int(&self.blk, INT_BYTES, 0, false)
+    }
+
+    fn clear(&self) -> DbResult<()> {
+        record_page::clear(&self.tx, &self.blk)
+    }
+
+    /// Skips the records moved here from other blocks, they are read through their stubs.
+    fn next_after(&self, slot: usize) -> DbResult<Option<usize>> {
+        for next in slot + 1..=self.num_slots()? {
+            let (offset, _) = self.entry(next)?;
+            if offset != 0 && self.tx.get_int(&self.blk, offset)? != MOVED {
+                return Ok(Some(next));
+            }
+        }
+        Ok(None)
+    }
+
+    fn insert_after(&self, slot: usize) -> DbResult<Option<usize>> {
+        let inserted = self.insert_record(slot, &self.new_record())?;
+        if inserted.is_none() && slot == 0 {
+            record_page::set_free(&self.tx, &self.blk, false)?;
+        }
+        Ok(inserted)
+    }
+
+    fn block(&self) -> &BlockId {
+        &self.blk
+    }
+}
+
+impl<'a> Drop for SlottedRecordPage<'a> {
+    fn drop(&mut self) {
+        self.tx.unpin(&self.blk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::layout::RecordFormat;
+    use crate::record::schema::Schema;
+    use crate::utils::testing_utils::temp_db;
+
+    fn test_layout() -> Layout {
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        schema.add_string_field("name", 200);
+        Layout::new(schema).with_format(RecordFormat::Slotted)
+    }
+
+    #[test]
+    fn test_slotted_record_page_basic() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+        let tx = db.new_tx()?;
+        let blk = tx.append("testfile")?;
+        {
+            let page = SlottedRecordPage::new(tx.clone(), blk.clone(), layout)?;
+            page.format()?;
+            // a fixed slot of this layout takes half the block, a record only the bytes of its values
+            let mut slots = Vec::new();
+            for id in 0..10 {
+                let slot = page.insert_after(0)?.expect("Failed to insert");
+                page.set_int(slot, "id", id)?;
+                page.set_string(slot, "name", &"x".repeat(id as usize))?;
+                slots.push(slot);
+            }
+            assert_eq!(slots, (1..=10).collect::<Vec<_>>());
+
+            let before_delete = page.free_space()?;
+            page.delete(3)?;
+            page.delete(7)?;
+            assert!(page.free_space()? > before_delete);
+            assert_eq!(page.next_after(2)?, Some(4));
+            assert_eq!(page.next_after(6)?, Some(8));
+
+            // the records keep their slots as the page is compacted
+            for (id, slot) in slots.iter().enumerate() {
+                if *slot == 3 || *slot == 7 {
+                    continue;
+                }
+                assert_eq!(page.get_int(*slot, "id")?, id as i32);
+                assert_eq!(page.get_string(*slot, "name")?, "x".repeat(id));
+            }
+
+            // a string of another length resizes the record in place
+            page.set_string(5, "name", "a longer name than before")?;
+            page.set_val(6, "name", &Constant::Null)?;
+            assert_eq!(page.get_string(5, "name")?, "a longer name than before");
+            assert_eq!(page.get_val(6, "name")?, Constant::Null);
+            assert_eq!(page.get_int(6, "id")?, 5);
+            assert_eq!(page.get_string(8, "name")?, "x".repeat(7));
+
+            // the empty slots are reused
+            assert_eq!(page.insert_after(0)?, Some(3));
+            assert_eq!(page.get_string(3, "name")?, "");
+            assert!(!page.is_null(3, "id")?);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_slotted_record_page_forwards_grown_record() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+        let tx = db.new_tx()?;
+        let blk = tx.append("testfile")?;
+        {
+            let page = SlottedRecordPage::new(tx.clone(), blk.clone(), layout.clone())?;
+            page.format()?;
+            let mut slot = 0;
+            while let Some(new_slot) = page.insert_after(slot)? {
+                page.set_int(new_slot, "id", new_slot as i32)?;
+                page.set_string(new_slot, "name", "abcd")?;
+                slot = new_slot;
+            }
+
+            // the grown record no longer fits and moves to a new block
+            let long_name = "y".repeat(150);
+            page.set_string(2, "name", &long_name)?;
+            assert_eq!(tx.size("testfile")?, 2);
+            assert_eq!(page.get_string(2, "name")?, long_name);
+            assert_eq!(page.get_int(2, "id")?, 2);
+            page.set_int(2, "id", 42)?;
+            assert_eq!(page.get_int(2, "id")?, 42);
+            assert_eq!(page.next_after(1)?, Some(2));
+
+            // scans only see the stub, not the moved record
+            {
+                let other = SlottedRecordPage::new(
+                    tx.clone(),
+                    BlockId::new("testfile".to_string(), 1),
+                    layout.clone(),
+                )?;
+                assert_eq!(other.next_after(0)?, None);
+            }
+
+            page.set_string(2, "name", "short")?;
+            assert_eq!(page.get_string(2, "name")?, "short");
+            assert_eq!(page.get_int(2, "id")?, 42);
+
+            // the moved record is deleted together with its stub
+            page.set_string(3, "name", &long_name)?;
+            let rid = page.forwarded_to(3)?.expect("Record not forwarded");
+            page.delete(3)?;
+            let other = page.page(rid.block_number())?;
+            assert_eq!(other.entry(rid.slot())?, (0, 0));
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_slotted_record_page_compaction_is_undone_by_rollback() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+
+        let tx = db.new_tx()?;
+        let blk = tx.append("testfile")?;
+        {
+            let page = SlottedRecordPage::new(tx.clone(), blk.clone(), layout.clone())?;
+            page.format()?;
+            for id in 1..=5 {
+                let slot = page.insert_after(0)?.expect("Failed to insert");
+                page.set_int(slot, "id", id)?;
+                page.set_string(slot, "name", &format!("name {id}"))?;
+            }
+        }
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        {
+            let page = SlottedRecordPage::new(tx.clone(), blk.clone(), layout.clone())?;
+            page.delete(1)?;
+            page.set_string(4, "name", "a much longer name")?;
+            assert_eq!(page.next_after(0)?, Some(2));
+        }
+        tx.rollback()?;
+
+        let tx = db.new_tx()?;
+        {
+            let page = SlottedRecordPage::new(tx.clone(), blk.clone(), layout)?;
+            for id in 1..=5 {
+                assert_eq!(page.get_int(id, "id")?, id as i32);
+                assert_eq!(page.get_string(id, "name")?, format!("name {id}"));
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
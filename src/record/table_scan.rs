@@ -1,7 +1,7 @@
-use super::RecordPage;
+use super::free_space_map::FreeSpaceMap;
 use super::layout::Layout;
+use super::record_page::{self, RecordPage};
 use super::rid::RID;
-use super::schema::FieldType;
 use crate::error::{DbError, DbResult};
 use crate::query::{Constant, Scan, UpdateScan};
 use crate::storage::BlockId;
@@ -10,7 +10,7 @@ use crate::tx::Transaction;
 pub struct TableScan<'tx> {
     tx: Transaction<'tx>,
     layout: Layout,
-    record_page: Option<RecordPage<'tx>>,
+    record_page: Option<Box<dyn RecordPage + 'tx>>,
     file_name: String,
     current_slot: Option<usize>,
 }
@@ -41,16 +41,6 @@ impl<'tx> TableScan<'tx> {
         self.move_to_block(size - 1)
     }
 
-    fn find_first_slot(&mut self) -> DbResult<bool> {
-        if let Some(rp) = &self.record_page {
-            if let Some(slot) = rp.next_after(0)? {
-                self.current_slot = Some(slot);
-                return Ok(true);
-            }
-        }
-        Ok(false)
-    }
-
     fn at_last_block(&self) -> DbResult<bool> {
         let rp = self
             .record_page
@@ -64,16 +54,33 @@ impl<'tx> TableScan<'tx> {
         self.record_page.take();
 
         let blk = BlockId::new(self.file_name.clone(), blk_number);
-        self.record_page = Some(RecordPage::new(self.tx.clone(), blk, self.layout.clone())?);
+        self.record_page = Some(record_page::open(
+            self.tx.clone(),
+            blk,
+            self.layout.clone(),
+        )?);
         self.current_slot = None;
         Ok(())
     }
 
+    /// Inserts a new record after the slot of the current block, returning false if it has no room.
+    fn insert_into_current_block(&mut self, slot: usize) -> DbResult<bool> {
+        let rp = self
+            .record_page
+            .as_ref()
+            .expect("Record page not initialized");
+        let inserted = rp.insert_after(slot)?;
+        if inserted.is_some() {
+            self.current_slot = inserted;
+        }
+        Ok(inserted.is_some())
+    }
+
     fn move_to_new_block(&mut self) -> DbResult<()> {
         self.record_page.take();
 
         let blk = self.tx.append(&self.file_name)?;
-        let record_page = RecordPage::new(self.tx.clone(), blk, self.layout.clone())?;
+        let record_page = record_page::open(self.tx.clone(), blk, self.layout.clone())?;
         record_page.format()?;
         self.record_page = Some(record_page);
         self.current_slot = None;
@@ -86,55 +93,61 @@ impl<'tx> Scan for TableScan<'tx> {
         self.move_to_block(0)
     }
 
+    /// Moves to the next record, skipping the blocks whose records were all deleted.
     fn next(&mut self) -> DbResult<bool> {
-        let current = self.current_slot.unwrap_or(0);
-
-        if let Some(rp) = &self.record_page {
+        while let Some(rp) = &self.record_page {
+            let current = self.current_slot.unwrap_or(0);
             if let Some(slot) = rp.next_after(current)? {
                 self.current_slot = Some(slot);
                 return Ok(true);
             }
 
-            if !self.at_last_block()? {
-                let next_block = rp.block().number() + 1;
-                self.move_to_block(next_block)?;
-                return self.find_first_slot();
+            if self.at_last_block()? {
+                break;
             }
+            let next_block = rp.block().number() + 1;
+            self.move_to_block(next_block)?;
         }
 
         Ok(false)
     }
 
+    /// Fails if the field is null, use `get_val` to read fields which may be null.
     fn get_int(&mut self, field_name: &str) -> DbResult<i32> {
         let slot = self.current_slot.expect("No current record");
         let rp = self
             .record_page
             .as_ref()
             .expect("Record page not initialized");
+        if rp.is_null(slot, field_name)? {
+            return Constant::Null.int_value(field_name);
+        }
         rp.get_int(slot, field_name)
     }
 
+    /// Fails if the field is null, use `get_val` to read fields which may be null.
     fn get_string(&mut self, field_name: &str) -> DbResult<String> {
         let slot = self.current_slot.expect("No current record");
         let rp = self
             .record_page
             .as_ref()
             .expect("Record page not initialized");
+        if rp.is_null(slot, field_name)? {
+            return Constant::Null.string_value(field_name);
+        }
         rp.get_string(slot, field_name)
     }
 
     fn get_val(&mut self, field_name: &str) -> DbResult<Constant> {
-        match self.layout.schema().field_type(field_name) {
-            Some(FieldType::Integer) => {
-                let val = self.get_int(field_name)?;
-                Ok(Constant::Int(val))
-            }
-            Some(FieldType::Varchar) => {
-                let val = self.get_string(field_name)?;
-                Ok(Constant::String(val))
-            }
-            None => Err(DbError::FieldNotFound(field_name.to_string())),
+        if !self.has_field(field_name) {
+            return Err(DbError::FieldNotFound(field_name.to_string()));
         }
+        let slot = self.current_slot.expect("No current record");
+        let rp = self
+            .record_page
+            .as_ref()
+            .expect("Record page not initialized");
+        rp.get_val(slot, field_name)
     }
 
     fn has_field(&self, field_name: &str) -> bool {
@@ -143,11 +156,28 @@ impl<'tx> Scan for TableScan<'tx> {
 }
 
 impl<'tx> UpdateScan for TableScan<'tx> {
+    /// Converts the value to the type of the field, failing if it doesn't fit, see
+    /// [Constant::coerce_to].
     fn set_val(&mut self, field_name: &str, val: Constant) -> DbResult<()> {
-        match val {
-            Constant::Int(i) => self.set_int(field_name, i),
-            Constant::String(s) => self.set_string(field_name, &s),
-        }
+        let field_type = self
+            .layout
+            .schema()
+            .field_type(field_name)
+            .ok_or_else(|| DbError::FieldNotFound(field_name.to_string()))?;
+        let val = val.coerce_to(field_type).ok_or_else(|| {
+            DbError::Schema(format!(
+                "Value {} does not fit field {} of type {}",
+                val.to_string(),
+                field_name,
+                field_type
+            ))
+        })?;
+        let slot = self.current_slot.expect("No current record");
+        let rp = self
+            .record_page
+            .as_ref()
+            .expect("Record page not initialized");
+        rp.set_val(slot, field_name, &val)
     }
 
     fn set_int(&mut self, field_name: &str, val: i32) -> DbResult<()> {
@@ -168,28 +198,37 @@ impl<'tx> UpdateScan for TableScan<'tx> {
         rp.set_string(slot, field_name, val)
     }
 
+    /// Inserts a new record after the current one or, if the current block has no room,
+    /// into a block listed in the free space map of the table, the last block or a new block.
     fn insert(&mut self) -> DbResult<()> {
         let current_slot = self.current_slot.unwrap_or(0);
-        let current_page = self.record_page.as_ref().unwrap();
-        let mut inserted_at_slot = current_page.insert_after(current_slot)?;
+        if self.insert_into_current_block(current_slot)? {
+            return Ok(());
+        }
 
-        loop {
-            if !inserted_at_slot.is_none() {
-                self.current_slot = inserted_at_slot;
+        // a listed block which turns out to be full is unlisted by the failed insert
+        let free_space_map = FreeSpaceMap::new(self.tx.clone(), &self.file_name);
+        while let Some(blk_num) = free_space_map.find_free()? {
+            self.move_to_block(blk_num)?;
+            if self.insert_into_current_block(0)? {
                 return Ok(());
             }
+        }
 
-            // have not found place in the current page, move to the next
-            // TODO just single method -> move_next()
-            if self.at_last_block()? {
-                self.move_to_new_block()?;
-            } else {
-                self.move_to_block(self.record_page.as_ref().unwrap().block().number() + 1)?;
+        if !self.at_last_block()? {
+            self.move_to_last()?;
+            if self.insert_into_current_block(0)? {
+                return Ok(());
             }
-
-            let current_slot = self.current_slot.unwrap_or(0);
-            inserted_at_slot = self.record_page.as_ref().unwrap().insert_after(current_slot)?;
         }
+        self.move_to_new_block()?;
+        if self.insert_into_current_block(0)? {
+            return Ok(());
+        }
+        Err(DbError::Schema(format!(
+            "Record of table file {} does not fit in a block",
+            self.file_name
+        )))
     }
 
     fn delete(&mut self) -> DbResult<()> {
@@ -214,7 +253,11 @@ impl<'tx> UpdateScan for TableScan<'tx> {
         self.record_page.take();
 
         let blk = BlockId::new(self.file_name.clone(), row_id.block_number());
-        self.record_page = Some(RecordPage::new(self.tx.clone(), blk, self.layout.clone())?);
+        self.record_page = Some(record_page::open(
+            self.tx.clone(),
+            blk,
+            self.layout.clone(),
+        )?);
         self.current_slot = Some(row_id.slot() as usize);
         Ok(())
     }
@@ -266,6 +309,50 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_insert_reuses_space_of_deleted_records() -> DbResult<()> {
+        let db = temp_db()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        schema.add_string_field("name", 20);
+        let layout = Layout::new(schema);
+
+        let tx = db.new_tx()?;
+        // the last block keeps room for a few records
+        for i in 0..95 {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            scan.insert()?;
+            scan.set_int("id", i)?;
+        }
+        let size = tx.size("test_table.tbl")?;
+        assert!(size > 3);
+
+        // free a slot in the second block
+        let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+        let mut deleted = None;
+        while scan.next()? {
+            if scan.get_rid()?.block_number() == 1 {
+                deleted = Some(scan.get_rid()?);
+                scan.delete()?;
+                break;
+            }
+        }
+        tx.commit()?;
+
+        let tx = db.new_tx()?;
+        let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+        scan.insert()?;
+        assert_eq!(Some(scan.get_rid()?), deleted);
+        // the block is full again, so the next record goes to the end of the table
+        let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+        scan.insert()?;
+        assert_eq!(scan.get_rid()?.block_number(), size - 1);
+        assert_eq!(tx.size("test_table.tbl")?, size);
+        tx.commit()?;
+        Ok(())
+    }
+
     #[test]
     fn test_insert_and_scan_in_dedicated_tx() -> DbResult<()> {
         let num_keys = 500;
@@ -306,4 +393,38 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_scan_skips_empty_blocks() -> DbResult<()> {
+        let db = temp_db()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        schema.add_string_field("name", 20);
+        let layout = Layout::new(schema);
+
+        let tx = db.new_tx()?;
+        let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+        for i in 0..50 {
+            scan.insert()?;
+            scan.set_int("id", i)?;
+        }
+        // empty the first blocks
+        scan.before_first()?;
+        while scan.next()? {
+            if scan.get_int("id")? < 40 {
+                scan.delete()?;
+            }
+        }
+        assert!(tx.size("test_table.tbl")? > 4);
+
+        scan.before_first()?;
+        let mut ids = Vec::new();
+        while scan.next()? {
+            ids.push(scan.get_int("id")?);
+        }
+        assert_eq!(ids, (40..50).collect::<Vec<_>>());
+        tx.commit()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::DbResult;
+use crate::record::overflow::OverflowFile;
+use crate::record::{FreeSpaceMap, Layout, Schema, TableScan};
+use crate::storage::TEMP_FILE_PREFIX;
+use crate::tx::Transaction;
+
+static NEXT_TABLE_NUM: AtomicUsize = AtomicUsize::new(0);
+
+/// A table which holds intermediate results of a query. It is not registered in the
+/// catalog and changes to it are not logged. Its file is deleted by its user once it is
+/// not needed anymore, and the ones left behind when the database restarts.
+#[derive(Clone)]
+pub struct TempTable {
+    table_name: String,
+    layout: Layout,
+}
+
+impl TempTable {
+    pub fn new(schema: Schema) -> Self {
+        let num = NEXT_TABLE_NUM.fetch_add(1, Ordering::SeqCst);
+        TempTable {
+            table_name: format!("{}{}", TEMP_FILE_PREFIX, num),
+            layout: Layout::new(schema),
+        }
+    }
+
+    pub fn open<'tx>(&self, tx: Transaction<'tx>) -> DbResult<TableScan<'tx>> {
+        TableScan::new(tx, &self.table_name, self.layout.clone())
+    }
+
+    /// Deletes the files of the table. The scans opened on it must be closed.
+    pub fn delete(&self, tx: &Transaction<'_>) -> DbResult<()> {
+        tx.remove_file(&format!("{}.tbl", self.table_name))?;
+        tx.remove_file(&OverflowFile::file_name(&self.table_name))?;
+        tx.remove_file(&FreeSpaceMap::file_name(&self.table_name))
+    }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        query::{Scan, UpdateScan},
+        utils::testing_utils::temp_db,
+    };
+
+    #[test]
+    fn test_temp_table() -> DbResult<()> {
+        let db = temp_db()?;
+        let tx = db.new_tx()?;
+
+        let mut schema = Schema::new();
+        schema.add_int_field("a");
+        let temp1 = TempTable::new(schema.clone());
+        let temp2 = TempTable::new(schema);
+        assert_ne!(temp1.table_name(), temp2.table_name());
+        assert!(temp1.table_name().starts_with(TEMP_FILE_PREFIX));
+
+        let log_before = db.log_mgr().latest_lsn();
+        let mut scan = temp1.open(tx.clone())?;
+        for i in 0..50 {
+            scan.insert()?;
+            scan.set_int("a", i)?;
+        }
+        assert_eq!(
+            db.log_mgr().latest_lsn(),
+            log_before,
+            "temp tables are not logged"
+        );
+
+        scan.before_first()?;
+        let mut count = 0;
+        while scan.next()? {
+            assert_eq!(scan.get_int("a")?, count);
+            count += 1;
+        }
+        assert_eq!(count, 50);
+        drop(scan);
+
+        temp1.delete(&tx)?;
+        assert_eq!(tx.size(&format!("{}.tbl", temp1.table_name()))?, 0);
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_temp_table_prefix_is_not_a_table_name() {
+        let parser = crate::parse::Parser::new();
+        let sql = format!("CREATE TABLE {}1 (a INT)", TEMP_FILE_PREFIX);
+        assert!(parser.parse(&sql).is_err());
+    }
+}
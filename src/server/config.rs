@@ -1,4 +1,7 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::log::{Durability, LogMgr};
 
 /// Configuration for file-based storage manager
 #[derive(Clone)]
@@ -76,6 +79,12 @@ pub struct Config {
     pub storage_mgr: StorageMgrConfig,
     pub buffer_capacity: usize,
     pub log_file_name: String,
+    /// Number of blocks in a log segment file
+    pub log_segment_blocks: usize,
+    /// How commits make the log durable
+    pub durability: Durability,
+    /// Interval between background non-quiescent checkpoints, `None` disables them
+    pub checkpoint_interval: Option<Duration>,
 }
 
 impl Config {
@@ -84,6 +93,9 @@ impl Config {
             storage_mgr,
             buffer_capacity: 64,
             log_file_name: "simpledb.log".to_string(),
+            log_segment_blocks: LogMgr::DEFAULT_SEGMENT_BLOCKS,
+            durability: Durability::PerCommit,
+            checkpoint_interval: None,
         }
     }
 
@@ -115,6 +127,21 @@ impl Config {
         self
     }
 
+    pub fn log_segment_blocks(mut self, log_segment_blocks: usize) -> Self {
+        self.log_segment_blocks = log_segment_blocks;
+        self
+    }
+
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    pub fn checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = Some(interval);
+        self
+    }
+
     pub fn log_file_path(&self) -> PathBuf {
         match &self.storage_mgr {
             StorageMgrConfig::File(config) => config.db_directory.join(&self.log_file_name),
@@ -1,5 +1,8 @@
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use crate::buffer::BufferMgr;
 use crate::error::DbResult;
@@ -10,6 +13,8 @@ use crate::plan::Planner;
 use crate::storage::{FileStorageMgr, MemStorageMgr, StorageMgr};
 use crate::tx::{Transaction, TransactionIntent};
 use crate::tx::concurrency::LockTable;
+use crate::tx::recovery::CheckpointMgr;
+use crate::tx::transaction::TxRegistry;
 
 use super::Config;
 
@@ -20,6 +25,39 @@ pub struct SimpleDB {
     planner: Option<Planner>,
     metadata_mgr: Option<Arc<MetadataMgr>>,
     lock_table: Arc<LockTable>,
+    tx_registry: Arc<TxRegistry>,
+    checkpointer: Option<Checkpointer>,
+}
+
+/// Background thread which periodically takes a non-quiescent checkpoint.
+/// It stops once the sender is dropped.
+struct Checkpointer {
+    stop: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl Checkpointer {
+    fn start(
+        interval: Duration,
+        log_mgr: Arc<LogMgr>,
+        buffer_mgr: Arc<BufferMgr>,
+        tx_registry: Arc<TxRegistry>,
+    ) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            let checkpoint_mgr = CheckpointMgr::new(&log_mgr, &buffer_mgr, &tx_registry);
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                // a failed checkpoint only makes the next recovery scan further back
+                let _ = checkpoint_mgr.checkpoint();
+            }
+        });
+        Checkpointer { stop, handle }
+    }
+
+    fn stop(self) {
+        drop(self.stop);
+        let _ = self.handle.join();
+    }
 }
 
 impl SimpleDB {
@@ -34,10 +72,12 @@ impl SimpleDB {
         };
         let is_new_db = storage_mgr.is_new();
 
-        let log_mgr = Arc::new(LogMgr::new(
+        let log_mgr = Arc::new(LogMgr::with_segment_blocks(
             Arc::clone(&storage_mgr),
             config.log_file_path().to_str().unwrap(),
-        )?);
+            config.log_segment_blocks,
+        )?
+        .durability(config.durability));
         let buffer_mgr = Arc::new(BufferMgr::new(
             Arc::clone(&storage_mgr),
             Arc::clone(&log_mgr),
@@ -45,7 +85,6 @@ impl SimpleDB {
         ));
         let lock_table = Arc::new(LockTable::new());
 
-        // TODO recover if is_new
         let mut db = Self {
             storage_mgr,
             log_mgr,
@@ -53,9 +92,14 @@ impl SimpleDB {
             metadata_mgr: None,
             planner: None,
             lock_table,
+            tx_registry: Arc::new(TxRegistry::new()),
+            checkpointer: None,
         };
 
         let tx = db.new_tx()?;
+        if !is_new_db {
+            tx.recover()?;
+        }
         let table_mgr = Arc::new(TableMgr::new(is_new_db, tx.clone())?);
         let index_mgr = Arc::new(IndexMgr::new(
             is_new_db,
@@ -71,6 +115,15 @@ impl SimpleDB {
         db.metadata_mgr = Some(metadata_mgr);
         db.planner = Some(planner);
 
+        if let Some(interval) = config.checkpoint_interval {
+            db.checkpointer = Some(Checkpointer::start(
+                interval,
+                Arc::clone(&db.log_mgr),
+                Arc::clone(&db.buffer_mgr),
+                Arc::clone(&db.tx_registry),
+            ));
+        }
+
         Ok(db)
     }
 
@@ -104,6 +157,7 @@ impl SimpleDB {
             &self.log_mgr,
             &self.buffer_mgr,
             Arc::clone(&self.lock_table),
+            &self.tx_registry,
             None
         )
     }
@@ -116,14 +170,37 @@ impl SimpleDB {
             &self.log_mgr,
             &self.buffer_mgr,
             Arc::clone(&self.lock_table),
+            &self.tx_registry,
             Some(TransactionIntent::WriteOnly)
         )
     }
 
+    /// Takes a non-quiescent checkpoint: flushes all modified pages and logs the
+    /// active transactions, which bounds how much of the log recovery has to read.
+    pub fn checkpoint(&self) -> DbResult<()> {
+        CheckpointMgr::new(&self.log_mgr, &self.buffer_mgr, &self.tx_registry).checkpoint()
+    }
+
+    /// Compacts a table and rebuilds its indexes in a transaction of its own.
+    pub fn vacuum(&self, table_name: &str) -> DbResult<()> {
+        let tx = self.new_write_tx()?;
+        match self.metadata_mgr().vacuum(table_name, tx.clone()) {
+            Ok(()) => tx.commit(),
+            Err(e) => {
+                tx.rollback()?;
+                Err(e)
+            }
+        }
+    }
+
     pub fn buffer_mgr<'a>(&'a self) -> &'a BufferMgr {
         &self.buffer_mgr
     }
 
+    pub fn log_mgr(&self) -> &LogMgr {
+        &self.log_mgr
+    }
+
     pub fn metadata_mgr(&self) -> &MetadataMgr {
         &*self.metadata_mgr.as_ref().unwrap()
     }
@@ -134,10 +211,23 @@ impl SimpleDB {
     }
 }
 
+impl Drop for SimpleDB {
+    fn drop(&mut self) {
+        if let Some(checkpointer) = self.checkpointer.take() {
+            checkpointer.stop();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{record::Schema, server::config::StorageMgrConfig};
+    use crate::{
+        record::Schema,
+        server::config::StorageMgrConfig,
+        tx::recovery::log_record::{NQCKPT_FLAG, create_log_record},
+        utils::testing_utils::temp_db_with_cfg,
+    };
     use tempfile::TempDir;
 
     #[test]
@@ -171,4 +261,41 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_background_checkpoint() -> DbResult<()> {
+        let db = temp_db_with_cfg(|cfg| cfg.checkpoint_interval(Duration::from_millis(10)))?;
+
+        {
+            let md_mgr = db.metadata_mgr();
+            let tx = db.new_tx()?;
+            let mut test_schema = Schema::new();
+            test_schema.add_int_field("id");
+            md_mgr.create_table("test_table", &test_schema, tx.clone())?;
+            tx.commit()?;
+        }
+
+        let has_nq_checkpoint = || -> DbResult<bool> {
+            let mut iter = db.log_mgr.iterator()?;
+            while iter.has_next() {
+                if create_log_record(&iter.next()?)?.op() == NQCKPT_FLAG {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        };
+
+        let start = std::time::Instant::now();
+        while !has_nq_checkpoint()? {
+            assert!(start.elapsed() < Duration::from_secs(5), "no checkpoint was written");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        let layout = db.metadata_mgr().get_layout("test_table", tx.clone())?;
+        assert!(layout.schema().has_field("id"));
+        tx.commit()?;
+        Ok(())
+    }
 }
@@ -25,6 +25,11 @@ impl BlockId {
     pub fn number(&self) -> i32 {
         self.number
     }
+
+    /// Returns true if the block belongs to a temporary table.
+    pub fn is_temp(&self) -> bool {
+        self.file_name.starts_with(super::TEMP_FILE_PREFIX)
+    }
 }
 
 impl Display for BlockId {
@@ -12,6 +12,11 @@ use std::sync::Mutex;
 
 use crate::error::DbResult;
 
+/// Files whose names start with this prefix hold temporary tables. They are deleted
+/// when the database starts. SQL identifiers can't start with a `~`, so no table
+/// created by a statement gets this prefix.
+pub const TEMP_FILE_PREFIX: &str = "~temp";
+
 /// Trait for file management operations.
 /// This allows for different implementations (e.g., basic file system, in-memory, etc.)
 /// Must be thread safe and support interrior mutability.
@@ -28,6 +33,19 @@ pub trait StorageMgr: Send + Sync {
     /// Returns the number of blocks in the specified file.
     fn block_cnt(&self, filename: &str) -> io::Result<i32>;
 
+    /// Forces all blocks written to the specified file to the storage device.
+    fn sync(&self, filename: &str) -> io::Result<()>;
+
+    /// Forces the files created, deleted and renamed so far to the storage device.
+    fn sync_dir(&self) -> io::Result<()>;
+
+    /// Deletes the specified file. Deleting a file which does not exist is not an error.
+    fn delete_file(&self, filename: &str) -> io::Result<()>;
+
+    /// Renames a file, replacing the file of the new name if there is one.
+    /// Renaming a file which does not exist is not an error.
+    fn rename_file(&self, from: &str, to: &str) -> io::Result<()>;
+
     /// Returns whether this is a new database.
     fn is_new(&self) -> bool;
 
@@ -63,7 +81,7 @@ impl FileStorageMgr {
                 let entry = entry?;
                 let filename = entry.file_name();
                 let filename_str = filename.to_string_lossy();
-                if filename_str.starts_with("temp") {
+                if filename_str.starts_with(TEMP_FILE_PREFIX) {
                     fs::remove_file(entry.path())?;
                 }
             }
@@ -161,6 +179,35 @@ impl StorageMgr for FileStorageMgr {
         Ok((file_size / self.block_size as u64) as i32)
     }
 
+    fn sync(&self, filename: &str) -> io::Result<()> {
+        self.get_file(filename)?.sync_data()
+    }
+
+    fn sync_dir(&self) -> io::Result<()> {
+        File::open(&self.db_directory)?.sync_all()
+    }
+
+    fn delete_file(&self, filename: &str) -> io::Result<()> {
+        let mut open_files = self.open_files.lock().unwrap();
+        open_files.remove(filename);
+
+        match fs::remove_file(self.db_directory.join(filename)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn rename_file(&self, from: &str, to: &str) -> io::Result<()> {
+        let mut open_files = self.open_files.lock().unwrap();
+        open_files.remove(from);
+        open_files.remove(to);
+
+        match fs::rename(self.db_directory.join(from), self.db_directory.join(to)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
     fn is_new(&self) -> bool {
         self.is_new
     }
@@ -262,6 +309,27 @@ impl StorageMgr for MemStorageMgr {
         Ok(file_blocks.map(|blocks| blocks.len() as i32).unwrap_or(0))
     }
 
+    fn sync(&self, _filename: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn sync_dir(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn delete_file(&self, filename: &str) -> io::Result<()> {
+        self.files.lock().unwrap().remove(filename);
+        Ok(())
+    }
+
+    fn rename_file(&self, from: &str, to: &str) -> io::Result<()> {
+        let mut files = self.files.lock().unwrap();
+        if let Some(blocks) = files.remove(from) {
+            files.insert(to.to_string(), blocks);
+        }
+        Ok(())
+    }
+
     fn is_new(&self) -> bool {
         true
     }
@@ -293,6 +361,37 @@ mod tests {
         assert_eq!(storage_mgr.block_cnt(filename).unwrap(), 3);
     }
 
+    #[test]
+    fn test_delete_file() {
+        let temp_dir = tempdir().unwrap();
+        let storage_mgr = FileStorageMgr::new(temp_dir.path(), 400).unwrap();
+
+        let filename = "testfile";
+        storage_mgr.append(filename).unwrap();
+        assert!(temp_dir.path().join(filename).exists());
+
+        storage_mgr.delete_file(filename).unwrap();
+        assert!(!temp_dir.path().join(filename).exists());
+        // deleting a missing file is a no-op
+        storage_mgr.delete_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_rename_file() {
+        let temp_dir = tempdir().unwrap();
+        let storage_mgr = FileStorageMgr::new(temp_dir.path(), 400).unwrap();
+
+        storage_mgr.append("old").unwrap();
+        storage_mgr.append("new").unwrap();
+        storage_mgr.append("new").unwrap();
+        storage_mgr.rename_file("new", "old").unwrap();
+        assert!(!temp_dir.path().join("new").exists());
+        assert_eq!(storage_mgr.block_cnt("old").unwrap(), 2);
+        // renaming a missing file is a no-op
+        storage_mgr.rename_file("new", "old").unwrap();
+        assert_eq!(storage_mgr.block_cnt("old").unwrap(), 2);
+    }
+
     #[test]
     fn test_read_write() {
         let temp_dir = tempdir().unwrap();
@@ -386,6 +485,30 @@ mod tests {
         assert_eq!(storage_mgr.block_cnt(filename).unwrap(), 3);
     }
 
+    #[test]
+    fn test_mem_storage_mgr_delete_file() {
+        let storage_mgr = MemStorageMgr::new(400);
+
+        let filename = "testfile";
+        storage_mgr.append(filename).unwrap();
+        storage_mgr.append(filename).unwrap();
+
+        storage_mgr.delete_file(filename).unwrap();
+        assert_eq!(storage_mgr.block_cnt(filename).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_mem_storage_mgr_rename_file() {
+        let storage_mgr = MemStorageMgr::new(400);
+
+        storage_mgr.append("old").unwrap();
+        storage_mgr.append("new").unwrap();
+        storage_mgr.append("new").unwrap();
+        storage_mgr.rename_file("new", "old").unwrap();
+        assert_eq!(storage_mgr.block_cnt("new").unwrap(), 0);
+        assert_eq!(storage_mgr.block_cnt("old").unwrap(), 2);
+    }
+
     #[test]
     fn test_mem_storage_mgr_read_write() {
         let storage_mgr = MemStorageMgr::new(400);
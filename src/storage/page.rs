@@ -35,6 +35,35 @@ impl Page {
         cursor.write_i32::<BigEndian>(n).unwrap();
     }
 
+    /// A long takes two int words, the high word first.
+    pub fn get_long(&self, offset: usize) -> i64 {
+        let mut cursor = Cursor::new(&self.buffer[offset..offset + 8]);
+        cursor.read_i64::<BigEndian>().unwrap()
+    }
+
+    pub fn set_long(&mut self, offset: usize, n: i64) {
+        let mut cursor = Cursor::new(&mut self.buffer[offset..offset + 8]);
+        cursor.write_i64::<BigEndian>(n).unwrap();
+    }
+
+    /// A double is stored as the long with the same bits.
+    pub fn get_double(&self, offset: usize) -> f64 {
+        f64::from_bits(self.get_long(offset) as u64)
+    }
+
+    pub fn set_double(&mut self, offset: usize, d: f64) {
+        self.set_long(offset, d.to_bits() as i64);
+    }
+
+    /// A boolean takes a whole int word, which is 1 for true and 0 for false.
+    pub fn get_bool(&self, offset: usize) -> bool {
+        self.get_int(offset) != 0
+    }
+
+    pub fn set_bool(&mut self, offset: usize, b: bool) {
+        self.set_int(offset, b as i32);
+    }
+
     // TODO potentially avoid unneeded copy?
     pub fn get_bytes(&self, offset: usize) -> Vec<u8> {
         let length = self.get_int(offset) as usize;
@@ -107,6 +136,30 @@ mod tests {
         assert_eq!(page.get_int(12), i32::MIN);
     }
 
+    #[test]
+    fn test_get_set_fixed_width_types() {
+        let mut page = Page::new(100);
+
+        page.set_long(0, i64::MIN);
+        assert_eq!(page.get_long(0), i64::MIN);
+        page.set_long(8, -5_000_000_000);
+        assert_eq!(page.get_long(8), -5_000_000_000);
+        // the high word comes first
+        page.set_long(16, (7 << 32) | 9);
+        assert_eq!((page.get_int(16), page.get_int(20)), (7, 9));
+
+        page.set_double(24, -2.5);
+        assert_eq!(page.get_double(24), -2.5);
+        page.set_double(32, f64::INFINITY);
+        assert_eq!(page.get_double(32), f64::INFINITY);
+
+        page.set_bool(40, true);
+        assert!(page.get_bool(40));
+        assert_eq!(page.get_int(40), 1);
+        page.set_bool(40, false);
+        assert!(!page.get_bool(40));
+    }
+
     #[test]
     fn test_get_set_bytes() {
         let mut page = Page::new(100);
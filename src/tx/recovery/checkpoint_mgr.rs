@@ -0,0 +1,105 @@
+use crate::buffer::BufferMgr;
+use crate::error::DbResult;
+use crate::log::LogMgr;
+use crate::tx::transaction::TxRegistry;
+
+use super::nq_checkpoint_record::NqCheckpointRecord;
+
+/// Writes non-quiescent checkpoints. New transactions are held back while a checkpoint
+/// is taken, but the active ones keep running: they are listed in the checkpoint record
+/// so recovery knows how far back beyond the checkpoint it has to scan.
+pub struct CheckpointMgr<'a> {
+    log_mgr: &'a LogMgr,
+    buffer_mgr: &'a BufferMgr,
+    tx_registry: &'a TxRegistry,
+}
+
+impl<'a> CheckpointMgr<'a> {
+    pub fn new(
+        log_mgr: &'a LogMgr,
+        buffer_mgr: &'a BufferMgr,
+        tx_registry: &'a TxRegistry,
+    ) -> Self {
+        CheckpointMgr {
+            log_mgr,
+            buffer_mgr,
+            tx_registry,
+        }
+    }
+
+    /// Flushes all modified buffers, syncs the files written since the last checkpoint
+    /// and writes a `<NQCKPT T1,T2,...>` record listing the active transactions. Log
+    /// segments older than the start of the oldest active transaction are not needed by
+    /// recovery anymore and get deleted.
+    pub fn checkpoint(&self) -> DbResult<()> {
+        // no transaction can start, commit or roll back until the record is written
+        let active_txs = self.tx_registry.lock();
+        self.buffer_mgr.flush_all_modified()?;
+        self.buffer_mgr.sync_written_files()?;
+
+        let checkpoint_record = NqCheckpointRecord::new(active_txs.keys().copied().collect());
+        let lsn = self.log_mgr.append(&checkpoint_record.to_bytes()?)?;
+        self.log_mgr.flush(lsn)?;
+
+        let oldest_needed_lsn = active_txs.values().copied().min().unwrap_or(lsn);
+        drop(active_txs);
+
+        self.log_mgr.truncate(oldest_needed_lsn)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        error::DbResult,
+        query::{Scan, UpdateScan},
+        record::{Layout, Schema, TableScan},
+        utils::testing_utils::temp_db_with_cfg,
+    };
+
+    #[test]
+    fn test_checkpoint_deletes_old_log_segments() -> DbResult<()> {
+        let mut log_path = PathBuf::new();
+        let db = temp_db_with_cfg(|cfg| {
+            log_path = cfg.log_file_path();
+            cfg.log_segment_blocks(2)
+        })?;
+        let segment_path =
+            |segment: i32| PathBuf::from(format!("{}.{segment}", log_path.display()));
+
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        let layout = Layout::new(schema);
+
+        for i in 0..50 {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                scan.insert()?;
+                scan.set_int("id", i)?;
+            }
+            tx.commit()?;
+        }
+        assert!(segment_path(0).exists());
+
+        db.checkpoint()?;
+        assert!(!segment_path(0).exists());
+
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            let mut i = 0;
+            while scan.next()? {
+                assert_eq!(scan.get_int("id")?, i);
+                i += 1;
+            }
+            assert_eq!(i, 50);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -35,6 +35,10 @@ impl LogRecord for CheckpointRecord {
         Ok(())
     }
 
+    fn redo(&self, _lsn: i32, _tx: Transaction) -> DbResult<()> {
+        Ok(())
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -38,6 +38,10 @@ impl LogRecord for CommitRecord {
         Ok(())
     }
 
+    fn redo(&self, _lsn: i32, _tx: Transaction) -> DbResult<()> {
+        Ok(())
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
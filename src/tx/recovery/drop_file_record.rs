@@ -0,0 +1,77 @@
+use std::any::Any;
+
+use bincode::serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::DbResult, tx::Transaction};
+
+use super::log_record::{DROPFILE_FLAG, LogRecord};
+
+/// Records that a transaction deletes a file. The file is deleted when the transaction
+/// commits; recovery ignores the earlier records of a file dropped by a committed transaction.
+#[derive(Serialize, Deserialize)]
+pub struct DropFileRecord {
+    pub tx_id: i32,
+    pub file_name: String,
+}
+
+impl DropFileRecord {
+    pub fn new(tx_id: i32, file_name: String) -> Self {
+        DropFileRecord { tx_id, file_name }
+    }
+
+    pub fn to_bytes(&self) -> DbResult<Vec<u8>> {
+        let mut result = vec![DROPFILE_FLAG as u8];
+        result.extend(serialize(self)?);
+        Ok(result)
+    }
+}
+
+impl LogRecord for DropFileRecord {
+    fn op(&self) -> i32 {
+        DROPFILE_FLAG
+    }
+
+    fn tx_id(&self) -> i32 {
+        self.tx_id
+    }
+
+    /// Nothing to undo, the file is only deleted once the transaction commits.
+    fn undo(&self, _tx_id: i32, _tx: Transaction) -> DbResult<()> {
+        Ok(())
+    }
+
+    /// The deletion is repeated by the recovery manager once the whole log is redone,
+    /// since a file of the same name may have been created after it.
+    fn redo(&self, _lsn: i32, _tx: Transaction) -> DbResult<()> {
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::recovery::log_record::create_log_record;
+
+    #[test]
+    fn test_drop_file_record_serialization() -> crate::error::DbResult<()> {
+        let record = DropFileRecord::new(7, "emp.tbl".to_string());
+        let bytes = record.to_bytes()?;
+
+        let deserialized = create_log_record(&bytes)?;
+
+        assert_eq!(deserialized.op(), DROPFILE_FLAG);
+        assert_eq!(deserialized.tx_id(), 7);
+
+        let drop_file = deserialized
+            .as_any()
+            .downcast_ref::<DropFileRecord>()
+            .expect("Failed to downcast to DropFileRecord");
+        assert_eq!(drop_file.file_name, "emp.tbl");
+        Ok(())
+    }
+}
@@ -3,11 +3,16 @@ use std::any::Any;
 use bincode::deserialize;
 
 use crate::error::DbResult;
+use crate::storage::BlockId;
 use crate::tx::Transaction;
 
 use super::checkpoint_record::CheckpointRecord;
 use super::commit_record::CommitRecord;
+use super::drop_file_record::DropFileRecord;
+use super::nq_checkpoint_record::NqCheckpointRecord;
+use super::replace_file_record::ReplaceFileRecord;
 use super::rollback_record::RollbackRecord;
+use super::set_bytes_record::SetBytesRecord;
 use super::set_int_record::SetIntRecord;
 use super::set_string_record::SetStringRecord;
 use super::start_record::StartRecord;
@@ -18,6 +23,10 @@ pub const COMMIT_FLAG: i32 = 2;
 pub const ROLLBACK_FLAG: i32 = 3;
 pub const SETINT_FLAG: i32 = 4;
 pub const SETSTRING_FLAG: i32 = 5;
+pub const NQCKPT_FLAG: i32 = 6;
+pub const DROPFILE_FLAG: i32 = 7;
+pub const SETBYTES_FLAG: i32 = 8;
+pub const REPLACEFILE_FLAG: i32 = 9;
 
 pub trait LogRecord: Send + Sync {
     fn op(&self) -> i32;
@@ -27,6 +36,15 @@ pub trait LogRecord: Send + Sync {
     /// Undoes the operation encoded by this log record.
     fn undo(&self, tx_id: i32, tx: Transaction) -> DbResult<()>;
 
+    /// Reapplies the operation encoded by this log record, unless the page
+    /// already reflects the record with the given LSN.
+    fn redo(&self, lsn: i32, tx: Transaction) -> DbResult<()>;
+
+    /// Returns the block modified by this log record, if it modifies one.
+    fn block(&self) -> Option<&BlockId> {
+        None
+    }
+
     fn as_any(&self) -> &dyn Any;
 }
 
@@ -41,6 +59,10 @@ pub fn create_log_record(bytes: &[u8]) -> DbResult<Box<dyn LogRecord>> {
         ROLLBACK_FLAG => Ok(Box::new(deserialize::<RollbackRecord>(&bytes[1..])?)),
         SETINT_FLAG => Ok(Box::new(deserialize::<SetIntRecord>(&bytes[1..])?)),
         SETSTRING_FLAG => Ok(Box::new(deserialize::<SetStringRecord>(&bytes[1..])?)),
+        NQCKPT_FLAG => Ok(Box::new(deserialize::<NqCheckpointRecord>(&bytes[1..])?)),
+        DROPFILE_FLAG => Ok(Box::new(deserialize::<DropFileRecord>(&bytes[1..])?)),
+        SETBYTES_FLAG => Ok(Box::new(deserialize::<SetBytesRecord>(&bytes[1..])?)),
+        REPLACEFILE_FLAG => Ok(Box::new(deserialize::<ReplaceFileRecord>(&bytes[1..])?)),
         _ => Err(crate::error::DbError::Schema(format!(
             "Unknown log record type: {}",
             record_flag
@@ -1,7 +1,16 @@
+pub mod checkpoint_mgr;
 pub mod checkpoint_record;
 pub mod commit_record;
+pub mod drop_file_record;
 pub mod log_record;
+pub mod nq_checkpoint_record;
+pub mod recovery_mgr;
+pub mod replace_file_record;
 pub mod rollback_record;
+pub mod set_bytes_record;
 pub mod set_int_record;
 pub mod set_string_record;
 pub mod start_record;
+
+pub use checkpoint_mgr::CheckpointMgr;
+pub use recovery_mgr::RecoveryMgr;
@@ -0,0 +1,74 @@
+use std::any::Any;
+
+use bincode::serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::DbResult, tx::Transaction};
+
+use super::log_record::{LogRecord, NQCKPT_FLAG};
+
+/// Non-quiescent checkpoint `<NQCKPT T1,T2,...>`. Every page modified before this
+/// record is on disk, except for the changes made by the listed active transactions.
+#[derive(Serialize, Deserialize)]
+pub struct NqCheckpointRecord {
+    pub active_txs: Vec<i32>,
+}
+
+impl NqCheckpointRecord {
+    pub fn new(active_txs: Vec<i32>) -> Self {
+        NqCheckpointRecord { active_txs }
+    }
+
+    pub fn to_bytes(&self) -> DbResult<Vec<u8>> {
+        let mut result = vec![NQCKPT_FLAG as u8];
+        result.extend(serialize(self)?);
+        Ok(result)
+    }
+}
+
+impl LogRecord for NqCheckpointRecord {
+    fn op(&self) -> i32 {
+        NQCKPT_FLAG
+    }
+
+    fn tx_id(&self) -> i32 {
+        -1
+    }
+
+    fn undo(&self, _tx_id: i32, _tx: Transaction) -> DbResult<()> {
+        Ok(())
+    }
+
+    fn redo(&self, _lsn: i32, _tx: Transaction) -> DbResult<()> {
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::recovery::log_record::create_log_record;
+
+    #[test]
+    fn test_nq_checkpoint_record_serialization() -> crate::error::DbResult<()> {
+        let record = NqCheckpointRecord::new(vec![3, 7, 12]);
+        let bytes = record.to_bytes()?;
+
+        let deserialized = create_log_record(&bytes)?;
+
+        assert_eq!(deserialized.op(), NQCKPT_FLAG);
+        assert_eq!(deserialized.tx_id(), -1);
+
+        let nq_checkpoint = deserialized
+            .as_any()
+            .downcast_ref::<NqCheckpointRecord>()
+            .expect("Failed to downcast to NqCheckpointRecord");
+        assert_eq!(nq_checkpoint.active_txs, vec![3, 7, 12]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,549 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::buffer::BufferMgr;
+use crate::error::DbResult;
+use crate::log::LogMgr;
+use crate::tx::Transaction;
+
+use super::checkpoint_record::CheckpointRecord;
+use super::drop_file_record::DropFileRecord;
+use super::log_record::{
+    CHECKPOINT_FLAG, COMMIT_FLAG, DROPFILE_FLAG, LogRecord, NQCKPT_FLAG, REPLACEFILE_FLAG,
+    ROLLBACK_FLAG, START_FLAG, create_log_record,
+};
+use super::nq_checkpoint_record::NqCheckpointRecord;
+use super::replace_file_record::ReplaceFileRecord;
+
+/// Restores the database to a consistent state on startup, ARIES style.
+/// The log is read back to the most recent quiescent checkpoint, or past the most
+/// recent non-quiescent one until the start records of all transactions it lists.
+/// Everything before that point is known to be on disk. The records are then
+/// redone in log order, which repeats history for committed and uncommitted
+/// transactions alike, and finally every modification made by a transaction
+/// which has neither a commit nor a rollback record is undone in reverse log order.
+/// Records of a file dropped by a committed transaction are skipped if they precede
+/// the drop, and the file is deleted again in case the crash came before the deletion.
+/// Likewise the records of both files of a committed replacement which precede it are
+/// skipped, and the file is renamed again.
+pub struct RecoveryMgr<'a> {
+    log_mgr: &'a LogMgr,
+    buffer_mgr: &'a BufferMgr,
+}
+
+impl<'a> RecoveryMgr<'a> {
+    pub fn new(log_mgr: &'a LogMgr, buffer_mgr: &'a BufferMgr) -> Self {
+        RecoveryMgr {
+            log_mgr,
+            buffer_mgr,
+        }
+    }
+
+    /// Redoes the logged history, undoes all incomplete transactions using the
+    /// given transaction, flushes and syncs the restored pages and writes a quiescent
+    /// checkpoint record.
+    pub fn recover(&self, tx: Transaction<'a>) -> DbResult<()> {
+        self.do_recover(tx.clone())?;
+        self.buffer_mgr.flush_all(tx.id())?;
+        self.buffer_mgr.sync_written_files()?;
+
+        let checkpoint_record = CheckpointRecord::new();
+        let lsn = self.log_mgr.append(&checkpoint_record.to_bytes()?)?;
+        self.log_mgr.flush(lsn)?;
+        Ok(())
+    }
+
+    fn do_recover(&self, tx: Transaction<'a>) -> DbResult<()> {
+        let mut finished_txs = HashSet::new();
+        let mut committed_txs = HashSet::new();
+        // transactions listed by the latest NQCKPT whose start record is not reached yet
+        let mut pending_txs: Option<HashSet<i32>> = None;
+        // records since the last checkpoint, newest first
+        let mut records = Vec::new();
+        let mut iter = self.log_mgr.iterator()?;
+
+        while iter.has_next() {
+            let bytes = iter.next()?;
+            let record = create_log_record(&bytes)?;
+
+            match record.op() {
+                CHECKPOINT_FLAG => break,
+                NQCKPT_FLAG if pending_txs.is_none() => {
+                    let nq_checkpoint = record
+                        .as_any()
+                        .downcast_ref::<NqCheckpointRecord>()
+                        .expect("NQCKPT flag on a record of another type");
+                    let active_txs: HashSet<i32> =
+                        nq_checkpoint.active_txs.iter().copied().collect();
+                    if active_txs.is_empty() {
+                        break;
+                    }
+                    pending_txs = Some(active_txs);
+                }
+                COMMIT_FLAG => {
+                    finished_txs.insert(record.tx_id());
+                    committed_txs.insert(record.tx_id());
+                }
+                ROLLBACK_FLAG => {
+                    finished_txs.insert(record.tx_id());
+                }
+                _ => {}
+            }
+            let is_start = record.op() == START_FLAG;
+            let tx_id = record.tx_id();
+            records.push((iter.current_lsn(), record));
+
+            let reached_oldest = pending_txs
+                .as_mut()
+                .is_some_and(|pending| is_start && pending.remove(&tx_id) && pending.is_empty());
+            if reached_oldest {
+                break;
+            }
+        }
+
+        // the latest committed drop of every file, records before it touch a deleted file
+        let mut dropped_files: HashMap<String, i32> = HashMap::new();
+        for (lsn, record) in records.iter() {
+            if record.op() == DROPFILE_FLAG && committed_txs.contains(&record.tx_id()) {
+                let drop_file = record
+                    .as_any()
+                    .downcast_ref::<DropFileRecord>()
+                    .expect("DROPFILE flag on a record of another type");
+                dropped_files
+                    .entry(drop_file.file_name.clone())
+                    .or_insert(*lsn);
+            }
+        }
+        // the committed replacements, newest first, and the latest one of every file involved
+        let mut replacements: Vec<(i32, &ReplaceFileRecord)> = Vec::new();
+        let mut replaced_files: HashMap<String, i32> = HashMap::new();
+        for (lsn, record) in records.iter() {
+            if record.op() == REPLACEFILE_FLAG && committed_txs.contains(&record.tx_id()) {
+                let replace_file = record
+                    .as_any()
+                    .downcast_ref::<ReplaceFileRecord>()
+                    .expect("REPLACEFILE flag on a record of another type");
+                for file_name in [&replace_file.from, &replace_file.to] {
+                    replaced_files.entry(file_name.clone()).or_insert(*lsn);
+                }
+                replacements.push((*lsn, replace_file));
+            }
+        }
+        let is_dropped = |lsn: i32, record: &dyn LogRecord| {
+            record.block().is_some_and(|blk| {
+                let before = |files: &HashMap<String, i32>| {
+                    files
+                        .get(blk.file_name())
+                        .is_some_and(|drop_lsn| lsn < *drop_lsn)
+                };
+                before(&dropped_files) || before(&replaced_files)
+            })
+        };
+
+        for (lsn, record) in records.iter().rev() {
+            if !is_dropped(*lsn, record.as_ref()) {
+                record.redo(*lsn, tx.clone())?;
+            }
+        }
+
+        for (lsn, record) in records.iter() {
+            if !finished_txs.contains(&record.tx_id()) && !is_dropped(*lsn, record.as_ref()) {
+                record.undo(record.tx_id(), tx.clone())?;
+            }
+        }
+
+        // a file created again after the drop was deleted before, only its new records remain
+        let recreated = |file_name: &str, drop_lsn: i32| {
+            records.iter().any(|(lsn, record)| {
+                *lsn > drop_lsn
+                    && record
+                        .block()
+                        .is_some_and(|blk| blk.file_name() == file_name)
+            })
+        };
+        for (file_name, drop_lsn) in dropped_files.iter() {
+            if !recreated(file_name, *drop_lsn) {
+                tx.remove_file(file_name)?;
+            }
+        }
+        // likewise a replacing file written again was renamed before, renaming a missing or
+        // empty file does nothing
+        for (lsn, replace_file) in replacements.iter().rev() {
+            if !recreated(&replace_file.from, *lsn) {
+                tx.rename_file(&replace_file.from, &replace_file.to)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::DbResult,
+        query::{Scan, UpdateScan},
+        record::{Layout, Schema, TableScan},
+        tx::recovery::{commit_record::CommitRecord, replace_file_record::ReplaceFileRecord},
+        utils::testing_utils::temp_db,
+    };
+
+    fn test_layout() -> Layout {
+        let mut schema = Schema::new();
+        schema.add_int_field("id");
+        schema.add_string_field("name", 20);
+        Layout::new(schema)
+    }
+
+    #[test]
+    fn test_recover_undoes_uncommitted_updates() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                for i in 0..10 {
+                    scan.insert()?;
+                    scan.set_int("id", i)?;
+                    scan.set_string("name", &format!("Name{i}"))?;
+                }
+            }
+            tx.commit()?;
+        }
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                while scan.next()? {
+                    let id = scan.get_int("id")?;
+                    scan.set_int("id", id + 100)?;
+                    scan.set_string("name", "Torn")?;
+                }
+            }
+            // modified pages reach the disk, but the commit record never does
+            db.buffer_mgr().flush_all(tx.id())?;
+            // the crash doesn't roll back the transaction
+            std::mem::forget(tx);
+        }
+
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            let mut i = 0;
+            while scan.next()? {
+                assert_eq!(scan.get_int("id")?, i);
+                assert_eq!(scan.get_string("name")?, format!("Name{i}"));
+                i += 1;
+            }
+            assert_eq!(i, 10);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_undoes_uncommitted_inserts() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                scan.insert()?;
+                scan.set_int("id", 1)?;
+                scan.set_string("name", "Committed")?;
+            }
+            tx.commit()?;
+        }
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                scan.move_to_last()?;
+                for i in 2..5 {
+                    scan.insert()?;
+                    scan.set_int("id", i)?;
+                    scan.set_string("name", "Uncommitted")?;
+                }
+            }
+            db.buffer_mgr().flush_all(tx.id())?;
+            std::mem::forget(tx);
+        }
+
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            assert!(scan.next()?);
+            assert_eq!(scan.get_int("id")?, 1);
+            assert_eq!(scan.get_string("name")?, "Committed");
+            assert!(!scan.next()?);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_keeps_committed_data_across_reopens() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                scan.insert()?;
+                scan.set_int("id", 7)?;
+                scan.set_string("name", "Kept")?;
+            }
+            tx.commit()?;
+        }
+
+        let db = db.reopen()?.reopen()?;
+        let tx = db.new_tx()?;
+        {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            assert!(scan.next()?);
+            assert_eq!(scan.get_int("id")?, 7);
+            assert_eq!(scan.get_string("name")?, "Kept");
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_redoes_committed_but_unflushed_updates() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                for i in 0..10 {
+                    scan.insert()?;
+                    scan.set_int("id", i)?;
+                    scan.set_string("name", &format!("Name{i}"))?;
+                }
+            }
+            // commit forces only the log, the modified pages stay in the buffer pool
+            tx.commit()?;
+        }
+
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            let mut i = 0;
+            while scan.next()? {
+                assert_eq!(scan.get_int("id")?, i);
+                assert_eq!(scan.get_string("name")?, format!("Name{i}"));
+                i += 1;
+            }
+            assert_eq!(i, 10);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_keeps_rolled_back_tx_undone() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                scan.insert()?;
+                scan.set_int("id", 1)?;
+                scan.set_string("name", "Original")?;
+            }
+            tx.commit()?;
+        }
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                assert!(scan.next()?);
+                scan.set_int("id", 2)?;
+                scan.set_string("name", "Changed")?;
+            }
+            tx.rollback()?;
+        }
+
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            assert!(scan.next()?);
+            assert_eq!(scan.get_int("id")?, 1);
+            assert_eq!(scan.get_string("name")?, "Original");
+            assert!(!scan.next()?);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_undoes_tx_active_during_nq_checkpoint() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                for i in 0..5 {
+                    scan.insert()?;
+                    scan.set_int("id", i)?;
+                    scan.set_string("name", "Committed")?;
+                }
+            }
+            tx.commit()?;
+        }
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                assert!(scan.next()?);
+                scan.set_string("name", "BeforeCheckpoint")?;
+
+                // flushes the change above, the record lists tx as active
+                db.checkpoint()?;
+
+                assert!(scan.next()?);
+                scan.set_string("name", "AfterCheckpoint")?;
+            }
+            db.buffer_mgr().flush_all(tx.id())?;
+            std::mem::forget(tx);
+        }
+
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            let mut i = 0;
+            while scan.next()? {
+                assert_eq!(scan.get_int("id")?, i);
+                assert_eq!(scan.get_string("name")?, "Committed");
+                i += 1;
+            }
+            assert_eq!(i, 5);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_skips_records_of_dropped_file() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                for i in 0..10 {
+                    scan.insert()?;
+                    scan.set_int("id", i)?;
+                    scan.set_string("name", "Dropped")?;
+                }
+            }
+            tx.commit()?;
+        }
+
+        {
+            let tx = db.new_tx()?;
+            tx.delete_file("test_table.tbl")?;
+            tx.commit()?;
+        }
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                scan.insert()?;
+                scan.set_int("id", 42)?;
+                scan.set_string("name", "Recreated")?;
+            }
+            tx.commit()?;
+        }
+
+        // redoing the records from before the drop would overwrite the new table
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            assert!(scan.next()?);
+            assert_eq!(scan.get_int("id")?, 42);
+            assert_eq!(scan.get_string("name")?, "Recreated");
+            assert!(!scan.next()?);
+        }
+        assert_eq!(tx.size("test_table.tbl")?, 1);
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_repeats_rename_of_committed_replacement() -> DbResult<()> {
+        let db = temp_db()?;
+        let layout = test_layout();
+
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+                for i in 0..10 {
+                    scan.insert()?;
+                    scan.set_int("id", i)?;
+                    scan.set_string("name", "Replaced")?;
+                }
+            }
+            tx.commit()?;
+        }
+
+        // crash after the commit record of the replacement but before the rename
+        {
+            let tx = db.new_tx()?;
+            {
+                let mut scan = TableScan::new(tx.clone(), "new_table", layout.clone())?;
+                scan.insert()?;
+                scan.set_int("id", 42)?;
+                scan.set_string("name", "Replacement")?;
+            }
+            db.buffer_mgr().flush_all(tx.id())?;
+            let replace_record = ReplaceFileRecord::new(
+                tx.id(),
+                "new_table.tbl".to_string(),
+                "test_table.tbl".to_string(),
+            );
+            db.log_mgr().append(&replace_record.to_bytes()?)?;
+            let lsn = db
+                .log_mgr()
+                .append(&CommitRecord::new(tx.id()).to_bytes()?)?;
+            db.log_mgr().flush(lsn)?;
+            // the crash doesn't roll back the transaction
+            std::mem::forget(tx);
+        }
+
+        let db = db.reopen()?;
+        let tx = db.new_tx()?;
+        {
+            let mut scan = TableScan::new(tx.clone(), "test_table", layout.clone())?;
+            assert!(scan.next()?);
+            assert_eq!(scan.get_int("id")?, 42);
+            assert_eq!(scan.get_string("name")?, "Replacement");
+            assert!(!scan.next()?);
+        }
+        assert_eq!(tx.size("new_table.tbl")?, 0);
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use std::any::Any;
+
+use bincode::serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::DbResult, tx::Transaction};
+
+use super::log_record::{LogRecord, REPLACEFILE_FLAG};
+
+/// Records that a transaction replaces a file with another one it has written. The file is
+/// renamed when the transaction commits, after its pages are written to disk, so recovery
+/// ignores the earlier records of both files and only repeats the rename.
+#[derive(Serialize, Deserialize)]
+pub struct ReplaceFileRecord {
+    pub tx_id: i32,
+    pub from: String,
+    pub to: String,
+}
+
+impl ReplaceFileRecord {
+    pub fn new(tx_id: i32, from: String, to: String) -> Self {
+        ReplaceFileRecord { tx_id, from, to }
+    }
+
+    pub fn to_bytes(&self) -> DbResult<Vec<u8>> {
+        let mut result = vec![REPLACEFILE_FLAG as u8];
+        result.extend(serialize(self)?);
+        Ok(result)
+    }
+}
+
+impl LogRecord for ReplaceFileRecord {
+    fn op(&self) -> i32 {
+        REPLACEFILE_FLAG
+    }
+
+    fn tx_id(&self) -> i32 {
+        self.tx_id
+    }
+
+    /// Nothing to undo, the file is only renamed once the transaction commits.
+    fn undo(&self, _tx_id: i32, _tx: Transaction) -> DbResult<()> {
+        Ok(())
+    }
+
+    /// The rename is repeated by the recovery manager once the whole log is redone.
+    fn redo(&self, _lsn: i32, _tx: Transaction) -> DbResult<()> {
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::recovery::log_record::create_log_record;
+
+    #[test]
+    fn test_replace_file_record_serialization() -> crate::error::DbResult<()> {
+        let record = ReplaceFileRecord::new(7, "emp.vacuum.tbl".to_string(), "emp.tbl".to_string());
+        let bytes = record.to_bytes()?;
+
+        let deserialized = create_log_record(&bytes)?;
+
+        assert_eq!(deserialized.op(), REPLACEFILE_FLAG);
+        assert_eq!(deserialized.tx_id(), 7);
+
+        let replace_file = deserialized
+            .as_any()
+            .downcast_ref::<ReplaceFileRecord>()
+            .expect("Failed to downcast to ReplaceFileRecord");
+        assert_eq!(replace_file.from, "emp.vacuum.tbl");
+        assert_eq!(replace_file.to, "emp.tbl");
+        Ok(())
+    }
+}
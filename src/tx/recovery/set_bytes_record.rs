@@ -0,0 +1,107 @@
+use std::any::Any;
+
+use bincode::serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::DbResult, storage::BlockId, tx::Transaction};
+
+use super::log_record::{LogRecord, SETBYTES_FLAG};
+
+/// Records a write of a range of bytes, such as the records moved by the compaction of a page.
+#[derive(Serialize, Deserialize)]
+pub struct SetBytesRecord {
+    tx_id: i32,
+    offset: usize,
+    old_val: Vec<u8>,
+    new_val: Vec<u8>,
+    blk: BlockId,
+}
+
+impl SetBytesRecord {
+    pub fn new(
+        tx_id: i32,
+        blk: BlockId,
+        offset: usize,
+        old_val: Vec<u8>,
+        new_val: Vec<u8>,
+    ) -> Self {
+        SetBytesRecord {
+            tx_id,
+            offset,
+            old_val,
+            new_val,
+            blk,
+        }
+    }
+
+    pub fn to_bytes(&self) -> DbResult<Vec<u8>> {
+        let mut result = vec![SETBYTES_FLAG as u8];
+        result.extend(serialize(self)?);
+        Ok(result)
+    }
+}
+
+impl LogRecord for SetBytesRecord {
+    fn op(&self) -> i32 {
+        SETBYTES_FLAG
+    }
+
+    fn tx_id(&self) -> i32 {
+        self.tx_id
+    }
+
+    /// Restores the old bytes. The write is logged so that a later redo pass
+    /// repeats the rollback instead of resurrecting the undone bytes.
+    fn undo(&self, _tx_id: i32, tx: Transaction) -> DbResult<()> {
+        tx.pin(&self.blk)?;
+        tx.write_bytes(&self.blk, self.offset, &self.old_val, true)?;
+        tx.unpin(&self.blk);
+        Ok(())
+    }
+
+    fn redo(&self, lsn: i32, tx: Transaction) -> DbResult<()> {
+        tx.pin(&self.blk)?;
+        if tx.page_lsn(&self.blk)? < lsn {
+            tx.write_bytes(&self.blk, self.offset, &self.new_val, false)?;
+            tx.set_page_lsn(&self.blk, lsn)?;
+        }
+        tx.unpin(&self.blk);
+        Ok(())
+    }
+
+    fn block(&self) -> Option<&BlockId> {
+        Some(&self.blk)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::recovery::log_record::create_log_record;
+
+    #[test]
+    fn test_set_bytes_record_serialization() -> DbResult<()> {
+        let blk = BlockId::new("datafile".to_string(), 7);
+        let record = SetBytesRecord::new(31, blk, 64, vec![1, 2, 3, 4], vec![5, 6, 7, 8]);
+        let bytes = record.to_bytes()?;
+
+        let deserialized = create_log_record(&bytes)?;
+        assert_eq!(deserialized.op(), SETBYTES_FLAG);
+        assert_eq!(deserialized.tx_id(), 31);
+
+        let set_bytes = deserialized
+            .as_any()
+            .downcast_ref::<SetBytesRecord>()
+            .expect("Failed to downcast to SetBytesRecord");
+        assert_eq!(set_bytes.offset, 64);
+        assert_eq!(set_bytes.old_val, vec![1, 2, 3, 4]);
+        assert_eq!(set_bytes.new_val, vec![5, 6, 7, 8]);
+        assert_eq!(set_bytes.blk.number(), 7);
+
+        Ok(())
+    }
+}
@@ -11,16 +11,18 @@ use super::log_record::{LogRecord, SETINT_FLAG};
 pub struct SetIntRecord {
     pub tx_id: i32,
     pub offset: usize,
-    pub val: i32,
+    pub old_val: i32,
+    pub new_val: i32,
     pub blk: BlockId,
 }
 
 impl SetIntRecord {
-    pub fn new(tx_id: i32, blk: BlockId, offset: usize, val: i32) -> Self {
+    pub fn new(tx_id: i32, blk: BlockId, offset: usize, old_val: i32, new_val: i32) -> Self {
         SetIntRecord {
             tx_id,
             offset,
-            val,
+            old_val,
+            new_val,
             blk,
         }
     }
@@ -41,13 +43,29 @@ impl LogRecord for SetIntRecord {
         self.tx_id
     }
 
+    /// Restores the old value. The write is logged so that a later redo pass
+    /// repeats the rollback instead of resurrecting the undone value.
     fn undo(&self, _tx_id: i32, tx: Transaction) -> DbResult<()> {
         tx.pin(&self.blk)?;
-        tx.set_int(&self.blk, self.offset, self.val, false)?;
+        tx.set_int(&self.blk, self.offset, self.old_val, true)?;
         tx.unpin(&self.blk);
         Ok(())
     }
 
+    fn redo(&self, lsn: i32, tx: Transaction) -> DbResult<()> {
+        tx.pin(&self.blk)?;
+        if tx.page_lsn(&self.blk)? < lsn {
+            tx.set_int(&self.blk, self.offset, self.new_val, false)?;
+            tx.set_page_lsn(&self.blk, lsn)?;
+        }
+        tx.unpin(&self.blk);
+        Ok(())
+    }
+
+    fn block(&self) -> Option<&BlockId> {
+        Some(&self.blk)
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -62,7 +80,7 @@ mod tests {
     #[test]
     fn test_set_int_record_serialization() -> crate::error::DbResult<()> {
         let blk = BlockId::new("testfile".to_string(), 42);
-        let record = SetIntRecord::new(101, blk, 16, 9999);
+        let record = SetIntRecord::new(101, blk, 16, 9999, 7);
         let bytes = record.to_bytes()?;
 
         let deserialized = create_log_record(&bytes)?;
@@ -76,7 +94,8 @@ mod tests {
             .expect("Failed to downcast to SetIntRecord");
         assert_eq!(set_int.tx_id, 101);
         assert_eq!(set_int.offset, 16);
-        assert_eq!(set_int.val, 9999);
+        assert_eq!(set_int.old_val, 9999);
+        assert_eq!(set_int.new_val, 7);
         assert_eq!(set_int.blk.file_name(), "testfile");
         assert_eq!(set_int.blk.number(), 42);
         Ok(())
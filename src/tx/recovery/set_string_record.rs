@@ -11,16 +11,18 @@ use super::log_record::{LogRecord, SETSTRING_FLAG};
 pub struct SetStringRecord {
     tx_id: i32,
     offset: usize,
-    val: String,
+    old_val: String,
+    new_val: String,
     blk: BlockId,
 }
 
 impl SetStringRecord {
-    pub fn new(tx_id: i32, blk: BlockId, offset: usize, val: String) -> Self {
+    pub fn new(tx_id: i32, blk: BlockId, offset: usize, old_val: String, new_val: String) -> Self {
         SetStringRecord {
             tx_id,
             offset,
-            val,
+            old_val,
+            new_val,
             blk,
         }
     }
@@ -41,13 +43,29 @@ impl LogRecord for SetStringRecord {
         self.tx_id
     }
 
+    /// Restores the old value. The write is logged so that a later redo pass
+    /// repeats the rollback instead of resurrecting the undone value.
     fn undo(&self, _tx_id: i32, tx: Transaction) -> DbResult<()> {
         tx.pin(&self.blk)?;
-        tx.set_string(&self.blk, self.offset, &self.val, false)?;
+        tx.set_string(&self.blk, self.offset, &self.old_val, true)?;
         tx.unpin(&self.blk);
         Ok(())
     }
 
+    fn redo(&self, lsn: i32, tx: Transaction) -> DbResult<()> {
+        tx.pin(&self.blk)?;
+        if tx.page_lsn(&self.blk)? < lsn {
+            tx.set_string(&self.blk, self.offset, &self.new_val, false)?;
+            tx.set_page_lsn(&self.blk, lsn)?;
+        }
+        tx.unpin(&self.blk);
+        Ok(())
+    }
+
+    fn block(&self) -> Option<&BlockId> {
+        Some(&self.blk)
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -63,7 +81,7 @@ mod tests {
     fn test_set_string_record_serialization() -> crate::error::DbResult<()> {
         let blk = BlockId::new("datafile".to_string(), 123);
         let test_string = "Hello, world!".to_string();
-        let record = SetStringRecord::new(202, blk, 32, test_string.clone());
+        let record = SetStringRecord::new(202, blk, 32, test_string.clone(), "bye".to_string());
         let bytes = record.to_bytes()?;
 
         let deserialized = create_log_record(&bytes)?;
@@ -78,7 +96,8 @@ mod tests {
 
         assert_eq!(set_string.tx_id, 202);
         assert_eq!(set_string.offset, 32);
-        assert_eq!(set_string.val, test_string);
+        assert_eq!(set_string.old_val, test_string);
+        assert_eq!(set_string.new_val, "bye");
         assert_eq!(set_string.blk.file_name(), "datafile");
         assert_eq!(set_string.blk.number(), 123);
 
@@ -43,6 +43,10 @@ impl LogRecord for StartRecord {
         Ok(())
     }
 
+    fn redo(&self, _lsn: i32, _tx: Transaction) -> DbResult<()> {
+        Ok(())
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -1,8 +1,9 @@
 use std::{
     cell::RefCell,
+    collections::BTreeMap,
     rc::Rc,
     sync::{
-        Arc,
+        Arc, Mutex, MutexGuard,
         atomic::{AtomicI32, Ordering},
     },
 };
@@ -10,16 +11,21 @@ use std::{
 use crate::{buffer::{BufferList, BufferMgr}, tx::TransactionIntent};
 use crate::error::DbResult;
 use crate::log::LogMgr;
+use crate::record::overflow::OverflowFile;
 use crate::{
     error::DbError,
-    storage::{BlockId, StorageMgr},
+    storage::{BlockId, Page, StorageMgr},
     tx::concurrency::{ConcurrencyMgr, LockTable},
 };
 
 use super::recovery::{
     commit_record::CommitRecord,
+    drop_file_record::DropFileRecord,
+    replace_file_record::ReplaceFileRecord,
     log_record::{START_FLAG, create_log_record},
+    recovery_mgr::RecoveryMgr,
     rollback_record::RollbackRecord,
+    set_bytes_record::SetBytesRecord,
     set_int_record::SetIntRecord,
     set_string_record::SetStringRecord,
     start_record::StartRecord,
@@ -27,6 +33,38 @@ use super::recovery::{
 
 static NEXT_TX_ID: AtomicI32 = AtomicI32::new(0);
 
+/// Keeps track of the transactions which have started but neither committed nor rolled back,
+/// mapped to the LSNs of their start records.
+/// A transaction is registered while its start record is appended, so holding the registry
+/// lock prevents new transactions from starting (used by checkpoints).
+#[derive(Default)]
+pub struct TxRegistry {
+    active: Mutex<BTreeMap<i32, i32>>,
+}
+
+impl TxRegistry {
+    pub fn new() -> Self {
+        TxRegistry {
+            active: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Locks the registry and returns the active transactions with their start LSNs.
+    pub fn lock(&self) -> MutexGuard<'_, BTreeMap<i32, i32>> {
+        self.active.lock().unwrap()
+    }
+
+    fn remove(&self, tx_id: i32) {
+        self.lock().remove(&tx_id);
+    }
+}
+
+/// Every data page starts with the LSN of the latest log record applied to it.
+/// Transactions only expose the area after this header, so page offsets seen by
+/// callers (and stored in log records) are relative to the end of the header.
+const PAGE_LSN_OFFSET: usize = 0;
+const PAGE_HEADER_SIZE: usize = 4;
+
 // Transaction is alive as long as DB is alive, so we can reference BufferMgr, StorageMgr, LogMgr.
 pub struct TransactionInner<'a> {
     id: i32,
@@ -35,7 +73,30 @@ pub struct TransactionInner<'a> {
     concurrency_mgr: ConcurrencyMgr,
     log_mgr: &'a LogMgr,
     storage_mgr: &'a dyn StorageMgr,
+    tx_registry: &'a TxRegistry,
     buffers: BufferList<'a>,
+    // files deleted by the transaction, removed from disk once it commits
+    dropped_files: Vec<String>,
+    // files written by the transaction, renamed over the second file once it commits
+    replaced_files: Vec<(String, String)>,
+    // chains of overflow files freed by the transaction, put on the free lists once it commits
+    freed_chains: Vec<(String, i32)>,
+    // blocks taken from overflow files by the transaction, put back if it rolls back
+    taken_blocks: Vec<(String, i32)>,
+    // set once the transaction committed or rolled back, the last handle rolls it back otherwise
+    finished: bool,
+}
+
+impl<'a> TransactionInner<'a> {
+    fn check_not_dropped(&self, file_name: &str) -> DbResult<()> {
+        if self.dropped_files.iter().any(|f| f == file_name) {
+            return Err(DbError::Schema(format!(
+                "File {} is dropped by the transaction, create it again in a new one",
+                file_name
+            )));
+        }
+        Ok(())
+    }
 }
 
 pub struct Transaction<'a> {
@@ -48,13 +109,17 @@ impl<'a> Transaction<'a> {
         log_mgr: &'a LogMgr,
         buffer_mgr: &'a BufferMgr,
         lock_table: Arc<LockTable>,
+        tx_registry: &'a TxRegistry,
         intent: Option<TransactionIntent>,
     ) -> DbResult<Self> {
+        let mut active_txs = tx_registry.lock();
         let tx_id = NEXT_TX_ID.fetch_add(1, Ordering::SeqCst) + 1;
 
         let start_record = StartRecord::create(tx_id);
         let bytes = start_record.to_bytes()?;
-        log_mgr.append(&bytes)?;
+        let start_lsn = log_mgr.append(&bytes)?;
+        active_txs.insert(tx_id, start_lsn);
+        drop(active_txs);
 
         let buffers = BufferList::new(&buffer_mgr);
 
@@ -62,10 +127,16 @@ impl<'a> Transaction<'a> {
             buffer_mgr,
             log_mgr,
             storage_mgr,
+            tx_registry,
             id: tx_id,
             buffers,
             concurrency_mgr: ConcurrencyMgr::new(lock_table),
-            intent
+            intent,
+            dropped_files: Vec::new(),
+            replaced_files: Vec::new(),
+            freed_chains: Vec::new(),
+            taken_blocks: Vec::new(),
+            finished: false,
         };
 
         Ok(Transaction {
@@ -73,39 +144,131 @@ impl<'a> Transaction<'a> {
         })
     }
 
+    /// Commits the transaction. Only the log is forced to disk, modified pages
+    /// are written lazily by the buffer manager (no-force).
     pub fn commit(self) -> DbResult<()> {
         let mut tx_inner = self.inner.borrow_mut();
-        tx_inner.buffer_mgr.flush_all(tx_inner.id)?;
+
+        // the replacing files must be complete on disk before the commit record makes
+        // the renames durable, as recovery does not redo their records. Flushing only
+        // hands the pages to the OS, so the files are synced as well.
+        if !tx_inner.replaced_files.is_empty() {
+            tx_inner.buffer_mgr.flush_all(tx_inner.id)?;
+            for (from, _) in &tx_inner.replaced_files {
+                tx_inner.storage_mgr.sync(from)?;
+            }
+        }
 
         let commit_record = CommitRecord::new(tx_inner.id);
         let bytes = commit_record.to_bytes()?;
         let lsn = tx_inner.log_mgr.append(&bytes)?;
         tx_inner.log_mgr.flush(lsn)?;
         let tx_id = tx_inner.id;
-        // TODO fsync
+        tx_inner.finished = true;
 
+        // the files are deleted while the locks are still held, so no other
+        // transaction sees a dropped file before it is gone. The transaction is
+        // committed already, so the locks are released even if this fails.
+        tx_inner.buffers.unpin_all();
+        let mut freed_chains = std::mem::take(&mut tx_inner.freed_chains);
+        freed_chains.retain(|(file_name, _)| {
+            !tx_inner.dropped_files.contains(file_name)
+                && !tx_inner.replaced_files.iter().any(|(_, to)| to == file_name)
+        });
+        let mut result = Ok(());
+        for file_name in std::mem::take(&mut tx_inner.dropped_files) {
+            tx_inner.buffer_mgr.discard_file(&file_name);
+            result = result.and(tx_inner.storage_mgr.delete_file(&file_name));
+        }
+        let replaced_files = std::mem::take(&mut tx_inner.replaced_files);
+        for (from, to) in &replaced_files {
+            tx_inner.buffer_mgr.discard_file(from);
+            tx_inner.buffer_mgr.discard_file(to);
+            result = result.and(tx_inner.storage_mgr.rename_file(from, to));
+        }
+        // the renames must be durable before a checkpoint lets their records go
+        if !replaced_files.is_empty() {
+            result = result.and(tx_inner.storage_mgr.sync_dir());
+        }
+
+        tx_inner.tx_registry.remove(tx_id);
         tx_inner.concurrency_mgr.release(tx_id);
+        drop(tx_inner);
 
-        tx_inner.buffers.unpin_all();
-        Ok(())
+        // the chains are put back once the locks are released, a crash before loses them
+        let released = self.release_chains(freed_chains);
+        result?;
+        released
     }
 
     pub fn rollback(self) -> DbResult<()> {
+        self.abort()
+    }
+
+    fn abort(&self) -> DbResult<()> {
         self.do_rollback()?;
 
         let mut tx_inner = self.inner.borrow_mut();
-        tx_inner.buffer_mgr.flush_all(tx_inner.id)?;
 
         let rollback_record = RollbackRecord::create(tx_inner.id);
         let bytes = rollback_record.to_bytes()?;
         let lsn = tx_inner.log_mgr.append(&bytes)?;
         tx_inner.log_mgr.flush(lsn)?;
         let tx_id = tx_inner.id;
+        tx_inner.dropped_files.clear();
+        tx_inner.replaced_files.clear();
+        tx_inner.freed_chains.clear();
+        let taken_blocks = std::mem::take(&mut tx_inner.taken_blocks);
+        tx_inner.finished = true;
 
+        tx_inner.tx_registry.remove(tx_id);
         tx_inner.concurrency_mgr.release(tx_id);
 
         tx_inner.buffers.unpin_all();
-        Ok(())
+        drop(tx_inner);
+
+        self.release_chains(taken_blocks)
+    }
+
+    /// Puts the chains of overflow files back on their free lists in a transaction of its own.
+    fn release_chains(&self, chains: Vec<(String, i32)>) -> DbResult<()> {
+        if chains.is_empty() {
+            return Ok(());
+        }
+        let tx = self.new_system_tx()?;
+        for (file_name, first) in chains {
+            OverflowFile::release(tx.clone(), &file_name, first)?;
+        }
+        tx.commit()
+    }
+
+    /// Starts a transaction sharing the managers of this one, which commits on its own.
+    /// Used to change structures shared by all the transactions, such as the free lists
+    /// of the overflow files, without holding their locks until this one finishes.
+    /// The transaction locks the blocks it reads exclusively.
+    pub(crate) fn new_system_tx(&self) -> DbResult<Transaction<'a>> {
+        let tx_inner = self.inner.borrow();
+        Transaction::new(
+            tx_inner.storage_mgr,
+            tx_inner.log_mgr,
+            tx_inner.buffer_mgr,
+            Arc::clone(&tx_inner.concurrency_mgr.lock_table),
+            tx_inner.tx_registry,
+            Some(TransactionIntent::WriteOnly),
+        )
+    }
+
+    /// Puts a chain of an overflow file on its free list once the transaction commits.
+    pub(crate) fn free_on_commit(&self, file_name: &str, first: i32) {
+        let mut tx_inner = self.inner.borrow_mut();
+        tx_inner.freed_chains.push((file_name.to_string(), first));
+    }
+
+    /// Puts a block taken from an overflow file back on its free list if the transaction
+    /// rolls back. The blocks taken by a transaction which crashed are lost.
+    pub(crate) fn free_on_rollback(&self, file_name: &str, blk_num: i32) {
+        let mut tx_inner = self.inner.borrow_mut();
+        tx_inner.taken_blocks.push((file_name.to_string(), blk_num));
     }
 
     fn do_rollback(&self) -> DbResult<()> {
@@ -123,6 +286,7 @@ impl<'a> Transaction<'a> {
                 if record.op() == START_FLAG {
                     return Ok(());
                 }
+                // undo writes are logged, so a redo pass replays the rollback as well
                 record.undo(tx_id, self.clone())?;
             }
         }
@@ -131,6 +295,17 @@ impl<'a> Transaction<'a> {
         Err(DbError::LogInconsistent)
     }
 
+    /// Rolls back all transactions left incomplete by a crash and writes a checkpoint.
+    /// Must be called before any other transaction starts.
+    pub fn recover(&self) -> DbResult<()> {
+        let tx_inner = self.inner.borrow();
+        tx_inner.buffer_mgr.flush_all(tx_inner.id)?;
+        let recovery_mgr = RecoveryMgr::new(tx_inner.log_mgr, tx_inner.buffer_mgr);
+        drop(tx_inner);
+
+        recovery_mgr.recover(self.clone())
+    }
+
     pub fn pin(&self, blk: &BlockId) -> DbResult<()> {
         self.inner.borrow_mut().buffers.pin(blk)
     }
@@ -153,7 +328,7 @@ impl<'a> Transaction<'a> {
             .get_buffer(blk)
             .expect(&format!("Block {blk} not pinned"));
         let buffer = guard.borrow();
-        Ok(buffer.page().get_int(offset))
+        Ok(buffer.page().get_int(PAGE_HEADER_SIZE + offset))
     }
 
     pub fn get_string(&self, blk: &BlockId, offset: usize) -> DbResult<String> {
@@ -170,7 +345,7 @@ impl<'a> Transaction<'a> {
             .get_buffer(blk)
             .expect(&format!("Buffer {blk} not pinned"));
         let buffer = guard.borrow();
-        Ok(buffer.page().get_string(offset))
+        Ok(buffer.page().get_string(PAGE_HEADER_SIZE + offset))
     }
 
     pub fn set_int(
@@ -189,21 +364,27 @@ impl<'a> Transaction<'a> {
             .expect(&format!("Block {blk} not pinned"));
         let mut buffer = guard.borrow_mut();
 
-        if log {
-            let old_val = buffer.page().get_int(offset);
+        // temporary tables do not outlive a restart, so there is nothing to recover
+        if log && !blk.is_temp() {
+            let old_val = buffer.page().get_int(PAGE_HEADER_SIZE + offset);
             let blk_clone = buffer
                 .block()
                 .expect("Buffer has no block assigned")
                 .clone();
 
-            let set_int_record = SetIntRecord::new(tx_inner.id, blk_clone, offset, old_val);
+            let set_int_record = SetIntRecord::new(tx_inner.id, blk_clone, offset, old_val, val);
             let bytes = set_int_record.to_bytes()?;
             let lsn = tx_inner.log_mgr.append(&bytes)?;
 
             buffer.set_modified(tx_inner.id, lsn);
+            buffer.contents_mut().set_int(PAGE_LSN_OFFSET, lsn);
+        } else {
+            buffer.set_modified(tx_inner.id, -1);
         }
 
-        buffer.contents_mut().set_int(offset, val);
+        buffer
+            .contents_mut()
+            .set_int(PAGE_HEADER_SIZE + offset, val);
         Ok(())
     }
 
@@ -217,26 +398,217 @@ impl<'a> Transaction<'a> {
             .expect(&format!("Block {blk} not pinned"));
         let mut buffer = guard.borrow_mut();
 
-        if log {
-            let old_val = buffer.page().get_string(offset);
+        if log && !blk.is_temp() {
+            let old_val = buffer.page().get_string(PAGE_HEADER_SIZE + offset);
             let blk_clone = buffer
                 .block()
                 .expect("Buffer has no block assigned")
                 .clone();
 
-            let set_string_record = SetStringRecord::new(tx_inner.id, blk_clone, offset, old_val);
+            let set_string_record =
+                SetStringRecord::new(tx_inner.id, blk_clone, offset, old_val, val.to_string());
             let bytes = set_string_record.to_bytes()?;
             let lsn = tx_inner.log_mgr.append(&bytes)?;
 
             buffer.set_modified(tx_inner.id, lsn);
+            buffer.contents_mut().set_int(PAGE_LSN_OFFSET, lsn);
+        } else {
+            buffer.set_modified(tx_inner.id, -1);
+        }
+
+        buffer
+            .contents_mut()
+            .set_string(PAGE_HEADER_SIZE + offset, val);
+        Ok(())
+    }
+
+    /// Reads `length` bytes of the pinned block. Unlike [Page::get_bytes] the range has
+    /// no length prefix.
+    pub fn read_bytes(&self, blk: &BlockId, offset: usize, length: usize) -> DbResult<Vec<u8>> {
+        self.read(blk, offset, |page, pos| page.contents()[pos..pos + length].to_vec())
+    }
+
+    /// Writes the bytes into the pinned block, logging them with as few records as fit into
+    /// the log blocks. This is how ranges such as the records moved by the compaction of
+    /// a page are logged.
+    pub fn write_bytes(
+        &self,
+        blk: &BlockId,
+        offset: usize,
+        bytes: &[u8],
+        log: bool,
+    ) -> DbResult<()> {
+        let mut tx_inner = self.inner.borrow_mut();
+        let tx_id = tx_inner.id;
+        tx_inner.concurrency_mgr.lock_x(blk, tx_id)?;
+        let guard = tx_inner
+            .buffers
+            .get_buffer(blk)
+            .unwrap_or_else(|| panic!("Block {blk} not pinned"));
+        let mut buffer = guard.borrow_mut();
+        let start = PAGE_HEADER_SIZE + offset;
+
+        if log && !blk.is_temp() {
+            let blk_clone = buffer
+                .block()
+                .expect("Buffer has no block assigned")
+                .clone();
+            // a record holds the old and the new bytes of its piece
+            let overhead =
+                SetBytesRecord::new(tx_id, blk_clone.clone(), offset, Vec::new(), Vec::new())
+                    .to_bytes()?
+                    .len();
+            let piece_len = (tx_inner.log_mgr.max_record_size() - overhead) / 2;
+
+            for (i, piece) in bytes.chunks(piece_len).enumerate() {
+                let piece_offset = offset + i * piece_len;
+                let pos = PAGE_HEADER_SIZE + piece_offset;
+                let old_val = buffer.page().contents()[pos..pos + piece.len()].to_vec();
+                let set_bytes_record = SetBytesRecord::new(
+                    tx_id,
+                    blk_clone.clone(),
+                    piece_offset,
+                    old_val,
+                    piece.to_vec(),
+                );
+                let lsn = tx_inner.log_mgr.append(&set_bytes_record.to_bytes()?)?;
+
+                buffer.set_modified(tx_id, lsn);
+                buffer.contents_mut().set_int(PAGE_LSN_OFFSET, lsn);
+            }
+        } else {
+            buffer.set_modified(tx_id, -1);
         }
 
-        buffer.contents_mut().set_string(offset, val);
+        buffer.contents_mut().contents_mut()[start..start + bytes.len()].copy_from_slice(bytes);
         Ok(())
     }
 
+    pub fn get_long(&self, blk: &BlockId, offset: usize) -> DbResult<i64> {
+        self.read(blk, offset, Page::get_long)
+    }
+
+    pub fn get_double(&self, blk: &BlockId, offset: usize) -> DbResult<f64> {
+        self.read(blk, offset, Page::get_double)
+    }
+
+    pub fn get_bool(&self, blk: &BlockId, offset: usize) -> DbResult<bool> {
+        self.read(blk, offset, Page::get_bool)
+    }
+
+    /// Reads a value from the pinned block with the same locking as `get_int`.
+    fn read<T>(
+        &self,
+        blk: &BlockId,
+        offset: usize,
+        read: impl FnOnce(&Page, usize) -> T,
+    ) -> DbResult<T> {
+        let mut tx_inner = self.inner.borrow_mut();
+        let tx_id = tx_inner.id;
+        if matches!(tx_inner.intent, Some(TransactionIntent::WriteOnly)) {
+            tx_inner.concurrency_mgr.lock_x(blk, tx_id)?;
+        } else {
+            tx_inner.concurrency_mgr.lock_s(blk, tx_id)?;
+        }
+
+        let guard = tx_inner
+            .buffers
+            .get_buffer(blk)
+            .unwrap_or_else(|| panic!("Block {blk} not pinned"));
+        let buffer = guard.borrow();
+        Ok(read(buffer.page(), PAGE_HEADER_SIZE + offset))
+    }
+
+    /// Writes the long as its two int words, high word first, so that it is logged
+    /// and recovered as two ints.
+    pub fn set_long(&self, blk: &BlockId, offset: usize, val: i64, log: bool) -> DbResult<()> {
+        self.set_int(blk, offset, (val >> 32) as i32, log)?;
+        self.set_int(blk, offset + 4, val as i32, log)
+    }
+
+    pub fn set_double(&self, blk: &BlockId, offset: usize, val: f64, log: bool) -> DbResult<()> {
+        self.set_long(blk, offset, val.to_bits() as i64, log)
+    }
+
+    pub fn set_bool(&self, blk: &BlockId, offset: usize, val: bool, log: bool) -> DbResult<()> {
+        self.set_int(blk, offset, val as i32, log)
+    }
+
+    /// Returns the LSN of the latest log record applied to the pinned block.
+    pub fn page_lsn(&self, blk: &BlockId) -> DbResult<i32> {
+        let mut tx_inner = self.inner.borrow_mut();
+        let tx_id = tx_inner.id;
+        tx_inner.concurrency_mgr.lock_s(blk, tx_id)?;
+
+        let guard = tx_inner
+            .buffers
+            .get_buffer(blk)
+            .unwrap_or_else(|| panic!("Block {blk} not pinned"));
+        let buffer = guard.borrow();
+        Ok(buffer.page().get_int(PAGE_LSN_OFFSET))
+    }
+
+    /// Stamps the pinned block with the LSN of a log record which was redone on it.
+    pub fn set_page_lsn(&self, blk: &BlockId, lsn: i32) -> DbResult<()> {
+        let mut tx_inner = self.inner.borrow_mut();
+        let tx_id = tx_inner.id;
+        tx_inner.concurrency_mgr.lock_x(blk, tx_id)?;
+
+        let guard = tx_inner
+            .buffers
+            .get_buffer(blk)
+            .unwrap_or_else(|| panic!("Block {blk} not pinned"));
+        let mut buffer = guard.borrow_mut();
+        buffer.set_modified(tx_id, -1);
+        buffer.contents_mut().set_int(PAGE_LSN_OFFSET, lsn);
+        Ok(())
+    }
+
+    /// Reads an int of the pinned block holding only the latch of its buffer, without
+    /// locking the block. Meant for hints, such as the free space maps, which other
+    /// transactions change while this one runs.
+    pub(crate) fn get_int_unlocked(&self, blk: &BlockId, offset: usize) -> i32 {
+        let tx_inner = self.inner.borrow();
+        let guard = tx_inner
+            .buffers
+            .get_buffer(blk)
+            .unwrap_or_else(|| panic!("Block {blk} not pinned"));
+        let buffer = guard.borrow();
+        buffer.page().get_int(PAGE_HEADER_SIZE + offset)
+    }
+
+    /// Writes an int of the pinned block holding only the latch of its buffer. The write
+    /// is not logged, so it is neither undone by a rollback nor redone by recovery.
+    pub(crate) fn set_int_unlocked(&self, blk: &BlockId, offset: usize, val: i32) {
+        let tx_inner = self.inner.borrow();
+        let guard = tx_inner
+            .buffers
+            .get_buffer(blk)
+            .unwrap_or_else(|| panic!("Block {blk} not pinned"));
+        let mut buffer = guard.borrow_mut();
+        buffer.set_modified(tx_inner.id, -1);
+        buffer
+            .contents_mut()
+            .set_int(PAGE_HEADER_SIZE + offset, val);
+    }
+
+    /// Returns the number of blocks of a file without locking its end.
+    pub(crate) fn size_unlocked(&self, file_name: &str) -> DbResult<i32> {
+        let tx_inner = self.inner.borrow();
+        tx_inner.check_not_dropped(file_name)?;
+        Ok(tx_inner.storage_mgr.block_cnt(file_name)?)
+    }
+
+    /// Appends a block to a file without locking its end.
+    pub(crate) fn append_unlocked(&self, file_name: &str) -> DbResult<BlockId> {
+        let tx_inner = self.inner.borrow();
+        tx_inner.check_not_dropped(file_name)?;
+        Ok(tx_inner.storage_mgr.append(file_name)?)
+    }
+
     pub fn size(&self, file_name: &str) -> DbResult<i32> {
         let mut tx_inner = self.inner.borrow_mut();
+        tx_inner.check_not_dropped(file_name)?;
         let tx_id = tx_inner.id;
         let dummy_blk = BlockId::new(file_name.to_string(), -1);
 
@@ -251,6 +623,7 @@ impl<'a> Transaction<'a> {
 
     pub fn append(&self, file_name: &str) -> DbResult<BlockId> {
         let mut tx_inner = self.inner.borrow_mut();
+        tx_inner.check_not_dropped(file_name)?;
         let tx_id = tx_inner.id;
         let dummy_blk = BlockId::new(file_name.to_string(), -1);
 
@@ -262,8 +635,67 @@ impl<'a> Transaction<'a> {
         Ok(tx_inner.storage_mgr.append(file_name)?)
     }
 
+    /// Deletes a file once the transaction commits, nothing happens if it rolls back.
+    /// The file is locked exclusively until then. The transaction can't use the file
+    /// anymore, so a file of the same name must be created by a new transaction.
+    pub fn delete_file(&self, file_name: &str) -> DbResult<()> {
+        let mut tx_inner = self.inner.borrow_mut();
+        let tx_id = tx_inner.id;
+        let dummy_blk = BlockId::new(file_name.to_string(), -1);
+        tx_inner.concurrency_mgr.lock_x(&dummy_blk, tx_id)?;
+
+        let record = DropFileRecord::new(tx_id, file_name.to_string());
+        tx_inner.log_mgr.append(&record.to_bytes()?)?;
+        if !tx_inner.dropped_files.iter().any(|f| f == file_name) {
+            tx_inner.dropped_files.push(file_name.to_string());
+        }
+        Ok(())
+    }
+
+    /// Deletes a file right away, dropping its cached pages. Used by recovery to
+    /// finish the deletions of committed transactions, and to clear the leftover
+    /// files of a vacuum which rolled back.
+    pub(crate) fn remove_file(&self, file_name: &str) -> DbResult<()> {
+        let tx_inner = self.inner.borrow();
+        tx_inner.buffer_mgr.discard_file(file_name);
+        Ok(tx_inner.storage_mgr.delete_file(file_name)?)
+    }
+
+    /// Replaces the file `to` with the file `from` once the transaction commits, nothing
+    /// happens if it rolls back. Both files are locked exclusively until then. The pages
+    /// written by the transaction are flushed before it commits, so `from` must only be
+    /// changed by this transaction and is complete when it is renamed.
+    pub fn replace_file(&self, from: &str, to: &str) -> DbResult<()> {
+        let mut tx_inner = self.inner.borrow_mut();
+        let tx_id = tx_inner.id;
+        for file_name in [from, to] {
+            let dummy_blk = BlockId::new(file_name.to_string(), -1);
+            tx_inner.concurrency_mgr.lock_x(&dummy_blk, tx_id)?;
+        }
+
+        let record = ReplaceFileRecord::new(tx_id, from.to_string(), to.to_string());
+        tx_inner.log_mgr.append(&record.to_bytes()?)?;
+        tx_inner.replaced_files.push((from.to_string(), to.to_string()));
+        Ok(())
+    }
+
+    /// Renames a file right away, dropping the cached pages of both files. Used by
+    /// recovery to finish the replacements of committed transactions. A replacing file
+    /// is never empty, so an empty one was created by looking it up after the rename
+    /// and is deleted instead.
+    pub(crate) fn rename_file(&self, from: &str, to: &str) -> DbResult<()> {
+        let tx_inner = self.inner.borrow();
+        tx_inner.buffer_mgr.discard_file(from);
+        if tx_inner.storage_mgr.block_cnt(from)? == 0 {
+            return Ok(tx_inner.storage_mgr.delete_file(from)?);
+        }
+        tx_inner.buffer_mgr.discard_file(to);
+        Ok(tx_inner.storage_mgr.rename_file(from, to)?)
+    }
+
+    /// Returns the number of bytes of a block available to the caller, i.e. without the page header.
     pub fn block_size(&self) -> usize {
-        self.inner.borrow().storage_mgr.block_size()
+        self.inner.borrow().storage_mgr.block_size() - PAGE_HEADER_SIZE
     }
 
     pub fn available_buffs(&self) -> usize {
@@ -283,6 +715,29 @@ impl<'a> Clone for Transaction<'a> {
     }
 }
 
+impl<'a> Drop for Transaction<'a> {
+    /// Rolls back a transaction whose last handle goes away before it committed or
+    /// rolled back, so it doesn't keep its locks and pin the log forever.
+    fn drop(&mut self) {
+        if Rc::strong_count(&self.inner) > 1 {
+            return;
+        }
+        let Ok(tx_inner) = self.inner.try_borrow() else {
+            return;
+        };
+        if tx_inner.finished {
+            return;
+        }
+        drop(tx_inner);
+
+        // a rollback which fails keeps the transaction registered, so its start record
+        // stays in the log and the next recovery undoes it
+        if !std::thread::panicking() {
+            let _ = self.abort();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -295,6 +750,7 @@ mod tests {
         log_mgr: Arc<LogMgr>,
         buffer_mgr: Arc<BufferMgr>,
         lock_table: Arc<LockTable>,
+        tx_registry: Arc<TxRegistry>,
     }
 
     impl Clone for TestEnvironment {
@@ -305,6 +761,7 @@ mod tests {
                 log_mgr: Arc::clone(&self.log_mgr),
                 buffer_mgr: Arc::clone(&self.buffer_mgr),
                 lock_table: Arc::clone(&self.lock_table),
+                tx_registry: Arc::clone(&self.tx_registry),
             }
         }
     }
@@ -328,6 +785,7 @@ mod tests {
                 log_mgr,
                 buffer_mgr,
                 lock_table,
+                tx_registry: Arc::new(TxRegistry::new()),
             })
         }
 
@@ -337,6 +795,7 @@ mod tests {
                 &self.log_mgr,
                 &self.buffer_mgr,
                 Arc::clone(&self.lock_table),
+                &self.tx_registry,
                 None
             )
         }
@@ -361,6 +820,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_transaction_fixed_width_types_rollback() -> DbResult<()> {
+        let env = TestEnvironment::new()?;
+
+        let tx1 = env.new_transaction()?;
+        let blk = tx1.append("testfile")?;
+        tx1.pin(&blk)?;
+        tx1.set_long(&blk, 0, -5_000_000_000, true)?;
+        tx1.set_double(&blk, 8, 1.25, true)?;
+        tx1.set_bool(&blk, 16, true, true)?;
+        tx1.commit()?;
+
+        let tx2 = env.new_transaction()?;
+        tx2.pin(&blk)?;
+        assert_eq!(tx2.get_long(&blk, 0)?, -5_000_000_000);
+        assert_eq!(tx2.get_double(&blk, 8)?, 1.25);
+        assert!(tx2.get_bool(&blk, 16)?);
+        tx2.set_long(&blk, 0, i64::MAX, true)?;
+        tx2.set_double(&blk, 8, -0.5, true)?;
+        tx2.set_bool(&blk, 16, false, true)?;
+        tx2.rollback()?;
+
+        let tx3 = env.new_transaction()?;
+        tx3.pin(&blk)?;
+        assert_eq!(tx3.get_long(&blk, 0)?, -5_000_000_000);
+        assert_eq!(tx3.get_double(&blk, 8)?, 1.25);
+        assert!(tx3.get_bool(&blk, 16)?);
+        tx3.commit()?;
+        Ok(())
+    }
+
     #[test]
     fn test_transaction_rollback1() -> DbResult<()> {
         let env = TestEnvironment::new()?;
@@ -437,6 +927,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_dropped_transaction_rolls_back() -> DbResult<()> {
+        let env = TestEnvironment::new()?;
+
+        let tx1 = env.new_transaction()?;
+        let blk = tx1.append("testfile")?;
+        tx1.pin(&blk)?;
+        tx1.set_int(&blk, 0, 777, true)?;
+        tx1.commit()?;
+
+        let tx2 = env.new_transaction()?;
+        tx2.pin(&blk)?;
+        tx2.set_int(&blk, 0, 999, true)?;
+        let tx2_clone = tx2.clone();
+        drop(tx2);
+        // another handle keeps the transaction alive
+        assert_eq!(env.tx_registry.lock().len(), 1);
+        drop(tx2_clone);
+        assert!(env.tx_registry.lock().is_empty());
+
+        // the X lock was released and the write undone
+        let tx3 = env.new_transaction()?;
+        tx3.pin(&blk)?;
+        assert_eq!(tx3.get_int(&blk, 0)?, 777);
+        tx3.commit()?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_transaction_stress_x_locks() -> DbResult<()> {
         const NUM_THREADS: usize = 5;